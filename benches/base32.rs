@@ -0,0 +1,63 @@
+//! Compares the CIDv1 `Display` path with the generic multibase encoder.
+//!
+//! Run with `cargo bench --bench base32`. The benchmark only uses the
+//! standard library, so it also builds where no benchmark framework is
+//! available.
+use std::{
+  fmt::Write,
+  hint::black_box,
+  time::{Duration, Instant},
+};
+
+use multibase::Base;
+use sp_cid::Cid;
+use sp_multihash::{Code, MultihashDigest};
+
+/// The number of calls per round.
+const ITERATIONS: u32 = 100_000;
+
+/// The number of rounds, the fastest one is reported.
+const ROUNDS: usize = 7;
+
+/// Runs `f` in several rounds and prints the mean time per call of the
+/// fastest round.
+fn bench(name: &str, mut f: impl FnMut()) {
+  let mut best = Duration::MAX;
+  for _ in 0..ROUNDS {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+      f();
+    }
+    best = best.min(start.elapsed() / ITERATIONS);
+  }
+  println!("{:<44} {:>8?}/iter", name, best);
+}
+
+fn main() {
+  let cids = [
+    Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x71, Code::Sha2_512.digest(b"foo")),
+  ];
+  for cid in &cids {
+    let bits = cid.hash().digest().len() * 8;
+    assert_eq!(
+      cid.to_string(),
+      multibase::encode(Base::Base32Lower, cid.to_bytes()),
+      "both paths give the same string"
+    );
+
+    bench(&format!("Display to a new string, {} bit digest", bits), || {
+      black_box(black_box(cid).to_string());
+    });
+    let mut buf = String::with_capacity(Cid::MAX_STRING_LEN);
+    bench(&format!("Display to a reused string, {} bit digest", bits), || {
+      buf.clear();
+      let _ = write!(buf, "{}", black_box(cid));
+      black_box(&buf);
+    });
+    bench(&format!("multibase::encode, {} bit digest", bits), || {
+      let bytes = black_box(cid).to_bytes();
+      black_box(multibase::encode(Base::Base32Lower, bytes));
+    });
+  }
+}
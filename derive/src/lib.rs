@@ -0,0 +1,97 @@
+//! Derive macro for the `ToCid` trait of `sp-cid`.
+//!
+//! Enable it with the `derive` feature of `sp-cid`, which re-exports the
+//! macro next to the trait.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+  parse_macro_input, parse_quote, DeriveInput, Error, Lit, Meta, NestedMeta,
+  Path, Result,
+};
+
+/// Implements `ToCid` by encoding the value and hashing the encoding.
+///
+/// The value is SCALE encoded by default, which requires the `scale-codec`
+/// feature of `sp-cid` and an `Encode` implementation. Other encodings, e.g.
+/// DAG-CBOR, are selected with a function from `&Self` to `Vec<u8>`:
+///
+/// ```ignore
+/// #[derive(Serialize, ToCid)]
+/// #[to_cid(encode_with = "dag_cbor_bytes")]
+/// struct Record {
+///   name: String,
+/// }
+/// ```
+#[proc_macro_derive(ToCid, attributes(to_cid))]
+pub fn derive_to_cid(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2> {
+  let encode_with = encode_with(&input)?;
+  let name = &input.ident;
+  let encode = match &encode_with {
+    Some(path) => quote!(#path(self)),
+    None => {
+      let ty = {
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        quote!(#name #ty_generics)
+      };
+      input
+        .generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(#ty: ::sp_cid::__private::Encode));
+      quote!(::sp_cid::__private::Encode::encode(self))
+    }
+  };
+  let (impl_generics, ty_generics, where_clause) =
+    input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics ::sp_cid::ToCid for #name #ty_generics #where_clause {
+      fn to_cid<
+        __S: ::sp_cid::sp_multihash::Size,
+        __C: ::sp_cid::sp_multihash::MultihashDigest,
+      >(
+        &self,
+        codec: u64,
+        code: __C,
+      ) -> ::sp_cid::Result<::sp_cid::CidGeneric<__S>> {
+        let bytes: ::sp_cid::__private::Vec<u8> = #encode;
+        ::sp_cid::CidGeneric::from_data(codec, code, &bytes)
+      }
+    }
+  })
+}
+
+/// Reads the `encode_with` function of the `#[to_cid(...)]` attribute.
+fn encode_with(input: &DeriveInput) -> Result<Option<Path>> {
+  let mut encode_with = None;
+  for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("to_cid")) {
+    let list = match attr.parse_meta()? {
+      Meta::List(list) => list,
+      meta => return Err(Error::new_spanned(meta, "expected `to_cid(...)`")),
+    };
+    for nested in list.nested {
+      match nested {
+        NestedMeta::Meta(Meta::NameValue(pair))
+          if pair.path.is_ident("encode_with") =>
+        {
+          match &pair.lit {
+            Lit::Str(lit) => encode_with = Some(lit.parse()?),
+            lit => {
+              return Err(Error::new_spanned(lit, "expected a function path"))
+            }
+          }
+        }
+        nested => {
+          return Err(Error::new_spanned(nested, "unknown `to_cid` option"))
+        }
+      }
+    }
+  }
+  Ok(encode_with)
+}
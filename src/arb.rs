@@ -1,3 +1,6 @@
+// Arbitrary instances are only used by property tests, where panics are fine.
+#![allow(clippy::unwrap_used, clippy::expect_used)]
+
 use sp_std::convert::TryFrom;
 
 use sp_multihash::{Code, Multihash, MultihashDigest};
@@ -12,7 +15,7 @@ use crate::{Cid, Version};
 
 impl Arbitrary for Version {
   fn arbitrary<G: Gen>(g: &mut G) -> Self {
-    let version = if g.gen_bool(0.7) { 1 } else { 0 };
+    let version = if g.gen_bool(0.7) { 1u64 } else { 0 };
     Version::try_from(version).unwrap()
   }
 }
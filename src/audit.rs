@@ -0,0 +1,99 @@
+//! JSON records of CIDs for audit logs.
+//!
+//! Compliance pipelines store and compare records byte for byte, so a record
+//! must not change when the crate is upgraded. [`Cid::to_audit_record`]
+//! writes a fixed set of keys in a fixed order, and doesn't depend on the
+//! features of a JSON library.
+use sp_std::fmt::Write;
+
+use alloc::string::String;
+use sp_multihash::Size;
+
+use crate::cid::Cid;
+
+/// The version of the record format, the `record_version` of every record.
+///
+/// It is only increased if the keys or the meaning of their values change.
+pub const AUDIT_RECORD_VERSION: u32 = 1;
+
+/// Writes a JSON string, or `null` if there is none.
+///
+/// The names of the code tables are plain ASCII without quotes or
+/// backslashes, so they need no escaping.
+fn write_name(record: &mut String, name: Option<&str>) {
+  match name {
+    Some(name) => {
+      record.push('"');
+      record.push_str(name);
+      record.push('"');
+    }
+    None => record.push_str("null"),
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Returns a JSON object describing the CID for audit logs.
+  ///
+  /// The object has these keys, in this order and without whitespace:
+  ///
+  /// - `cid`: the default string form, see [`Cid::to_string`]
+  /// - `cid_version`: the version as a number
+  /// - `codec`: the codec as a number
+  /// - `codec_name`: the name of the codec, or `null` if it is unknown
+  /// - `digest`: the digest in lower case hex
+  /// - `hash`: the multihash code as a number
+  /// - `hash_name`: the name of the hash function, or `null` if it is
+  ///   unknown
+  /// - `record_version`: [`AUDIT_RECORD_VERSION`]
+  ///
+  /// The keys are sorted, so the records are also in the canonical form of
+  /// RFC 8785. Names are taken from the [`names`](crate::names) tables. A
+  /// code that is added to the tables later gets a name instead of `null`,
+  /// the shape of the record stays the same.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let digest = concat!(
+  ///   "2c26b46b68ffc68ff99b453c1d304134",
+  ///   "13422d706483bfa0f98a5e886266e7ae",
+  /// );
+  /// let expected = format!(
+  ///   concat!(
+  ///     r#"{{"cid":"{}","cid_version":1,"codec":85,"codec_name":"raw","#,
+  ///     r#""digest":"{}","hash":18,"hash_name":"sha2-256","#,
+  ///     r#""record_version":1}}"#,
+  ///   ),
+  ///   cid, digest,
+  /// );
+  /// assert_eq!(cid.to_audit_record(), expected);
+  /// ```
+  ///
+  /// [`Cid::to_string`]: alloc::string::ToString::to_string
+  pub fn to_audit_record(&self) -> String {
+    let hash = self.hash();
+    let mut record = String::new();
+    // Writing to a string can't fail
+    let _ = write!(
+      record,
+      r#"{{"cid":"{}","cid_version":{},"codec":{},"codec_name":"#,
+      self,
+      u64::from(self.version()),
+      self.codec(),
+    );
+    write_name(&mut record, self.codec_name());
+    record.push_str(r#","digest":""#);
+    for byte in hash.digest() {
+      let _ = write!(record, "{:02x}", byte);
+    }
+    let _ = write!(record, r#"","hash":{},"hash_name":"#, hash.code());
+    write_name(&mut record, self.hash_name());
+    let _ =
+      write!(record, r#","record_version":{}}}"#, AUDIT_RECORD_VERSION);
+    record
+  }
+}
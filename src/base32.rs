@@ -0,0 +1,164 @@
+//! A specialized base32 codec for the default CIDv1 string form.
+//!
+//! The generic multibase encoder always allocates a new `String`. This one
+//! writes RFC4648 lower case base32 without padding straight into any
+//! `fmt::Write`, which keeps `Display` of CIDv1 free of allocations.
+//!
+//! `cargo bench --bench base32` compares it with the multibase encoder.
+use sp_std::{fmt, str, vec::Vec};
+
+use alloc::string::String;
+
+/// The RFC4648 base32 alphabet, lower case.
+const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// Number of characters buffered before they are flushed to the writer, a
+/// multiple of the 8 symbols of a group.
+const CHUNK: usize = 64;
+
+/// Length of the encoded output for `len` bytes of input.
+pub(crate) const fn encoded_len(len: usize) -> usize {
+  (len * 8).div_ceil(5)
+}
+
+/// Writes the 8 symbols of a group of 5 bytes into `out`.
+fn encode_group(group: [u8; 5], out: &mut [u8]) {
+  let [a, b, c, d, e] = group;
+  let bits = u64::from_be_bytes([0, 0, 0, a, b, c, d, e]);
+  for (i, symbol) in out.iter_mut().enumerate() {
+    *symbol = ALPHABET[(bits >> (35 - i * 5)) as usize & 0x1f];
+  }
+}
+
+/// Writes `input` as lower case base32 without padding.
+pub(crate) fn encode_lower<W: fmt::Write>(
+  input: &[u8],
+  w: &mut W,
+) -> fmt::Result {
+  let mut out = [0u8; CHUNK];
+  let mut pos = 0;
+  // Full groups are copied with a fixed length, only the last one varies
+  let mut blocks = input.chunks_exact(5);
+  for block in &mut blocks {
+    let mut group = [0u8; 5];
+    group.copy_from_slice(block);
+    encode_group(group, &mut out[pos..pos + 8]);
+    pos += 8;
+    if pos == CHUNK {
+      w.write_str(as_str(&out))?;
+      pos = 0;
+    }
+  }
+  let rest = blocks.remainder();
+  if !rest.is_empty() {
+    let mut group = [0u8; 5];
+    group[..rest.len()].copy_from_slice(rest);
+    // The symbols past the input are written but not flushed
+    encode_group(group, &mut out[pos..pos + 8]);
+    pos += encoded_len(rest.len());
+  }
+  w.write_str(as_str(&out[..pos]))
+}
+
+/// Appends `input` as lower case base32 without padding to `out`.
+pub(crate) fn push_lower(input: &[u8], out: &mut String) {
+  // Writing to a `String` never fails.
+  let _ = encode_lower(input, out);
+}
+
+/// The value of every byte in the alphabet, [`NO_SYMBOL`] for the others.
+const VALUES: [u8; 256] = {
+  let mut values = [NO_SYMBOL; 256];
+  let mut i = 0;
+  while i < ALPHABET.len() {
+    values[ALPHABET[i] as usize] = i as u8;
+    i += 1;
+  }
+  values
+};
+
+/// The value of bytes that aren't in the alphabet.
+const NO_SYMBOL: u8 = 0xff;
+
+/// Decodes lower case base32 without padding.
+///
+/// Returns `None` if `input` contains characters outside of the alphabet, has
+/// an impossible length or non-zero trailing bits.
+pub(crate) fn decode_lower(input: &str) -> Option<Vec<u8>> {
+  let mut out = Vec::with_capacity(input.len() * 5 / 8);
+  decode_lower_into(input, &mut out).then_some(out)
+}
+
+/// Decodes lower case base32 without padding like [`decode_lower`], into
+/// `out`, which is cleared first so it can be reused as a scratch buffer.
+///
+/// Returns whether `input` was valid.
+pub(crate) fn decode_lower_into(input: &str, out: &mut Vec<u8>) -> bool {
+  out.clear();
+  if matches!(input.len() % 8, 1 | 3 | 6) {
+    return false;
+  }
+  let input = input.as_bytes();
+  // Blocks of 8 characters are 5 whole bytes
+  let mut blocks = input.chunks_exact(8);
+  for block in &mut blocks {
+    let mut bits = 0u64;
+    for c in block {
+      let value = VALUES[usize::from(*c)];
+      if value == NO_SYMBOL {
+        return false;
+      }
+      bits = bits << 5 | u64::from(value);
+    }
+    out.extend_from_slice(&bits.to_be_bytes()[3..]);
+  }
+  let mut bits = 0u16;
+  let mut n = 0;
+  for c in blocks.remainder() {
+    let value = VALUES[usize::from(*c)];
+    if value == NO_SYMBOL {
+      return false;
+    }
+    bits = bits << 5 | u16::from(value);
+    n += 5;
+    if n >= 8 {
+      n -= 8;
+      out.push((bits >> n) as u8);
+      bits &= (1 << n) - 1;
+    }
+  }
+  bits == 0
+}
+
+fn as_str(ascii: &[u8]) -> &str {
+  // Only ever contains characters of `ALPHABET`.
+  str::from_utf8(ascii).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::{string::String, vec::Vec};
+  use multibase::Base;
+
+  #[test]
+  fn matches_multibase() {
+    let data: Vec<u8> = (0..=255u8).rev().collect();
+    for len in 0..data.len() {
+      let mut out = String::new();
+      super::encode_lower(&data[..len], &mut out).unwrap();
+      assert_eq!(out, Base::Base32Lower.encode(&data[..len]));
+      assert_eq!(out.len(), super::encoded_len(len));
+      assert_eq!(super::decode_lower(&out).unwrap(), &data[..len]);
+    }
+  }
+
+  #[test]
+  fn rejects_invalid() {
+    assert_eq!(super::decode_lower("a"), None);
+    assert_eq!(super::decode_lower("AE"), None);
+    assert_eq!(super::decode_lower("a1"), None);
+    // `ab` has non-zero trailing bits.
+    assert_eq!(super::decode_lower("ab"), None);
+    assert_eq!(super::decode_lower("aa").as_deref(), Some(&[0u8][..]));
+  }
+}
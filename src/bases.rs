@@ -0,0 +1,693 @@
+//! Multibase encoding and decoding of binary CIDs.
+//!
+//! The canonical bases, Base32Lower for CIDv1 and Base58Btc for CIDv0, are
+//! always available, as are Base32Upper for QR codes and Base36 for IPNS
+//! names. Every other base of the multibase table has a feature, e.g.
+//! `base64` or `base32-variants`, and the `all-bases` feature, enabled by
+//! default, turns them all on. The codecs and alphabets of disabled bases
+//! are never referenced, so size sensitive builds such as runtime Wasm
+//! blobs only carry the bases they select.
+//!
+//! The bases are encoded and decoded with the codecs of this module, only
+//! Base256Emoji uses the ones of the multibase crate.
+use sp_std::{fmt, str, vec::Vec};
+
+use alloc::string::String;
+use multibase::Base;
+
+use crate::{
+  base32,
+  cid::MAX_LEN,
+  error::{Error, Result},
+};
+
+/// The Base58 bitcoin alphabet.
+const BASE58_BTC: &str =
+  "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// The lower case Base36 alphabet.
+const BASE36: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+/// The Base58 flickr alphabet.
+#[cfg(feature = "base58-flickr")]
+const BASE58_FLICKR: &str =
+  "123456789abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// The Base45 alphabet.
+#[cfg(feature = "base45")]
+const BASE45: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// How the symbols of a base map to bytes.
+#[derive(Clone, Copy)]
+pub(crate) enum Kind {
+  /// The symbols are the bytes.
+  #[cfg_attr(not(feature = "identity-base"), allow(dead_code))]
+  Identity,
+  /// Every symbol is a fixed number of bits, optionally padded with `=` to a
+  /// multiple of a number of symbols.
+  Bits {
+    /// The bits per symbol.
+    bits: u32,
+    /// The padded group size, if the base is padded.
+    group: Option<usize>,
+  },
+  /// The symbols are the digits of a big integer.
+  BigInt(u32),
+  /// Groups of three symbols are two bytes.
+  #[cfg_attr(not(feature = "base45"), allow(dead_code))]
+  Base45,
+}
+
+/// Returns whether the codec of `base` is compiled in, see the module
+/// documentation.
+pub(crate) fn is_enabled(base: Base) -> bool {
+  match base {
+    Base::Base32Lower
+    | Base::Base32Upper
+    | Base::Base36Lower
+    | Base::Base36Upper
+    | Base::Base58Btc => true,
+    Base::Identity => cfg!(feature = "identity-base"),
+    Base::Base2 => cfg!(feature = "base2"),
+    Base::Base8 => cfg!(feature = "base8"),
+    Base::Base10 => cfg!(feature = "base10"),
+    Base::Base16Lower | Base::Base16Upper => cfg!(feature = "base16"),
+    Base::Base32PadLower
+    | Base::Base32PadUpper
+    | Base::Base32HexLower
+    | Base::Base32HexUpper
+    | Base::Base32HexPadLower
+    | Base::Base32HexPadUpper
+    | Base::Base32Z => cfg!(feature = "base32-variants"),
+    Base::Base45 => cfg!(feature = "base45"),
+    Base::Base58Flickr => cfg!(feature = "base58-flickr"),
+    Base::Base64 | Base::Base64Pad | Base::Base64Url | Base::Base64UrlPad => {
+      cfg!(feature = "base64")
+    }
+    Base::Base256Emoji => cfg!(feature = "base256emoji"),
+  }
+}
+
+/// Returns how the symbols of `base` map to bytes, and its alphabet.
+///
+/// The alphabets of bases with a lower and an upper case variant are lower
+/// case. Fails for disabled bases and Base256Emoji.
+pub(crate) fn kind(base: Base) -> Result<(Kind, &'static str)> {
+  let bits = |bits, group| Kind::Bits { bits, group };
+  let kind = match base {
+    #[cfg(feature = "identity-base")]
+    Base::Identity => (Kind::Identity, ""),
+    #[cfg(feature = "base2")]
+    Base::Base2 => (bits(1, None), "01"),
+    #[cfg(feature = "base8")]
+    Base::Base8 => (bits(3, None), "01234567"),
+    #[cfg(feature = "base10")]
+    Base::Base10 => (Kind::BigInt(10), "0123456789"),
+    #[cfg(feature = "base16")]
+    Base::Base16Lower | Base::Base16Upper => {
+      (bits(4, None), "0123456789abcdef")
+    }
+    Base::Base32Lower | Base::Base32Upper => {
+      (bits(5, None), "abcdefghijklmnopqrstuvwxyz234567")
+    }
+    #[cfg(feature = "base32-variants")]
+    Base::Base32PadLower | Base::Base32PadUpper => {
+      (bits(5, Some(8)), "abcdefghijklmnopqrstuvwxyz234567")
+    }
+    #[cfg(feature = "base32-variants")]
+    Base::Base32HexLower | Base::Base32HexUpper => {
+      (bits(5, None), "0123456789abcdefghijklmnopqrstuv")
+    }
+    #[cfg(feature = "base32-variants")]
+    Base::Base32HexPadLower | Base::Base32HexPadUpper => {
+      (bits(5, Some(8)), "0123456789abcdefghijklmnopqrstuv")
+    }
+    #[cfg(feature = "base32-variants")]
+    Base::Base32Z => (bits(5, None), "ybndrfg8ejkmcpqxot1uwisza345h769"),
+    Base::Base36Lower | Base::Base36Upper => (Kind::BigInt(36), BASE36),
+    #[cfg(feature = "base45")]
+    Base::Base45 => (Kind::Base45, BASE45),
+    #[cfg(feature = "base58-flickr")]
+    Base::Base58Flickr => (Kind::BigInt(58), BASE58_FLICKR),
+    Base::Base58Btc => (Kind::BigInt(58), BASE58_BTC),
+    #[cfg(feature = "base64")]
+    Base::Base64 | Base::Base64Pad => (
+      bits(6, if base == Base::Base64Pad { Some(4) } else { None }),
+      "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+    ),
+    #[cfg(feature = "base64")]
+    Base::Base64Url | Base::Base64UrlPad => (
+      bits(6, if base == Base::Base64UrlPad { Some(4) } else { None }),
+      "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+    ),
+    // Emoji have varying UTF-8 lengths
+    _ => return Err(Error::UnsupportedBase(base)),
+  };
+  Ok(kind)
+}
+
+/// Returns the value of a symbol that was checked by [`is_alphabet_char`],
+/// whose case is then either the case of the alphabet or ignored.
+pub(crate) fn value(alphabet: &str, byte: u8) -> Option<u32> {
+  let position = |byte| alphabet.bytes().position(|a| a == byte);
+  let value = position(byte)
+    .or_else(|| position(byte.to_ascii_lowercase()))
+    .or_else(|| position(byte.to_ascii_uppercase()))?;
+  Some(value as u32)
+}
+
+/// Encodes `bytes` as Base58Btc, without the multibase prefix.
+pub(crate) fn encode_base58btc(bytes: &[u8]) -> String {
+  base_x::encode(BASE58_BTC, bytes)
+}
+
+/// Writes `bytes` as Base58Btc, without the multibase prefix and without
+/// allocating.
+pub(crate) fn write_base58btc<W: fmt::Write>(
+  bytes: &[u8],
+  w: &mut W,
+) -> fmt::Result {
+  write_base_x(BASE58_BTC, bytes, w)
+}
+
+/// Writes `bytes` as a big integer in the digits of `alphabet`, where every
+/// leading zero byte is a zero digit.
+fn write_base_x<W: fmt::Write>(
+  alphabet: &str,
+  bytes: &[u8],
+  w: &mut W,
+) -> fmt::Result {
+  let alphabet = alphabet.as_bytes();
+  let mut digits = [0u8; 3 * MAX_LEN];
+  let (zeros, len) = base_x_digits(alphabet.len() as u32, bytes, &mut digits);
+  for _ in 0..zeros {
+    w.write_char(char::from(alphabet[0]))?;
+  }
+  for digit in digits[..len.min(digits.len())].iter().rev() {
+    w.write_char(char::from(alphabet[usize::from(*digit)]))?;
+  }
+  Ok(())
+}
+
+/// Writes `bytes` in `base`, without the multibase prefix.
+///
+/// Only Base256Emoji allocates. Bytes that can't be encoded in `base`, see
+/// [`check`], fail with [`fmt::Error`].
+pub(crate) fn write_encoded<W: fmt::Write>(
+  base: Base,
+  bytes: &[u8],
+  w: &mut W,
+) -> fmt::Result {
+  check(base, bytes).map_err(|_| fmt::Error)?;
+  let upper = matches!(
+    base,
+    Base::Base16Upper
+      | Base::Base32Upper
+      | Base::Base32PadUpper
+      | Base::Base32HexUpper
+      | Base::Base32HexPadUpper
+      | Base::Base36Upper
+  );
+  if upper {
+    write_lower(lower_variant(base), bytes, &mut Uppercase(w))
+  } else {
+    write_lower(base, bytes, w)
+  }
+}
+
+/// Writes `bytes` in `base` with the alphabet of [`kind`].
+fn write_lower<W: fmt::Write>(
+  base: Base,
+  bytes: &[u8],
+  w: &mut W,
+) -> fmt::Result {
+  let (kind, alphabet) = match kind(base) {
+    Ok(kind) => kind,
+    Err(_) => {
+      return w.write_str(&encode(base, bytes).map_err(|_| fmt::Error)?);
+    }
+  };
+  let symbol = |value: u32| char::from(alphabet.as_bytes()[value as usize]);
+  match kind {
+    Kind::Identity => {
+      w.write_str(str::from_utf8(bytes).map_err(|_| fmt::Error)?)
+    }
+    Kind::Bits { bits, group } => {
+      let mut acc = 0u32;
+      let mut acc_len = 0;
+      let mut symbols = 0;
+      for byte in bytes {
+        acc = (acc << 8) | u32::from(*byte);
+        acc_len += 8;
+        while acc_len >= bits {
+          acc_len -= bits;
+          w.write_char(symbol((acc >> acc_len) & ((1 << bits) - 1)))?;
+          symbols += 1;
+        }
+        acc &= (1 << acc_len) - 1;
+      }
+      if acc_len > 0 {
+        w.write_char(symbol((acc << (bits - acc_len)) & ((1 << bits) - 1)))?;
+        symbols += 1;
+      }
+      if let Some(group) = group {
+        for _ in 0..(group - symbols % group) % group {
+          w.write_char('=')?;
+        }
+      }
+      Ok(())
+    }
+    Kind::BigInt(_) => write_base_x(alphabet, bytes, w),
+    Kind::Base45 => bytes.chunks(2).try_for_each(|pair| {
+      let n = pair.iter().fold(0, |n, byte| n * 256 + u32::from(*byte));
+      w.write_char(symbol(n % 45))?;
+      w.write_char(symbol(n / 45 % 45))?;
+      if pair.len() == 2 {
+        w.write_char(symbol(n / (45 * 45)))?;
+      }
+      Ok(())
+    }),
+  }
+}
+
+/// Returns the lower case variant of an upper case base.
+fn lower_variant(base: Base) -> Base {
+  match base {
+    Base::Base16Upper => Base::Base16Lower,
+    Base::Base32Upper => Base::Base32Lower,
+    Base::Base32PadUpper => Base::Base32PadLower,
+    Base::Base32HexUpper => Base::Base32HexLower,
+    Base::Base32HexPadUpper => Base::Base32HexPadLower,
+    Base::Base36Upper => Base::Base36Lower,
+    base => base,
+  }
+}
+
+/// Writes ASCII letters upper case.
+struct Uppercase<'a, W>(&'a mut W);
+
+impl<W: fmt::Write> fmt::Write for Uppercase<'_, W> {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    s.chars().try_for_each(|c| self.write_char(c))
+  }
+
+  fn write_char(&mut self, c: char) -> fmt::Result {
+    self.0.write_char(c.to_ascii_uppercase())
+  }
+}
+
+/// Checks that `bytes` can be encoded in `base`.
+///
+/// The identity base can only carry bytes that are valid UTF-8.
+pub(crate) fn check(base: Base, bytes: &[u8]) -> Result<()> {
+  match base {
+    _ if !is_enabled(base) => Err(Error::UnsupportedBase(base)),
+    Base::Identity if str::from_utf8(bytes).is_err() => {
+      Err(Error::UnsupportedBase(base))
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Encodes `bytes` in `base`, without the multibase prefix.
+pub(crate) fn encode(base: Base, bytes: &[u8]) -> Result<String> {
+  check(base, bytes)?;
+  match base {
+    Base::Base32Lower => {
+      let mut s = String::with_capacity(base32::encoded_len(bytes.len()));
+      base32::push_lower(bytes, &mut s);
+      Ok(s)
+    }
+    Base::Base32Upper => {
+      let mut s = encode(Base::Base32Lower, bytes)?;
+      s.make_ascii_uppercase();
+      Ok(s)
+    }
+    Base::Base36Lower => Ok(base_x::encode(BASE36, bytes)),
+    Base::Base36Upper => {
+      let mut s = base_x::encode(BASE36, bytes);
+      s.make_ascii_uppercase();
+      Ok(s)
+    }
+    Base::Base58Btc => Ok(encode_base58btc(bytes)),
+    #[cfg(feature = "base256emoji")]
+    Base::Base256Emoji => Ok(base.encode(bytes)),
+    _ => {
+      let mut s = String::new();
+      write_encoded(base, bytes, &mut s)
+        .map_err(|_| Error::UnsupportedBase(base))?;
+      Ok(s)
+    }
+  }
+}
+
+/// Appends `bytes` encoded in `base` to `out`, without the multibase prefix.
+///
+/// Unlike [`encode`] nothing is allocated besides growing `out`, except for
+/// Base256Emoji. Nothing is appended if the bytes can't be encoded in `base`.
+pub(crate) fn encode_into(
+  base: Base,
+  bytes: &[u8],
+  out: &mut String,
+) -> Result<()> {
+  check(base, bytes)?;
+  if base == Base::Base32Lower {
+    base32::push_lower(bytes, out);
+    return Ok(());
+  }
+  write_encoded(base, bytes, out).map_err(|_| Error::UnsupportedBase(base))
+}
+
+/// Returns the length in bytes of the string `bytes` encode to in `base`,
+/// without the multibase prefix.
+///
+/// The length is computed without encoding, except for Base256Emoji, whose
+/// characters have varying UTF-8 lengths.
+pub(crate) fn encoded_len(base: Base, bytes: &[u8]) -> Result<usize> {
+  check(base, bytes)?;
+  let len = bytes.len();
+  let encoded = match base {
+    Base::Identity => len,
+    Base::Base2 => len * 8,
+    Base::Base8 => (len * 8).div_ceil(3),
+    Base::Base10 => base_x_len(10, bytes),
+    Base::Base16Lower | Base::Base16Upper => len * 2,
+    Base::Base32PadLower
+    | Base::Base32PadUpper
+    | Base::Base32HexPadLower
+    | Base::Base32HexPadUpper => len.div_ceil(5) * 8,
+    Base::Base32Lower
+    | Base::Base32Upper
+    | Base::Base32HexLower
+    | Base::Base32HexUpper
+    | Base::Base32Z => base32::encoded_len(len),
+    Base::Base36Lower | Base::Base36Upper => base_x_len(36, bytes),
+    Base::Base45 => len / 2 * 3 + len % 2 * 2,
+    Base::Base58Flickr | Base::Base58Btc => base_x_len(58, bytes),
+    Base::Base64Pad | Base::Base64UrlPad => len.div_ceil(3) * 4,
+    Base::Base64 | Base::Base64Url => (len * 8).div_ceil(6),
+    Base::Base256Emoji => encode(base, bytes)?.len(),
+  };
+  Ok(encoded)
+}
+
+/// Returns the number of digits of `bytes` in a big integer base of `radix`,
+/// where every leading zero byte is encoded as a zero digit.
+fn base_x_len(radix: u32, bytes: &[u8]) -> usize {
+  // Base 10 needs the most digits, less than 2.5 per byte
+  let mut digits = [0u8; 3 * MAX_LEN];
+  let (zeros, len) = base_x_digits(radix, bytes, &mut digits);
+  zeros + len
+}
+
+/// Converts `bytes` to a big integer base of `radix`, with the least
+/// significant digit first, returning the number of leading zero bytes and
+/// the number of digits.
+///
+/// Digits that don't fit into `digits` are counted but not stored.
+fn base_x_digits(
+  radix: u32,
+  bytes: &[u8],
+  digits: &mut [u8; 3 * MAX_LEN],
+) -> (usize, usize) {
+  let zeros = bytes.iter().take_while(|b| **b == 0).count();
+  let mut len = 0;
+  for byte in &bytes[zeros..] {
+    let mut carry = u32::from(*byte);
+    for digit in &mut digits[..len] {
+      carry += u32::from(*digit) << 8;
+      *digit = (carry % radix) as u8;
+      carry /= radix;
+    }
+    while carry > 0 {
+      if let Some(digit) = digits.get_mut(len) {
+        *digit = (carry % radix) as u8;
+      }
+      len += 1;
+      carry /= radix;
+    }
+  }
+  (zeros, len)
+}
+
+/// Returns whether `c` is accepted by the decoder of `base`.
+///
+/// Like the multibase decoders, the decoders of the Base16, Base32, Base36
+/// and Base45 alphabets ignore case.
+pub(crate) fn is_alphabet_char(base: Base, c: char) -> bool {
+  let lower = c.to_ascii_lowercase();
+  match base {
+    Base::Identity | Base::Base256Emoji => true,
+    Base::Base2 => matches!(c, '0' | '1'),
+    Base::Base8 => matches!(c, '0'..='7'),
+    Base::Base10 => c.is_ascii_digit(),
+    Base::Base16Lower | Base::Base16Upper => c.is_ascii_hexdigit(),
+    Base::Base32Lower | Base::Base32Upper => {
+      matches!(lower, 'a'..='z' | '2'..='7')
+    }
+    Base::Base32PadLower | Base::Base32PadUpper => {
+      matches!(lower, 'a'..='z' | '2'..='7' | '=')
+    }
+    Base::Base32HexLower | Base::Base32HexUpper => {
+      matches!(lower, '0'..='9' | 'a'..='v')
+    }
+    Base::Base32HexPadLower | Base::Base32HexPadUpper => {
+      matches!(lower, '0'..='9' | 'a'..='v' | '=')
+    }
+    Base::Base32Z => "ybndrfg8ejkmcpqxot1uwisza345h769".contains(c),
+    Base::Base36Lower | Base::Base36Upper => lower.is_ascii_alphanumeric(),
+    Base::Base45 => lower.is_ascii_alphanumeric() || " $%*+-./:".contains(c),
+    // Both Base58 alphabets leave out `0`, `I`, `O` and `l`
+    Base::Base58Flickr | Base::Base58Btc => BASE58_BTC.contains(c),
+    Base::Base64 => c.is_ascii_alphanumeric() || matches!(c, '+' | '/'),
+    Base::Base64Pad => {
+      c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '=')
+    }
+    Base::Base64Url => c.is_ascii_alphanumeric() || matches!(c, '-' | '_'),
+    Base::Base64UrlPad => {
+      c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '=')
+    }
+  }
+}
+
+/// Returns whether the letters of a multibase string are in the case of its
+/// base, if it is one of the base32 bases.
+///
+/// Strings of other bases, and ones without a valid prefix, are left to the
+/// decoder and always pass.
+pub(crate) fn is_canonical_case(input: &str) -> bool {
+  let mut chars = input.chars();
+  let base = match chars.next().map(Base::from_code) {
+    Some(Ok(base)) => base,
+    _ => return true,
+  };
+  let body = chars.as_str();
+  match base {
+    Base::Base32Lower
+    | Base::Base32PadLower
+    | Base::Base32HexLower
+    | Base::Base32HexPadLower => !body.bytes().any(|b| b.is_ascii_uppercase()),
+    Base::Base32Upper
+    | Base::Base32PadUpper
+    | Base::Base32HexUpper
+    | Base::Base32HexPadUpper => !body.bytes().any(|b| b.is_ascii_lowercase()),
+    _ => true,
+  }
+}
+
+/// Encodes `bytes` in `base`, with the multibase prefix.
+pub(crate) fn encode_prefixed(base: Base, bytes: &[u8]) -> Result<String> {
+  let encoded = encode(base, bytes)?;
+  let mut s = String::with_capacity(4 + encoded.len());
+  s.push(base.code());
+  s.push_str(&encoded);
+  Ok(s)
+}
+
+/// Decodes `input` encoded in `base`, without the multibase prefix.
+pub(crate) fn decode(base: Base, input: &str) -> Result<Vec<u8>> {
+  let invalid = Error::MultibaseError(multibase::Error::InvalidBaseString);
+  match base {
+    // Like the multibase decoders, both ignore case. Only strings with
+    // upper case letters are copied.
+    Base::Base32Lower | Base::Base32Upper => {
+      if input.bytes().any(|b| b.is_ascii_uppercase()) {
+        base32::decode_lower(&input.to_ascii_lowercase()).ok_or(invalid)
+      } else {
+        base32::decode_lower(input).ok_or(invalid)
+      }
+    }
+    Base::Base36Lower | Base::Base36Upper => {
+      base_x::decode(BASE36, &input.to_ascii_lowercase()).map_err(|_| invalid)
+    }
+    Base::Base58Btc => base_x::decode(BASE58_BTC, input).map_err(|_| invalid),
+    #[cfg(feature = "base256emoji")]
+    Base::Base256Emoji => Ok(base.decode(input)?),
+    _ if is_enabled(base) => decode_symbols(base, input).ok_or(invalid),
+    _ => Err(Error::UnsupportedBase(base)),
+  }
+}
+
+/// Decodes `input` in a base of [`kind`], without the multibase prefix,
+/// `None` if it isn't valid in the base.
+///
+/// Like the multibase decoders, the unused bits of a partial last symbol
+/// have to be zero, and padded bases require the padding.
+fn decode_symbols(base: Base, input: &str) -> Option<Vec<u8>> {
+  let (kind, alphabet) = kind(base).ok()?;
+  if let Kind::Identity = kind {
+    return Some(input.as_bytes().to_vec());
+  }
+  let body = input.trim_end_matches('=');
+  let padding = input.len() - body.len();
+  let values = body
+    .bytes()
+    .map(|byte| {
+      let c = char::from(byte);
+      if !byte.is_ascii() || c == '=' || !is_alphabet_char(base, c) {
+        return None;
+      }
+      value(alphabet, byte)
+    })
+    .collect::<Option<Vec<_>>>()?;
+  if padding > 0 && !matches!(kind, Kind::Bits { group: Some(_), .. }) {
+    return None;
+  }
+  let mut out = Vec::with_capacity(values.len());
+  match kind {
+    Kind::Identity => {}
+    Kind::Bits { bits, group } => {
+      if let Some(group) = group {
+        if padding >= group || (values.len() + padding) % group != 0 {
+          return None;
+        }
+      }
+      let mut acc = 0u32;
+      let mut acc_len = 0;
+      for value in values {
+        acc = (acc << bits) | value;
+        acc_len += bits;
+        if acc_len >= 8 {
+          acc_len -= 8;
+          out.push((acc >> acc_len) as u8);
+          acc &= (1 << acc_len) - 1;
+        }
+      }
+      // Leftover bits must be the zero bits of a partial last symbol
+      if acc_len >= bits || acc != 0 {
+        return None;
+      }
+    }
+    Kind::BigInt(radix) => {
+      // Leading zero digits are leading zero bytes
+      let zeros = values.iter().take_while(|value| **value == 0).count();
+      for value in &values[zeros..] {
+        let mut carry = *value;
+        for byte in &mut out {
+          carry += u32::from(*byte) * radix;
+          *byte = carry as u8;
+          carry >>= 8;
+        }
+        while carry > 0 {
+          out.push(carry as u8);
+          carry >>= 8;
+        }
+      }
+      out.resize(out.len() + zeros, 0);
+      out.reverse();
+    }
+    Kind::Base45 => {
+      for group in values.chunks(3) {
+        let n = group.iter().rev().fold(0, |n, value| n * 45 + value);
+        match group.len() {
+          3 if n <= 0xffff => out.extend_from_slice(&[(n >> 8) as u8, n as u8]),
+          2 if n <= 0xff => out.push(n as u8),
+          _ => return None,
+        }
+      }
+    }
+  }
+  Some(out)
+}
+
+/// Decodes a hex string of either case, without a multibase prefix, `None`
+/// if it isn't one.
+///
+/// Unlike [`decode`] this works without the `base16` feature, for inputs
+/// that aren't multibase strings.
+pub(crate) fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+  if hex.len() % 2 != 0 {
+    return None;
+  }
+  hex
+    .as_bytes()
+    .chunks(2)
+    .map(|pair| {
+      let high = char::from(pair[0]).to_digit(16)?;
+      let low = char::from(pair[1]).to_digit(16)?;
+      Some((high * 16 + low) as u8)
+    })
+    .collect()
+}
+
+/// Decodes a multibase string, returning its base and the decoded bytes.
+pub(crate) fn decode_prefixed(input: &str) -> Result<(Base, Vec<u8>)> {
+  let code = match input.chars().next() {
+    Some(code) => code,
+    None => {
+      return Err(Error::MultibaseError(multibase::Error::InvalidBaseString));
+    }
+  };
+  let base = Base::from_code(code)?;
+  let bytes = decode(base, &input[code.len_utf8()..])?;
+  Ok((base, bytes))
+}
+
+#[cfg(all(test, feature = "all-bases"))]
+mod tests {
+  use alloc::{format, vec, vec::Vec};
+  use multibase::Base;
+
+  use super::{check, decode, encode};
+  use crate::weights::BASES;
+
+  #[test]
+  fn matches_multibase() {
+    let inputs: Vec<Vec<u8>> = (0..12)
+      .map(|len| (0..len).map(|i| (i * 37 + len) as u8).collect())
+      .chain([vec![0, 0, 1], vec![0xff; 7], vec![]])
+      .collect();
+    for base in BASES {
+      for bytes in inputs.iter().filter(|bytes| check(base, bytes).is_ok()) {
+        let s = base.encode(bytes);
+        assert_eq!(decode(base, &s).ok().as_ref(), Some(bytes), "{:?}", base);
+        assert_eq!(encode(base, bytes).unwrap(), s, "{:?}", base);
+        // Truncated, extended and corrupted strings fail like in multibase
+        let mut variants: Vec<_> = (0..s.len())
+          .filter(|len| s.is_char_boundary(*len))
+          .map(|len| s[..len].into())
+          .chain(["=", "==", "0", "a", "Z", "!"].map(|c| format!("{}{}", s, c)))
+          .collect();
+        variants.push(s.to_ascii_uppercase());
+        variants.push(s.to_ascii_lowercase());
+        for s in &variants {
+          let expected = base.decode(s).ok();
+          // Multibase truncates the last pair of Base45 symbols to a byte
+          if base == Base::Base45 && s.len() % 3 == 2 {
+            let decoded = decode(base, s).ok();
+            assert!(decoded.is_none() || decoded == expected, "{}", s);
+            continue;
+          }
+          assert_eq!(decode(base, s).ok(), expected, "{:?} {}", base, s);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn rejects_large_base45_pairs() {
+    // 15 + 16 * 45 doesn't fit into a byte
+    assert!(decode(Base::Base45, "FG").is_err());
+    assert_eq!(decode(Base::Base45, "U5").unwrap(), vec![0xff]);
+  }
+}
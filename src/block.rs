@@ -0,0 +1,144 @@
+//! Blocks read together with their CID.
+//!
+//! CAR files and several block exchange protocols store a block after its
+//! CID, in a frame prefixed with the length of both. [`Cid::read_block`]
+//! reads such a frame and checks the block against the CID in the same step,
+//! so importers can't forget to verify what they store.
+use alloc::{vec, vec::Vec};
+use sp_std::convert::TryFrom;
+
+use bytecursor::ByteCursor;
+use sp_multihash::{Size, U64};
+use unsigned_varint::encode as varint_encode;
+
+use crate::{
+  cid::Cid,
+  cid_ref::CidRef,
+  error::{Error, Result},
+  registry::{DefaultHashers, HasherRegistry},
+};
+
+/// A block with its CID, read by [`Cid::read_block`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Block<S: Size = U64> {
+  /// The CID of the block.
+  cid: Cid<S>,
+  /// The bytes of the block.
+  data: Vec<u8>,
+  /// Whether the block was hashed and matched the CID.
+  verified: bool,
+}
+
+impl<S: Size> Block<S> {
+  /// Returns the CID of the block.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Returns the bytes of the block.
+  pub fn data(&self) -> &[u8] {
+    &self.data
+  }
+
+  /// Returns whether the block was checked against its CID.
+  ///
+  /// Blocks whose hash function isn't known to the registry are returned
+  /// unchecked.
+  pub fn is_verified(&self) -> bool {
+    self.verified
+  }
+
+  /// Returns the CID and the bytes of the block.
+  pub fn into_parts(self) -> (Cid<S>, Vec<u8>) {
+    (self.cid, self.data)
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Reads a CID and its block from a frame prefixed with their length as
+  /// an unsigned varint, the sections of CAR files, and checks the block
+  /// with the hash functions of the sp-multihash code table.
+  ///
+  /// See [`Cid::read_block_with`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use bytecursor::ByteCursor;
+  /// use sp_cid::{Cid, Error};
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let mut w = ByteCursor::new(Vec::new());
+  /// cid.write_block(b"foo", &mut w).unwrap();
+  /// cid.write_block(b"bar", &mut w).unwrap();
+  ///
+  /// let mut r = ByteCursor::new(w.into_inner());
+  /// let block: sp_cid::Block = Cid::read_block(&mut r).unwrap();
+  /// assert_eq!(block.cid(), &cid);
+  /// assert_eq!(block.data(), b"foo");
+  /// assert!(block.is_verified());
+  /// let err = Cid::read_block(&mut r).unwrap_err();
+  /// assert_eq!(err, Error::DigestMismatch { offset: 40 });
+  /// ```
+  pub fn read_block(r: &mut ByteCursor) -> Result<Block<S>> {
+    Self::read_block_with(&DefaultHashers, r)
+  }
+
+  /// Reads a CID and its block from a frame prefixed with their length as
+  /// an unsigned varint, and checks the block with the hash functions of
+  /// `registry`.
+  ///
+  /// Byte offsets in errors are positions within the stream. Fails with
+  /// [`Error::DigestMismatch`] at the start of the frame if the block
+  /// doesn't match the CID, see [`Cid::verify_with`]. If the registry has
+  /// no hash function for the multihash code, the block is returned
+  /// unchecked, see [`Block::is_verified`]. Frames longer than the rest of
+  /// the stream fail before anything is allocated.
+  pub fn read_block_with<R: HasherRegistry + ?Sized>(
+    registry: &R,
+    r: &mut ByteCursor,
+  ) -> Result<Block<S>> {
+    let start = r.position() as usize;
+    let len = crate::varint_read_u64(r)?;
+    let body = r.position() as usize;
+    let remaining = r.get_ref().len().saturating_sub(body);
+    let len = match usize::try_from(len) {
+      Ok(len) if len <= remaining => len,
+      _ => return Err(Error::UnexpectedEof { offset: body }),
+    };
+    let mut data = vec![0u8; len];
+    if r.read_exact(&mut data).is_err() {
+      return Err(Error::UnexpectedEof { offset: body });
+    }
+    let (cid, rest) = CidRef::read(&data).map_err(|err| err.shifted(body))?;
+    let cid = cid.to_owned::<S>().map_err(|err| err.shifted(body))?;
+    let cid_len = len - rest.len();
+    // Move the block to the front instead of copying it
+    data.drain(..cid_len);
+    let verified = match cid.check_with(registry, &data) {
+      Some(true) => true,
+      Some(false) => return Err(Error::DigestMismatch { offset: start }),
+      None => false,
+    };
+    Ok(Block { cid, data, verified })
+  }
+
+  /// Writes the CID and `data` prefixed with their length as an unsigned
+  /// varint, see [`Cid::read_block`].
+  ///
+  /// The block isn't checked against the CID.
+  pub fn write_block(&self, data: &[u8], w: &mut ByteCursor) -> Result<()> {
+    let cid = self.to_bytes();
+    let mut len_buf = varint_encode::usize_buffer();
+    let prefix = varint_encode::usize(cid.len() + data.len(), &mut len_buf);
+    match w
+      .write_all(prefix)
+      .and_then(|_| w.write_all(&cid))
+      .and_then(|_| w.write_all(data))
+    {
+      Ok(_) => Ok(()),
+      Err(_) => Err(Error::WriteError),
+    }
+  }
+}
@@ -0,0 +1,189 @@
+//! A CID wrapper for FRAME storage.
+//!
+//! FRAME storage items require values with a known maximum encoded length.
+//! [`BoundedCid`] fixes the maximum digest length at compile time, checks it
+//! when it is built and decoded, and is SCALE encoded in the canonical binary
+//! form, so the maximum length of its encoding follows from the bound.
+use sp_std::{convert::TryFrom, fmt, marker::PhantomData, ops::Deref};
+
+use parity_scale_codec::{
+  Compact, CompactLen, Decode, Encode, EncodeLike, Error, Input,
+  MaxEncodedLen, Output,
+};
+use sp_multihash::{Size, U64};
+
+use crate::{
+  cid::Cid,
+  error,
+  scale::{decode_canonical, CanonicalCid},
+};
+
+/// A CID whose digest is at most `MaxHashLen` bytes long, for use as a
+/// storage value or key.
+///
+/// The SCALE encoding is the one of a [`CanonicalCid`], decoding fails for
+/// CIDs with longer digests.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{BoundedCid, Cid, Error};
+/// use sp_multihash::{typenum::U32, Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let bounded = BoundedCid::<U32>::new(cid).unwrap();
+/// assert_eq!(bounded.into_inner(), cid);
+///
+/// let long = Cid::new_v1(0x55, Code::Sha2_512.digest(b"foo"));
+/// let err = BoundedCid::<U32>::new(long).unwrap_err();
+/// assert_eq!(err, Error::DigestTooLarge { size: 64, max: 32 });
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedCid<MaxHashLen: Size, S: Size = U64> {
+  /// The checked CID.
+  cid: Cid<S>,
+  /// The bound on the digest length.
+  _max_hash_len: PhantomData<MaxHashLen>,
+}
+
+impl<MaxHashLen: Size, S: Size> BoundedCid<MaxHashLen, S> {
+  /// Wraps a CID, which fails with [`error::Error::DigestTooLarge`] if its
+  /// digest is longer than `MaxHashLen`.
+  pub fn new(cid: Cid<S>) -> error::Result<Self> {
+    let size = usize::from(cid.hash().size());
+    if size > MaxHashLen::USIZE {
+      let max = MaxHashLen::USIZE;
+      return Err(error::Error::DigestTooLarge { size, max });
+    }
+    Ok(Self { cid, _max_hash_len: PhantomData })
+  }
+
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> Deref for BoundedCid<MaxHashLen, S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> TryFrom<Cid<S>> for BoundedCid<MaxHashLen, S> {
+  type Error = error::Error;
+
+  fn try_from(cid: Cid<S>) -> error::Result<Self> {
+    Self::new(cid)
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> From<BoundedCid<MaxHashLen, S>> for Cid<S> {
+  fn from(bounded: BoundedCid<MaxHashLen, S>) -> Self {
+    bounded.cid
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> TryFrom<&[u8]> for BoundedCid<MaxHashLen, S> {
+  type Error = error::Error;
+
+  fn try_from(bytes: &[u8]) -> error::Result<Self> {
+    Cid::try_from(bytes).and_then(Self::new)
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> Encode for BoundedCid<MaxHashLen, S> {
+  fn size_hint(&self) -> usize {
+    Self::max_encoded_len()
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    self.cid.to_bytes().encode_to(dest)
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> EncodeLike for BoundedCid<MaxHashLen, S> {}
+
+impl<MaxHashLen: Size, S: Size> EncodeLike<CanonicalCid<S>>
+  for BoundedCid<MaxHashLen, S>
+{
+}
+
+impl<MaxHashLen: Size, S: Size> Decode for BoundedCid<MaxHashLen, S> {
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    let cid = decode_canonical(input, Cid::<MaxHashLen>::MAX_BINARY_LEN)?;
+    Self::new(cid).map_err(|_| "CID digest exceeds the bound".into())
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> MaxEncodedLen for BoundedCid<MaxHashLen, S> {
+  fn max_encoded_len() -> usize {
+    let len = Cid::<MaxHashLen>::MAX_BINARY_LEN;
+    Compact::<u32>::compact_len(&(len as u32)) + len
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<MaxHashLen: Size + 'static, S: Size + 'static> scale_info::TypeInfo
+  for BoundedCid<MaxHashLen, S>
+{
+  type Identity = CanonicalCid<S>;
+
+  fn type_info() -> scale_info::Type {
+    CanonicalCid::<S>::type_info()
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> fmt::Display for BoundedCid<MaxHashLen, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.cid, f)
+  }
+}
+
+impl<MaxHashLen: Size, S: Size> fmt::Debug for BoundedCid<MaxHashLen, S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sp_std::convert::TryFrom;
+
+  use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
+  use sp_multihash::{Code, MultihashDigest, U32, U64};
+
+  use super::BoundedCid;
+  use crate::{cid::Cid, error::Error, scale::CanonicalCid};
+
+  #[test]
+  fn max_encoded_len() {
+    assert_eq!(BoundedCid::<U32>::max_encoded_len(), 1 + 54);
+    assert_eq!(BoundedCid::<U64>::max_encoded_len(), 2 + 86);
+
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let bounded = BoundedCid::<U32>::new(cid).unwrap();
+    assert_eq!(bounded.encode(), CanonicalCid::new(cid).encode());
+    assert!(bounded.encode().len() <= BoundedCid::<U32>::max_encoded_len());
+  }
+
+  #[test]
+  fn checks_bound() {
+    let small = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let encoded = small.to_bytes().encode();
+    let bounded = BoundedCid::<U32>::decode(&mut &encoded[..]).unwrap();
+    assert_eq!(bounded.into_inner(), small);
+
+    let large = Cid::<U64>::new_v1(0x55, Code::Sha2_512.digest(b"foo"));
+    let encoded = large.to_bytes().encode();
+    assert!(BoundedCid::<U32>::decode(&mut &encoded[..]).is_err());
+    assert!(BoundedCid::<U64>::decode(&mut &encoded[..]).is_ok());
+
+    let err = Error::DigestTooLarge { size: 64, max: 32 };
+    assert_eq!(BoundedCid::<U32>::new(large).unwrap_err(), err);
+    let bytes = large.to_bytes();
+    assert_eq!(BoundedCid::<U32>::try_from(&bytes[..]).unwrap_err(), err);
+  }
+}
@@ -0,0 +1,106 @@
+//! Comparing CIDs by their multihash only.
+//!
+//! The same bytes can be linked with different codecs, e.g. as a raw block
+//! and wrapped in DAG-PB by older importers, and with CIDv0 and CIDv1.
+//! Storage accounting counts them once. [`ByDigest`] compares CIDs the way
+//! the blockstore sees them.
+use sp_std::{cmp::Ordering, fmt, hash, ops::Deref};
+
+use sp_multihash::Size;
+
+use crate::cid::Cid;
+
+/// A CID that is equal to, ordered and hashed like its multihash.
+///
+/// The version and codec are ignored, so a set of `ByDigest` keeps one CID of
+/// each block, the first one inserted.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{ByDigest, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+/// use std::collections::BTreeSet;
+///
+/// let hash = Code::Sha2_256.digest(b"foo");
+/// let mut blocks = BTreeSet::new();
+/// blocks.insert(ByDigest(Cid::new_v1(0x55, hash)));
+/// blocks.insert(ByDigest(Cid::new_v1(0x70, hash)));
+/// assert_eq!(blocks.len(), 1);
+/// ```
+#[derive(Clone)]
+pub struct ByDigest<S: Size>(pub Cid<S>);
+
+impl<S: Size> ByDigest<S> {
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.0
+  }
+}
+
+impl<S: Size> Deref for ByDigest<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.0
+  }
+}
+
+impl<S: Size> From<Cid<S>> for ByDigest<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self(cid)
+  }
+}
+
+impl<S: Size> From<ByDigest<S>> for Cid<S> {
+  fn from(by_digest: ByDigest<S>) -> Self {
+    by_digest.0
+  }
+}
+
+impl<S: Size> PartialEq for ByDigest<S> {
+  fn eq(&self, other: &Self) -> bool {
+    let (hash, other_hash) = (self.0.hash(), other.0.hash());
+    hash.code() == other_hash.code() && hash.digest() == other_hash.digest()
+  }
+}
+
+impl<S: Size> Eq for ByDigest<S> {}
+
+impl<S: Size> PartialOrd for ByDigest<S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// Orders by multihash code, then by digest.
+impl<S: Size> Ord for ByDigest<S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let (hash, other_hash) = (self.0.hash(), other.0.hash());
+    hash
+      .code()
+      .cmp(&other_hash.code())
+      .then_with(|| hash.digest().cmp(other_hash.digest()))
+  }
+}
+
+/// Hashes the multihash code and digest, like the `Hash` impl of [`Cid`]
+/// without the version and codec.
+impl<S: Size> hash::Hash for ByDigest<S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    self.0.hash().code().hash(state);
+    self.0.hash().digest().hash(state);
+  }
+}
+
+impl<S: Size> fmt::Display for ByDigest<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.0, f)
+  }
+}
+
+impl<S: Size> fmt::Debug for ByDigest<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.0, f)
+  }
+}
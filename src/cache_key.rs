@@ -0,0 +1,137 @@
+//! Keys and weights of CIDs in block caches.
+//!
+//! Caches such as `lru` or `moka` hash their keys on every access and weigh
+//! entries to bound their memory. Keying them by a CID hashes the whole
+//! digest each time, and the memory of a CID depends on its type. A
+//! [`CacheKey`] provides a 16 byte fingerprint to key by instead, and the
+//! heap memory of the CID.
+use sp_std::mem;
+
+use sp_multihash::Size;
+
+use crate::{
+  cid::{Cid, IDENTITY, MAX_LEN},
+  cid_bytes::CidBytes,
+  cid_dyn::CidDyn,
+  cid_ref::CidRef,
+  hashed::HashedCid,
+  key::{write_key, CidKey},
+};
+
+/// The FNV-1a 128 bit offset basis.
+const FNV_OFFSET: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+/// The FNV-1a 128 bit prime.
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+/// A CID used as a cache key.
+///
+/// # Example
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use sp_cid::{CacheKey, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let mut blocks = HashMap::new();
+/// blocks.insert(cid.fingerprint(), b"foo".to_vec());
+/// assert_eq!(blocks[&cid.fingerprint()], b"foo");
+/// assert_eq!(cid.heap_size(), 0);
+/// ```
+pub trait CacheKey {
+  /// Returns a 128 bit fingerprint of the CID.
+  ///
+  /// CIDs with a digest of at least 16 bytes use its first 16 bytes, mixed
+  /// with a hash of the version, codec and multihash code, so the digest
+  /// isn't hashed again. Other CIDs and identity multihashes, whose digest is
+  /// the data itself, use a hash of the whole CID. The hashes are FNV-1a,
+  /// which isn't keyed: caches of identity CIDs from untrusted input should
+  /// compare the CID on a hit.
+  fn fingerprint(&self) -> u128;
+
+  /// Returns the number of bytes the CID owns on the heap.
+  fn heap_size(&self) -> usize;
+
+  /// Returns the number of bytes of the CID in memory, for cache weighers.
+  fn weight(&self) -> usize
+  where
+    Self: Sized,
+  {
+    mem::size_of::<Self>() + self.heap_size()
+  }
+}
+
+/// Computes the FNV-1a 128 bit hash of `bytes`.
+fn fnv1a(bytes: &[u8]) -> u128 {
+  bytes.iter().fold(FNV_OFFSET, |hash, byte| {
+    (hash ^ u128::from(*byte)).wrapping_mul(FNV_PRIME)
+  })
+}
+
+/// Computes the fingerprint of a CID, see [`CacheKey::fingerprint`].
+fn fingerprint(key: &dyn CidKey) -> u128 {
+  let mut buf = [0u8; MAX_LEN];
+  let len = write_key(key, &mut buf);
+  let digest = key.digest();
+  match digest.get(..16) {
+    Some(head) if key.hash_code() != IDENTITY => {
+      let mut bytes = [0u8; 16];
+      bytes.copy_from_slice(head);
+      fnv1a(&buf[..len - digest.len()]) ^ u128::from_le_bytes(bytes)
+    }
+    _ => fnv1a(&buf[..len]),
+  }
+}
+
+impl<S: Size> CacheKey for Cid<S> {
+  fn fingerprint(&self) -> u128 {
+    fingerprint(self)
+  }
+
+  /// The digest is stored inline, so this is zero.
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl CacheKey for CidRef<'_> {
+  fn fingerprint(&self) -> u128 {
+    fingerprint(self)
+  }
+
+  /// The digest is borrowed, so this is zero.
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
+
+impl CacheKey for CidDyn {
+  fn fingerprint(&self) -> u128 {
+    fingerprint(self)
+  }
+
+  fn heap_size(&self) -> usize {
+    self.digest().len()
+  }
+}
+
+impl<S: Size> CacheKey for CidBytes<S> {
+  fn fingerprint(&self) -> u128 {
+    fingerprint(self.cid())
+  }
+
+  fn heap_size(&self) -> usize {
+    self.as_bytes().len()
+  }
+}
+
+impl<S: Size> CacheKey for HashedCid<S> {
+  fn fingerprint(&self) -> u128 {
+    fingerprint(self.cid())
+  }
+
+  fn heap_size(&self) -> usize {
+    0
+  }
+}
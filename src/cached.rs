@@ -0,0 +1,129 @@
+//! A CID wrapper that memoizes its string representation.
+//!
+//! Rendering a CID as a string means encoding it to bytes and then to a
+//! multibase string each time. Services that log or serve the same CIDs over
+//! and over can wrap them in a [`CachedCid`], which does this work once.
+use std::{cmp::Ordering, fmt, hash, ops::Deref, sync::OnceLock};
+
+use sp_multihash::Size;
+
+use crate::{cache_key::CacheKey, cid::Cid};
+
+/// A CID that computes its canonical string form at most once.
+///
+/// The string is computed on first use and then reused by
+/// [`CachedCid::as_str`] and the `Display` implementation. Comparisons and
+/// hashing only consider the wrapped CID.
+pub struct CachedCid<S: Size> {
+  /// The wrapped CID.
+  cid: Cid<S>,
+  /// The memoized canonical string.
+  string: OnceLock<String>,
+}
+
+impl<S: Size> CachedCid<S> {
+  /// Wraps a CID.
+  pub fn new(cid: Cid<S>) -> Self {
+    Self { cid, string: OnceLock::new() }
+  }
+
+  /// Returns the wrapped CID.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Unwraps the CID, dropping the cached string.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+
+  /// Returns the canonical string form of the CID, computing it on first use.
+  pub fn as_str(&self) -> &str {
+    self.string.get_or_init(|| self.cid.to_string())
+  }
+}
+
+impl<S: Size> Clone for CachedCid<S> {
+  fn clone(&self) -> Self {
+    Self { cid: self.cid.clone(), string: self.string.clone() }
+  }
+}
+
+impl<S: Size> Deref for CachedCid<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CachedCid<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self::new(cid)
+  }
+}
+
+impl<S: Size> From<CachedCid<S>> for Cid<S> {
+  fn from(cached: CachedCid<S>) -> Self {
+    cached.cid
+  }
+}
+
+impl<S: Size> CacheKey for CachedCid<S> {
+  fn fingerprint(&self) -> u128 {
+    self.cid.fingerprint()
+  }
+
+  /// Counts the string once it was computed.
+  fn heap_size(&self) -> usize {
+    self.string.get().map_or(0, String::capacity)
+  }
+}
+
+impl<S: Size> PartialEq for CachedCid<S> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cid == other.cid
+  }
+}
+
+impl<S: Size> Eq for CachedCid<S> {}
+
+impl<S: Size> PartialOrd for CachedCid<S>
+where
+  Cid<S>: Ord,
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<S: Size> Ord for CachedCid<S>
+where
+  Cid<S>: Ord,
+{
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.cid.cmp(&other.cid)
+  }
+}
+
+impl<S: Size> hash::Hash for CachedCid<S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    hash::Hash::hash(&self.cid, state)
+  }
+}
+
+impl<S: Size> fmt::Display for CachedCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if f.alternate() {
+      fmt::Display::fmt(&self.cid, f)
+    } else {
+      f.pad(self.as_str())
+    }
+  }
+}
+
+impl<S: Size> fmt::Debug for CachedCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
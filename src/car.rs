@@ -0,0 +1,144 @@
+//! Statistics of CAR files.
+//!
+//! Operators validating uploads before pinning want to know what a CAR file
+//! holds and whether its DAG is complete. [`stats`] reads the blocks of a
+//! CARv1 file in one pass, checks every block against its CID with
+//! [`Cid::read_block`] and counts them, holding only the CIDs in memory.
+//!
+//! The crate has no IPLD codecs, so the caller finds the links of a block,
+//! e.g. with a DAG-CBOR or DAG-PB decoder. The header, which holds the
+//! roots, is skipped without decoding it.
+use alloc::collections::BTreeSet;
+use sp_std::convert::TryFrom;
+
+use bytecursor::ByteCursor;
+use sp_multihash::Size;
+
+use crate::{
+  block::Block,
+  cid::{Cid, IDENTITY},
+  error::{Error, Result},
+  stats::CidStats,
+};
+
+/// The counts of a CAR file, see [`stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CarStats {
+  /// The number of blocks.
+  blocks: usize,
+  /// The number of distinct CIDs of the blocks.
+  unique_cids: usize,
+  /// The CIDs of all blocks by their parts.
+  cids: CidStats,
+  /// The number of distinct linked CIDs without a block.
+  dangling_links: usize,
+}
+
+impl CarStats {
+  /// Returns the number of blocks, including repeated blocks.
+  pub fn blocks(&self) -> usize {
+    self.blocks
+  }
+
+  /// Returns the number of distinct CIDs of the blocks.
+  pub fn unique_cids(&self) -> usize {
+    self.unique_cids
+  }
+
+  /// Returns the counts of the CIDs of all blocks, e.g. the distribution of
+  /// their codecs.
+  pub fn cid_stats(&self) -> &CidStats {
+    &self.cids
+  }
+
+  /// Returns the number of distinct CIDs that blocks link to but that have
+  /// no block in the file.
+  ///
+  /// Links to identity CIDs hold their data inline and never dangle.
+  pub fn dangling_links(&self) -> usize {
+    self.dangling_links
+  }
+}
+
+/// Reads the CARv1 file at the position of `r` to its end and counts its
+/// blocks, with `links` returning the CIDs a block links to.
+///
+/// Byte offsets in errors are positions within the stream. Fails with the
+/// errors of [`Cid::read_block`], e.g. with [`Error::DigestMismatch`] for a
+/// block that doesn't match its CID.
+///
+/// # Example
+///
+/// ```
+/// use bytecursor::ByteCursor;
+/// use sp_cid::{car, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let leaf = Cid::new_v1(0x55, Code::Sha2_256.digest(b"leaf"));
+/// let missing = Cid::new_v1(0x55, Code::Sha2_256.digest(b"missing"));
+/// // A made up codec whose blocks are the concatenated bytes of their links
+/// let node_data = [leaf.to_bytes(), missing.to_bytes()].concat();
+/// let node = Cid::new_v1(0x30_0001, Code::Sha2_256.digest(&node_data));
+///
+/// // The header isn't decoded, an empty one will do
+/// let mut w = ByteCursor::new(vec![0]);
+/// w.set_position(1);
+/// node.write_block(&node_data, &mut w).unwrap();
+/// leaf.write_block(b"leaf", &mut w).unwrap();
+///
+/// let mut r = ByteCursor::new(w.into_inner());
+/// let stats = car::stats(&mut r, |block: &sp_cid::Block| {
+///   let mut data = block.data();
+///   let mut links = Vec::new();
+///   if block.cid().codec() == 0x30_0001 {
+///     while let Ok((cid, rest)) = sp_cid::CidRef::read(data) {
+///       links.push(cid.to_owned().unwrap());
+///       data = rest;
+///     }
+///   }
+///   links
+/// })
+/// .unwrap();
+/// assert_eq!(stats.blocks(), 2);
+/// assert_eq!(stats.cid_stats().codecs()[&0x55], 1);
+/// assert_eq!(stats.dangling_links(), 1);
+/// ```
+pub fn stats<S, F, I>(r: &mut ByteCursor, mut links: F) -> Result<CarStats>
+where
+  S: Size,
+  F: FnMut(&Block<S>) -> I,
+  I: IntoIterator<Item = Cid<S>>,
+{
+  skip_header(r)?;
+  let mut stats = CarStats::default();
+  let mut seen = BTreeSet::new();
+  let mut linked = BTreeSet::new();
+  while (r.position() as usize) < r.get_ref().len() {
+    let block = Cid::read_block(r)?;
+    stats.blocks += 1;
+    stats.cids.add(block.cid());
+    linked.extend(links(&block));
+    seen.insert(block.into_parts().0);
+  }
+  stats.unique_cids = seen.len();
+  stats.dangling_links = linked
+    .difference(&seen)
+    .filter(|cid| cid.hash().code() != IDENTITY)
+    .count();
+  Ok(stats)
+}
+
+/// Skips the header of a CAR file, which is prefixed with its length as an
+/// unsigned varint.
+fn skip_header(r: &mut ByteCursor) -> Result<()> {
+  let len = crate::varint_read_u64(r)?;
+  let start = r.position() as usize;
+  let remaining = r.get_ref().len().saturating_sub(start);
+  match usize::try_from(len) {
+    Ok(len) if len <= remaining => {
+      r.set_position((start + len) as u64);
+      Ok(())
+    }
+    _ => Err(Error::UnexpectedEof { offset: start }),
+  }
+}
@@ -6,34 +6,52 @@
 //!
 //! As a library author that works with CIDs that should support hashes of
 //! anysize, you would import the `Cid` type from this module.
-use sp_std::{borrow, convert::TryFrom, fmt, str, vec::Vec};
+use sp_std::{
+  borrow, cmp::Ordering, convert::TryFrom, fmt, hash, str, vec::Vec,
+};
 
 use alloc::string::{String, ToString};
 use bytecursor::ByteCursor;
 use unsigned_varint::encode as varint_encode;
 
-use multibase::{encode as base_encode, Base};
-use sp_multihash::{MultihashGeneric as Multihash, Size};
+use multibase::Base;
+use sp_multihash::{MultihashDigest, MultihashGeneric as Multihash, Size};
 
 use crate::{
+  base32, bases,
+  cid_dyn::CidDyn,
+  cid_ref::CidRef,
+  context::DecodeContext,
   error::{Error, Result},
+  names, trace,
   version::Version,
+  weights,
 };
 
 /// DAG-PB multicodec code
-const DAG_PB: u64 = 0x70;
+pub(crate) const DAG_PB: u64 = 0x70;
 /// The SHA_256 multicodec code
-const SHA2_256: u64 = 0x12;
+pub(crate) const SHA2_256: u64 = 0x12;
+/// The identity multihash code, the digest is the data itself
+pub(crate) const IDENTITY: u64 = 0x00;
+/// The BLAKE3 multihash code, with the default 32 byte digest
+#[cfg(feature = "rayon")]
+const BLAKE3_256: u64 = 0x1e;
+/// Upper bound of the binary length of a CID: the version, codec, multihash
+/// code and digest size varints followed by a digest of at most 255 bytes.
+pub(crate) const MAX_LEN: usize = 1 + 10 + 10 + 2 + 255;
 
 /// Representation of a CID.
 ///
 /// The generic is about the allocated size of the multihash.
-#[derive(PartialEq, Eq, Clone, PartialOrd, Ord, Hash)]
-#[cfg_attr(feature = "scale-codec", derive(parity_scale_codec::Decode))]
-#[cfg_attr(feature = "scale-codec", derive(parity_scale_codec::Encode))]
-#[cfg_attr(feature = "serde-codec", derive(serde::Deserialize))]
-#[cfg_attr(feature = "serde-codec", derive(serde::Serialize))]
-#[cfg_attr(feature = "serde-codec", serde(bound = "S: Size"))]
+///
+/// CIDs are ordered by their binary encoding, so sorted CIDs are in the same
+/// order as their bytes, e.g. when they are used as database keys.
+///
+/// A `Cid` of any size is `Send`, `Sync` and `Unpin`, so futures and shared
+/// state can hold it. It is `Copy` if its digest array is, which is the case
+/// for all `typenum` sizes, e.g. the [`Cid`](crate::Cid) alias.
+#[derive(Clone)]
 pub struct Cid<S: Size> {
   /// The version of CID.
   version: Version,
@@ -45,11 +63,56 @@ pub struct Cid<S: Size> {
 
 impl<S: Size> Copy for Cid<S> where S::ArrayType: Copy {}
 
+// The digest array of a generic size isn't known to be `Unpin`, which makes
+// generic futures holding a CID `!Unpin`. Nothing of a CID is ever pinned.
+impl<S: Size> Unpin for Cid<S> {}
+
+// Async code holds CIDs across await points, where losing an auto trait
+// surfaces as an error far from the CID. Keep them for every size.
+const _: fn() = || {
+  fn auto_traits<T: Send + Sync + Unpin>() {}
+  fn copy<T: Copy>() {}
+  fn all_sizes<S: Size>() {
+    auto_traits::<Cid<S>>();
+  }
+  all_sizes::<sp_multihash::U64>();
+  copy::<Cid<sp_multihash::U16>>();
+  copy::<Cid<sp_multihash::U32>>();
+  copy::<crate::Cid>();
+};
+
 impl<S: Size> Cid<S> {
+  /// The maximum length of the binary encoding of a `Cid` of this size.
+  ///
+  /// This accounts for the largest possible codec and multihash code varints
+  /// and a digest filling the whole allocated size.
+  pub const MAX_BINARY_LEN: usize = {
+    let digest = if S::USIZE < 255 { S::USIZE } else { 255 };
+    1 + 10 + 10 + varint_len(digest as u64) + digest
+  };
+  /// The maximum length of the default string representation (`Display`) of
+  /// a `Cid` of this size, including the multibase prefix.
+  pub const MAX_STRING_LEN: usize =
+    1 + base32::encoded_len(Self::MAX_BINARY_LEN);
+  /// The inline limit Kubo uses for `--inline`, see
+  /// [`Cid::from_data_inline`].
+  pub const DEFAULT_INLINE_LIMIT: usize = 32;
+  /// The input length from which [`Cid::from_data`] hashes in parallel, if
+  /// the hash function and the enabled features allow it.
+  pub const PARALLEL_HASH_THRESHOLD: usize = 128 * 1024;
+  /// The shortest truncated digest that [`Cid::verify`] accepts, 160 bits
+  /// like a SHA-1 digest.
+  pub const MIN_TRUNCATED_DIGEST_LEN: usize = 20;
+  /// The length of the binary encoding of every CIDv0, a SHA2-256 multihash.
+  pub const V0_BINARY_LEN: usize = 34;
+  /// The length of the binary encoding of a CIDv1 with a SHA2-256 multihash
+  /// and a codec below 0x80, such as raw, DAG-PB and DAG-CBOR.
+  pub const V1_SHA256_BINARY_LEN: usize = 36;
+
   /// Create a new CIDv0.
   pub fn new_v0(hash: Multihash<S>) -> Result<Self> {
     if hash.code() != SHA2_256 {
-      return Err(Error::InvalidCidV0Multihash);
+      return Err(Error::InvalidCidV0Multihash(hash.code()));
     }
     Ok(Self {
       version: Version::V0,
@@ -67,12 +130,95 @@ impl<S: Size> Cid<S> {
     }
   }
 
+  /// Create a CIDv1 of `data` hashed with `code`.
+  ///
+  /// With the `rayon` feature, BLAKE3 hashes inputs of at least
+  /// [`Cid::PARALLEL_HASH_THRESHOLD`] bytes on all cores. Fails if the digest
+  /// doesn't fit into the allocated size.
+  pub fn from_data<C: MultihashDigest>(
+    codec: u64,
+    code: C,
+    data: &[u8],
+  ) -> Result<Self> {
+    if data.len() >= Self::PARALLEL_HASH_THRESHOLD {
+      if let Some(digest) = parallel_digest(code.into(), data) {
+        let hash = Multihash::wrap(code.into(), &digest)?;
+        return Ok(Self::new_v1(codec, hash));
+      }
+    }
+    let hash = code.digest(data);
+    let hash = Multihash::wrap(hash.code(), hash.digest())?;
+    Ok(Self::new_v1(codec, hash))
+  }
+
+  /// Create a CIDv1 of `data` like [`Cid::from_data`], but embed data of at
+  /// most `inline_limit` bytes with an identity multihash instead of hashing
+  /// it.
+  ///
+  /// This matches the `--inline` option of Kubo, which uses a limit of
+  /// [`Cid::DEFAULT_INLINE_LIMIT`] bytes. Fails if the digest doesn't fit
+  /// into the allocated size.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::Code;
+  ///
+  /// const RAW: u64 = 0x55;
+  ///
+  /// let limit = Cid::DEFAULT_INLINE_LIMIT;
+  /// let cid = Cid::from_data_inline(RAW, Code::Sha2_256, b"foo", limit);
+  /// assert_eq!(cid.unwrap().hash().digest(), b"foo");
+  /// ```
+  pub fn from_data_inline<C: MultihashDigest>(
+    codec: u64,
+    code: C,
+    data: &[u8],
+    inline_limit: usize,
+  ) -> Result<Self> {
+    if data.len() <= inline_limit {
+      let hash = Multihash::wrap(IDENTITY, data)?;
+      return Ok(Self::new_v1(codec, hash));
+    }
+    Self::from_data(codec, code, data)
+  }
+
+  /// Create a CIDv1 of `data` like [`Cid::from_data`], with the digest
+  /// truncated to its first `len` bytes.
+  ///
+  /// The multihash spec permits truncated digests, their multihash encodes the
+  /// shorter length. Digests shorter than [`Cid::MIN_TRUNCATED_DIGEST_LEN`]
+  /// don't verify. Fails if the digest is shorter than `len` bytes or doesn't
+  /// fit into the allocated size.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::Code;
+  ///
+  /// const RAW: u64 = 0x55;
+  ///
+  /// let cid = Cid::from_data_truncated(RAW, Code::Sha2_256, b"foo", 20);
+  /// assert_eq!(cid.unwrap().hash().size(), 20);
+  /// ```
+  pub fn from_data_truncated<C: MultihashDigest>(
+    codec: u64,
+    code: C,
+    data: &[u8],
+    len: usize,
+  ) -> Result<Self> {
+    let hash = code.digest(data);
+    Ok(Self::new_v1(codec, truncate(hash.code(), hash.digest(), len)?))
+  }
+
   /// Create a new CID.
   pub fn new(version: Version, codec: u64, hash: Multihash<S>) -> Result<Self> {
     match version {
       Version::V0 => {
         if codec != DAG_PB {
-          return Err(Error::InvalidCidV0Codec);
+          return Err(Error::InvalidCidV0Codec(codec));
         }
         Self::new_v0(hash)
       }
@@ -80,6 +226,63 @@ impl<S: Size> Cid<S> {
     }
   }
 
+  /// Create a CIDv1 of `codec` from the binary form of a multihash, as DHT
+  /// and provider records store it.
+  ///
+  /// Fails if the bytes aren't exactly one multihash or its digest doesn't
+  /// fit into the allocated size.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let hash = Code::Sha2_256.digest(b"foo");
+  /// let cid = Cid::from_multihash_bytes(0x55, &hash.to_bytes()).unwrap();
+  /// assert_eq!(cid, Cid::new_v1(0x55, hash));
+  /// ```
+  pub fn from_multihash_bytes(codec: u64, bytes: &[u8]) -> Result<Self> {
+    let hash = Multihash::from_bytes(bytes)?;
+    Ok(Self::new_v1(codec, hash))
+  }
+
+  /// Returns the zero CID, a sentinel for "no CID".
+  ///
+  /// It is a CIDv1 with codec 0 and an empty multihash of code 0. That is a
+  /// valid CID that round-trips through storage, so check for it with
+  /// [`Cid::is_zero`].
+  pub fn zero() -> Self {
+    Self {
+      version: Version::V1,
+      codec: 0,
+      hash: Multihash::<S>::default(),
+    }
+  }
+
+  /// Returns whether this is the [`Cid::zero`] sentinel.
+  pub fn is_zero(&self) -> bool {
+    self.version == Version::V1
+      && self.codec == 0
+      && self.hash.code() == 0
+      && self.hash.size() == 0
+  }
+
+  /// Create a CID from its parts without validating them.
+  ///
+  /// This skips the CIDv0 codec and multihash checks of [`Cid::new`] and is
+  /// meant for parts that were validated before, e.g. read from storage the
+  /// runtime wrote itself. It trusts its input: a CIDv0 that isn't DAG-PB
+  /// with a SHA2-256 multihash doesn't round-trip through its binary or
+  /// string form.
+  pub const fn from_raw_parts_unchecked(
+    version: Version,
+    codec: u64,
+    hash: Multihash<S>,
+  ) -> Self {
+    Self { version, codec, hash }
+  }
+
   /// Returns the cid version.
   pub fn version(&self) -> Version {
     self.version
@@ -95,36 +298,143 @@ impl<S: Size> Cid<S> {
     &self.hash
   }
 
+  /// Unwraps the cid multihash, e.g. to key providers by multihash.
+  pub fn into_hash(self) -> Multihash<S> {
+    self.hash
+  }
+
+  /// Returns the digest of the cid multihash as an array.
+  ///
+  /// Fails with [`sp_multihash::Error::InvalidSize`] of the digest length if
+  /// the digest isn't exactly `N` bytes long.
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let digest: [u8; 32] = cid.digest_as_array().unwrap();
+  /// assert_eq!(digest, cid.hash().digest());
+  /// assert!(cid.digest_as_array::<64>().is_err());
+  /// ```
+  pub fn digest_as_array<const N: usize>(&self) -> Result<[u8; N]> {
+    let digest = self.hash.digest();
+    <[u8; N]>::try_from(digest)
+      .map_err(|_| sp_multihash::Error::InvalidSize(digest.len() as u64).into())
+  }
+
+  /// Returns the name of the cid codec, if it is a known codec.
+  ///
+  /// See [`names::CODECS`](crate::names::CODECS) for the known codecs.
+  pub fn codec_name(&self) -> Option<&'static str> {
+    names::codec_name(self.codec)
+  }
+
+  /// Returns the media type to serve the block of the CID as, if the codec
+  /// has one.
+  ///
+  /// See [`names::CONTENT_TYPES`](crate::names::CONTENT_TYPES) for the media
+  /// types and [`names::content_type_codec`](crate::names::content_type_codec)
+  /// for the reverse lookup.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x71, Code::Sha2_256.digest(b"foo"));
+  /// let content_type = cid.preferred_content_type();
+  /// assert_eq!(content_type, Some("application/vnd.ipld.dag-cbor"));
+  /// ```
+  pub fn preferred_content_type(&self) -> Option<&'static str> {
+    names::content_type(self.codec)
+  }
+
+  /// Returns the name of the hash function of the cid multihash, if it is a
+  /// known function.
+  ///
+  /// See [`names::HASHES`](crate::names::HASHES) for the known functions.
+  pub fn hash_name(&self) -> Option<&'static str> {
+    names::hash_name(self.hash.code())
+  }
+
+  /// Converts the CID to the allocated digest size `S2`.
+  ///
+  /// Fails if the digest doesn't fit into `S2`.
+  pub fn resize<S2: Size>(&self) -> Result<Cid<S2>> {
+    let hash = Multihash::<S2>::wrap(self.hash.code(), self.hash.digest())?;
+    Ok(Cid { version: self.version, codec: self.codec, hash })
+  }
+
+  /// Checks whether the CIDs have the same version, codec, multihash code
+  /// and digest, regardless of their allocated sizes.
+  ///
+  /// `==` only compares CIDs of the same size, so that the type of the other
+  /// side can be inferred.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest, U32};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let small = cid.resize::<U32>().unwrap();
+  /// assert!(cid.eq_across_sizes(&small));
+  /// ```
+  pub fn eq_across_sizes<S2: Size>(&self, other: &Cid<S2>) -> bool {
+    self.version == other.version
+      && self.codec == other.codec
+      && self.hash.code() == other.hash.code()
+      && self.hash.digest() == other.hash.digest()
+  }
+
   /// Reads the bytes from a byte stream.
+  ///
+  /// Byte offsets in errors are positions within the stream. Fails with
+  /// [`Error::DigestTooLarge`] if the digest is valid but doesn't fit into
+  /// the allocated size, see [`Cid::read_bytes_lossy`] for those. See
+  /// [`DecodeContext::read_bytes`] for tighter limits.
   pub fn read_bytes(r: &mut ByteCursor) -> Result<Self> {
-    let version = match crate::varint_read_u64(r) {
-      Ok(v) => v,
-      Err(e) => return Err(e),
-    };
-    let codec = match crate::varint_read_u64(r) {
-      Ok(v) => v,
-      Err(e) => return Err(e),
-    };
-    // CIDv0 has the fixed `0x12 0x20` prefix
-    if [version, codec] == [0x12, 0x20] {
-      let mut digest = [0u8; 32];
-      match r.read_exact(&mut digest) {
-        Ok(_) => (),
-        Err(_) => return Err(Error::VarIntDecodeError),
-      };
-      let mh = Multihash::wrap(version, &digest).expect("Digest is always 32 bytes.");
-      Self::new_v0(mh)
-    } else {
-      let version = match Version::try_from(version) {
-        Ok(ver) => ver,
-        Err(_) => return Err(Error::VarIntDecodeError),
-      };
-      let mh = match Multihash::read(r) {
-        Ok(dig) => dig,
-        Err(_) => return Err(Error::VarIntDecodeError),
-      };
-      Self::new(version, codec, mh)
-    }
+    DecodeContext::new().read_bytes(r)
+  }
+
+  /// Reads the bytes from a byte stream like [`Cid::read_bytes`], into a
+  /// [`CidDyn`] that holds digests of any size.
+  ///
+  /// Callers can tell a CID that is only bigger than the allocated size from
+  /// a corrupt one by falling back to this on [`Error::DigestTooLarge`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use bytecursor::ByteCursor;
+  /// use sp_cid::{CidGeneric, Error};
+  /// use sp_multihash::{Code, MultihashDigest, U32};
+  ///
+  /// type Cid = CidGeneric<U32>;
+  ///
+  /// let hash = Code::Sha2_512.digest(b"foo");
+  /// let bytes = CidGeneric::new_v1(0x55, hash).to_bytes();
+  /// let read = Cid::read_bytes(&mut ByteCursor::new(bytes.clone()));
+  /// assert_eq!(read, Err(Error::DigestTooLarge { size: 64, max: 32 }));
+  /// let cid = Cid::read_bytes_lossy(&mut ByteCursor::new(bytes)).unwrap();
+  /// assert_eq!(cid.digest().len(), 64);
+  /// ```
+  pub fn read_bytes_lossy(r: &mut ByteCursor) -> Result<CidDyn> {
+    let mut buf = [0u8; 255];
+    let (version, codec, code, digest) =
+      DecodeContext::new().read_parts(r, &mut buf)?;
+    CidDyn::new(version, codec, code, digest)
+  }
+
+  /// Reads a CID from a byte iterator, e.g. over chunked buffers.
+  ///
+  /// Only the bytes of the CID are consumed, pass `&mut iter` to continue
+  /// reading after it. Byte offsets in errors count the consumed bytes.
+  pub fn from_byte_iter<I: IntoIterator<Item = u8>>(bytes: I) -> Result<Self> {
+    DecodeContext::new().read_byte_iter(bytes)
   }
 
   fn write_bytes_v1(&self, w: &mut ByteCursor) -> Result<()> {
@@ -136,15 +446,15 @@ impl<S: Size> Cid<S> {
 
     match w.write_all(version) {
       Ok(_) => (),
-      Err(_) => return Err(Error::InvalidCidVersion),
+      Err(_) => return Err(Error::WriteError),
     };
     match w.write_all(codec) {
       Ok(_) => (),
-      Err(_) => return Err(Error::InvalidCidV0Codec),
+      Err(_) => return Err(Error::WriteError),
     };
     match self.hash.write(w) {
       Ok(_) => (),
-      Err(_) => return Err(Error::VarIntDecodeError),
+      Err(_) => return Err(Error::WriteError),
     };
     Ok(())
   }
@@ -154,29 +464,239 @@ impl<S: Size> Cid<S> {
     match self.version {
       Version::V0 => match self.hash.write(w) {
         Ok(_) => (),
-        Err(_) => return Err(Error::VarIntDecodeError),
+        Err(_) => return Err(Error::WriteError),
       },
       Version::V1 => match self.write_bytes_v1(w) {
         Ok(_) => (),
-        Err(_) => return Err(Error::VarIntDecodeError),
+        Err(_) => return Err(Error::WriteError),
       },
     };
     Ok(())
   }
 
+  /// Reads a CID that is prefixed with its length as an unsigned varint, the
+  /// framing used by CAR files and several libp2p protocols.
+  ///
+  /// The CID has to fill the frame exactly, otherwise
+  /// [`Error::TrailingBytes`] is returned.
+  pub fn read_framed(r: &mut ByteCursor) -> Result<Self> {
+    DecodeContext::new().read_framed(r)
+  }
+
+  /// Writes the CID prefixed with its length as an unsigned varint, see
+  /// [`Cid::read_framed`].
+  pub fn write_framed(&self, w: &mut ByteCursor) -> Result<()> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    let mut len_buf = varint_encode::usize_buffer();
+    let prefix = varint_encode::usize(len, &mut len_buf);
+    match w.write_all(prefix).and_then(|_| w.write_all(&buf[..len])) {
+      Ok(_) => Ok(()),
+      Err(_) => Err(Error::WriteError),
+    }
+  }
+
+  /// Parses a CID string from a nul-terminated C string.
+  ///
+  /// A null pointer returns [`Error::InputTooShort`].
+  ///
+  /// # Safety
+  ///
+  /// `ptr` must be null or point to a nul-terminated string that is valid for
+  /// reads and not mutated for the duration of the call, see
+  /// [`std::ffi::CStr::from_ptr`].
+  #[cfg(feature = "std")]
+  pub unsafe fn from_c_str(ptr: *const std::os::raw::c_char) -> Result<Self> {
+    if ptr.is_null() {
+      return Err(Error::InputTooShort);
+    }
+    Self::try_from(std::ffi::CStr::from_ptr(ptr))
+  }
+
   /// Returns the encoded bytes of the `Cid`.
   pub fn to_bytes(&self) -> Vec<u8> {
-    let mut bytes = ByteCursor::new(Vec::new());
-    self.write_bytes(&mut bytes).unwrap();
-    bytes.into_inner()
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    buf[..len].to_vec()
+  }
+
+  /// Writes the encoded bytes of the `Cid` into `buf` without allocating,
+  /// returning their length.
+  ///
+  /// Fails with [`Error::WriteError`] if `buf` is shorter than
+  /// [`Cid::binary_len`], [`Cid::MAX_BINARY_LEN`] bytes always suffice.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use std::convert::TryFrom;
+  ///
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let mut buf = [0u8; Cid::MAX_BINARY_LEN];
+  /// let len = cid.encode_into(&mut buf).unwrap();
+  /// assert_eq!(Cid::try_from(&buf[..len]).unwrap(), cid);
+  /// assert!(cid.encode_into(&mut buf[..len - 1]).is_err());
+  /// ```
+  pub fn encode_into(&self, buf: &mut [u8]) -> Result<usize> {
+    let mut bytes = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut bytes);
+    let dest = buf.get_mut(..len).ok_or(Error::WriteError)?;
+    dest.copy_from_slice(&bytes[..len]);
+    Ok(len)
+  }
+
+  /// Returns the length of [`Cid::to_bytes`], without encoding the CID.
+  pub fn binary_len(&self) -> usize {
+    let hash = &self.hash;
+    let digest = hash.digest().len();
+    let multihash =
+      varint_len(hash.code()) + varint_len(digest as u64) + digest;
+    match self.version {
+      Version::V0 => multihash,
+      Version::V1 => 1 + varint_len(self.codec) + multihash,
+    }
+  }
+
+  /// Returns whether the binary encoding has one of the fixed lengths of the
+  /// common CID shapes, [`Cid::V0_BINARY_LEN`] for a CIDv0 and
+  /// [`Cid::V1_SHA256_BINARY_LEN`] for a CIDv1, e.g. to store it in a fixed
+  /// width column.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let raw = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// assert!(raw.is_fixed_size());
+  /// assert_eq!(raw.binary_len(), Cid::V1_SHA256_BINARY_LEN);
+  /// let dag_json = Cid::new_v1(0x0129, Code::Sha2_256.digest(b"foo"));
+  /// assert!(!dag_json.is_fixed_size());
+  /// ```
+  pub fn is_fixed_size(&self) -> bool {
+    let len = self.binary_len();
+    match self.version {
+      Version::V0 => len == Self::V0_BINARY_LEN,
+      Version::V1 => len == Self::V1_SHA256_BINARY_LEN,
+    }
   }
 
   fn to_string_v0(&self) -> String {
-    Base::Base58Btc.encode(self.hash.to_bytes())
+    bases::encode_base58btc(&self.hash.to_bytes())
+  }
+
+  /// Writes the binary form of the CID into `buf`, returning its length.
+  pub(crate) fn write_to_slice(&self, buf: &mut [u8; MAX_LEN]) -> usize {
+    let hash = &self.hash;
+    write_parts(self.version, self.codec, hash.code(), hash.digest(), buf)
   }
 
-  fn to_string_v1(&self) -> String {
-    multibase::encode(Base::Base32Lower, self.to_bytes().as_slice())
+  /// Writes a CIDv1 as Base32Lower without allocating.
+  fn write_v1<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    w.write_char(Base::Base32Lower.code())?;
+    base32::encode_lower(&buf[..len], w)
+  }
+
+  /// Writes a CIDv0 as Base58Btc without allocating.
+  fn write_v0<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    bases::write_base58btc(&buf[..len], w)
+  }
+
+  /// Writes the default string form, or the CIDv1 form of a CIDv0 if `v1`
+  /// is set.
+  pub(crate) fn fmt_string<W: fmt::Write>(
+    &self,
+    w: &mut W,
+    v1: bool,
+  ) -> fmt::Result {
+    match self.version {
+      Version::V0 if v1 => {
+        Self::new_v1(self.codec, self.hash.clone()).write_v1(w)
+      }
+      Version::V0 => self.write_v0(w),
+      Version::V1 => self.write_v1(w),
+    }
+  }
+
+  /// Returns an adapter that displays the CID in `base`, for use in
+  /// `format!` and logging macros.
+  ///
+  /// Fails like [`Cid::to_string_of_base`] if the CID can't be encoded in
+  /// `base`. The CID is written without allocating, except in Base256Emoji
+  /// or with a width or precision.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let display = cid.display_base(Base::Base58Btc).unwrap();
+  /// assert_eq!(
+  ///   format!("{}", display),
+  ///   cid.to_string_of_base(Base::Base58Btc).unwrap()
+  /// );
+  /// ```
+  pub fn display_base(&self, base: Base) -> Result<DisplayBase<'_, S>> {
+    match self.version {
+      Version::V0 if base != Base::Base58Btc => {
+        return Err(Error::InvalidCidV0Base)
+      }
+      Version::V0 => (),
+      Version::V1 => {
+        let mut buf = [0u8; MAX_LEN];
+        let len = self.write_to_slice(&mut buf);
+        bases::check(base, &buf[..len])?;
+      }
+    }
+    Ok(DisplayBase { cid: self, base })
+  }
+
+  /// Returns the exact length in bytes of [`Cid::to_string_of_base`],
+  /// without encoding the CID.
+  ///
+  /// Fails in the same cases as [`Cid::to_string_of_base`].
+  pub fn to_string_len(&self, base: Base) -> Result<usize> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    match self.version {
+      Version::V0 if base != Base::Base58Btc => Err(Error::InvalidCidV0Base),
+      Version::V0 => bases::encoded_len(base, &buf[..len]),
+      Version::V1 => {
+        let prefix = base.code().len_utf8();
+        Ok(prefix + bases::encoded_len(base, &buf[..len])?)
+      }
+    }
+  }
+
+  /// Returns an adapter that displays the CID abbreviated, like
+  /// `bafy…dqg4`, for log lines and dashboards.
+  ///
+  /// By default 4 characters are kept at each end, use [`ShortCid::head`] and
+  /// [`ShortCid::tail`] to change that.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// assert_eq!(cid.short().to_string(), "bafk…xhvy");
+  /// assert_eq!(cid.short().head(6).tail(2).to_string(), "bafkre…vy");
+  /// ```
+  pub fn short(&self) -> ShortCid<'_, S> {
+    ShortCid { cid: self, head: 4, tail: 4 }
   }
 
   /// Convert CID into a multibase encoded string
@@ -206,28 +726,362 @@ impl<S: Size> Cid<S> {
           Err(Error::InvalidCidV0Base)
         }
       }
-      Version::V1 => Ok(base_encode(base, self.to_bytes())),
+      Version::V1 => bases::encode_prefixed(base, &self.to_bytes()),
+    }
+  }
+
+  /// Convert CID into multibase encoded bytes.
+  ///
+  /// [`Base::Identity`] gives the binary form after a `0x00` prefix, the
+  /// form DAG-CBOR and other binary protocols embed CIDs in. It is available
+  /// for both versions. All other bases give the bytes of
+  /// [`Cid::to_string_of_base`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use multibase::Base;
+  ///
+  /// let cid: Cid = "bafkqaaa".parse().unwrap();
+  /// assert_eq!(
+  ///   cid.to_multibase_bytes(Base::Identity).unwrap(),
+  ///   [0x00, 0x01, 0x55, 0x00, 0x00]
+  /// );
+  /// let base32 = cid.to_multibase_bytes(Base::Base32Lower).unwrap();
+  /// assert_eq!(base32, b"bafkqaaa");
+  /// ```
+  pub fn to_multibase_bytes(&self, base: Base) -> Result<Vec<u8>> {
+    if base != Base::Identity {
+      return self.to_string_of_base(base).map(String::into_bytes);
+    }
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    let mut bytes = Vec::with_capacity(1 + len);
+    bytes.push(0x00);
+    bytes.extend_from_slice(&buf[..len]);
+    Ok(bytes)
+  }
+
+  /// Convert CID into a multibase encoded string like
+  /// [`Cid::to_string_of_base`], but encode a CIDv0 as the equivalent CIDv1
+  /// if `base` isn't Base58Btc, like `ipfs cid base32` does.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use multibase::Base;
+  ///
+  /// let v0: Cid = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n"
+  ///   .parse()
+  ///   .unwrap();
+  /// assert_eq!(
+  ///   v0.to_string_of_base_upgrading(Base::Base32Lower).unwrap(),
+  ///   "bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+  /// );
+  /// ```
+  pub fn to_string_of_base_upgrading(&self, base: Base) -> Result<String> {
+    match self.version {
+      Version::V0 if base != Base::Base58Btc => {
+        Self::new_v1(self.codec, self.hash.clone()).to_string_of_base(base)
+      }
+      _ => self.to_string_of_base(base),
+    }
+  }
+
+  /// Convert CID into upper case base32 for QR codes, e.g. on tickets.
+  ///
+  /// The string only has characters of the alphanumeric QR mode, which
+  /// takes less space than the byte mode a lower case string needs. The
+  /// multibase prefix is `B`, the code of Base32Upper. A CIDv0 is encoded as
+  /// the equivalent CIDv1, it can't be represented in base32. This works
+  /// without the `all-bases` feature.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let cid: Cid = s.parse().unwrap();
+  /// let qr = cid.to_qr_string();
+  /// assert_eq!(qr, s.to_ascii_uppercase());
+  /// assert_eq!(qr.parse::<Cid>().unwrap(), cid);
+  /// ```
+  pub fn to_qr_string(&self) -> String {
+    let mut s = String::with_capacity(Self::MAX_STRING_LEN);
+    // Writing to a `String` never fails.
+    let _ = self.fmt_string(&mut s, true);
+    s.make_ascii_uppercase();
+    s
+  }
+
+  /// Parses a CID string encoded in `base`, without the multibase prefix.
+  ///
+  /// Gateways get such strings when the prefix was stripped, e.g. from
+  /// subdomain labels or file names. A Base58Btc string may also be a CIDv0.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use multibase::Base;
+  ///
+  /// let s = "afkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let cid = Cid::from_str_multibase(Base::Base32Lower, s).unwrap();
+  /// assert_eq!(cid.to_string(), format!("b{}", s));
+  /// ```
+  pub fn from_str_multibase(base: Base, cid_str: &str) -> Result<Self> {
+    let result =
+      bases::decode(base, cid_str).and_then(|bytes| read_slice(&bytes));
+    trace::parse_result(result, cid_str.len())
+  }
+
+  /// Parses a CID string like [`Cid::try_from`] and returns the base it was
+  /// encoded in, Base58Btc for a CIDv0.
+  ///
+  /// The base comes from the same decoding pass, e.g. for gateways that
+  /// answer in the encoding of the request.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::Cid;
+  ///
+  /// let s = "BAFKREIBME22GW2H7Y2H7TG2FHQOTAQJUCNBC24DEQO72B6MKL2EGEZXHVY";
+  /// let (cid, base) = Cid::try_from_with_base(s).unwrap();
+  /// assert_eq!(base, Base::Base32Upper);
+  /// assert_eq!(cid.to_string_of_base(base).unwrap(), s);
+  /// ```
+  pub fn try_from_with_base(cid_str: &str) -> Result<(Self, Base)> {
+    let result = decode_stripped_with_base(strip_ipfs_path(cid_str))
+      .and_then(|(base, bytes)| Ok((read_slice(&bytes)?, base)));
+    trace::parse_result(result, cid_str.len())
+  }
+
+  /// Returns the CID as an HTTP entity tag, the quoted default string form
+  /// like IPFS gateways use.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let cid: Cid = s.parse().unwrap();
+  /// assert_eq!(cid.to_etag(), format!("\"{}\"", s));
+  /// assert_eq!(Cid::from_etag(&format!("W/\"{}.raw\"", s)).unwrap(), cid);
+  /// assert!(cid.matches_etag(&format!("\"foo\", \"{}\"", s)));
+  /// ```
+  pub fn to_etag(&self) -> String {
+    let mut etag = String::with_capacity(Self::MAX_STRING_LEN + 2);
+    etag.push('"');
+    // Writing to a `String` never fails.
+    let _ = self.fmt_string(&mut etag, false);
+    etag.push('"');
+    etag
+  }
+
+  /// Parses an HTTP entity tag of a CID.
+  ///
+  /// Weak tags (`W/"..."`) are accepted, and a suffix after the first `.`
+  /// inside the quotes is ignored, which gateways add for other response
+  /// formats, e.g. `"<cid>.car"`. Tags without quotes return
+  /// [`multibase::Error::InvalidBaseString`].
+  pub fn from_etag(etag: &str) -> Result<Self> {
+    let tag = etag.trim();
+    let tag = tag.strip_prefix("W/").unwrap_or(tag);
+    let cid_str = tag
+      .strip_prefix('"')
+      .and_then(|tag| tag.strip_suffix('"'))
+      .ok_or(Error::MultibaseError(multibase::Error::InvalidBaseString))?;
+    let end = cid_str.find('.').unwrap_or(cid_str.len());
+    Self::try_from(&cid_str[..end])
+  }
+
+  /// Checks whether the value of an `If-None-Match` or `If-Match` header
+  /// matches the CID, for answering conditional requests.
+  ///
+  /// The value is a comma separated list of entity tags or `*`, which
+  /// matches any CID. Tags are compared weakly, like [`Cid::from_etag`]
+  /// parses them, and tags that aren't CIDs don't match.
+  pub fn matches_etag(&self, header: &str) -> bool {
+    header.split(',').any(|tag| {
+      tag.trim() == "*"
+        || Cid::<S>::from_etag(tag).is_ok_and(|cid| cid == *self)
+    })
+  }
+
+  /// Checks whether two CID strings refer to the same CID, in any base and
+  /// with or without an `/ipfs/` prefix.
+  ///
+  /// A CIDv0 is the same CID as the DAG-PB CIDv1 of its multihash, other
+  /// CIDs are the same if their codecs and multihashes are equal. Strings
+  /// that aren't CIDs, or whose digest doesn't fit into the allocated size,
+  /// are different from all strings.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  /// let v1 = "bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+  /// assert!(Cid::str_eq(v0, v1));
+  /// assert!(Cid::str_eq(v1, &v1.to_uppercase()));
+  /// assert!(!Cid::str_eq(v0, "bafkqaaa"));
+  /// assert!(!Cid::str_eq("foo", "foo"));
+  /// ```
+  pub fn str_eq(a: &str, b: &str) -> bool {
+    match (Self::try_from(a), Self::try_from(b)) {
+      (Ok(a), Ok(b)) => a.codec == b.codec && a.hash == b.hash,
+      _ => false,
     }
   }
 }
 
+/// Number of bytes of the unsigned varint encoding of `n`.
+pub(crate) const fn varint_len(mut n: u64) -> usize {
+  let mut len = 1;
+  while n >= 0x80 {
+    n >>= 7;
+    len += 1;
+  }
+  len
+}
+
+/// Returns [`Cid::zero`].
+///
+/// Trait impls can't be deprecated, but new code should call [`Cid::zero`]
+/// explicitly or use `Option<Cid>`, so that placeholder CIDs are visible at
+/// the call site.
 impl<S: Size> Default for Cid<S> {
   fn default() -> Self {
-    Self {
-      version: Version::V1,
-      codec: 0,
-      hash: Multihash::<S>::default(),
-    }
+    Self::zero()
+  }
+}
+
+/// CIDs are equal if their version, codec, multihash code and digest are
+/// equal, see [`Cid::eq_across_sizes`] for CIDs of different sizes.
+impl<S: Size> PartialEq for Cid<S> {
+  fn eq(&self, other: &Self) -> bool {
+    self.eq_across_sizes(other)
   }
 }
 
+impl<S: Size> Eq for Cid<S> {}
+
+impl<S: Size> PartialOrd for Cid<S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<S: Size> Ord for Cid<S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    let mut other_buf = [0u8; MAX_LEN];
+    let other_len = other.write_to_slice(&mut other_buf);
+    buf[..len].cmp(&other_buf[..other_len])
+  }
+}
+
+/// Hashes the version, codec, multihash code and digest, but not the unused
+/// part of the allocated digest, so equal CIDs of different sizes hash equally.
+impl<S: Size> hash::Hash for Cid<S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    self.version.hash(state);
+    self.codec.hash(state);
+    self.hash.code().hash(state);
+    self.hash.digest().hash(state);
+  }
+}
+
+/// Formats the CID in its default string form.
+///
+/// Width, fill, alignment and precision (truncation) are applied like for
+/// strings. The alternate form `{:#}` prints CIDv0 in the canonical CIDv1
+/// base32 form.
 impl<S: Size> fmt::Display for Cid<S> {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-    let output = match self.version {
-      Version::V0 => self.to_string_v0(),
-      Version::V1 => self.to_string_v1(),
-    };
-    write!(f, "{}", output)
+    if f.width().is_none() && f.precision().is_none() {
+      return self.fmt_string(f, f.alternate());
+    }
+    let mut s = String::with_capacity(Self::MAX_STRING_LEN);
+    self.fmt_string(&mut s, f.alternate())?;
+    f.pad(&s)
+  }
+}
+
+/// Displays a CID in a chosen multibase, see [`Cid::display_base`].
+///
+/// Width, fill, alignment and precision are applied like for strings.
+#[derive(Clone, Copy, Debug)]
+pub struct DisplayBase<'a, S: Size> {
+  /// The displayed CID.
+  cid: &'a Cid<S>,
+  /// The base to display the CID in.
+  base: Base,
+}
+
+impl<'a, S: Size> DisplayBase<'a, S> {
+  fn write<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    match (self.cid.version, self.base) {
+      (Version::V0, _) => self.cid.write_v0(w),
+      (Version::V1, Base::Base32Lower) => self.cid.write_v1(w),
+      (Version::V1, base) => {
+        let mut buf = [0u8; MAX_LEN];
+        let len = self.cid.write_to_slice(&mut buf);
+        w.write_char(base.code())?;
+        bases::write_encoded(base, &buf[..len], w)
+      }
+    }
+  }
+}
+
+impl<'a, S: Size> fmt::Display for DisplayBase<'a, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if f.width().is_none() && f.precision().is_none() {
+      return self.write(f);
+    }
+    let mut s = String::new();
+    self.write(&mut s)?;
+    f.pad(&s)
+  }
+}
+
+/// Displays a CID abbreviated to its first and last characters, see
+/// [`Cid::short`].
+#[derive(Clone, Copy, Debug)]
+pub struct ShortCid<'a, S: Size> {
+  /// The displayed CID.
+  cid: &'a Cid<S>,
+  /// The number of characters kept at the start.
+  head: usize,
+  /// The number of characters kept at the end.
+  tail: usize,
+}
+
+impl<'a, S: Size> ShortCid<'a, S> {
+  /// Sets the number of characters kept at the start.
+  pub fn head(mut self, head: usize) -> Self {
+    self.head = head;
+    self
+  }
+
+  /// Sets the number of characters kept at the end.
+  pub fn tail(mut self, tail: usize) -> Self {
+    self.tail = tail;
+    self
+  }
+}
+
+impl<'a, S: Size> fmt::Display for ShortCid<'a, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.cid.fmt_short_into(self.head, self.tail, f)
   }
 }
 
@@ -240,11 +1094,7 @@ impl<S: Size> fmt::Debug for Cid<S> {
         .field("hash", self.hash())
         .finish()
     } else {
-      let output = match self.version {
-        Version::V0 => self.to_string_v0(),
-        Version::V1 => self.to_string_v1(),
-      };
-      write!(f, "Cid({})", output)
+      write!(f, "Cid({})", self)
     }
   }
 }
@@ -269,30 +1119,285 @@ impl<S: Size> TryFrom<&str> for Cid<S> {
   type Error = Error;
 
   fn try_from(cid_str: &str) -> Result<Self> {
-    static IPFS_DELIMETER: &str = "/ipfs/";
+    trace::parse_result(DecodeContext::new().parse(cid_str), cid_str.len())
+  }
+}
 
-    let hash = match cid_str.find(IPFS_DELIMETER) {
-      Some(index) => &cid_str[index + IPFS_DELIMETER.len()..],
-      _ => cid_str,
-    };
+/// Hashes `data` on all cores, if the hash function of `code` supports it.
+#[cfg(feature = "rayon")]
+fn parallel_digest(code: u64, data: &[u8]) -> Option<[u8; 32]> {
+  if code != BLAKE3_256 {
+    return None;
+  }
+  let mut hasher = blake3::Hasher::new();
+  hasher.update_rayon(data);
+  Some(*hasher.finalize().as_bytes())
+}
 
-    if hash.len() < 2 {
-      return Err(Error::InputTooShort);
+/// Hashing in parallel requires the `rayon` feature.
+#[cfg(not(feature = "rayon"))]
+fn parallel_digest(_code: u64, _data: &[u8]) -> Option<[u8; 32]> {
+  None
+}
+
+/// Writes the binary form of the CID with the given parts into `buf`,
+/// returning its length.
+///
+/// The digest must be at most 255 bytes long.
+pub(crate) fn write_parts(
+  version: Version,
+  codec: u64,
+  hash_code: u64,
+  digest: &[u8],
+  buf: &mut [u8; MAX_LEN],
+) -> usize {
+  let mut len = 0;
+  let mut push = |bytes: &[u8]| {
+    buf[len..len + bytes.len()].copy_from_slice(bytes);
+    len += bytes.len();
+  };
+
+  if version == Version::V1 {
+    let mut version_buf = varint_encode::u64_buffer();
+    push(varint_encode::u64(version.into(), &mut version_buf));
+    let mut codec_buf = varint_encode::u64_buffer();
+    push(varint_encode::u64(codec, &mut codec_buf));
+  }
+  let mut code_buf = varint_encode::u64_buffer();
+  push(varint_encode::u64(hash_code, &mut code_buf));
+  let mut size_buf = varint_encode::u8_buffer();
+  push(varint_encode::u8(digest.len() as u8, &mut size_buf));
+  push(digest);
+  len
+}
+
+/// Returns the CID string after an `/ipfs/` path prefix.
+pub(crate) fn strip_ipfs_path(cid_str: &str) -> &str {
+  static IPFS_DELIMETER: &str = "/ipfs/";
+
+  match cid_str.find(IPFS_DELIMETER) {
+    Some(index) => &cid_str[index + IPFS_DELIMETER.len()..],
+    _ => cid_str,
+  }
+}
+
+/// Decodes a CID string into the binary form of the CID.
+///
+/// Accepts an optional `/ipfs/` path prefix.
+pub(crate) fn decode_str(cid_str: &str) -> Result<Vec<u8>> {
+  decode_stripped(strip_ipfs_path(cid_str))
+}
+
+/// Decodes a CID string without a path prefix into the binary form of the
+/// CID.
+pub(crate) fn decode_stripped(hash: &str) -> Result<Vec<u8>> {
+  decode_stripped_with_base(hash).map(|(_, decoded)| decoded)
+}
+
+/// Decodes a CID string without a path prefix like [`decode_stripped`], and
+/// returns the base of the string.
+fn decode_stripped_with_base(hash: &str) -> Result<(Base, Vec<u8>)> {
+  if hash.len() < 2 {
+    return Err(Error::InputTooShort);
+  }
+
+  // `Q` isn't a multibase code, so strings starting with it can only be a
+  // CIDv0, which is the Base58Btc encoded multihash without a prefix.
+  if hash.starts_with('Q') {
+    let decoded = bases::decode(Base::Base58Btc, hash)?;
+    if !Version::is_v0_binary(&decoded) {
+      return Err(Error::MultibaseError(multibase::Error::UnknownBase('Q')));
     }
+    Ok((Base::Base58Btc, decoded))
+  } else {
+    bases::decode_prefixed(hash)
+  }
+}
+
+/// Cheaply checks whether `cid_str` looks like a CID string, e.g. as a guard
+/// in request routers before the full parse.
+///
+/// Only the multibase prefix, the alphabet and the length are checked, the
+/// string isn't decoded. A string that passes can still fail to parse, a
+/// string that fails is never a valid CID.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::is_valid_cid_str;
+///
+/// assert!(is_valid_cid_str(
+///   "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"
+/// ));
+/// assert!(!is_valid_cid_str("bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24de0"));
+/// ```
+pub fn is_valid_cid_str(cid_str: &str) -> bool {
+  let mut chars = cid_str.chars();
+  let base = match chars.next() {
+    // CIDv0 is the Base58Btc encoded multihash without a prefix
+    Some('Q') => {
+      return cid_str.len() == 46
+        && cid_str.chars().all(|c| bases::is_alphabet_char(Base::Base58Btc, c))
+    }
+    Some(code) => match Base::from_code(code) {
+      Ok(base) => base,
+      Err(_) => return false,
+    },
+    None => return false,
+  };
+  if bases::check(base, &[]).is_err() {
+    return false;
+  }
+  let body = chars.as_str();
+  let len = body.chars().count();
+  // The shortest CIDv1 has a version, codec, multihash code and digest size
+  // of one byte each
+  (2..=weights::max_base_len(base, MAX_LEN)).contains(&len)
+    && body.chars().all(|c| bases::is_alphabet_char(base, c))
+}
+
+/// The encoding of a CID string, see [`detect_base`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CidBase {
+  /// A CIDv0, Base58Btc without a multibase prefix.
+  V0,
+  /// A multibase string.
+  Multibase(Base),
+}
+
+impl CidBase {
+  /// Returns the base of the string, Base58Btc for a CIDv0.
+  pub fn base(self) -> Base {
+    match self {
+      CidBase::V0 => Base::Base58Btc,
+      CidBase::Multibase(base) => base,
+    }
+  }
+}
 
-    let decoded = if Version::is_v0_str(hash) {
-      match Base::Base58Btc.decode(hash) {
-        Ok(d) => d,
-        Err(_) => return Err(Error::ParsingError),
+/// Reports the encoding of `cid_str` from its first character, without
+/// decoding it, e.g. to enforce or log encoding policies.
+///
+/// The string itself isn't checked, so it can still fail to parse. Bases that
+/// are left out of this build, see the base features like `all-bases`, are
+/// detected as well.
+///
+/// # Example
+///
+/// ```
+/// use multibase::Base;
+/// use sp_cid::{detect_base, CidBase};
+///
+/// let v1 = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+/// let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+/// assert_eq!(detect_base(v1).unwrap(), CidBase::Multibase(Base::Base32Lower));
+/// assert_eq!(detect_base(v0).unwrap(), CidBase::V0);
+/// ```
+pub fn detect_base(cid_str: &str) -> Result<CidBase> {
+  match cid_str.chars().next() {
+    // `Q` isn't a multibase code, see `decode_str`
+    Some('Q') => Ok(CidBase::V0),
+    Some(code) => Ok(CidBase::Multibase(Base::from_code(code)?)),
+    None => Err(Error::InputTooShort),
+  }
+}
+
+/// Re-encodes a CID string in `to`, e.g. for proxies that normalize the
+/// CIDs they forward.
+///
+/// The string is decoded and its binary form validated and encoded again,
+/// without building a [`Cid`]: digests of any size are kept. A CIDv0 stays
+/// a CIDv0 in Base58Btc and becomes a CIDv1 in other bases, like
+/// [`Cid::to_string_of_base_upgrading`]. Accepts the strings of
+/// [`Cid::try_from`], except for strings that decode to more bytes than the
+/// CID, which fail with [`Error::TrailingBytes`].
+///
+/// # Example
+///
+/// ```
+/// use multibase::Base;
+/// use sp_cid::transcode_base;
+///
+/// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+/// let base36 = transcode_base(s, Base::Base36Lower).unwrap();
+/// assert_eq!(
+///   base36,
+///   "k2cwue9r0p518otr4itz8hs8b4wjk4nmepaw4lgybiheoa4csgoe6my6"
+/// );
+/// assert_eq!(transcode_base(&base36, Base::Base32Lower).unwrap(), s);
+/// ```
+pub fn transcode_base(cid_str: &str, to: Base) -> Result<String> {
+  let stripped = strip_ipfs_path(cid_str);
+  let result = decode_stripped(stripped).and_then(|bytes| {
+    let (cid, rest) = CidRef::read(&bytes)?;
+    if !rest.is_empty() {
+      let offset = bytes.len() - rest.len();
+      return Err(Error::TrailingBytes { offset });
+    }
+    match cid.version() {
+      Version::V0 if to == Base::Base58Btc => {
+        Ok(bases::encode_base58btc(&bytes))
       }
-    } else {
-      match multibase::decode(hash) {
-        Ok((_, d)) => d,
-        Err(_) => return Err(Error::VarIntDecodeError),
+      Version::V0 => {
+        let mut v1 = Vec::with_capacity(2 + bytes.len());
+        v1.extend_from_slice(&[1, DAG_PB as u8]);
+        v1.extend_from_slice(&bytes);
+        bases::encode_prefixed(to, &v1)
       }
-    };
+      Version::V1 => bases::encode_prefixed(to, &bytes),
+    }
+  });
+  trace::parse_result(result, cid_str.len())
+}
 
-    Self::try_from(decoded)
+/// Parses a CID string passed over FFI, without copying it.
+///
+/// Strings that aren't valid UTF-8 can't be multibase and are rejected with
+/// [`multibase::Error::InvalidBaseString`].
+#[cfg(feature = "std")]
+impl<S: Size> TryFrom<&std::ffi::CStr> for Cid<S> {
+  type Error = Error;
+
+  fn try_from(cid_str: &std::ffi::CStr) -> Result<Self> {
+    match cid_str.to_str() {
+      Ok(cid_str) => Self::try_from(cid_str),
+      Err(_) => Err(Error::MultibaseError(multibase::Error::InvalidBaseString)),
+    }
+  }
+}
+
+/// Parses the file name of a block file, which is a CID string optionally
+/// followed by extensions, e.g. `bafk...vy.data`.
+///
+/// Names that aren't valid UTF-8 are rejected with
+/// [`multibase::Error::InvalidBaseString`].
+#[cfg(feature = "std")]
+impl<S: Size> TryFrom<&std::ffi::OsStr> for Cid<S> {
+  type Error = Error;
+
+  fn try_from(name: &std::ffi::OsStr) -> Result<Self> {
+    let name = name
+      .to_str()
+      .ok_or(Error::MultibaseError(multibase::Error::InvalidBaseString))?;
+    match name.split_once('.') {
+      // Some bases, e.g. Base45, have `.` in their alphabet
+      Some((stem, _)) => Self::try_from(name).or_else(|_| Self::try_from(stem)),
+      None => Self::try_from(name),
+    }
+  }
+}
+
+/// Parses the file name of a block file, see the `TryFrom<&OsStr>`
+/// implementation. The directories of the path are ignored.
+#[cfg(feature = "std")]
+impl<S: Size> TryFrom<&std::path::Path> for Cid<S> {
+  type Error = Error;
+
+  fn try_from(path: &std::path::Path) -> Result<Self> {
+    match path.file_name() {
+      Some(name) => Self::try_from(name),
+      None => Err(Error::InputTooShort),
+    }
   }
 }
 
@@ -308,10 +1413,68 @@ impl<S: Size> TryFrom<&[u8]> for Cid<S> {
   type Error = Error;
 
   fn try_from(bytes: &[u8]) -> Result<Self> {
-    Self::read_bytes(&mut ByteCursor::new(bytes.to_vec()))
+    trace::parse_result(read_slice(bytes), bytes.len())
   }
 }
 
+/// Wraps the first `len` bytes of `digest` into a multihash.
+pub(crate) fn truncate<S: Size>(
+  code: u64,
+  digest: &[u8],
+  len: usize,
+) -> Result<Multihash<S>> {
+  match digest.get(..len) {
+    Some(digest) => Ok(Multihash::wrap(code, digest)?),
+    None => Err(sp_multihash::Error::InvalidSize(len as u64).into()),
+  }
+}
+
+/// Wraps a digest into a multihash of the allocated size `S`.
+///
+/// Fails with [`Error::DigestTooLarge`] if the digest doesn't fit.
+pub(crate) fn wrap_digest<S: Size>(
+  code: u64,
+  digest: &[u8],
+) -> Result<Multihash<S>> {
+  Multihash::wrap(code, digest).map_err(|_| Error::DigestTooLarge {
+    size: digest.len(),
+    max: S::USIZE,
+  })
+}
+
+/// Reads a CID from the start of `bytes`.
+pub(crate) fn read_slice<S: Size>(bytes: &[u8]) -> Result<Cid<S>> {
+  let (cid, _) = CidRef::read(bytes)?;
+  cid.to_owned()
+}
+
+/// Implements `TryFrom<&[u8; N]>` for fixed-width storage columns, where the
+/// CID has to fill the whole array.
+macro_rules! impl_try_from_array {
+  ($($len:expr),*) => {
+    $(
+      impl<S: Size> TryFrom<&[u8; $len]> for Cid<S> {
+        type Error = Error;
+
+        fn try_from(bytes: &[u8; $len]) -> Result<Self> {
+          let (cid, rest) = CidRef::read(bytes)?;
+          if !rest.is_empty() {
+            return Err(Error::TrailingBytes { offset: $len - rest.len() });
+          }
+          cid.to_owned()
+        }
+      }
+    )*
+  };
+}
+
+// A CIDv0 is 34 bytes, a CIDv1 with a 32 byte digest 36 to 38 bytes,
+// depending on the length of the codec and multihash code varints.
+impl_try_from_array!(34, 36, 37, 38);
+
+// The empty identity CID of `consts::IDENTITY_EMPTY_BYTES` is 4 bytes.
+impl_try_from_array!(4);
+
 impl<S: Size> From<&Cid<S>> for Cid<S>
 where
   S::ArrayType: Copy,
@@ -327,6 +1490,12 @@ impl<S: Size> From<Cid<S>> for Vec<u8> {
   }
 }
 
+impl<S: Size> From<Cid<S>> for Multihash<S> {
+  fn from(cid: Cid<S>) -> Self {
+    cid.into_hash()
+  }
+}
+
 impl<S: Size> From<Cid<S>> for String {
   fn from(cid: Cid<S>) -> Self {
     cid.to_string()
@@ -0,0 +1,132 @@
+//! A CID that keeps its binary encoding.
+//!
+//! Database layers that use CIDs as keys need the binary form on every
+//! access. A [`CidBytes`] stores it alongside the parsed CID and borrows as
+//! `[u8]`, so it can be used as a key without re-encoding.
+use sp_std::{
+  borrow::Borrow, boxed::Box, cmp::Ordering, convert::TryFrom, fmt, hash,
+  ops::Deref,
+};
+
+use sp_multihash::Size;
+
+use crate::{
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// A CID together with its canonical binary encoding.
+///
+/// Comparisons and hashing are those of the bytes, which makes the
+/// `Borrow<[u8]>` implementation sound: a map keyed by `CidBytes` can be
+/// queried with a byte slice.
+#[derive(Clone)]
+pub struct CidBytes<S: Size> {
+  /// The parsed CID.
+  cid: Cid<S>,
+  /// The binary encoding of the CID.
+  bytes: Box<[u8]>,
+}
+
+impl<S: Size> CidBytes<S> {
+  /// Wraps a CID and encodes it.
+  pub fn new(cid: Cid<S>) -> Self {
+    let bytes = cid.to_bytes().into_boxed_slice();
+    Self { cid, bytes }
+  }
+
+  /// Returns the wrapped CID.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Returns the binary encoding of the CID.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.bytes
+  }
+
+  /// Unwraps the CID, dropping the encoding.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+}
+
+impl<S: Size> Deref for CidBytes<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<S: Size> AsRef<[u8]> for CidBytes<S> {
+  fn as_ref(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+impl<S: Size> Borrow<[u8]> for CidBytes<S> {
+  fn borrow(&self) -> &[u8] {
+    &self.bytes
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CidBytes<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self::new(cid)
+  }
+}
+
+impl<S: Size> From<CidBytes<S>> for Cid<S> {
+  fn from(bytes: CidBytes<S>) -> Self {
+    bytes.cid
+  }
+}
+
+/// Parses a CID and stores its canonical encoding, which isn't necessarily
+/// the input.
+impl<S: Size> TryFrom<&[u8]> for CidBytes<S> {
+  type Error = Error;
+
+  fn try_from(bytes: &[u8]) -> Result<Self> {
+    Cid::try_from(bytes).map(Self::new)
+  }
+}
+
+impl<S: Size> PartialEq for CidBytes<S> {
+  fn eq(&self, other: &Self) -> bool {
+    self.bytes == other.bytes
+  }
+}
+
+impl<S: Size> Eq for CidBytes<S> {}
+
+impl<S: Size> PartialOrd for CidBytes<S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<S: Size> Ord for CidBytes<S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.bytes.cmp(&other.bytes)
+  }
+}
+
+impl<S: Size> hash::Hash for CidBytes<S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    self.bytes.hash(state)
+  }
+}
+
+impl<S: Size> fmt::Display for CidBytes<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.cid, f)
+  }
+}
+
+impl<S: Size> fmt::Debug for CidBytes<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
@@ -0,0 +1,250 @@
+//! This module contains a CID whose digest is stored on the heap.
+//!
+//! [`Cid`] keeps its digest in an array of the allocated size `S`, which has
+//! to be chosen at compile time. Tooling that must accept digests of any
+//! size, e.g. identity multihashes larger than 64 bytes, can use a
+//! [`CidDyn`] instead.
+use sp_std::{boxed::Box, convert::TryFrom, fmt, str, vec::Vec};
+
+use alloc::string::String;
+use unsigned_varint::encode as varint_encode;
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  base32, bases,
+  cid::{self, wrap_digest, Cid, DAG_PB, SHA2_256},
+  cid_ref::CidRef,
+  context::DecodeContext,
+  error::{Error, Result},
+  version::Version,
+};
+
+/// A CID whose multihash digest is stored on the heap.
+///
+/// Unlike [`Cid`] it isn't limited to an allocated digest size, so it can
+/// hold multihashes of any size.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct CidDyn {
+  /// The version of CID.
+  version: Version,
+  /// The codec of CID.
+  codec: u64,
+  /// The multihash code of CID.
+  hash_code: u64,
+  /// The multihash digest of CID.
+  digest: Box<[u8]>,
+}
+
+impl CidDyn {
+  /// Create a new CID.
+  pub fn new(
+    version: Version,
+    codec: u64,
+    hash_code: u64,
+    digest: impl Into<Box<[u8]>>,
+  ) -> Result<Self> {
+    if version == Version::V0 {
+      if codec != DAG_PB {
+        return Err(Error::InvalidCidV0Codec(codec));
+      }
+      if hash_code != SHA2_256 {
+        return Err(Error::InvalidCidV0Multihash(hash_code));
+      }
+    }
+    Ok(Self { version, codec, hash_code, digest: digest.into() })
+  }
+
+  /// Create a new CIDv1.
+  pub fn new_v1(
+    codec: u64,
+    hash_code: u64,
+    digest: impl Into<Box<[u8]>>,
+  ) -> Self {
+    Self { version: Version::V1, codec, hash_code, digest: digest.into() }
+  }
+
+  /// Returns the cid version.
+  pub fn version(&self) -> Version {
+    self.version
+  }
+
+  /// Returns the cid codec.
+  pub fn codec(&self) -> u64 {
+    self.codec
+  }
+
+  /// Returns the code of the cid multihash.
+  pub fn hash_code(&self) -> u64 {
+    self.hash_code
+  }
+
+  /// Returns the digest of the cid multihash.
+  pub fn digest(&self) -> &[u8] {
+    &self.digest
+  }
+
+  /// Reads a CID from the start of `bytes`.
+  ///
+  /// Returns the CID and the remaining bytes after it.
+  pub fn read(bytes: &[u8]) -> Result<(Self, &[u8])> {
+    let (cid, rest) = DecodeContext::unbounded().read_ref(bytes)?;
+    Ok((Self::from(cid), rest))
+  }
+
+  /// Returns the encoded bytes of the cid multihash.
+  pub fn hash_bytes(&self) -> Vec<u8> {
+    let mut code = varint_encode::u64_buffer();
+    let mut size = varint_encode::u64_buffer();
+    let code = varint_encode::u64(self.hash_code, &mut code);
+    let size = varint_encode::u64(self.digest.len() as u64, &mut size);
+    let mut bytes =
+      Vec::with_capacity(code.len() + size.len() + self.digest.len());
+    bytes.extend_from_slice(code);
+    bytes.extend_from_slice(size);
+    bytes.extend_from_slice(&self.digest);
+    bytes
+  }
+
+  /// Returns the encoded bytes of the `CidDyn`.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let hash = self.hash_bytes();
+    if self.version == Version::V0 {
+      return hash;
+    }
+    let mut version = varint_encode::u64_buffer();
+    let mut codec = varint_encode::u64_buffer();
+    let version = varint_encode::u64(self.version.into(), &mut version);
+    let codec = varint_encode::u64(self.codec, &mut codec);
+    let mut bytes =
+      Vec::with_capacity(version.len() + codec.len() + hash.len());
+    bytes.extend_from_slice(version);
+    bytes.extend_from_slice(codec);
+    bytes.extend_from_slice(&hash);
+    bytes
+  }
+
+  /// Convert the `CidDyn` into a multibase encoded string.
+  pub fn to_string_of_base(&self, base: Base) -> Result<String> {
+    match self.version {
+      Version::V0 => {
+        if base == Base::Base58Btc {
+          Ok(bases::encode_base58btc(&self.hash_bytes()))
+        } else {
+          Err(Error::InvalidCidV0Base)
+        }
+      }
+      Version::V1 => bases::encode_prefixed(base, &self.to_bytes()),
+    }
+  }
+
+  /// Copies the `CidDyn` into a [`Cid`] of allocated size `S`.
+  ///
+  /// Fails with [`Error::DigestTooLarge`] if the digest doesn't fit into the
+  /// allocated size.
+  pub fn to_cid<S: Size>(&self) -> Result<Cid<S>> {
+    let hash = wrap_digest(self.hash_code, &self.digest)?;
+    Cid::new(self.version, self.codec, hash)
+  }
+
+  fn to_default_string(&self) -> String {
+    match self.version {
+      Version::V0 => bases::encode_base58btc(&self.hash_bytes()),
+      Version::V1 => {
+        let bytes = self.to_bytes();
+        let mut s = String::with_capacity(1 + base32::encoded_len(bytes.len()));
+        s.push(Base::Base32Lower.code());
+        base32::push_lower(&bytes, &mut s);
+        s
+      }
+    }
+  }
+}
+
+impl<'a> From<CidRef<'a>> for CidDyn {
+  fn from(cid: CidRef<'a>) -> Self {
+    Self {
+      version: cid.version(),
+      codec: cid.codec(),
+      hash_code: cid.hash_code(),
+      digest: cid.digest().into(),
+    }
+  }
+}
+
+impl<S: Size> From<&Cid<S>> for CidDyn {
+  fn from(cid: &Cid<S>) -> Self {
+    Self {
+      version: cid.version(),
+      codec: cid.codec(),
+      hash_code: cid.hash().code(),
+      digest: cid.hash().digest().into(),
+    }
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CidDyn {
+  fn from(cid: Cid<S>) -> Self {
+    Self::from(&cid)
+  }
+}
+
+impl<S: Size> TryFrom<CidDyn> for Cid<S> {
+  type Error = Error;
+
+  fn try_from(cid: CidDyn) -> Result<Self> {
+    cid.to_cid()
+  }
+}
+
+impl TryFrom<&[u8]> for CidDyn {
+  type Error = Error;
+
+  fn try_from(bytes: &[u8]) -> Result<Self> {
+    Self::read(bytes).map(|(cid, _)| cid)
+  }
+}
+
+impl TryFrom<&str> for CidDyn {
+  type Error = Error;
+
+  fn try_from(cid_str: &str) -> Result<Self> {
+    Self::try_from(cid::decode_str(cid_str)?.as_slice())
+  }
+}
+
+impl str::FromStr for CidDyn {
+  type Err = Error;
+
+  fn from_str(cid_str: &str) -> Result<Self> {
+    Self::try_from(cid_str)
+  }
+}
+
+impl From<CidDyn> for Vec<u8> {
+  fn from(cid: CidDyn) -> Self {
+    cid.to_bytes()
+  }
+}
+
+impl fmt::Display for CidDyn {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.pad(&self.to_default_string())
+  }
+}
+
+impl fmt::Debug for CidDyn {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+      f.debug_struct("CidDyn")
+        .field("version", &self.version())
+        .field("codec", &self.codec())
+        .field("hash_code", &self.hash_code())
+        .field("digest", &self.digest())
+        .finish()
+    } else {
+      write!(f, "CidDyn({})", self.to_default_string())
+    }
+  }
+}
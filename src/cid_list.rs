@@ -0,0 +1,288 @@
+//! A bounded list of distinct CIDs for extrinsics.
+//!
+//! Pallets that accept batches of CIDs from users, e.g. to pin or to
+//! register them, need a bound on the batch for their weights and can't
+//! charge twice for the same CID. [`CidList`] checks both when it is built
+//! and when it is decoded, so the checks can't be skipped by a call that is
+//! submitted directly.
+use sp_std::{
+  convert::TryFrom, fmt, marker::PhantomData, ops::Deref, slice, vec::Vec,
+};
+
+use parity_scale_codec::{
+  Compact, CompactLen, Decode, Encode, EncodeLike, Error, Input,
+  MaxEncodedLen, Output,
+};
+use sp_multihash::{
+  typenum::Unsigned, MultihashGeneric as Multihash, Size, U64,
+};
+
+use crate::cid::Cid;
+
+/// A list of at most `MaxLen` distinct CIDs, in the order they were added.
+///
+/// The SCALE encoding is the same as the one of a `Vec` of [`Cid`]s.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidList, CidListError};
+/// use sp_multihash::{typenum::U2, Code, MultihashDigest};
+///
+/// let foo = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let bar = Cid::new_v1(0x55, Code::Sha2_256.digest(b"bar"));
+/// let mut list = CidList::<U2>::new();
+/// list.try_push(foo).unwrap();
+/// assert_eq!(list.try_push(foo), Err(CidListError::Duplicate { index: 1 }));
+/// list.try_push(bar).unwrap();
+/// let baz = Cid::new_v1(0x55, Code::Sha2_256.digest(b"baz"));
+/// assert_eq!(list.try_push(baz), Err(CidListError::TooLong { len: 3 }));
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CidList<MaxLen: Unsigned, S: Size = U64> {
+  /// The CIDs, without duplicates.
+  cids: Vec<Cid<S>>,
+  /// The bound on the number of CIDs.
+  _max_len: PhantomData<MaxLen>,
+}
+
+/// The reason a list of CIDs isn't a valid [`CidList`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CidListError {
+  /// The list has more CIDs than the bound.
+  TooLong {
+    /// The number of CIDs.
+    len: usize,
+  },
+  /// A CID occurs more than once.
+  Duplicate {
+    /// The position of the second occurrence.
+    index: usize,
+  },
+}
+
+impl<MaxLen: Unsigned, S: Size> CidList<MaxLen, S> {
+  /// Creates an empty list.
+  pub fn new() -> Self {
+    Self { cids: Vec::new(), _max_len: PhantomData }
+  }
+
+  /// Returns the maximum number of CIDs.
+  pub fn bound() -> usize {
+    MaxLen::USIZE
+  }
+
+  /// Appends a CID, unless the list is full or already has it.
+  pub fn try_push(&mut self, cid: Cid<S>) -> Result<(), CidListError> {
+    let index = self.cids.len();
+    if self.cids.contains(&cid) {
+      return Err(CidListError::Duplicate { index });
+    }
+    if index >= MaxLen::USIZE {
+      return Err(CidListError::TooLong { len: index + 1 });
+    }
+    self.cids.push(cid);
+    Ok(())
+  }
+
+  /// Returns the CIDs.
+  pub fn as_slice(&self) -> &[Cid<S>] {
+    &self.cids
+  }
+
+  /// Unwraps the CIDs.
+  pub fn into_inner(self) -> Vec<Cid<S>> {
+    self.cids
+  }
+
+  /// Checks the bound and that the CIDs are distinct.
+  fn check(cids: &[Cid<S>]) -> Result<(), CidListError> {
+    if cids.len() > MaxLen::USIZE {
+      return Err(CidListError::TooLong { len: cids.len() });
+    }
+    // Sort the positions instead of the CIDs to report the second occurrence
+    let mut order: Vec<usize> = (0..cids.len()).collect();
+    order.sort_by(|a, b| cids[*a].cmp(&cids[*b]).then(a.cmp(b)));
+    let duplicate = order
+      .windows(2)
+      .filter(|pair| cids[pair[0]] == cids[pair[1]])
+      .map(|pair| pair[1])
+      .min();
+    match duplicate {
+      Some(index) => Err(CidListError::Duplicate { index }),
+      None => Ok(()),
+    }
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> Default for CidList<MaxLen, S> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> Deref for CidList<MaxLen, S> {
+  type Target = [Cid<S>];
+
+  fn deref(&self) -> &[Cid<S>] {
+    &self.cids
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> TryFrom<Vec<Cid<S>>> for CidList<MaxLen, S> {
+  type Error = CidListError;
+
+  fn try_from(cids: Vec<Cid<S>>) -> Result<Self, CidListError> {
+    Self::check(&cids)?;
+    Ok(Self { cids, _max_len: PhantomData })
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> From<CidList<MaxLen, S>> for Vec<Cid<S>> {
+  fn from(list: CidList<MaxLen, S>) -> Self {
+    list.cids
+  }
+}
+
+impl<'a, MaxLen: Unsigned, S: Size> IntoIterator for &'a CidList<MaxLen, S> {
+  type Item = &'a Cid<S>;
+  type IntoIter = slice::Iter<'a, Cid<S>>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.cids.iter()
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> Encode for CidList<MaxLen, S>
+where
+  Multihash<S>: Encode,
+{
+  fn size_hint(&self) -> usize {
+    self.cids.size_hint()
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    self.cids.encode_to(dest)
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> EncodeLike for CidList<MaxLen, S>
+where
+  Multihash<S>: Encode,
+{
+}
+
+impl<MaxLen: Unsigned, S: Size> EncodeLike<Vec<Cid<S>>>
+  for CidList<MaxLen, S>
+where
+  Multihash<S>: Encode,
+{
+}
+
+impl<MaxLen: Unsigned, S: Size> Decode for CidList<MaxLen, S>
+where
+  Multihash<S>: Decode,
+{
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    // Check the length before decoding any CID
+    let len = Compact::<u32>::decode(input)?.0 as usize;
+    if len > MaxLen::USIZE {
+      return Err("too many CIDs in list".into());
+    }
+    let cids = (0..len)
+      .map(|_| Cid::decode(input))
+      .collect::<Result<Vec<_>, _>>()?;
+    Self::try_from(cids).map_err(|_| "duplicate CID in list".into())
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> MaxEncodedLen for CidList<MaxLen, S>
+where
+  Multihash<S>: Encode,
+{
+  fn max_encoded_len() -> usize {
+    Compact::<u32>::compact_len(&(MaxLen::USIZE as u32))
+      + MaxLen::USIZE * Cid::<S>::max_encoded_len()
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<MaxLen: Unsigned + 'static, S: Size + 'static> scale_info::TypeInfo
+  for CidList<MaxLen, S>
+{
+  type Identity = Vec<Cid<S>>;
+
+  fn type_info() -> scale_info::Type {
+    <Vec<Cid<S>> as scale_info::TypeInfo>::type_info()
+  }
+}
+
+impl<MaxLen: Unsigned, S: Size> fmt::Debug for CidList<MaxLen, S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(&self.cids).finish()
+  }
+}
+
+impl fmt::Display for CidListError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CidListError::TooLong { len } => {
+        write!(f, "List of {} CIDs exceeds the bound", len)
+      }
+      CidListError::Duplicate { index } => {
+        write!(f, "Duplicate CID at position {}", index)
+      }
+    }
+  }
+}
+
+impl core::error::Error for CidListError {}
+
+#[cfg(test)]
+mod tests {
+  use sp_std::convert::TryFrom;
+
+  use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
+  use sp_multihash::{typenum::U3, Code, MultihashDigest, U64};
+
+  use super::{CidList, CidListError};
+  use crate::cid::Cid;
+
+  fn cids() -> Vec<Cid<U64>> {
+    [b"foo", b"bar", b"baz", b"qux"]
+      .iter()
+      .map(|data| Cid::new_v1(0x55, Code::Sha2_256.digest(*data)))
+      .collect()
+  }
+
+  #[test]
+  fn checks_bound_and_duplicates() {
+    let cids = cids();
+    let list = CidList::<U3, U64>::try_from(cids[..3].to_vec()).unwrap();
+    assert_eq!(list.as_slice(), &cids[..3]);
+    assert_eq!(
+      CidList::<U3, U64>::try_from(cids.clone()),
+      Err(CidListError::TooLong { len: 4 })
+    );
+    let twice = vec![cids[1], cids[0], cids[1]];
+    assert_eq!(
+      CidList::<U3, U64>::try_from(twice),
+      Err(CidListError::Duplicate { index: 2 })
+    );
+  }
+
+  #[test]
+  fn decode_checks_bound_and_duplicates() {
+    let cids = cids();
+    let list = CidList::<U3, U64>::try_from(cids[..3].to_vec()).unwrap();
+    let encoded = list.encode();
+    assert_eq!(encoded, cids[..3].to_vec().encode());
+    assert_eq!(CidList::<U3, U64>::decode(&mut &encoded[..]).unwrap(), list);
+    assert!(encoded.len() <= CidList::<U3, U64>::max_encoded_len());
+
+    let too_long = cids.encode();
+    assert!(CidList::<U3, U64>::decode(&mut &too_long[..]).is_err());
+    let twice = vec![cids[0], cids[0]].encode();
+    assert!(CidList::<U3, U64>::decode(&mut &twice[..]).is_err());
+  }
+}
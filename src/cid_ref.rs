@@ -0,0 +1,170 @@
+//! This module contains a borrowed view of a binary encoded CID.
+//!
+//! A [`CidRef`] validates the structure of a CID when it is created, but keeps
+//! pointing into the original buffer instead of copying the digest. This is
+//! useful when scanning large buffers (e.g. memory mapped CAR files), where
+//! most CIDs are only inspected and never need to be owned.
+use sp_std::{convert::TryFrom, fmt};
+
+use alloc::string::String;
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  base32, bases,
+  cid::{wrap_digest, Cid},
+  context::DecodeContext,
+  error::{Error, Result},
+  prefix::CidPrefix,
+  version::Version,
+};
+
+/// A borrowed view of a binary encoded CID.
+///
+/// The version, codec and multihash are parsed at construction, the digest is
+/// not copied.
+#[derive(PartialEq, Eq, Clone, Copy, PartialOrd, Ord, Hash)]
+pub struct CidRef<'a> {
+  /// The bytes of the CID, as they were read.
+  bytes: &'a [u8],
+  /// The version of CID.
+  version: Version,
+  /// The codec of CID.
+  codec: u64,
+  /// The multihash code of CID.
+  hash_code: u64,
+  /// Offset of the multihash within `bytes`.
+  hash_offset: usize,
+  /// Offset of the digest within `bytes`.
+  digest_offset: usize,
+}
+
+impl<'a> CidRef<'a> {
+  /// Reads a CID from the start of `bytes`.
+  ///
+  /// Returns the CID and the remaining bytes after it. Byte offsets in errors
+  /// are positions within `bytes`. See [`DecodeContext::read_ref`] for
+  /// tighter limits.
+  pub fn read(bytes: &'a [u8]) -> Result<(Self, &'a [u8])> {
+    DecodeContext::new().read_ref(bytes)
+  }
+
+  /// Creates the view of the CID at the start of `bytes` with the header
+  /// `prefix`, if its digest is complete.
+  pub(crate) fn from_prefix(
+    bytes: &'a [u8],
+    prefix: CidPrefix,
+  ) -> Result<(Self, &'a [u8])> {
+    let digest_offset = prefix.header_len();
+    if prefix.digest_len() > bytes.len() - digest_offset {
+      return Err(Error::UnexpectedEof { offset: digest_offset });
+    }
+
+    let (bytes, rest) = bytes.split_at(prefix.total_len());
+    let cid = Self {
+      bytes,
+      version: prefix.version(),
+      codec: prefix.codec(),
+      hash_code: prefix.hash_code(),
+      hash_offset: prefix.hash_offset(),
+      digest_offset,
+    };
+    Ok((cid, rest))
+  }
+
+  /// Returns the cid version.
+  pub fn version(&self) -> Version {
+    self.version
+  }
+
+  /// Returns the cid codec.
+  pub fn codec(&self) -> u64 {
+    self.codec
+  }
+
+  /// Returns the code of the cid multihash.
+  pub fn hash_code(&self) -> u64 {
+    self.hash_code
+  }
+
+  /// Returns the digest of the cid multihash.
+  pub fn digest(&self) -> &'a [u8] {
+    &self.bytes[self.digest_offset..]
+  }
+
+  /// Returns the encoded bytes of the cid multihash.
+  pub fn hash_bytes(&self) -> &'a [u8] {
+    &self.bytes[self.hash_offset..]
+  }
+
+  /// Returns the encoded bytes of the `CidRef`.
+  pub fn as_bytes(&self) -> &'a [u8] {
+    self.bytes
+  }
+
+  /// Copies the `CidRef` into an owned [`Cid`].
+  ///
+  /// Fails with [`Error::DigestTooLarge`] if the digest doesn't fit into the
+  /// allocated size `S`.
+  pub fn to_owned<S: Size>(&self) -> Result<Cid<S>> {
+    let hash = wrap_digest(self.hash_code, self.digest())?;
+    Cid::new(self.version, self.codec, hash)
+  }
+
+  /// Convert the `CidRef` into a multibase encoded string.
+  pub fn to_string_of_base(&self, base: Base) -> Result<String> {
+    match self.version {
+      Version::V0 => {
+        if base == Base::Base58Btc {
+          Ok(bases::encode_base58btc(self.hash_bytes()))
+        } else {
+          Err(Error::InvalidCidV0Base)
+        }
+      }
+      Version::V1 => bases::encode_prefixed(base, self.bytes),
+    }
+  }
+
+  /// Writes the default string form, without allocating.
+  fn write_default<W: fmt::Write>(&self, w: &mut W) -> fmt::Result {
+    match self.version {
+      Version::V0 => bases::write_base58btc(self.hash_bytes(), w),
+      Version::V1 => {
+        w.write_char(Base::Base32Lower.code())?;
+        base32::encode_lower(self.bytes, w)
+      }
+    }
+  }
+}
+
+impl<'a> TryFrom<&'a [u8]> for CidRef<'a> {
+  type Error = Error;
+
+  fn try_from(bytes: &'a [u8]) -> Result<Self> {
+    Self::read(bytes).map(|(cid, _)| cid)
+  }
+}
+
+impl<'a> fmt::Display for CidRef<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    self.write_default(f)
+  }
+}
+
+impl<'a> fmt::Debug for CidRef<'a> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+      f.debug_struct("CidRef")
+        .field("version", &self.version())
+        .field("codec", &self.codec())
+        .field("hash_code", &self.hash_code())
+        .field("digest", &self.digest())
+        .finish()
+    } else {
+      f.write_str("CidRef(")?;
+      self.write_default(f)?;
+      f.write_str(")")
+    }
+  }
+}
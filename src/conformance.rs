@@ -0,0 +1,133 @@
+//! Invariant checks for CIDs, behind the `conformance` feature.
+//!
+//! Crates that wrap [`Cid`] in their own types, or that store and transport
+//! CIDs in their own formats, can run the same consistency checks against
+//! their CIDs in their CI that this crate runs against its own. The checks
+//! panic with a description of the broken invariant, like `assert!`.
+// The checks are meant for tests, where panics report failures.
+#![allow(clippy::panic)]
+
+use sp_std::convert::TryFrom;
+
+use alloc::string::ToString;
+use sp_multihash::Size;
+
+use crate::{
+  cid::{detect_base, Cid, CidBase},
+  cid_ref::CidRef,
+  options::ParseOptions,
+  version::Version,
+  weights::BASES,
+};
+
+/// Checks that the binary form, the strings and the parts of `cid` all
+/// convert back to `cid`.
+///
+/// Every multibase the CID can be written in is checked, which depends on
+/// the version and the enabled base features.
+///
+/// # Panics
+///
+/// Panics if any of the conversions fails or returns a different CID.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{conformance, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// conformance::assert_roundtrip(&cid);
+/// ```
+pub fn assert_roundtrip<S: Size>(cid: &Cid<S>) {
+  let bytes = cid.to_bytes();
+  assert_eq!(bytes.len(), cid.binary_len(), "binary length of {}", cid);
+  match Cid::<S>::try_from(bytes.as_slice()) {
+    Ok(decoded) => assert_eq!(&decoded, cid, "binary round trip"),
+    Err(err) => panic!("binary form of {} doesn't decode: {}", cid, err),
+  }
+  match CidRef::read(&bytes) {
+    Ok((cid_ref, rest)) => {
+      assert!(rest.is_empty(), "CidRef of {} leaves bytes", cid);
+      assert_eq!(cid_ref.version(), cid.version(), "CidRef version");
+      assert_eq!(cid_ref.codec(), cid.codec(), "CidRef codec");
+      assert_eq!(cid_ref.hash_bytes(), cid.hash().to_bytes(), "CidRef hash");
+    }
+    Err(err) => panic!("binary form of {} doesn't read: {}", cid, err),
+  }
+
+  match Cid::new(cid.version(), cid.codec(), cid.hash().clone()) {
+    Ok(rebuilt) => assert_eq!(&rebuilt, cid, "parts round trip"),
+    Err(err) => panic!("parts of {} don't build a CID: {}", cid, err),
+  }
+
+  let s = cid.to_string();
+  match Cid::<S>::try_from(s.as_str()) {
+    Ok(parsed) => assert_eq!(&parsed, cid, "string round trip of {}", s),
+    Err(err) => panic!("{} doesn't parse: {}", s, err),
+  }
+  for base in BASES {
+    // Bases that can't write this CID or aren't enabled are skipped
+    let s = match cid.to_string_of_base(base) {
+      Ok(s) => s,
+      Err(_) => continue,
+    };
+    match Cid::<S>::try_from(s.as_str()) {
+      Ok(parsed) => assert_eq!(&parsed, cid, "{:?} round trip", base),
+      Err(err) => panic!("{:?} string {} doesn't parse: {}", base, s, err),
+    }
+  }
+}
+
+/// Checks that `cid_str` parses, that the CID written in the same base
+/// parses to the same CID and, if `cid_str` is canonical, is `cid_str`
+/// again. Then checks the CID with [`assert_roundtrip`].
+///
+/// A string is canonical if it is accepted by [`ParseOptions::strict`], has
+/// no `/ipfs/` prefix and has a multibase prefix unless it is a CIDv0.
+/// Returns the parsed CID.
+///
+/// # Panics
+///
+/// Panics if `cid_str` doesn't parse or any of the checks fails.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::conformance;
+/// use sp_multihash::U64;
+///
+/// let s = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+/// let cid = conformance::assert_string_roundtrip::<U64>(s);
+/// assert_eq!(cid.to_string(), s);
+/// ```
+pub fn assert_string_roundtrip<S: Size>(cid_str: &str) -> Cid<S> {
+  let (cid, meta) = match Cid::<S>::parse(cid_str) {
+    Ok(parsed) => parsed,
+    Err(err) => panic!("{} doesn't parse: {}", cid_str, err),
+  };
+  let base = meta.base().base();
+  let written = match cid.to_string_of_base(base) {
+    Ok(written) => written,
+    Err(err) => panic!("{} can't be written in {:?}: {}", cid, base, err),
+  };
+  match Cid::<S>::try_from(written.as_str()) {
+    Ok(parsed) => assert_eq!(parsed, cid, "round trip of {}", cid_str),
+    Err(err) => panic!("{} doesn't parse: {}", written, err),
+  }
+  // A CIDv0 can also be written as a Base58Btc multibase string
+  let canonical = meta.wrapper().is_none()
+    && (meta.base() == CidBase::V0) == (cid.version() == Version::V0)
+    && ParseOptions::strict().parse::<S>(cid_str).is_ok();
+  if canonical {
+    assert_eq!(written, cid_str, "canonical string round trip");
+  }
+  assert_eq!(
+    detect_base(&written).ok().map(|detected| detected.base()),
+    Some(base),
+    "base of {}",
+    written
+  );
+  assert_roundtrip(&cid);
+  cid
+}
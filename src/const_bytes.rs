@@ -0,0 +1,126 @@
+//! Binary CIDs built at compile time.
+//!
+//! Genesis configs and test fixtures embed well-known CIDs. Parsing them from
+//! strings, or hashing their blocks, on every start costs runtime work for
+//! data that never changes. [`cid_to_bytes`] and the [`cid_bytes!`] macro
+//! encode the parts of a CID in a `const` context instead, so the binary CID
+//! can be a `static`.
+//!
+//! [`cid_bytes!`]: crate::cid_bytes!
+use crate::{cid::varint_len, version::Version};
+
+/// Returns the length of the binary form of a CID with the given parts.
+///
+/// This is the `N` of [`cid_to_bytes`].
+pub const fn cid_binary_len(
+  version: Version,
+  codec: u64,
+  hash_code: u64,
+  digest_len: usize,
+) -> usize {
+  let multihash = varint_len(hash_code) + varint_len(digest_len as u64);
+  match version {
+    Version::V0 => multihash + digest_len,
+    Version::V1 => 1 + varint_len(codec) + multihash + digest_len,
+  }
+}
+
+/// Writes the varint of `n` into `buf` at `pos`, returns the position after
+/// it.
+const fn write_varint<const N: usize>(
+  buf: &mut [u8; N],
+  mut pos: usize,
+  mut n: u64,
+) -> usize {
+  while n >= 0x80 {
+    buf[pos] = (n as u8) | 0x80;
+    n >>= 7;
+    pos += 1;
+  }
+  buf[pos] = n as u8;
+  pos + 1
+}
+
+/// Encodes the parts of a CID into its binary form at compile time.
+///
+/// The parts aren't validated, like in [`Cid::from_raw_parts_unchecked`]:
+/// the result is the binary form of the CID that function returns. `N` must
+/// be [`cid_binary_len`] of the parts, [`cid_bytes!`] computes it. Evaluating
+/// the function in a `const` with the wrong `N`, or a digest longer than 255
+/// bytes, fails to compile.
+///
+/// [`Cid::from_raw_parts_unchecked`]: crate::Cid::from_raw_parts_unchecked
+/// [`cid_bytes!`]: crate::cid_bytes!
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use sp_cid::{cid_binary_len, cid_to_bytes, Cid, Version};
+///
+/// const LEN: usize = cid_binary_len(Version::V1, 0x55, 0x00, 3);
+/// static FOO: [u8; LEN] = cid_to_bytes(Version::V1, 0x55, 0x00, b"foo");
+///
+/// let cid = Cid::try_from(&FOO[..]).unwrap();
+/// assert_eq!(cid.hash().digest(), b"foo");
+/// ```
+pub const fn cid_to_bytes<const N: usize>(
+  version: Version,
+  codec: u64,
+  hash_code: u64,
+  digest: &[u8],
+) -> [u8; N] {
+  assert!(digest.len() <= 0xff, "the digest is longer than 255 bytes");
+  assert!(
+    N == cid_binary_len(version, codec, hash_code, digest.len()),
+    "N isn't the binary length of the CID"
+  );
+  let mut buf = [0u8; N];
+  let mut pos = 0;
+  if let Version::V1 = version {
+    pos = write_varint(&mut buf, pos, version.to_u64());
+    pos = write_varint(&mut buf, pos, codec);
+  }
+  pos = write_varint(&mut buf, pos, hash_code);
+  pos = write_varint(&mut buf, pos, digest.len() as u64);
+  let mut i = 0;
+  while i < digest.len() {
+    buf[pos + i] = digest[i];
+    i += 1;
+  }
+  buf
+}
+
+/// Encodes the parts of a CID into a `[u8; N]` at compile time, with `N`
+/// computed from the parts.
+///
+/// The arguments are the ones of [`cid_to_bytes`] and must be constant
+/// expressions.
+///
+/// # Example
+///
+/// ```
+/// use std::convert::TryFrom;
+///
+/// use sp_cid::{cid_bytes, Cid, Version};
+///
+/// const SHA2_256: u64 = 0x12;
+/// static GENESIS: &[u8] = &cid_bytes!(Version::V0, 0x70, SHA2_256, &[0; 32]);
+///
+/// let cid = Cid::try_from(GENESIS).unwrap();
+/// assert_eq!(cid.version(), Version::V0);
+/// assert_eq!(GENESIS.len(), Cid::V0_BINARY_LEN);
+/// ```
+#[macro_export]
+macro_rules! cid_bytes {
+  ($version:expr, $codec:expr, $hash_code:expr, $digest:expr $(,)?) => {{
+    // Items aren't hygienic, so the names avoid clashes with the arguments
+    const __CID_DIGEST: &[u8] = $digest;
+    const __CID_LEN: usize =
+      $crate::cid_binary_len($version, $codec, $hash_code, __CID_DIGEST.len());
+    const __CID_BYTES: [u8; __CID_LEN] =
+      $crate::cid_to_bytes($version, $codec, $hash_code, __CID_DIGEST);
+    __CID_BYTES
+  }};
+}
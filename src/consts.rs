@@ -0,0 +1,66 @@
+//! Well-known CIDs.
+//!
+//! Multihashes can't be built in const context, so the CIDs are given as
+//! their strings and binary forms. The binary forms are encoded from their
+//! parts with [`cid_bytes!`] and convert to a [`Cid`] with `TryFrom`:
+//!
+//! ```
+//! use sp_cid::{consts, Cid};
+//! use std::convert::TryFrom;
+//!
+//! let cid = Cid::try_from(&consts::EMPTY_DIR_BYTES).unwrap();
+//! assert_eq!(cid.to_string(), consts::EMPTY_DIR);
+//! ```
+//!
+//! [`Cid`]: crate::Cid
+//! [`cid_bytes!`]: crate::cid_bytes!
+use crate::{
+  cid::{DAG_PB, IDENTITY, SHA2_256},
+  version::Version,
+};
+
+const RAW: u64 = 0x55;
+
+/// The SHA2-256 digest of the empty UnixFS directory block.
+const EMPTY_DIR_DIGEST: [u8; 32] = [
+  0x59, 0x94, 0x84, 0x39, 0x06, 0x5f, 0x29, 0x61, 0x9e, 0xf4, 0x12, 0x80,
+  0xcb, 0xb9, 0x32, 0xbe, 0x52, 0xc5, 0x6d, 0x99, 0xc5, 0x96, 0x6b, 0x65,
+  0xe0, 0x11, 0x12, 0x39, 0xf0, 0x98, 0xbb, 0xef,
+];
+
+/// The SHA2-256 digest of the empty block.
+const EMPTY_RAW_DIGEST: [u8; 32] = [
+  0xe3, 0xb0, 0xc4, 0x42, 0x98, 0xfc, 0x1c, 0x14, 0x9a, 0xfb, 0xf4, 0xc8,
+  0x99, 0x6f, 0xb9, 0x24, 0x27, 0xae, 0x41, 0xe4, 0x64, 0x9b, 0x93, 0x4c,
+  0xa4, 0x95, 0x99, 0x1b, 0x78, 0x52, 0xb8, 0x55,
+];
+
+/// The empty UnixFS directory as a CIDv0, DAG-PB hashed with SHA2-256.
+pub const EMPTY_DIR_V0: &str = "QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn";
+
+/// The binary form of [`EMPTY_DIR_V0`].
+pub const EMPTY_DIR_V0_BYTES: [u8; 34] =
+  crate::cid_bytes!(Version::V0, DAG_PB, SHA2_256, &EMPTY_DIR_DIGEST);
+
+/// The empty UnixFS directory as a CIDv1, DAG-PB hashed with SHA2-256.
+pub const EMPTY_DIR: &str =
+  "bafybeiczsscdsbs7ffqz55asqdf3smv6klcw3gofszvwlyarci47bgf354";
+
+/// The binary form of [`EMPTY_DIR`].
+pub const EMPTY_DIR_BYTES: [u8; 36] =
+  crate::cid_bytes!(Version::V1, DAG_PB, SHA2_256, &EMPTY_DIR_DIGEST);
+
+/// The empty raw block, hashed with SHA2-256.
+pub const EMPTY_RAW: &str =
+  "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+
+/// The binary form of [`EMPTY_RAW`].
+pub const EMPTY_RAW_BYTES: [u8; 36] =
+  crate::cid_bytes!(Version::V1, RAW, SHA2_256, &EMPTY_RAW_DIGEST);
+
+/// The empty raw block with an identity multihash, the data is inlined.
+pub const IDENTITY_EMPTY: &str = "bafkqaaa";
+
+/// The binary form of [`IDENTITY_EMPTY`].
+pub const IDENTITY_EMPTY_BYTES: [u8; 4] =
+  crate::cid_bytes!(Version::V1, RAW, IDENTITY, &[]);
@@ -0,0 +1,474 @@
+//! Resource limits and strictness of decoding, in one place.
+//!
+//! The decoders bound their work by limits that follow from the format: a
+//! CID is at most [`Cid::MAX_BINARY_LEN`] bytes and a varint at most 10
+//! bytes. Fuzzers and services that decode untrusted input often want
+//! tighter bounds and stricter parsing. A [`DecodeContext`] holds all of
+//! them and decodes with them, e.g. with [`DecodeContext::read_bytes`],
+//! [`DecodeContext::read_ref`] and [`DecodeContext::parse`].
+//!
+//! The other decoders of the crate, from [`Cid::read_bytes`] and
+//! `TryFrom<&[u8]>` to [`CidRef::read`] and [`Cid::from_str`], decode with
+//! the default context.
+//!
+//! [`Cid::from_str`]: core::str::FromStr::from_str
+use sp_std::convert::TryFrom;
+
+use bytecursor::ByteCursor;
+use sp_multihash::{MultihashGeneric as Multihash, Size};
+use unsigned_varint::{decode as varint_decode, encode as varint_encode};
+
+use crate::{
+  cid::{
+    decode_stripped, detect_base, wrap_digest, Cid, DAG_PB, MAX_LEN, SHA2_256,
+  },
+  cid_ref::CidRef,
+  error::{Error, Result},
+  options::ParseOptions,
+  prefix::CidPrefix,
+  version::Version,
+  weights,
+};
+
+/// The longest varint of a `u64`.
+const MAX_VARINT_LEN: usize = 10;
+
+/// Limits and strictness flags for decoding CIDs.
+///
+/// The default context accepts the same input as [`Cid::read_bytes`] and
+/// [`Cid::from_str`].
+///
+/// # Example
+///
+/// ```
+/// use bytecursor::ByteCursor;
+/// use sp_cid::{Cid, DecodeContext, Error};
+/// use sp_multihash::{Code, MultihashDigest, U64};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_512.digest(b"foo"));
+/// let context = DecodeContext::new().max_bytes(40);
+/// let read = context.read_bytes::<U64>(&mut ByteCursor::new(cid.to_bytes()));
+/// assert_eq!(read, Err(Error::LimitExceeded { offset: 4 }));
+/// ```
+///
+/// [`Cid::from_str`]: core::str::FromStr::from_str
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeContext {
+  /// The most bytes a binary CID may take.
+  max_bytes: usize,
+  /// The most bytes a varint may take.
+  max_varint_len: usize,
+  /// The longest digest, only raised for [`CidDyn`](crate::CidDyn).
+  max_digest_len: u64,
+  /// Whether to reject decoded strings with bytes after the CID.
+  reject_trailing_bytes: bool,
+  /// The options of string parsing.
+  options: ParseOptions,
+}
+
+impl Default for DecodeContext {
+  fn default() -> Self {
+    Self {
+      max_bytes: MAX_LEN,
+      max_varint_len: MAX_VARINT_LEN,
+      max_digest_len: u64::from(u8::MAX),
+      reject_trailing_bytes: false,
+      options: ParseOptions::new(),
+    }
+  }
+}
+
+impl DecodeContext {
+  /// Create a context with the limits of the format and lenient parsing.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Create a context with the limits of the format and all strictness
+  /// flags enabled, see [`ParseOptions::strict`] and
+  /// [`DecodeContext::reject_trailing_bytes`].
+  pub fn strict() -> Self {
+    Self {
+      reject_trailing_bytes: true,
+      options: ParseOptions::strict(),
+      ..Self::default()
+    }
+  }
+
+  /// Create a context without limits on the length of CIDs and their
+  /// digests, for [`CidDyn`](crate::CidDyn).
+  pub(crate) fn unbounded() -> Self {
+    Self {
+      max_bytes: usize::MAX,
+      max_digest_len: u64::MAX,
+      ..Self::default()
+    }
+  }
+
+  /// Sets the most bytes a binary CID may take, [`Cid::MAX_BINARY_LEN`] by
+  /// default. Larger values have no effect.
+  ///
+  /// Strings are limited to the length of that many bytes in their base,
+  /// and rejected before they are decoded. Input over the limit returns
+  /// [`Error::LimitExceeded`].
+  pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+    self.max_bytes = max_bytes.min(MAX_LEN);
+    self
+  }
+
+  /// Sets the most bytes of each varint, i.e. of the version, the codec, the
+  /// multihash code and the digest size, 10 by default. Larger values have
+  /// no effect.
+  ///
+  /// Longer varints return [`Error::LimitExceeded`] at their start.
+  pub fn max_varint_len(mut self, max_varint_len: usize) -> Self {
+    self.max_varint_len = max_varint_len.min(MAX_VARINT_LEN);
+    self
+  }
+
+  /// Sets whether decoded strings have to end with the CID. Off by default,
+  /// like [`Cid::from_str`].
+  ///
+  /// Strings with further bytes return [`Error::TrailingBytes`].
+  ///
+  /// [`Cid::from_str`]: core::str::FromStr::from_str
+  pub fn reject_trailing_bytes(mut self, reject: bool) -> Self {
+    self.reject_trailing_bytes = reject;
+    self
+  }
+
+  /// Sets the checks of strings before they are decoded, e.g. the accepted
+  /// bases. Lenient by default.
+  pub fn parse_options(mut self, options: ParseOptions) -> Self {
+    self.options = options;
+    self
+  }
+
+  /// Reads a CID from a byte stream within the limits of the context.
+  ///
+  /// Byte offsets in errors are positions within the stream. Fails with the
+  /// errors of [`Cid::read_bytes`] and with [`Error::LimitExceeded`].
+  pub fn read_bytes<S: Size>(&self, r: &mut ByteCursor) -> Result<Cid<S>> {
+    self.read_cid(r)
+  }
+
+  /// Reads a CID from a byte iterator within the limits of the context, see
+  /// [`Cid::from_byte_iter`].
+  pub fn read_byte_iter<S: Size, I: IntoIterator<Item = u8>>(
+    &self,
+    bytes: I,
+  ) -> Result<Cid<S>> {
+    self.read_cid(&mut IterSource { iter: bytes.into_iter(), position: 0 })
+  }
+
+  /// Reads a CID prefixed with its length within the limits of the context,
+  /// see [`Cid::read_framed`].
+  ///
+  /// The length prefix isn't limited, only the CID within the frame.
+  pub fn read_framed<S: Size>(&self, r: &mut ByteCursor) -> Result<Cid<S>> {
+    let len = crate::varint_read_u64(r)?;
+    let start = r.position() as usize;
+    let mut buf = [0u8; MAX_LEN];
+    // Any CID ends within `max_bytes`, the rest of a longer frame trails
+    let max = self.max_bytes.min(MAX_LEN);
+    let read = usize::try_from(len).map_or(max, |len| len.min(max));
+    if r.read_exact(&mut buf[..read]).is_err() {
+      return Err(Error::UnexpectedEof { offset: start });
+    }
+    let (cid, rest) =
+      self.read_ref(&buf[..read]).map_err(|err| err.shifted(start))?;
+    if !rest.is_empty() || len > read as u64 {
+      let offset = start + read - rest.len();
+      return Err(Error::TrailingBytes { offset });
+    }
+    cid.to_owned().map_err(|err| err.shifted(start))
+  }
+
+  /// Reads the header of the CID at the start of `bytes` within the limits
+  /// of the context, see [`CidPrefix::read`].
+  pub fn read_prefix(&self, bytes: &[u8]) -> Result<CidPrefix> {
+    let mut r = SliceSource { bytes, position: 0 };
+    self.read_header(&mut r, self.end(0))
+  }
+
+  /// Reads a CID from the start of `bytes` within the limits of the context,
+  /// see [`CidRef::read`].
+  pub fn read_ref<'a>(
+    &self,
+    bytes: &'a [u8],
+  ) -> Result<(CidRef<'a>, &'a [u8])> {
+    let prefix = self.read_prefix(bytes)?;
+    if prefix.total_len() > self.end(0) {
+      return Err(Error::LimitExceeded { offset: prefix.header_len() });
+    }
+    CidRef::from_prefix(bytes, prefix)
+  }
+
+  /// Reads a multihash from a byte stream within the limits of the context.
+  ///
+  /// Byte offsets in errors are positions within the stream. Fails with
+  /// [`Error::DigestTooLarge`] if the digest doesn't fit into the allocated
+  /// size.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use bytecursor::ByteCursor;
+  /// use sp_cid::{DecodeContext, Error};
+  /// use sp_multihash::{Code, MultihashDigest, U64};
+  ///
+  /// let hash = Code::Sha2_256.digest(b"foo");
+  /// let mut r = ByteCursor::new(hash.to_bytes());
+  /// let read = DecodeContext::new().read_multihash::<U64>(&mut r);
+  /// assert_eq!(read.unwrap(), hash);
+  /// ```
+  pub fn read_multihash<S: Size>(
+    &self,
+    r: &mut ByteCursor,
+  ) -> Result<Multihash<S>> {
+    let mut buf = [0u8; 255];
+    let end = self.end(r.position() as usize);
+    let hash_offset = r.position() as usize;
+    let code = self.read_varint(r, end)?;
+    let len = self.read_digest_len(r, end, hash_offset)?;
+    let digest = self.read_digest(r, end, &mut buf[..len])?;
+    wrap_digest(code, digest)
+  }
+
+  /// Parses a CID string within the limits of the context, with its
+  /// [`ParseOptions`], see [`ParseOptions::parse`].
+  ///
+  /// The string is rejected if it is longer than [`DecodeContext::max_bytes`]
+  /// allow, before it is decoded. Byte offsets in errors are positions
+  /// within the decoded bytes, except for the offset of a string over the
+  /// limit, which is the number of characters allowed.
+  pub fn parse<S: Size>(&self, cid_str: &str) -> Result<Cid<S>> {
+    self.options.parse_within(cid_str, self)
+  }
+
+  /// Decodes a CID string without a path prefix, which passed the checks of
+  /// the [`ParseOptions`].
+  pub(crate) fn read_str<S: Size>(&self, stripped: &str) -> Result<Cid<S>> {
+    // Strings without a known base fail to decode with a better error
+    if let Ok(base) = detect_base(stripped) {
+      // The multibase prefix comes on top of the encoded bytes
+      let limit = 1 + weights::max_base_len(base.base(), self.max_bytes);
+      if stripped.chars().take(limit + 1).count() > limit {
+        return Err(Error::LimitExceeded { offset: limit });
+      }
+    }
+    let bytes = decode_stripped(stripped)?;
+    let (cid, rest) = self.read_ref(&bytes)?;
+    if self.reject_trailing_bytes && !rest.is_empty() {
+      let offset = bytes.len() - rest.len();
+      return Err(Error::TrailingBytes { offset });
+    }
+    cid.to_owned()
+  }
+
+  /// Reads the version, codec, multihash code and digest of a CID from a
+  /// byte stream, with the digest read into `buf`.
+  pub(crate) fn read_parts<'a, R: Source>(
+    &self,
+    r: &mut R,
+    buf: &'a mut [u8; 255],
+  ) -> Result<(Version, u64, u64, &'a [u8])> {
+    let end = self.end(r.position());
+    let prefix = self.read_header(r, end)?;
+    let offset = r.position();
+    let digest = match buf.get_mut(..prefix.digest_len()) {
+      Some(digest) => digest,
+      None => return Err(Error::LimitExceeded { offset }),
+    };
+    let digest = self.read_digest(r, end, digest)?;
+    Ok((prefix.version(), prefix.codec(), prefix.hash_code(), digest))
+  }
+
+  /// Reads a CID from a byte source.
+  fn read_cid<S: Size, R: Source>(&self, r: &mut R) -> Result<Cid<S>> {
+    let mut buf = [0u8; 255];
+    let (version, codec, code, digest) = self.read_parts(r, &mut buf)?;
+    Cid::new(version, codec, wrap_digest(code, digest)?)
+  }
+
+  /// Reads the header of a CID ending before `end`, up to its digest.
+  fn read_header<R: Source>(&self, r: &mut R, end: usize) -> Result<CidPrefix> {
+    let start = r.position();
+    let version = self.read_varint(r, end)?;
+    let codec = self.read_varint(r, end)?;
+    // CIDv0 has the fixed `0x12 0x20` prefix
+    if [version, codec] == [SHA2_256, 0x20] {
+      return Ok(CidPrefix::new(Version::V0, DAG_PB, SHA2_256, 32, 0, 2));
+    }
+    let version = Version::try_from(version)?;
+    let hash_offset = r.position();
+    let code = self.read_varint(r, end)?;
+    let digest_len = self.read_digest_len(r, end, hash_offset)?;
+    if version == Version::V0 {
+      if codec != DAG_PB {
+        return Err(Error::InvalidCidV0Codec(codec));
+      }
+      if code != SHA2_256 {
+        return Err(Error::InvalidCidV0Multihash(code));
+      }
+    }
+    let digest_offset = r.position() - start;
+    let hash_offset = hash_offset - start;
+    Ok(CidPrefix::new(
+      version,
+      codec,
+      code,
+      digest_len,
+      hash_offset,
+      digest_offset,
+    ))
+  }
+
+  /// Returns the stream position up to which the input may be read.
+  fn end(&self, position: usize) -> usize {
+    position.saturating_add(self.max_bytes)
+  }
+
+  /// Reads the digest size of the multihash at `hash_offset`.
+  fn read_digest_len<R: Source>(
+    &self,
+    r: &mut R,
+    end: usize,
+    hash_offset: usize,
+  ) -> Result<usize> {
+    let size = self.read_varint(r, end)?;
+    match usize::try_from(size) {
+      Ok(len) if size <= self.max_digest_len => Ok(len),
+      _ => Err(Error::InvalidMultihash { offset: hash_offset }),
+    }
+  }
+
+  /// Fills `digest` from the stream, if it ends before `end`.
+  fn read_digest<'a, R: Source>(
+    &self,
+    r: &mut R,
+    end: usize,
+    digest: &'a mut [u8],
+  ) -> Result<&'a [u8]> {
+    let offset = r.position();
+    if offset + digest.len() > end {
+      return Err(Error::LimitExceeded { offset });
+    }
+    match r.fill(digest) {
+      true => Ok(digest),
+      false => Err(Error::UnexpectedEof { offset }),
+    }
+  }
+
+  /// Reads an unsigned varint of at most `max_varint_len` bytes, ending
+  /// before `end`.
+  fn read_varint<R: Source>(&self, r: &mut R, end: usize) -> Result<u64> {
+    let offset = r.position();
+    let mut b = varint_encode::u64_buffer();
+    for i in 0..b.len() {
+      if i == self.max_varint_len || offset + i == end {
+        return Err(Error::LimitExceeded { offset });
+      }
+      b[i] = match r.next_byte() {
+        Some(byte) => byte,
+        None => return Err(Error::UnexpectedEof { offset }),
+      };
+      if varint_decode::is_last(b[i]) {
+        return Ok(varint_decode::u64(&b[..=i])?.0);
+      }
+    }
+    Err(Error::VarIntDecodeError(varint_decode::Error::Overflow))
+  }
+}
+
+/// The input of the decoders.
+pub(crate) trait Source {
+  /// Returns the position of the next byte.
+  fn position(&self) -> usize;
+
+  /// Reads the next byte, if the input doesn't end before.
+  fn next_byte(&mut self) -> Option<u8>;
+
+  /// Fills `buf`, returns `false` if the input ends before.
+  fn fill(&mut self, buf: &mut [u8]) -> bool;
+}
+
+impl Source for ByteCursor {
+  fn position(&self) -> usize {
+    ByteCursor::position(self) as usize
+  }
+
+  fn next_byte(&mut self) -> Option<u8> {
+    let mut byte = [0u8];
+    match self.read(&mut byte) {
+      0 => None,
+      _ => Some(byte[0]),
+    }
+  }
+
+  fn fill(&mut self, buf: &mut [u8]) -> bool {
+    self.read_exact(buf).is_ok()
+  }
+}
+
+/// A slice read from its start.
+struct SliceSource<'a> {
+  /// The input.
+  bytes: &'a [u8],
+  /// The position of the next byte.
+  position: usize,
+}
+
+impl Source for SliceSource<'_> {
+  fn position(&self) -> usize {
+    self.position
+  }
+
+  fn next_byte(&mut self) -> Option<u8> {
+    let byte = self.bytes.get(self.position).copied()?;
+    self.position += 1;
+    Some(byte)
+  }
+
+  fn fill(&mut self, buf: &mut [u8]) -> bool {
+    let end = self.position + buf.len();
+    match self.bytes.get(self.position..end) {
+      Some(bytes) => {
+        buf.copy_from_slice(bytes);
+        self.position = end;
+        true
+      }
+      None => false,
+    }
+  }
+}
+
+/// An iterator counting the bytes it yields.
+struct IterSource<I> {
+  /// The input.
+  iter: I,
+  /// The number of bytes read.
+  position: usize,
+}
+
+impl<I: Iterator<Item = u8>> Source for IterSource<I> {
+  fn position(&self) -> usize {
+    self.position
+  }
+
+  fn next_byte(&mut self) -> Option<u8> {
+    let byte = self.iter.next()?;
+    self.position += 1;
+    Some(byte)
+  }
+
+  fn fill(&mut self, buf: &mut [u8]) -> bool {
+    buf.iter_mut().all(|byte| match self.next_byte() {
+      Some(next) => {
+        *byte = next;
+        true
+      }
+      None => false,
+    })
+  }
+}
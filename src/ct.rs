@@ -0,0 +1,70 @@
+//! Handling of digests with data-independent control flow.
+//!
+//! Enclaves and HSMs that store digests of secret data, e.g. commitments to
+//! keys, treat the digests as sensitive: timing that depends on their bytes
+//! leaks them. [`Cid::from_bytes_ct`] and [`Cid::ct_eq`] only branch on the
+//! header of a CID, which is public, and never on the digest bytes.
+//!
+//! String decoding isn't covered, the multibase decoders look up every
+//! symbol in tables. Sensitive CIDs should be kept in their binary form.
+use core::hint::black_box;
+
+use sp_multihash::Size;
+
+use crate::{
+  cid::{wrap_digest, Cid},
+  error::{Error, Result},
+  prefix::CidPrefix,
+};
+
+impl<S: Size> Cid<S> {
+  /// Decodes a binary CID at the start of `bytes` with control flow that
+  /// doesn't depend on the digest bytes.
+  ///
+  /// Only the version, codec and multihash header decide between success
+  /// and the errors of [`Cid::try_from`], the digest is copied with a loop
+  /// over its length. Accepts the same input as `Cid::try_from(&[u8])`,
+  /// including bytes after the CID.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"secret"));
+  /// let decoded = Cid::from_bytes_ct(&cid.to_bytes()).unwrap();
+  /// assert!(decoded.ct_eq(&cid));
+  /// ```
+  pub fn from_bytes_ct(bytes: &[u8]) -> Result<Self> {
+    let prefix = CidPrefix::read(bytes)?;
+    let digest = bytes
+      .get(prefix.header_len()..prefix.total_len())
+      .ok_or(Error::UnexpectedEof { offset: prefix.header_len() })?;
+    let mut buf = [0u8; 255];
+    for (dest, byte) in buf.iter_mut().zip(digest) {
+      *dest = black_box(*byte);
+    }
+    let hash = wrap_digest(prefix.hash_code(), &buf[..digest.len()])?;
+    Self::new(prefix.version(), prefix.codec(), hash)
+  }
+
+  /// Checks whether two CIDs are equal, comparing all bytes of the digests
+  /// instead of stopping at the first difference.
+  ///
+  /// The time only depends on the headers of the CIDs, it is the same for
+  /// all digests of equal length.
+  pub fn ct_eq<S2: Size>(&self, other: &Cid<S2>) -> bool {
+    let (a, b) = (self.hash(), other.hash());
+    let header = self.version() == other.version()
+      && self.codec() == other.codec()
+      && a.code() == b.code()
+      && a.digest().len() == b.digest().len();
+    let diff = a
+      .digest()
+      .iter()
+      .zip(b.digest())
+      .fold(0u8, |diff, (x, y)| black_box(diff | (x ^ y)));
+    header & (diff == 0)
+  }
+}
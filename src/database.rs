@@ -0,0 +1,182 @@
+//! Database column support for CIDs.
+//!
+//! CIDs are stored in their binary form, in a `BYTEA` column on Postgres and
+//! a `BLOB` column elsewhere. Decoding a column validates the CID, so a
+//! corrupted row surfaces as a decode error instead of a bogus CID.
+
+#[cfg(feature = "sqlx-codec")]
+mod sqlx_impls {
+  use std::convert::TryFrom;
+
+  use sp_multihash::Size;
+  use sqlx::{
+    database::{HasArguments, HasValueRef},
+    encode::IsNull,
+    error::BoxDynError,
+    Database, Decode, Encode, Type,
+  };
+
+  use crate::cid::Cid;
+
+  impl<DB: Database, S: Size> Type<DB> for Cid<S>
+  where
+    Vec<u8>: Type<DB>,
+  {
+    fn type_info() -> DB::TypeInfo {
+      <Vec<u8> as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+      <Vec<u8> as Type<DB>>::compatible(ty)
+    }
+  }
+
+  impl<'q, DB: Database, S: Size> Encode<'q, DB> for Cid<S>
+  where
+    Vec<u8>: Encode<'q, DB>,
+  {
+    fn encode_by_ref(
+      &self,
+      buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer,
+    ) -> IsNull {
+      self.to_bytes().encode(buf)
+    }
+  }
+
+  impl<'r, DB: Database, S: Size> Decode<'r, DB> for Cid<S>
+  where
+    &'r [u8]: Decode<'r, DB>,
+  {
+    fn decode(
+      value: <DB as HasValueRef<'r>>::ValueRef,
+    ) -> Result<Self, BoxDynError> {
+      let bytes = <&[u8] as Decode<DB>>::decode(value)?;
+      Ok(Cid::try_from(bytes)?)
+    }
+  }
+}
+
+#[cfg(feature = "diesel-codec")]
+mod diesel_impls {
+  use std::{convert::TryFrom, io::Write};
+
+  use diesel::{
+    backend::Backend,
+    deserialize::{self, FromSql, FromSqlRow, Queryable},
+    expression::{bound::Bound, AsExpression},
+    row::Row,
+    serialize::{self, Output, ToSql},
+    sql_types::Binary,
+  };
+  use sp_multihash::Size;
+
+  use crate::cid::Cid;
+
+  impl<S: Size> AsExpression<Binary> for Cid<S> {
+    type Expression = Bound<Binary, Self>;
+
+    fn as_expression(self) -> Self::Expression {
+      Bound::new(self)
+    }
+  }
+
+  impl<S: Size> AsExpression<Binary> for &Cid<S> {
+    type Expression = Bound<Binary, Self>;
+
+    fn as_expression(self) -> Self::Expression {
+      Bound::new(self)
+    }
+  }
+
+  impl<S: Size, DB: Backend> ToSql<Binary, DB> for Cid<S>
+  where
+    [u8]: ToSql<Binary, DB>,
+  {
+    fn to_sql<W: Write>(&self, out: &mut Output<W, DB>) -> serialize::Result {
+      self.to_bytes().as_slice().to_sql(out)
+    }
+  }
+
+  impl<S: Size, DB: Backend> FromSql<Binary, DB> for Cid<S>
+  where
+    Vec<u8>: FromSql<Binary, DB>,
+  {
+    fn from_sql(bytes: Option<&DB::RawValue>) -> deserialize::Result<Self> {
+      let bytes = Vec::<u8>::from_sql(bytes)?;
+      Ok(Cid::try_from(bytes.as_slice())?)
+    }
+  }
+
+  impl<S: Size, DB: Backend> FromSqlRow<Binary, DB> for Cid<S>
+  where
+    Self: FromSql<Binary, DB>,
+  {
+    fn build_from_row<R: Row<DB>>(row: &mut R) -> deserialize::Result<Self> {
+      FromSql::from_sql(row.take())
+    }
+  }
+
+  impl<S: Size, DB: Backend> Queryable<Binary, DB> for Cid<S>
+  where
+    Self: FromSql<Binary, DB>,
+  {
+    type Row = Self;
+
+    fn build(row: Self) -> Self {
+      row
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sp_multihash::{Code, MultihashDigest};
+
+  use crate::Cid;
+
+  #[cfg(feature = "sqlx-codec")]
+  #[test]
+  fn sqlx_round_trip() {
+    use sqlx::{Connection, SqliteConnection};
+
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+    runtime.block_on(async {
+      let url = "sqlite::memory:";
+      let mut conn = SqliteConnection::connect(url).await.unwrap();
+      let read: Cid = sqlx::query_scalar("SELECT ?")
+        .bind(cid)
+        .fetch_one(&mut conn)
+        .await
+        .unwrap();
+      assert_eq!(read, cid);
+
+      let invalid = sqlx::query_scalar::<_, Cid>("SELECT x'0155'")
+        .fetch_one(&mut conn)
+        .await;
+      assert!(matches!(invalid, Err(sqlx::Error::ColumnDecode { .. })));
+    });
+  }
+
+  #[cfg(feature = "diesel-codec")]
+  #[test]
+  fn diesel_round_trip() {
+    use diesel::{
+      dsl::sql, result::Error::DeserializationError, sql_types::Binary,
+      Connection, IntoSql, RunQueryDsl, SqliteConnection,
+    };
+
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let conn = SqliteConnection::establish(":memory:").unwrap();
+    let read: Cid =
+      diesel::select(cid.into_sql::<Binary>()).get_result(&conn).unwrap();
+    assert_eq!(read, cid);
+
+    let invalid =
+      diesel::select(sql::<Binary>("x'0155'")).get_result::<Cid>(&conn);
+    assert!(matches!(invalid, Err(DeserializationError(_))));
+  }
+}
@@ -0,0 +1,147 @@
+//! Decoding of CID streams.
+//!
+//! Some formats, e.g. block lists, are binary CIDs written back to back
+//! without length prefixes. A [`CidDecoder`] reads them from any reader,
+//! buffering partial reads until a whole CID is available.
+use std::{io::Read, marker::PhantomData};
+
+use sp_multihash::{Size, U64};
+
+use crate::{
+  cid::Cid,
+  cid_ref::CidRef,
+  error::{Error, Result},
+  prefix::CidPrefix,
+};
+
+/// The number of bytes requested from the reader at once.
+const CHUNK_LEN: usize = 4096;
+
+/// An iterator over the CIDs of a reader, until its end.
+///
+/// Byte offsets in errors are positions within the stream. Iteration stops
+/// after the first error.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidDecoder};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cids = [
+///   Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo")),
+///   Cid::new_v1(0x71, Code::Sha2_512.digest(b"bar")),
+/// ];
+/// let stream: Vec<u8> = cids.iter().flat_map(Cid::to_bytes).collect();
+/// let decoded: Result<Vec<Cid>, _> = CidDecoder::new(&stream[..]).collect();
+/// assert_eq!(decoded.unwrap(), cids);
+/// ```
+pub struct CidDecoder<R, S: Size = U64> {
+  /// The stream.
+  reader: R,
+  /// Bytes read from the stream that weren't decoded yet.
+  buf: Vec<u8>,
+  /// The stream position of the start of `buf`.
+  offset: usize,
+  /// Whether the stream ended or an error was returned.
+  done: bool,
+  /// The allocated size of the decoded CIDs.
+  size: PhantomData<S>,
+}
+
+impl<R: Read, S: Size> CidDecoder<R, S> {
+  /// Create a decoder reading from `reader`.
+  pub fn new(reader: R) -> Self {
+    Self {
+      reader,
+      buf: Vec::new(),
+      offset: 0,
+      done: false,
+      size: PhantomData,
+    }
+  }
+
+  /// Returns the stream position of the next CID.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Unwraps the reader. Bytes that were read but not decoded are lost.
+  pub fn into_inner(self) -> R {
+    self.reader
+  }
+
+  /// Reads the next chunk of the stream into the buffer, returning the
+  /// number of bytes read.
+  fn fill(&mut self) -> std::io::Result<usize> {
+    let mut chunk = [0u8; CHUNK_LEN];
+    loop {
+      match self.reader.read(&mut chunk) {
+        Ok(read) => {
+          self.buf.extend_from_slice(&chunk[..read]);
+          return Ok(read);
+        }
+        Err(err) if err.kind() == std::io::ErrorKind::Interrupted => (),
+        Err(err) => return Err(err),
+      }
+    }
+  }
+
+  /// Ends the iteration with `err`, whose offsets are relative to the
+  /// buffer.
+  fn fail(&mut self, err: Error) -> Option<Result<Cid<S>>> {
+    self.done = true;
+    Some(Err(err.shifted(self.offset)))
+  }
+}
+
+impl<R: Read, S: Size> Iterator for CidDecoder<R, S> {
+  type Item = Result<Cid<S>>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+    let len = loop {
+      // The header is complete once it reads without running out of bytes
+      let prefix = match CidPrefix::read(&self.buf) {
+        Ok(prefix) => Some(prefix),
+        Err(Error::UnexpectedEof { .. }) => None,
+        Err(err) => return self.fail(err),
+      };
+      match prefix {
+        Some(prefix) if prefix.total_len() <= self.buf.len() => {
+          break prefix.total_len();
+        }
+        _ => (),
+      }
+      match self.fill() {
+        Ok(0) if self.buf.is_empty() => {
+          self.done = true;
+          return None;
+        }
+        Ok(0) => {
+          let err = match prefix {
+            Some(prefix) => {
+              Error::UnexpectedEof { offset: prefix.header_len() }
+            }
+            None => CidPrefix::read(&self.buf).err()?,
+          };
+          return self.fail(err);
+        }
+        Ok(_) => (),
+        Err(err) => return self.fail(err.into()),
+      }
+    };
+    let cid =
+      CidRef::read(&self.buf[..len]).and_then(|(cid, _)| cid.to_owned());
+    match cid {
+      Ok(cid) => {
+        self.buf.drain(..len);
+        self.offset += len;
+        Some(Ok(cid))
+      }
+      Err(err) => self.fail(err),
+    }
+  }
+}
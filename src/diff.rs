@@ -0,0 +1,100 @@
+//! This module describes how two CIDs differ.
+//!
+//! Verification code that expected one CID and got another can report the
+//! fields that mismatched instead of two opaque strings.
+use sp_std::{fmt, vec::Vec};
+
+use sp_multihash::Size;
+
+use crate::{cid::Cid, version::Version};
+
+/// A field in which two CIDs differ, see [`Cid::diff`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CidDiff {
+  /// The versions differ.
+  Version {
+    /// The version of the expected CID.
+    expected: Version,
+    /// The version of the CID that was found.
+    found: Version,
+  },
+  /// The codecs differ.
+  Codec {
+    /// The codec of the expected CID.
+    expected: u64,
+    /// The codec of the CID that was found.
+    found: u64,
+  },
+  /// The hash functions of the multihashes differ.
+  HashCode {
+    /// The multihash code of the expected CID.
+    expected: u64,
+    /// The multihash code of the CID that was found.
+    found: u64,
+  },
+  /// The digests differ.
+  Digest,
+}
+
+impl fmt::Display for CidDiff {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CidDiff::Version { expected, found } => {
+        write!(f, "expected {}, found {}", expected, found)
+      }
+      CidDiff::Codec { expected, found } => {
+        write!(f, "expected codec 0x{:x}, found 0x{:x}", expected, found)
+      }
+      CidDiff::HashCode { expected, found } => {
+        write!(f, "expected multihash 0x{:x}, found 0x{:x}", expected, found)
+      }
+      CidDiff::Digest => f.write_str("digests differ"),
+    }
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Returns the fields in which `found` differs from this CID, in the order
+  /// version, codec, hash function and digest.
+  ///
+  /// The result is empty if the CIDs are equal.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Cid, CidDiff};
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let expected = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let found = Cid::new_v1(0x70, Code::Sha2_256.digest(b"foo"));
+  /// assert_eq!(
+  ///   expected.diff(&found),
+  ///   [CidDiff::Codec { expected: 0x55, found: 0x70 }]
+  /// );
+  /// ```
+  pub fn diff<S2: Size>(&self, found: &Cid<S2>) -> Vec<CidDiff> {
+    let mut diff = Vec::new();
+    if self.version() != found.version() {
+      diff.push(CidDiff::Version {
+        expected: self.version(),
+        found: found.version(),
+      });
+    }
+    if self.codec() != found.codec() {
+      diff.push(CidDiff::Codec {
+        expected: self.codec(),
+        found: found.codec(),
+      });
+    }
+    if self.hash().code() != found.hash().code() {
+      diff.push(CidDiff::HashCode {
+        expected: self.hash().code(),
+        found: found.hash().code(),
+      });
+    }
+    if self.hash().digest() != found.hash().digest() {
+      diff.push(CidDiff::Digest);
+    }
+    diff
+  }
+}
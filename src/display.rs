@@ -0,0 +1,213 @@
+//! `Display` adapters of CIDs that don't allocate.
+//!
+//! Template engines and logging macros take `impl Display` arguments, and
+//! formatting a CID into a `String` first costs an allocation per field.
+//! [`Cid::fmt_v1`], [`Cid::fmt_short`] and [`Cid::fmt_base`] return
+//! adapters that write the CID straight into the formatter.
+use sp_std::{fmt, str};
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  base32, bases,
+  cid::{Cid, MAX_LEN},
+  error::Result,
+  version::Version,
+};
+
+/// The maximum length of a CID string in any base, Base2 being the longest.
+const MAX_STR_LEN: usize = 1 + 8 * MAX_LEN;
+
+/// The maximum length of a CID string in its canonical base.
+const MAX_CANONICAL_LEN: usize = 1 + base32::encoded_len(MAX_LEN);
+
+/// A string in a stack buffer of `N` bytes.
+struct StackStr<const N: usize> {
+  /// The bytes of the string.
+  buf: [u8; N],
+  /// The length of the string.
+  len: usize,
+}
+
+impl<const N: usize> StackStr<N> {
+  /// Create an empty string.
+  fn new() -> Self {
+    Self { buf: [0; N], len: 0 }
+  }
+
+  /// Returns the string.
+  fn as_str(&self) -> &str {
+    // Only whole `str`s are written
+    str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+  }
+}
+
+impl<const N: usize> fmt::Write for StackStr<N> {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    let end = self.len + s.len();
+    let dest = self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+    dest.copy_from_slice(s.as_bytes());
+    self.len = end;
+    Ok(())
+  }
+}
+
+/// Displays what a function writes.
+///
+/// Width, fill, alignment and precision are applied like for strings, with
+/// the output collected on the stack.
+struct WriteFn<F>(F);
+
+impl<F: Fn(&mut dyn fmt::Write) -> fmt::Result> fmt::Display for WriteFn<F> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    if f.width().is_none() && f.precision().is_none() {
+      return (self.0)(f);
+    }
+    let mut s = StackStr::<MAX_STR_LEN>::new();
+    (self.0)(&mut s)?;
+    f.pad(s.as_str())
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Returns an adapter that displays the CID as a CIDv1 in Base32Lower,
+  /// also if it is a CIDv0, like the alternate form `{:#}`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let s = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  /// let v0: Cid = s.parse().unwrap();
+  /// assert_eq!(
+  ///   format!("cid={}", v0.fmt_v1()),
+  ///   "cid=bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+  /// );
+  /// ```
+  pub fn fmt_v1(&self) -> impl fmt::Display + '_ {
+    WriteFn(move |mut w: &mut dyn fmt::Write| self.fmt_string(&mut w, true))
+  }
+
+  /// Returns an adapter that displays the first and last `n` characters of
+  /// the CID, joined by `…`.
+  ///
+  /// This is [`Cid::short`] with `n` characters kept at both ends, CIDs of at
+  /// most `2 * n` characters are displayed whole.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// assert_eq!(format!("{}", cid.fmt_short(4)), "bafk…xhvy");
+  /// assert_eq!(format!("{:>12}", cid.fmt_short(2)), "       ba…vy");
+  /// ```
+  pub fn fmt_short(&self, n: usize) -> impl fmt::Display + '_ {
+    WriteFn(move |w: &mut dyn fmt::Write| self.write_short(n, n, w))
+  }
+
+  /// Writes the first `head` and last `tail` characters of the CID, joined
+  /// by `…`, or the whole CID if it isn't longer than both.
+  pub(crate) fn write_short(
+    &self,
+    head: usize,
+    tail: usize,
+    w: &mut dyn fmt::Write,
+  ) -> fmt::Result {
+    let mut s = StackStr::<MAX_CANONICAL_LEN>::new();
+    self.fmt_string(&mut s, false)?;
+    // The canonical bases are ASCII, so characters are bytes
+    let s = s.as_str();
+    if head.saturating_add(tail) >= s.len() {
+      return w.write_str(s);
+    }
+    w.write_str(&s[..head])?;
+    w.write_char('…')?;
+    w.write_str(&s[s.len() - tail..])
+  }
+
+  /// Displays the CID like [`Cid::short`] into `f`, applying its width and
+  /// alignment.
+  pub(crate) fn fmt_short_into(
+    &self,
+    head: usize,
+    tail: usize,
+    f: &mut fmt::Formatter,
+  ) -> fmt::Result {
+    let short =
+      WriteFn(|w: &mut dyn fmt::Write| self.write_short(head, tail, w));
+    fmt::Display::fmt(&short, f)
+  }
+
+  /// Returns an adapter that displays the CID in `base`.
+  ///
+  /// A CIDv0 is displayed as a CIDv1 in all bases except Base58Btc, like
+  /// [`Cid::to_string_of_base_upgrading`]. Fails if the CID can't be
+  /// encoded in `base`. Only Base256Emoji allocates.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::Cid;
+  ///
+  /// let cid: Cid = "bafkqaaa".parse().unwrap();
+  /// let display = cid.fmt_base(Base::Base16Upper).unwrap();
+  /// assert_eq!(format!("{}", display), "F01550000");
+  /// ```
+  pub fn fmt_base(&self, base: Base) -> Result<impl fmt::Display> {
+    let v0 = self.version() == Version::V0 && base == Base::Base58Btc;
+    let mut buf = [0u8; MAX_LEN];
+    let len = if self.version() == Version::V0 && !v0 {
+      Self::new_v1(self.codec(), self.hash().clone()).write_to_slice(&mut buf)
+    } else {
+      self.write_to_slice(&mut buf)
+    };
+    if !v0 {
+      bases::check(base, &buf[..len])?;
+    }
+    Ok(WriteFn(move |mut w: &mut dyn fmt::Write| {
+      if v0 {
+        return bases::write_base58btc(&buf[..len], &mut w);
+      }
+      w.write_char(base.code())?;
+      bases::write_encoded(base, &buf[..len], &mut w)
+    }))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::{format, string::ToString};
+  use sp_multihash::{Code, MultihashDigest, U64};
+
+  use crate::{cid::Cid, weights::BASES};
+
+  #[test]
+  fn fmt_base_matches_to_string() {
+    let cids = [
+      Cid::<U64>::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+      Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo")),
+      Cid::new_v1(0x3fff, Code::Sha2_512.digest(b"bar")),
+      Cid::new_v1(0, sp_multihash::Multihash::wrap(0, &[0; 3]).unwrap()),
+    ];
+    for cid in &cids {
+      for base in BASES {
+        let expected = cid.to_string_of_base_upgrading(base);
+        match cid.fmt_base(base) {
+          Ok(display) => {
+            let s = display.to_string();
+            assert_eq!(Ok(&s), expected.as_ref(), "{:?}", base);
+            let padded = format!("{:-<1$}", display, s.chars().count() + 2);
+            assert_eq!(padded, format!("{}--", s));
+          }
+          Err(_) => assert!(expected.is_err(), "{:?}", base),
+        }
+      }
+    }
+  }
+}
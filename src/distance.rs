@@ -0,0 +1,106 @@
+//! This module contains the XOR distance between CIDs.
+//!
+//! Kademlia-style content routing places keys in a metric space where the
+//! distance between two keys is their bitwise XOR. The distance between CIDs
+//! is taken over their multihash digests.
+use sp_std::{cmp::Ordering, hash, iter, vec::Vec};
+
+use sp_multihash::Size;
+
+use crate::cid::Cid;
+
+/// The XOR distance between two keys.
+///
+/// The keys are read as bit strings from their first byte. A shorter key is
+/// padded with zero bits at its end, so digests of different sizes share the
+/// same prefix space. Distances are ordered by magnitude, like the binary
+/// fraction `0.b0 b1 b2 ...` of the key bits.
+#[derive(Clone, Debug)]
+pub struct Distance(Vec<u8>);
+
+impl Distance {
+  /// Computes the XOR distance between two keys.
+  pub fn between(a: &[u8], b: &[u8]) -> Self {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    let padded = short.iter().copied().chain(iter::repeat(0));
+    Self(long.iter().zip(padded).map(|(a, b)| a ^ b).collect())
+  }
+
+  /// Returns the bytes of the distance.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.0
+  }
+
+  /// Returns whether the keys are equal, up to zero padding.
+  pub fn is_zero(&self) -> bool {
+    self.0.iter().all(|b| *b == 0)
+  }
+
+  /// Returns the number of leading zero bits, which is the length of the
+  /// prefix the keys share.
+  ///
+  /// Returns `None` for a zero distance.
+  pub fn leading_zeros(&self) -> Option<u32> {
+    let index = self.0.iter().position(|b| *b != 0)?;
+    Some(index as u32 * 8 + self.0[index].leading_zeros())
+  }
+
+  /// The bytes without the trailing zero padding.
+  fn significant(&self) -> &[u8] {
+    let len = self.0.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    &self.0[..len]
+  }
+}
+
+impl PartialEq for Distance {
+  fn eq(&self, other: &Self) -> bool {
+    self.significant() == other.significant()
+  }
+}
+
+impl Eq for Distance {}
+
+impl PartialOrd for Distance {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Distance {
+  fn cmp(&self, other: &Self) -> Ordering {
+    // Trailing zeros don't change the magnitude of a binary fraction
+    self.significant().cmp(other.significant())
+  }
+}
+
+impl hash::Hash for Distance {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    self.significant().hash(state)
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Returns the XOR distance between the multihash digests of two CIDs.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// const RAW: u64 = 0x55;
+  ///
+  /// let a = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  /// let b = Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar"));
+  /// assert!(a.xor_distance(&a) < a.xor_distance(&b));
+  /// ```
+  pub fn xor_distance<S2: Size>(&self, other: &Cid<S2>) -> Distance {
+    Distance::between(self.hash().digest(), other.hash().digest())
+  }
+
+  /// Returns the XOR distance between the multihash digest of the CID and a
+  /// raw key, e.g. a peer id.
+  pub fn xor_distance_key(&self, key: &[u8]) -> Distance {
+    Distance::between(self.hash().digest(), key)
+  }
+}
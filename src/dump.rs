@@ -0,0 +1,197 @@
+//! Annotated hex dumps of binary CIDs.
+//!
+//! Bug reports about CIDs that don't round-trip usually come with a string
+//! or a hex blob, and telling where a varint ends by hand is error prone.
+//! [`Cid::to_debug_bytes`] labels every field of the binary form, and
+//! [`render_table`] lists the fields of many CIDs side by side.
+use sp_std::{fmt, fmt::Write, vec::Vec};
+
+use alloc::{
+  format,
+  string::{String, ToString},
+};
+use sp_multihash::Size;
+use unsigned_varint::encode as varint_encode;
+
+use crate::{cid::Cid, version::Version};
+
+/// The number of digest bytes per line.
+const BYTES_PER_LINE: usize = 16;
+
+/// Displays the annotated hex dump of a CID.
+struct DebugBytes<'a, S: Size>(&'a Cid<S>);
+
+/// Writes a line of the dump, the hex of `bytes` and its label.
+fn write_line(
+  f: &mut fmt::Formatter,
+  bytes: &[u8],
+  label: Option<fmt::Arguments>,
+) -> fmt::Result {
+  bytes.iter().try_for_each(|byte| write!(f, "{:02x}", byte))?;
+  if let Some(label) = label {
+    // Align the labels after a full line of digest bytes
+    let padding = 2 * (BYTES_PER_LINE - bytes.len().min(BYTES_PER_LINE)) + 2;
+    write!(f, "{:padding$}{}", "", label, padding = padding)?;
+  }
+  f.write_str("\n")
+}
+
+/// Writes a varint field of the dump.
+fn write_varint(
+  f: &mut fmt::Formatter,
+  value: u64,
+  label: fmt::Arguments,
+) -> fmt::Result {
+  let mut buf = varint_encode::u64_buffer();
+  write_line(f, varint_encode::u64(value, &mut buf), Some(label))
+}
+
+/// The header of [`render_table`].
+const TABLE_HEADER: [&str; 5] = ["CID", "VERSION", "CODEC", "HASH", "DIGEST"];
+
+/// Formats a code with its name, if it is known.
+struct Named(u64, Option<&'static str>);
+
+impl fmt::Display for Named {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.1 {
+      Some(name) => write!(f, "{} (0x{:x})", name, self.0),
+      None => write!(f, "0x{:x}", self.0),
+    }
+  }
+}
+
+impl<S: Size> fmt::Display for DebugBytes<'_, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let cid = self.0;
+    match cid.version() {
+      Version::V0 => f.write_str("CIDv0: implicit version 0, codec dag-pb\n")?,
+      Version::V1 => {
+        write_varint(f, 1, format_args!("version: 1"))?;
+        let codec = Named(cid.codec(), cid.codec_name());
+        write_varint(f, cid.codec(), format_args!("codec: {}", codec))?;
+      }
+    }
+    let code = cid.hash().code();
+    let name = Named(code, cid.hash_name());
+    write_varint(f, code, format_args!("hash: {}", name))?;
+    let digest = cid.hash().digest();
+    let len = digest.len();
+    write_varint(f, len as u64, format_args!("digest length: {}", len))?;
+    for (i, line) in digest.chunks(BYTES_PER_LINE).enumerate() {
+      let label = if i == 0 { Some(format_args!("digest")) } else { None };
+      write_line(f, line, label)?;
+    }
+    Ok(())
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Renders the binary form of the CID as a hex dump with one line per
+  /// field, for bug reports and inspection tools.
+  ///
+  /// Every varint is on its own line, labeled with its field and value.
+  /// The digest follows with 16 bytes per line. A CIDv0 has no version and
+  /// codec fields, which its first line notes instead.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let cid: Cid = "bafkqaaa".parse().unwrap();
+  /// let dump = cid.to_debug_bytes();
+  /// assert_eq!(
+  ///   dump.lines().collect::<Vec<_>>(),
+  ///   [
+  ///     "01                                version: 1",
+  ///     "55                                codec: raw (0x55)",
+  ///     "00                                hash: identity (0x0)",
+  ///     "00                                digest length: 0",
+  ///   ]
+  /// );
+  /// ```
+  pub fn to_debug_bytes(&self) -> String {
+    DebugBytes(self).to_string()
+  }
+}
+
+/// Returns the name of a code, or the code in hex if it is unknown.
+fn name_or_code(code: u64, name: Option<&'static str>) -> String {
+  match name {
+    Some(name) => name.to_string(),
+    None => format!("0x{:x}", code),
+  }
+}
+
+/// Renders CIDs as a table with a row per CID, for command line output and
+/// debug endpoints.
+///
+/// The columns are the [short form](Cid::short) of the CID, its version,
+/// codec, hash function and digest length in bytes. Unknown codes are shown
+/// in hex. Columns are aligned with spaces and every line ends with a
+/// newline.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{render_table, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cids = [
+///   Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+///   Cid::new_v1(0x55, Code::Sha2_512.digest(b"foo")),
+/// ];
+/// assert_eq!(
+///   render_table(&cids),
+///   "\
+/// CID        VERSION  CODEC   HASH      DIGEST
+/// QmRJ…Emoj  0        dag-pb  sha2-256      32
+/// bafk…ypno  1        raw     sha2-512      64
+/// "
+/// );
+/// ```
+pub fn render_table<'a, S, I>(cids: I) -> String
+where
+  S: Size + 'a,
+  I: IntoIterator<Item = &'a Cid<S>>,
+{
+  let rows: Vec<[String; 5]> = cids
+    .into_iter()
+    .map(|cid| {
+      [
+        cid.short().to_string(),
+        cid.version().to_u64().to_string(),
+        name_or_code(cid.codec(), cid.codec_name()),
+        name_or_code(cid.hash().code(), cid.hash_name()),
+        cid.hash().digest().len().to_string(),
+      ]
+    })
+    .collect();
+  let mut widths = TABLE_HEADER.map(|title| title.chars().count());
+  for row in &rows {
+    for (width, cell) in widths.iter_mut().zip(row) {
+      *width = (*width).max(cell.chars().count());
+    }
+  }
+  let header = TABLE_HEADER.map(String::from);
+  let mut table = String::new();
+  for row in sp_std::iter::once(&header).chain(&rows) {
+    // Writing to a `String` never fails
+    let _ = writeln!(
+      table,
+      "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {:>w4$}",
+      row[0],
+      row[1],
+      row[2],
+      row[3],
+      row[4],
+      w0 = widths[0],
+      w1 = widths[1],
+      w2 = widths[2],
+      w3 = widths[3],
+      w4 = widths[4],
+    );
+  }
+  table
+}
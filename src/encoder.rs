@@ -0,0 +1,146 @@
+//! A reusable encoder for formatting many CIDs.
+//!
+//! Calling `to_bytes()` or `to_string()` allocates fresh buffers for every
+//! CID. Services that format large numbers of CIDs (indexers, gateways) can
+//! keep a [`CidEncoder`] around instead, which owns its scratch buffers and
+//! reuses them between calls.
+use sp_std::vec::Vec;
+
+use alloc::string::String;
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  base32, bases,
+  cid::{Cid, MAX_LEN},
+  error::{Error, Result},
+  version::Version,
+};
+
+/// An encoder that owns scratch buffers which are reused between calls.
+///
+/// # Example
+///
+/// ```
+/// use multibase::Base;
+/// use sp_cid::{Cid, CidEncoder};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// const RAW: u64 = 0x55;
+///
+/// let mut encoder = CidEncoder::new();
+/// let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+/// assert_eq!(encoder.encode_bytes(&cid), cid.to_bytes().as_slice());
+/// assert_eq!(
+///   encoder.encode_str(&cid, Base::Base32Lower).unwrap(),
+///   "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy"
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CidEncoder {
+  /// Scratch buffer for the binary encoding.
+  bytes: Vec<u8>,
+  /// Scratch buffer for the string encoding.
+  string: String,
+}
+
+impl CidEncoder {
+  /// Create a new encoder with empty buffers.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Create a new encoder whose buffers can hold a CID of `capacity` bytes
+  /// and its Base32 or Base58 string without growing.
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      bytes: Vec::with_capacity(capacity),
+      // Base58 strings are shorter than Base32 ones
+      string: String::with_capacity(1 + base32::encoded_len(capacity)),
+    }
+  }
+
+  /// Encodes the CID into its binary representation.
+  ///
+  /// The returned slice borrows the encoder's buffer and is only valid until
+  /// the next call.
+  pub fn encode_bytes<S: Size>(&mut self, cid: &Cid<S>) -> &[u8] {
+    let mut buf = [0u8; MAX_LEN];
+    let len = cid.write_to_slice(&mut buf);
+    self.bytes.clear();
+    self.bytes.extend_from_slice(&buf[..len]);
+    &self.bytes
+  }
+
+  /// Encodes the CID into a multibase string of the given base.
+  ///
+  /// CIDv0 can only be encoded as Base58Btc, any other base returns
+  /// [`Error::InvalidCidV0Base`]. The returned string borrows the encoder's
+  /// buffer and is only valid until the next call.
+  pub fn encode_str<S: Size>(
+    &mut self,
+    cid: &Cid<S>,
+    base: Base,
+  ) -> Result<&str> {
+    self.string.clear();
+    match cid.version() {
+      Version::V0 => {
+        if base != Base::Base58Btc {
+          return Err(Error::InvalidCidV0Base);
+        }
+        self.encode_bytes(cid);
+        bases::encode_into(base, &self.bytes, &mut self.string)?;
+      }
+      Version::V1 => {
+        self.encode_bytes(cid);
+        self.string.push(base.code());
+        bases::encode_into(base, &self.bytes, &mut self.string)?;
+      }
+    }
+    Ok(&self.string)
+  }
+
+  /// Encodes CIDs into their default string forms, like `to_string()`.
+  ///
+  /// The binary encoding of every CID goes through the same scratch buffer
+  /// and every string is allocated once with its final length.
+  pub fn encode_all<S: Size>(&mut self, cids: &[Cid<S>]) -> Vec<String> {
+    cids
+      .iter()
+      .map(|cid| {
+        let mut s = String::new();
+        self.push_default(cid, &mut s);
+        s
+      })
+      .collect()
+  }
+
+  /// Appends the default string forms of CIDs to `out`, one per line.
+  ///
+  /// The output can be read back with [`crate::parse_cid_list`].
+  pub fn write_all<S: Size>(&mut self, cids: &[Cid<S>], out: &mut String) {
+    for cid in cids {
+      self.push_default(cid, out);
+      out.push('\n');
+    }
+  }
+
+  /// Appends the default string form of the CID to `out`.
+  fn push_default<S: Size>(&mut self, cid: &Cid<S>, out: &mut String) {
+    self.encode_bytes(cid);
+    match cid.version() {
+      Version::V0 => {
+        // Base58Btc can encode any bytes
+        let len = bases::encoded_len(Base::Base58Btc, &self.bytes);
+        out.reserve(len.unwrap_or(0));
+        let _ = bases::encode_into(Base::Base58Btc, &self.bytes, out);
+      }
+      Version::V1 => {
+        out.reserve(1 + base32::encoded_len(self.bytes.len()));
+        out.push(Base::Base32Lower.code());
+        base32::push_lower(&self.bytes, out);
+      }
+    }
+  }
+}
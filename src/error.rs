@@ -5,67 +5,466 @@ pub type Result<T> = core::result::Result<T, Error>;
 
 /// Error types
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
   /// Unknown CID codec.
-  UnknownCodec,
+  UnknownCodec(u64),
   /// Input data is too short.
   InputTooShort,
-  /// Multibase or multihash codec failure
-  ParsingError,
+  /// Multihash codec failure.
+  ParsingError(sp_multihash::Error),
+  /// Multibase decode failure.
+  MultibaseError(multibase::Error),
   /// Invalid CID version.
+  #[deprecated(note = "unknown versions are reported as `UnsupportedVersion`")]
   InvalidCidVersion,
-  /// Invalid CIDv0 codec.
-  InvalidCidV0Codec,
-  /// Invalid CIDv0 multihash.
-  InvalidCidV0Multihash,
+  /// Invalid CIDv0 codec, contains the codec that was found.
+  InvalidCidV0Codec(u64),
+  /// Invalid CIDv0 multihash, contains the multihash code that was found.
+  InvalidCidV0Multihash(u64),
   /// Invalid CIDv0 base encoding.
   InvalidCidV0Base,
+  /// The base can't represent the CID, contains the base.
+  UnsupportedBase(multibase::Base),
   /// Varint decode failure.
-  VarIntDecodeError,
+  VarIntDecodeError(unsigned_varint::decode::Error),
+  /// The CID could not be written to the output.
+  WriteError,
+  /// Input ended before the CID was complete.
+  UnexpectedEof {
+    /// Byte offset of the field that could not be read completely.
+    offset: usize,
+  },
+  /// The multihash of the CID is malformed.
+  InvalidMultihash {
+    /// Byte offset of the multihash.
+    offset: usize,
+  },
+  /// The CID version is not supported, contains the version that was found.
+  ///
+  /// Versions other than 0 and 1 are reserved for future use.
+  UnsupportedVersion(u64),
   /// Io error.
   #[cfg(feature = "std")]
   Io(std::io::Error),
+  /// The input continues after the CID.
+  TrailingBytes {
+    /// Byte offset of the first byte after the CID.
+    offset: usize,
+  },
+  /// The CID has a different codec than expected.
+  UnexpectedCodec {
+    /// The expected codec.
+    expected: u64,
+    /// The codec that was found.
+    found: u64,
+  },
+  /// The multiaddr has no `/ipfs/` or `/p2p/` component.
+  NoMultiaddrCid,
+  /// The TXT record value isn't a `dnslink=/ipfs/...` value.
+  InvalidDnsLink,
+  /// The filename of the CID is longer than the limit of common
+  /// filesystems.
+  FilenameTooLong {
+    /// The length of the filename.
+    len: usize,
+  },
+  /// The codec isn't allowed by a [`CidPolicy`](crate::CidPolicy), contains
+  /// the codec.
+  DisallowedCodec(u64),
+  /// The hash function isn't allowed by a [`CidPolicy`](crate::CidPolicy),
+  /// contains the multihash code.
+  DisallowedHash(u64),
+  /// The digest is valid but longer than the allocated size of the CID.
+  DigestTooLarge {
+    /// The length of the digest.
+    size: usize,
+    /// The allocated size.
+    max: usize,
+  },
+  /// The block doesn't match the digest of its CID.
+  DigestMismatch {
+    /// Byte offset of the frame of the CID and the block.
+    offset: usize,
+  },
+  /// The input exceeds a limit of a [`DecodeContext`](crate::DecodeContext).
+  LimitExceeded {
+    /// Byte offset of the field that exceeds the limit.
+    offset: usize,
+  },
 }
 
-#[cfg(feature = "std")]
-impl std::error::Error for Error {}
+/// The coarse category of an [`Error`], see [`Error::kind`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+  /// The input isn't a well-formed CID, e.g. a bad string, truncated bytes
+  /// or a malformed multihash.
+  Syntax,
+  /// The CID is well-formed but uses something that isn't supported or
+  /// allowed, e.g. an unknown version, codec or hash function.
+  UnsupportedFeature,
+  /// The CID is well-formed but exceeds a size limit.
+  Limit,
+  /// Reading or writing the CID failed.
+  Io,
+}
+
+impl Error {
+  /// Returns the category of the error.
+  ///
+  /// Services can map the categories to responses without matching every
+  /// variant, e.g. [`ErrorKind::Syntax`] to 400,
+  /// [`ErrorKind::UnsupportedFeature`] and [`ErrorKind::Limit`] to 422, and
+  /// [`ErrorKind::Io`] to 500. New variants are assigned to one of the
+  /// existing categories.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use std::convert::TryFrom;
+  ///
+  /// use sp_cid::{Cid, ErrorKind};
+  ///
+  /// let err = Cid::try_from("not a cid").unwrap_err();
+  /// assert_eq!(err.kind(), ErrorKind::Syntax);
+  /// let err = Cid::try_from(&[0x02, 0x55, 0x00, 0x00][..]).unwrap_err();
+  /// assert_eq!(err.kind(), ErrorKind::UnsupportedFeature);
+  /// ```
+  pub fn kind(&self) -> ErrorKind {
+    use self::Error::*;
+    match self {
+      ParsingError(sp_multihash::Error::UnsupportedCode(_)) => {
+        ErrorKind::UnsupportedFeature
+      }
+      #[cfg(feature = "std")]
+      ParsingError(sp_multihash::Error::Io(_)) => ErrorKind::Io,
+      InputTooShort
+      | ParsingError(_)
+      | MultibaseError(_)
+      | InvalidCidV0Codec(_)
+      | InvalidCidV0Multihash(_)
+      | InvalidCidV0Base
+      | VarIntDecodeError(_)
+      | UnexpectedEof { .. }
+      | InvalidMultihash { .. }
+      | TrailingBytes { .. }
+      | NoMultiaddrCid
+      | InvalidDnsLink
+      | DigestMismatch { .. } => ErrorKind::Syntax,
+      #[allow(deprecated)]
+      InvalidCidVersion => ErrorKind::UnsupportedFeature,
+      UnknownCodec(_)
+      | UnsupportedBase(_)
+      | UnsupportedVersion(_)
+      | UnexpectedCodec { .. }
+      | DisallowedCodec(_)
+      | DisallowedHash(_) => ErrorKind::UnsupportedFeature,
+      FilenameTooLong { .. }
+      | DigestTooLarge { .. }
+      | LimitExceeded { .. } => ErrorKind::Limit,
+      WriteError => ErrorKind::Io,
+      #[cfg(feature = "std")]
+      Io(_) => ErrorKind::Io,
+    }
+  }
+
+  /// Returns the stable numeric code of the error, for transporting errors
+  /// across Wasm and FFI boundaries.
+  ///
+  /// The code identifies the variant, its payload isn't included. Codes never
+  /// change and are the same for all feature sets:
+  ///
+  /// | Code | Variant                          |
+  /// |------|----------------------------------|
+  /// | 1    | [`Error::UnknownCodec`]          |
+  /// | 2    | [`Error::InputTooShort`]         |
+  /// | 3    | [`Error::ParsingError`]          |
+  /// | 4    | [`Error::MultibaseError`]        |
+  /// | 5    | `Error::InvalidCidVersion`       |
+  /// | 6    | [`Error::InvalidCidV0Codec`]     |
+  /// | 7    | [`Error::InvalidCidV0Multihash`] |
+  /// | 8    | [`Error::InvalidCidV0Base`]      |
+  /// | 9    | [`Error::UnsupportedBase`]       |
+  /// | 10   | [`Error::VarIntDecodeError`]     |
+  /// | 11   | [`Error::WriteError`]            |
+  /// | 12   | [`Error::UnexpectedEof`]         |
+  /// | 13   | [`Error::InvalidMultihash`]      |
+  /// | 14   | [`Error::UnsupportedVersion`]    |
+  /// | 15   | `Error::Io`                      |
+  /// | 16   | [`Error::NoMultiaddrCid`]        |
+  /// | 17   | [`Error::TrailingBytes`]         |
+  /// | 18   | [`Error::UnexpectedCodec`]       |
+  /// | 19   | [`Error::InvalidDnsLink`]        |
+  /// | 20   | [`Error::FilenameTooLong`]       |
+  /// | 21   | [`Error::DisallowedCodec`]       |
+  /// | 22   | [`Error::DisallowedHash`]        |
+  /// | 23   | [`Error::DigestTooLarge`]        |
+  /// | 24   | [`Error::DigestMismatch`]        |
+  /// | 25   | [`Error::LimitExceeded`]         |
+  ///
+  /// Code 0 is never used, so it can signal success.
+  pub fn code(&self) -> u32 {
+    use self::Error::*;
+    match self {
+      UnknownCodec(_) => 1,
+      InputTooShort => 2,
+      ParsingError(_) => 3,
+      MultibaseError(_) => 4,
+      #[allow(deprecated)]
+      InvalidCidVersion => 5,
+      InvalidCidV0Codec(_) => 6,
+      InvalidCidV0Multihash(_) => 7,
+      InvalidCidV0Base => 8,
+      UnsupportedBase(_) => 9,
+      VarIntDecodeError(_) => 10,
+      WriteError => 11,
+      UnexpectedEof { .. } => 12,
+      InvalidMultihash { .. } => 13,
+      UnsupportedVersion(_) => 14,
+      #[cfg(feature = "std")]
+      Io(_) => 15,
+      NoMultiaddrCid => 16,
+      TrailingBytes { .. } => 17,
+      UnexpectedCodec { .. } => 18,
+      InvalidDnsLink => 19,
+      FilenameTooLong { .. } => 20,
+      DisallowedCodec(_) => 21,
+      DisallowedHash(_) => 22,
+      DigestTooLarge { .. } => 23,
+      DigestMismatch { .. } => 24,
+      LimitExceeded { .. } => 25,
+    }
+  }
+
+  /// Moves the byte offsets of the error by `by` bytes, for errors of data
+  /// that was read at that position.
+  pub(crate) fn shifted(self, by: usize) -> Self {
+    use self::Error::*;
+    match self {
+      UnexpectedEof { offset } => UnexpectedEof { offset: offset + by },
+      InvalidMultihash { offset } => InvalidMultihash { offset: offset + by },
+      TrailingBytes { offset } => TrailingBytes { offset: offset + by },
+      DigestMismatch { offset } => DigestMismatch { offset: offset + by },
+      LimitExceeded { offset } => LimitExceeded { offset: offset + by },
+      err => err,
+    }
+  }
+
+  /// Rebuilds an error from its [`Error::code`].
+  ///
+  /// Since the code doesn't carry the payload, numbers and offsets are zero
+  /// and wrapped errors are replaced by a fixed error of the wrapped type.
+  /// Returns `None` for unknown codes and for variants that aren't enabled
+  /// in this build.
+  pub fn from_code(code: u32) -> Option<Self> {
+    use self::Error::*;
+    let err = match code {
+      1 => UnknownCodec(0),
+      2 => InputTooShort,
+      3 => ParsingError(sp_multihash::Error::InvalidSize(0)),
+      4 => MultibaseError(multibase::Error::InvalidBaseString),
+      #[allow(deprecated)]
+      5 => InvalidCidVersion,
+      6 => InvalidCidV0Codec(0),
+      7 => InvalidCidV0Multihash(0),
+      8 => InvalidCidV0Base,
+      9 => UnsupportedBase(multibase::Base::Identity),
+      10 => VarIntDecodeError(unsigned_varint::decode::Error::Overflow),
+      11 => WriteError,
+      12 => UnexpectedEof { offset: 0 },
+      13 => InvalidMultihash { offset: 0 },
+      14 => UnsupportedVersion(0),
+      #[cfg(feature = "std")]
+      15 => Io(std::io::ErrorKind::Other.into()),
+      16 => NoMultiaddrCid,
+      17 => TrailingBytes { offset: 0 },
+      18 => UnexpectedCodec { expected: 0, found: 0 },
+      19 => InvalidDnsLink,
+      20 => FilenameTooLong { len: 0 },
+      21 => DisallowedCodec(0),
+      22 => DisallowedHash(0),
+      23 => DigestTooLarge { size: 0, max: 0 },
+      24 => DigestMismatch { offset: 0 },
+      25 => LimitExceeded { offset: 0 },
+      _ => return None,
+    };
+    Some(err)
+  }
+}
+
+/// Errors are equal if they are the same variant with equal payloads, so
+/// tests can assert on specific errors. The wrapped I/O errors aren't
+/// comparable themselves and are compared by their kind.
+impl PartialEq for Error {
+  fn eq(&self, other: &Self) -> bool {
+    use self::Error::*;
+    // The codes tell the variants apart, only payloads are left to compare
+    self.code() == other.code()
+      && match (self, other) {
+        (UnknownCodec(a), UnknownCodec(b)) => a == b,
+        (ParsingError(a), ParsingError(b)) => multihash_error_eq(a, b),
+        (MultibaseError(a), MultibaseError(b)) => a == b,
+        (InvalidCidV0Codec(a), InvalidCidV0Codec(b)) => a == b,
+        (InvalidCidV0Multihash(a), InvalidCidV0Multihash(b)) => a == b,
+        (UnsupportedBase(a), UnsupportedBase(b)) => a == b,
+        (VarIntDecodeError(a), VarIntDecodeError(b)) => a == b,
+        (UnexpectedEof { offset: a }, UnexpectedEof { offset: b }) => a == b,
+        (InvalidMultihash { offset: a }, InvalidMultihash { offset: b }) => {
+          a == b
+        }
+        (UnsupportedVersion(a), UnsupportedVersion(b)) => a == b,
+        #[cfg(feature = "std")]
+        (Io(a), Io(b)) => a.kind() == b.kind(),
+        (TrailingBytes { offset: a }, TrailingBytes { offset: b }) => a == b,
+        (
+          UnexpectedCodec { expected: a, found: c },
+          UnexpectedCodec { expected: b, found: d },
+        ) => a == b && c == d,
+        (FilenameTooLong { len: a }, FilenameTooLong { len: b }) => a == b,
+        (DisallowedCodec(a), DisallowedCodec(b)) => a == b,
+        (DisallowedHash(a), DisallowedHash(b)) => a == b,
+        (
+          DigestTooLarge { size: a, max: c },
+          DigestTooLarge { size: b, max: d },
+        ) => a == b && c == d,
+        (DigestMismatch { offset: a }, DigestMismatch { offset: b }) => a == b,
+        (LimitExceeded { offset: a }, LimitExceeded { offset: b }) => a == b,
+        // Variants without a payload
+        _ => true,
+      }
+  }
+}
+
+impl Eq for Error {}
+
+/// Compares multihash errors, which don't implement `PartialEq`.
+fn multihash_error_eq(
+  a: &sp_multihash::Error,
+  b: &sp_multihash::Error,
+) -> bool {
+  use sp_multihash::Error::*;
+  match (a, b) {
+    #[cfg(feature = "std")]
+    (Io(a), Io(b)) => a.kind() == b.kind(),
+    (UnsupportedCode(a), UnsupportedCode(b)) => a == b,
+    (InvalidSize(a), InvalidSize(b)) => a == b,
+    (Varint(a), Varint(b)) => a == b,
+    _ => false,
+  }
+}
+
+/// Implemented without `std` as well, so that no_std crates can box and chain
+/// the error. The wrapped errors only implement the trait with `std`, so
+/// without it there is no source.
+impl core::error::Error for Error {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    match self {
+      #[cfg(feature = "std")]
+      Error::ParsingError(err) => Some(err),
+      #[cfg(feature = "std")]
+      Error::MultibaseError(err) => Some(err),
+      #[cfg(feature = "std")]
+      Error::VarIntDecodeError(err) => Some(err),
+      #[cfg(feature = "std")]
+      Error::Io(err) => Some(err),
+      _ => None,
+    }
+  }
+}
 
 impl fmt::Display for Error {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     use self::Error::*;
     let error = match self {
-      UnknownCodec => "Unknown codec",
+      UnknownCodec(codec) => return write!(f, "Unknown codec 0x{:x}", codec),
       InputTooShort => "Input too short",
-      ParsingError => "Failed to parse multihash",
+      ParsingError(_) => "Failed to parse multihash",
+      MultibaseError(_) => "Failed to decode multibase",
+      #[allow(deprecated)]
       InvalidCidVersion => "Unrecognized CID version",
-      InvalidCidV0Codec => "CIDv0 requires a DagPB codec",
-      InvalidCidV0Multihash => "CIDv0 requires a Sha-256 multihash",
+      InvalidCidV0Codec(codec) => {
+        return write!(f, "CIDv0 requires a DagPB codec, found 0x{:x}", codec)
+      }
+      InvalidCidV0Multihash(code) => {
+        return write!(
+          f,
+          "CIDv0 requires a Sha-256 multihash, found 0x{:x}",
+          code
+        )
+      }
       InvalidCidV0Base => "CIDv0 requires a Base58 base",
-      VarIntDecodeError => "Failed to decode unsigned varint format",
+      UnsupportedBase(base) => {
+        return write!(f, "Base {:?} can't represent the CID", base)
+      }
+      VarIntDecodeError(_) => "Failed to decode unsigned varint format",
+      WriteError => "Failed to write CID",
+      UnexpectedEof { offset } => {
+        return write!(f, "Unexpected end of input at byte {}", offset)
+      }
+      InvalidMultihash { offset } => {
+        return write!(f, "Invalid multihash at byte {}", offset)
+      }
+      UnsupportedVersion(version) => {
+        return write!(f, "Unsupported CID version {}", version)
+      }
       #[cfg(feature = "std")]
-      Io(err) => return write!(f, "{}", err),
+      Io(_) => "I/O error",
+      TrailingBytes { offset } => {
+        return write!(f, "Unexpected bytes after the CID at byte {}", offset)
+      }
+      UnexpectedCodec { expected, found } => {
+        return write!(
+          f,
+          "Expected codec 0x{:x}, found 0x{:x}",
+          expected, found
+        )
+      }
+      NoMultiaddrCid => "Multiaddr has no /ipfs/ or /p2p/ component",
+      InvalidDnsLink => "Not a dnslink=/ipfs/ record value",
+      FilenameTooLong { len } => {
+        return write!(f, "Filename of {} characters is too long", len)
+      }
+      DisallowedCodec(codec) => {
+        return write!(f, "Codec 0x{:x} is not allowed", codec)
+      }
+      DisallowedHash(code) => {
+        return write!(f, "Multihash 0x{:x} is not allowed", code)
+      }
+      DigestTooLarge { size, max } => {
+        return write!(
+          f,
+          "Digest of {} bytes exceeds the allocated size of {} bytes",
+          size, max
+        )
+      }
+      DigestMismatch { offset } => {
+        return write!(f, "Block at byte {} doesn't match its CID", offset)
+      }
+      LimitExceeded { offset } => {
+        return write!(f, "Decode limit exceeded at byte {}", offset)
+      }
     };
 
     f.write_str(error)
   }
 }
 
-#[cfg(feature = "std")]
 impl From<multibase::Error> for Error {
-  fn from(_: multibase::Error) -> Error {
-    Error::ParsingError
+  fn from(err: multibase::Error) -> Error {
+    Error::MultibaseError(err)
   }
 }
 
 impl From<sp_multihash::Error> for Error {
-  fn from(_: sp_multihash::Error) -> Error {
-    Error::ParsingError
+  fn from(err: sp_multihash::Error) -> Error {
+    Error::ParsingError(err)
   }
 }
 
 impl From<unsigned_varint::decode::Error> for Error {
-  fn from(_: unsigned_varint::decode::Error) -> Self {
-    Error::VarIntDecodeError
+  fn from(err: unsigned_varint::decode::Error) -> Self {
+    Error::VarIntDecodeError(err)
   }
 }
 
@@ -75,7 +474,8 @@ impl From<unsigned_varint::io::ReadError> for Error {
     use unsigned_varint::io::ReadError::*;
     match err {
       Io(err) => Self::Io(err),
-      _ => Self::VarIntDecodeError,
+      Decode(err) => Self::VarIntDecodeError(err),
+      err => Self::Io(std::io::Error::other(err)),
     }
   }
 }
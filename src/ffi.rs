@@ -0,0 +1,226 @@
+//! Bindings for Kotlin and Swift, behind the `uniffi-bindings` feature.
+//!
+//! Mobile apps get the parser and encoders of this crate through a
+//! [UniFFI](https://mozilla.github.io/uniffi-rs/) wrapper, so they validate
+//! and render CIDs exactly like the node does. Build the crate as a `cdylib`
+//! with the feature and generate the bindings with `uniffi-bindgen`.
+use std::{convert::TryFrom, fmt, sync::Arc};
+
+use multibase::Base;
+
+use crate::{cid, error::Error, version::Version};
+
+/// The CID type of the bindings.
+type Cid = cid::Cid<sp_multihash::U64>;
+
+/// An error of the bindings.
+#[derive(Debug, uniffi::Error)]
+pub enum CidError {
+  /// The input isn't a valid CID, or the CID can't be converted.
+  Invalid {
+    /// The stable code of the error, see [`Error::code`].
+    code: u32,
+    /// The description of the error.
+    message: String,
+  },
+  /// The multibase code isn't known.
+  UnknownBase {
+    /// The multibase code.
+    code: String,
+  },
+}
+
+impl fmt::Display for CidError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CidError::Invalid { message, .. } => f.write_str(message),
+      CidError::UnknownBase { code } => {
+        write!(f, "Unknown multibase code {:?}", code)
+      }
+    }
+  }
+}
+
+impl std::error::Error for CidError {}
+
+impl From<Error> for CidError {
+  fn from(err: Error) -> Self {
+    CidError::Invalid { code: err.code(), message: err.to_string() }
+  }
+}
+
+/// Returns the base of a one character multibase code, e.g. `b`.
+fn base_of(code: &str) -> Result<Base, CidError> {
+  let unknown = || CidError::UnknownBase { code: code.into() };
+  let mut chars = code.chars();
+  match (chars.next(), chars.next()) {
+    (Some(c), None) => Base::from_code(c).map_err(|_| unknown()),
+    _ => Err(unknown()),
+  }
+}
+
+/// A CID exported to the bindings.
+#[derive(Debug, uniffi::Object)]
+pub struct FfiCid(Cid);
+
+#[uniffi::export]
+impl FfiCid {
+  /// Parses a CID string.
+  #[uniffi::constructor]
+  pub fn parse(cid_str: String) -> Result<Arc<Self>, CidError> {
+    Ok(Arc::new(Self(Cid::try_from(cid_str)?)))
+  }
+
+  /// Reads a binary CID.
+  #[uniffi::constructor]
+  pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, CidError> {
+    Ok(Arc::new(Self(Cid::try_from(bytes)?)))
+  }
+
+  /// Returns the version number.
+  pub fn version(&self) -> u64 {
+    self.0.version().to_u64()
+  }
+
+  /// Returns the codec.
+  pub fn codec(&self) -> u64 {
+    self.0.codec()
+  }
+
+  /// Returns the name of the codec, if it is known.
+  pub fn codec_name(&self) -> Option<String> {
+    self.0.codec_name().map(String::from)
+  }
+
+  /// Returns the multihash code.
+  pub fn hash_code(&self) -> u64 {
+    self.0.hash().code()
+  }
+
+  /// Returns the name of the hash function, if it is known.
+  pub fn hash_name(&self) -> Option<String> {
+    self.0.hash_name().map(String::from)
+  }
+
+  /// Returns the digest.
+  pub fn digest(&self) -> Vec<u8> {
+    self.0.hash().digest().to_vec()
+  }
+
+  /// Returns the binary form.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    self.0.to_bytes()
+  }
+
+  /// Returns the default string form.
+  pub fn encode(&self) -> String {
+    self.0.to_string()
+  }
+
+  /// Returns the string form in the base of a multibase code, e.g. `z`.
+  pub fn encode_base(&self, code: String) -> Result<String, CidError> {
+    Ok(self.0.to_string_of_base(base_of(&code)?)?)
+  }
+
+  /// Converts to a CIDv1.
+  pub fn to_v1(&self) -> Arc<Self> {
+    Arc::new(Self(Cid::new_v1(self.0.codec(), *self.0.hash())))
+  }
+
+  /// Converts to a CIDv0, if the CID is a DAG-PB SHA2-256 CID.
+  pub fn to_v0(&self) -> Result<Arc<Self>, CidError> {
+    let cid = Cid::new(Version::V0, self.0.codec(), *self.0.hash())?;
+    Ok(Arc::new(Self(cid)))
+  }
+
+  /// Checks whether both are the same CID.
+  pub fn equals(&self, other: Arc<FfiCid>) -> bool {
+    self.0 == other.0
+  }
+}
+
+/// Checks whether a string is a valid CID.
+#[uniffi::export]
+pub fn is_valid_cid(cid_str: String) -> bool {
+  Cid::try_from(cid_str).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::convert::TryFrom;
+
+  use multibase::Base;
+  use sp_multihash::{Code, MultihashDigest};
+
+  use super::{is_valid_cid, Cid, CidError, FfiCid};
+  use crate::{error::Error, version::Version};
+
+  /// Returns the code of the error of an invalid input, or panics.
+  fn code_of(err: CidError) -> u32 {
+    match err {
+      CidError::Invalid { code, .. } => code,
+      err => panic!("unexpected error {:?}", err),
+    }
+  }
+
+  #[test]
+  fn exports_cid() {
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let parsed = FfiCid::parse(cid.to_string()).unwrap();
+    assert_eq!(parsed.version(), 1);
+    assert_eq!(parsed.codec(), 0x55);
+    assert_eq!(parsed.codec_name().as_deref(), Some("raw"));
+    assert_eq!(parsed.hash_code(), 0x12);
+    assert_eq!(parsed.hash_name().as_deref(), Some("sha2-256"));
+    assert_eq!(parsed.digest(), cid.hash().digest());
+    assert_eq!(parsed.to_bytes(), cid.to_bytes());
+    assert_eq!(parsed.encode(), cid.to_string());
+    assert!(is_valid_cid(cid.to_string()));
+
+    let read = FfiCid::from_bytes(cid.to_bytes()).unwrap();
+    assert!(read.equals(parsed.clone()));
+    assert!(parsed.to_v1().equals(parsed));
+  }
+
+  #[test]
+  fn encodes_in_base() {
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let ffi = FfiCid::from_bytes(cid.to_bytes()).unwrap();
+    let encoded = cid.to_string_of_base(Base::Base32Lower).unwrap();
+    assert_eq!(ffi.encode_base("b".into()).unwrap(), encoded);
+    let encoded = cid.to_string_of_base(Base::Base58Btc).unwrap();
+    assert_eq!(ffi.encode_base("z".into()).unwrap(), encoded);
+
+    for code in ["", "?", "zz"] {
+      match ffi.encode_base(code.into()) {
+        Err(CidError::UnknownBase { code: unknown }) => {
+          assert_eq!(unknown, code)
+        }
+        encoded => panic!("unexpected result {:?}", encoded),
+      }
+    }
+  }
+
+  #[test]
+  fn maps_errors() {
+    let err = FfiCid::parse("invalid".into()).unwrap_err();
+    let expected = Cid::try_from("invalid").unwrap_err();
+    assert_eq!(err.to_string(), expected.to_string());
+    assert_eq!(code_of(err), expected.code());
+    assert!(!is_valid_cid("invalid".into()));
+
+    let err = FfiCid::from_bytes(vec![0x01]).unwrap_err();
+    assert_eq!(code_of(err), Error::UnexpectedEof { offset: 1 }.code());
+
+    let raw = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let ffi = FfiCid::from_bytes(raw.to_bytes()).unwrap();
+    let err = ffi.to_v0().unwrap_err();
+    assert_eq!(code_of(err), Error::InvalidCidV0Codec(0x55).code());
+
+    let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+    let ffi = FfiCid::from_bytes(v0.to_bytes()).unwrap();
+    let converted = ffi.to_v1().to_v0().unwrap();
+    assert_eq!(converted.version(), Version::V0.to_u64());
+    assert_eq!(converted.encode(), v0.to_string());
+  }
+}
@@ -0,0 +1,70 @@
+//! Deterministic CIDs for tests and benchmarks.
+//!
+//! Tests that need many distinct CIDs usually hash counters, which is slow
+//! in benchmarks and yields CIDs that can't be told apart from real content.
+//! [`Cid::from_seed`] derives the digest from a seed instead, with a marker
+//! that no real digest has.
+use sp_multihash::Size;
+
+use crate::{
+  cid::{wrap_digest, Cid, SHA2_256},
+  error::Result,
+};
+
+/// The first bytes of every fixture digest.
+const MARKER: &[u8; 8] = b"fixture:";
+
+/// The length of fixture digests, the length of SHA2-256 digests.
+const DIGEST_LEN: usize = 32;
+
+/// Returns the next output of the SplitMix64 generator with state `state`.
+fn splitmix64(state: &mut u64) -> u64 {
+  *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+  let mut z = *state;
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+  z ^ (z >> 31)
+}
+
+impl<S: Size> Cid<S> {
+  /// Create a CIDv1 of `codec` for tests and benchmarks, with a SHA2-256
+  /// multihash whose digest is derived from `seed`.
+  ///
+  /// The same seed always gives the same CID and different seeds give
+  /// different CIDs. The digest starts with the ASCII bytes `fixture:`, so
+  /// the CID is valid but clearly not the hash of any content, see
+  /// [`Cid::is_fixture`]. Fails if the 32 byte digest doesn't fit into the
+  /// allocated size.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let cids: Vec<Cid> =
+  ///   (0..3).map(|seed| Cid::from_seed(seed, 0x55).unwrap()).collect();
+  /// assert_eq!(cids[1], Cid::from_seed(1, 0x55).unwrap());
+  /// assert_ne!(cids[0], cids[1]);
+  /// assert!(cids.iter().all(Cid::is_fixture));
+  /// ```
+  pub fn from_seed(seed: u64, codec: u64) -> Result<Self> {
+    let mut digest = [0u8; DIGEST_LEN];
+    let (marker, rest) = digest.split_at_mut(MARKER.len());
+    marker.copy_from_slice(MARKER);
+    // The first output is a bijection of the seed, so seeds don't collide
+    let mut state = seed;
+    for word in rest.chunks_mut(8) {
+      word.copy_from_slice(&splitmix64(&mut state).to_be_bytes());
+    }
+    Ok(Self::new_v1(codec, wrap_digest(SHA2_256, &digest)?))
+  }
+
+  /// Returns whether the CID was created by [`Cid::from_seed`], e.g. to
+  /// keep fixtures out of production stores.
+  pub fn is_fixture(&self) -> bool {
+    let hash = self.hash();
+    hash.code() == SHA2_256
+      && hash.digest().len() == DIGEST_LEN
+      && hash.digest().starts_with(MARKER)
+  }
+}
@@ -0,0 +1,119 @@
+//! A CID wrapper that precomputes its hash.
+//!
+//! Hashing a CID feeds its version, codec and whole digest to the hasher on
+//! every map lookup. Indexers doing millions of lookups per second can wrap
+//! their keys in a [`HashedCid`], which hashes the CID once at construction.
+use sp_std::{cmp::Ordering, fmt, hash, ops::Deref};
+
+use sp_multihash::Size;
+
+use crate::cid::{Cid, MAX_LEN};
+
+/// The FNV-1a 64 bit offset basis.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+/// The FNV-1a 64 bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A CID with a precomputed 64 bit hash.
+///
+/// `Hash` writes the precomputed value with a single `write_u64`, so the
+/// wrapper can be used with pass-through hashers such as the ones of the
+/// `nohash-hasher` crate (enable the `nohash-hasher` feature). The hash is
+/// FNV-1a over the binary form of the CID. It isn't keyed, so maps that hold
+/// CIDs chosen by an attacker should keep using a keyed hasher.
+#[derive(Clone)]
+pub struct HashedCid<S: Size> {
+  /// The wrapped CID.
+  cid: Cid<S>,
+  /// The precomputed hash of the CID.
+  hash: u64,
+}
+
+impl<S: Size> HashedCid<S> {
+  /// Wraps a CID and computes its hash.
+  pub fn new(cid: Cid<S>) -> Self {
+    let mut buf = [0u8; MAX_LEN];
+    let len = cid.write_to_slice(&mut buf);
+    let hash = buf[..len].iter().fold(FNV_OFFSET, |hash, byte| {
+      (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    Self { cid, hash }
+  }
+
+  /// Returns the wrapped CID.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+
+  /// Returns the precomputed hash.
+  pub fn precomputed_hash(&self) -> u64 {
+    self.hash
+  }
+}
+
+impl<S: Size> Deref for HashedCid<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<S: Size> From<Cid<S>> for HashedCid<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self::new(cid)
+  }
+}
+
+impl<S: Size> From<HashedCid<S>> for Cid<S> {
+  fn from(hashed: HashedCid<S>) -> Self {
+    hashed.cid
+  }
+}
+
+impl<S: Size> PartialEq for HashedCid<S> {
+  fn eq(&self, other: &Self) -> bool {
+    // Different hashes rule out equal CIDs without comparing digests
+    self.hash == other.hash && self.cid == other.cid
+  }
+}
+
+impl<S: Size> Eq for HashedCid<S> {}
+
+impl<S: Size> PartialOrd for HashedCid<S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<S: Size> Ord for HashedCid<S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.cid.cmp(&other.cid)
+  }
+}
+
+impl<S: Size> hash::Hash for HashedCid<S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    state.write_u64(self.hash)
+  }
+}
+
+#[cfg(feature = "nohash-hasher")]
+impl<S: Size> nohash_hasher::IsEnabled for HashedCid<S> {}
+
+impl<S: Size> fmt::Display for HashedCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.cid, f)
+  }
+}
+
+impl<S: Size> fmt::Debug for HashedCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
@@ -0,0 +1,91 @@
+//! Incremental hashing of data into a CID.
+//!
+//! [`Cid::from_data`] needs the whole data in memory. A [`CidHasher`] is fed
+//! the data in chunks instead, so large files can be addressed while they are
+//! streamed.
+use sp_multihash::{
+  MultihashDigest, MultihashGeneric as Multihash, Size, StatefulHasher,
+};
+
+use crate::{
+  cid::{truncate, Cid},
+  error::Result,
+};
+
+/// A hasher that finalizes to a CIDv1.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidHasher};
+/// use sp_multihash::{Code, MultihashDigest, Sha2_256};
+///
+/// const RAW: u64 = 0x55;
+///
+/// let mut hasher = CidHasher::<Sha2_256>::new(RAW, Code::Sha2_256);
+/// hasher.update(b"fo");
+/// hasher.update(b"o");
+/// let cid: Cid = hasher.finalize().unwrap();
+/// assert_eq!(cid, Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")));
+/// ```
+#[derive(Debug)]
+pub struct CidHasher<H: StatefulHasher> {
+  /// The codec of the CID.
+  codec: u64,
+  /// The multihash code of the hash function.
+  code: u64,
+  /// The state of the hash function.
+  hasher: H,
+}
+
+impl<H: StatefulHasher> CidHasher<H> {
+  /// Create a hasher for a CID with `codec`, hashing with `H`.
+  ///
+  /// `code` is the multihash code of `H`, e.g. [`Code::Sha2_256`] for
+  /// [`Sha2_256`]. It isn't checked against the hasher.
+  ///
+  /// [`Code::Sha2_256`]: sp_multihash::Code::Sha2_256
+  /// [`Sha2_256`]: sp_multihash::Sha2_256
+  pub fn new<C: MultihashDigest>(codec: u64, code: C) -> Self {
+    Self { codec, code: code.into(), hasher: H::default() }
+  }
+
+  /// Hashes a chunk of the data.
+  pub fn update(&mut self, data: &[u8]) {
+    self.hasher.update(data)
+  }
+
+  /// Returns the CID of the data hashed so far.
+  ///
+  /// Fails if the digest doesn't fit into the allocated size `S`.
+  pub fn finalize<S: Size>(&self) -> Result<Cid<S>> {
+    let digest = self.hasher.finalize();
+    let hash = Multihash::wrap(self.code, digest.as_ref())?;
+    Ok(Cid::new_v1(self.codec, hash))
+  }
+
+  /// Returns the CID of the data hashed so far, with the digest truncated to
+  /// its first `len` bytes, see [`Cid::from_data_truncated`].
+  pub fn finalize_truncated<S: Size>(&self, len: usize) -> Result<Cid<S>> {
+    let digest = self.hasher.finalize();
+    let hash = truncate(self.code, digest.as_ref(), len)?;
+    Ok(Cid::new_v1(self.codec, hash))
+  }
+
+  /// Resets the hasher to hash new data.
+  pub fn reset(&mut self) {
+    self.hasher.reset()
+  }
+}
+
+#[cfg(feature = "std")]
+impl<H: StatefulHasher> std::io::Write for CidHasher<H> {
+  fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+    self.update(buf);
+    Ok(buf.len())
+  }
+
+  fn flush(&mut self) -> std::io::Result<()> {
+    Ok(())
+  }
+}
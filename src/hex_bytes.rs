@@ -0,0 +1,103 @@
+//! `0x` prefixed hex serialization of CIDs, behind the `serde-codec` feature.
+//!
+//! The JSON-RPC APIs of EVM-compatible chains pass all binary data as `0x`
+//! prefixed hex strings, and their tooling can't handle multibase strings.
+//! [`CidHexBytes`] serializes a CID in that convention.
+use sp_std::{convert::TryFrom, fmt, marker::PhantomData, ops::Deref};
+
+use serde::{
+  de::{self, Visitor},
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+use sp_multihash::{Size, U64};
+
+use crate::{
+  bases,
+  cid::{Cid, MAX_LEN},
+};
+
+/// A CID that serializes as the `0x` prefixed lower case hex of its binary
+/// form, in human readable and binary formats alike.
+///
+/// Deserialization accepts hex of either case, with a `0x` or `0X` prefix.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidHexBytes};
+///
+/// let cid: Cid = "bafkqaaa".parse().unwrap();
+/// let json = serde_json::to_string(&CidHexBytes(cid)).unwrap();
+/// assert_eq!(json, r#""0x01550000""#);
+/// let hex: CidHexBytes = serde_json::from_str(&json).unwrap();
+/// assert_eq!(hex.0, cid);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CidHexBytes<S: Size = U64>(pub Cid<S>);
+
+impl<S: Size> Deref for CidHexBytes<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.0
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CidHexBytes<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self(cid)
+  }
+}
+
+impl<S: Size> From<CidHexBytes<S>> for Cid<S> {
+  fn from(hex: CidHexBytes<S>) -> Self {
+    hex.0
+  }
+}
+
+/// Displays bytes as `0x` prefixed lower case hex.
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("0x")?;
+    self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+  }
+}
+
+impl<S: Size> Serialize for CidHexBytes<S> {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.0.write_to_slice(&mut buf);
+    serializer.collect_str(&Hex(&buf[..len]))
+  }
+}
+
+impl<'de, S: Size> Deserialize<'de> for CidHexBytes<S> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    deserializer.deserialize_str(HexVisitor(PhantomData))
+  }
+}
+
+/// Visitor for the `0x` prefixed hex string of a CID.
+struct HexVisitor<S>(PhantomData<S>);
+
+impl<S: Size> Visitor<'_> for HexVisitor<S> {
+  type Value = CidHexBytes<S>;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str("a 0x prefixed hex string of a binary CID")
+  }
+
+  fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+    let hex = s
+      .strip_prefix("0x")
+      .or_else(|| s.strip_prefix("0X"))
+      .ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))?;
+    let bytes = bases::decode_hex(hex)
+      .ok_or_else(|| E::invalid_value(de::Unexpected::Str(s), &self))?;
+    Cid::try_from(bytes.as_slice()).map(CidHexBytes).map_err(E::custom)
+  }
+}
@@ -0,0 +1,159 @@
+//! Hints on how to fix CID strings that don't parse.
+//!
+//! Most CID strings that reach RPC endpoints and fail to parse are a valid
+//! CID with a common mistake: a hex dump of the binary form, a CIDv0 with a
+//! multibase prefix, or a missing or wrong prefix. [`parse_with_hint`] tries
+//! a few cheap repairs of a failed input and reports the one that works as a
+//! [`Hint`], which can be shown to the user next to the error.
+//!
+//! [`parse_with_hint`]: crate::ParseOptions::parse_with_hint
+use sp_std::{convert::TryFrom, fmt};
+
+use alloc::format;
+use sp_multihash::Size;
+
+use crate::{
+  bases,
+  cid::{Cid, MAX_LEN},
+  error::Error,
+  version::Version,
+};
+
+/// The multibase prefixes that are tried for a missing or wrong prefix, the
+/// ones of the bases that are always supported.
+const PREFIXES: [char; 4] = ['b', 'z', 'k', 'B'];
+
+/// A likely fix for a CID string that doesn't parse.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+  /// The string is the hex encoded binary form of a CID, without the `f`
+  /// prefix of the Base16 multibase.
+  HexEncoded,
+  /// The string is a CIDv0 with this multibase prefix, but CIDv0 strings
+  /// have none.
+  PrefixedV0(char),
+  /// The string parses with this multibase prefix in front of it.
+  MissingPrefix(char),
+  /// The string parses with a different multibase prefix.
+  WrongPrefix {
+    /// The prefix of the string.
+    found: char,
+    /// The prefix the string parses with.
+    expected: char,
+  },
+}
+
+impl Hint {
+  /// Looks for a repair of `input` that parses as a CID.
+  ///
+  /// Inputs longer than any CID string in the supported bases are not
+  /// examined.
+  pub(crate) fn diagnose<S: Size>(input: &str) -> Option<Self> {
+    if input.is_empty() || input.len() > 4 * MAX_LEN {
+      return None;
+    }
+    let hex = input.strip_prefix("0x").unwrap_or(input);
+    if let Some(bytes) = bases::decode_hex(hex) {
+      if Cid::<S>::try_from(bytes.as_slice()).is_ok() {
+        return Some(Hint::HexEncoded);
+      }
+    }
+    let mut chars = input.chars();
+    let found = chars.next()?;
+    let rest = chars.as_str();
+    if rest.starts_with("Qm") {
+      if let Ok(cid) = Cid::<S>::try_from(rest) {
+        if cid.version() == Version::V0 {
+          return Some(Hint::PrefixedV0(found));
+        }
+      }
+    }
+    let parses = |s: &str| Cid::<S>::try_from(s).is_ok();
+    if let Some(prefix) =
+      PREFIXES.iter().find(|prefix| parses(&format!("{}{}", prefix, input)))
+    {
+      return Some(Hint::MissingPrefix(*prefix));
+    }
+    PREFIXES
+      .iter()
+      .filter(|prefix| **prefix != found)
+      .find(|prefix| parses(&format!("{}{}", prefix, rest)))
+      .map(|expected| Hint::WrongPrefix { found, expected: *expected })
+  }
+}
+
+impl fmt::Display for Hint {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Hint::HexEncoded => f.write_str(
+        "the input is a hex encoded binary CID, prefix it with `f` to make it \
+         a multibase string",
+      ),
+      Hint::PrefixedV0(prefix) => write!(
+        f,
+        "CIDv0 strings have no multibase prefix, remove `{}`",
+        prefix
+      ),
+      Hint::MissingPrefix(prefix) => {
+        write!(f, "the multibase prefix is missing, add `{}`", prefix)
+      }
+      Hint::WrongPrefix { found, expected } => write!(
+        f,
+        "the multibase prefix `{}` doesn't match the encoding, use `{}`",
+        found, expected
+      ),
+    }
+  }
+}
+
+/// The error of a failed parse, with a hint on how to fix the input.
+#[derive(Debug, PartialEq)]
+pub struct HintedError {
+  /// The error of the parse.
+  error: Error,
+  /// The likely fix, if one was found.
+  hint: Option<Hint>,
+}
+
+impl HintedError {
+  /// Creates the error for a failed parse of `input`.
+  pub(crate) fn new<S: Size>(error: Error, input: &str) -> Self {
+    Self { error, hint: Hint::diagnose::<S>(input) }
+  }
+
+  /// Returns the error of the parse.
+  pub fn error(&self) -> &Error {
+    &self.error
+  }
+
+  /// Returns the likely fix of the input, if one was found.
+  pub fn hint(&self) -> Option<Hint> {
+    self.hint
+  }
+
+  /// Drops the hint.
+  pub fn into_error(self) -> Error {
+    self.error
+  }
+}
+
+impl From<HintedError> for Error {
+  fn from(err: HintedError) -> Self {
+    err.error
+  }
+}
+
+impl fmt::Display for HintedError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.hint {
+      Some(hint) => write!(f, "{} ({})", self.error, hint),
+      None => fmt::Display::fmt(&self.error, f),
+    }
+  }
+}
+
+impl core::error::Error for HintedError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    Some(&self.error)
+  }
+}
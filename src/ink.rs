@@ -0,0 +1,102 @@
+//! Storage of CIDs in ink! smart contracts, behind the `ink` feature.
+//!
+//! A CID is packed into a single storage cell in its SCALE encoding, like the
+//! primitive types of ink!.
+use ink_primitives::Key;
+use ink_storage::traits::{
+  forward_clear_packed, forward_pull_packed, forward_push_packed, KeyPtr,
+  PackedLayout, SpreadLayout,
+};
+use parity_scale_codec::{Decode, Encode};
+use sp_multihash::{MultihashGeneric as Multihash, Size};
+
+use crate::cid::Cid;
+
+impl<S: Size> SpreadLayout for Cid<S>
+where
+  Multihash<S>: Encode + Decode,
+{
+  const FOOTPRINT: u64 = 1;
+
+  fn pull_spread(ptr: &mut KeyPtr) -> Self {
+    forward_pull_packed::<Self>(ptr)
+  }
+
+  fn push_spread(&self, ptr: &mut KeyPtr) {
+    forward_push_packed::<Self>(self, ptr)
+  }
+
+  fn clear_spread(&self, ptr: &mut KeyPtr) {
+    forward_clear_packed::<Self>(self, ptr)
+  }
+}
+
+/// A CID has no fields in other cells, so there is nothing to pull, push or
+/// clear besides its own encoding.
+impl<S: Size> PackedLayout for Cid<S>
+where
+  Multihash<S>: Encode + Decode,
+{
+  fn pull_packed(&mut self, _at: &Key) {}
+
+  fn push_packed(&self, _at: &Key) {}
+
+  fn clear_packed(&self, _at: &Key) {}
+}
+
+/// The layout is only part of the contract metadata, which is generated with
+/// the `std` feature.
+#[cfg(feature = "std")]
+impl<S: Size + 'static> ink_storage::traits::StorageLayout for Cid<S>
+where
+  Multihash<S>: Encode + Decode,
+{
+  fn layout(key_ptr: &mut KeyPtr) -> ink_metadata::layout::Layout {
+    use ink_metadata::layout::{CellLayout, Layout, LayoutKey};
+
+    Layout::Cell(CellLayout::new::<Self>(LayoutKey::from(
+      key_ptr.advance_by(1),
+    )))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use ink_primitives::Key;
+  use ink_storage::traits::{KeyPtr, PackedLayout, SpreadLayout};
+  use parity_scale_codec::{Decode, Encode};
+  use sp_multihash::{Code, MultihashDigest, U32, U64};
+
+  use crate::cid::Cid;
+
+  #[test]
+  fn packs_into_one_cell() {
+    assert_eq!(<Cid<U64> as SpreadLayout>::FOOTPRINT, 1);
+
+    let cid = Cid::<U32>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let mut packed = cid;
+    packed.push_packed(&Key::from([0x42; 32]));
+    packed.pull_packed(&Key::from([0x42; 32]));
+    assert_eq!(packed, cid);
+
+    // The cell holds the SCALE encoding
+    let cell = cid.encode();
+    assert_eq!(Cid::<U32>::decode(&mut &cell[..]).unwrap(), cid);
+  }
+
+  #[cfg(feature = "std")]
+  #[test]
+  fn layout() {
+    use ink_metadata::layout::{CellLayout, Layout, LayoutKey};
+    use ink_storage::traits::StorageLayout;
+
+    let key = Key::from([0x42; 32]);
+    let mut key_ptr = KeyPtr::from(key);
+    let mut expected = KeyPtr::from(key);
+    for _ in 0..2 {
+      let cell = LayoutKey::from(expected.next_for::<Cid<U64>>());
+      let layout = Layout::Cell(CellLayout::new::<Cid<U64>>(cell));
+      assert_eq!(<Cid<U64> as StorageLayout>::layout(&mut key_ptr), layout);
+    }
+  }
+}
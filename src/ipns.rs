@@ -0,0 +1,130 @@
+//! IPNS names.
+//!
+//! An IPNS name is the CIDv1 of a public key with the libp2p-key codec. Its
+//! text form is Base36, as in `/ipns/k51...`, or Base32. [`IpnsName`] only
+//! holds such CIDs, so naming code can't mix up names and content CIDs.
+use sp_std::{convert::TryFrom, fmt, str};
+
+use alloc::string::{String, ToString};
+use multibase::Base;
+use sp_multihash::{MultihashGeneric as Multihash, Size, U64};
+
+use crate::{
+  bases,
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// The libp2p-key multicodec code, the codec of peer ids and IPNS names.
+pub(crate) const LIBP2P_KEY: u64 = 0x72;
+
+/// A CIDv1 with the libp2p-key codec, the name of an IPNS record.
+///
+/// It is displayed in lower case Base36, the canonical form of IPNS names,
+/// which works without the `all-bases` feature.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::IpnsName;
+///
+/// let name: IpnsName =
+///   "k51qzi5uqu5dlvj2baxnqndepeb86cbk3ng7n3i46uzyxzyqj2xjonzllnv0v8"
+///     .parse()
+///     .unwrap();
+/// assert_eq!(name.as_cid().codec(), 0x72);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpnsName<S: Size = U64>(Cid<S>);
+
+impl<S: Size> IpnsName<S> {
+  /// Wraps a CID, which has to have the libp2p-key codec.
+  ///
+  /// A CIDv0 always has the DAG-PB codec, so the CID is a CIDv1.
+  pub fn new(cid: Cid<S>) -> Result<Self> {
+    if cid.codec() != LIBP2P_KEY {
+      return Err(Error::UnexpectedCodec {
+        expected: LIBP2P_KEY,
+        found: cid.codec(),
+      });
+    }
+    Ok(Self(cid))
+  }
+
+  /// Creates the name of the public key with the multihash `hash`.
+  pub fn from_hash(hash: Multihash<S>) -> Self {
+    Self(Cid::new_v1(LIBP2P_KEY, hash))
+  }
+
+  /// Returns the multihash of the public key.
+  pub fn hash(&self) -> &Multihash<S> {
+    self.0.hash()
+  }
+
+  /// Returns the CID of the name.
+  pub fn as_cid(&self) -> &Cid<S> {
+    &self.0
+  }
+
+  /// Unwraps the CID of the name.
+  pub fn into_cid(self) -> Cid<S> {
+    self.0
+  }
+
+  /// Encodes the name in lower case Base32 instead of Base36.
+  pub fn to_base32_string(&self) -> String {
+    self.0.to_string()
+  }
+}
+
+impl<S: Size> fmt::Display for IpnsName<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let s = bases::encode_prefixed(Base::Base36Lower, &self.0.to_bytes())
+      .map_err(|_| fmt::Error)?;
+    f.write_str(&s)
+  }
+}
+
+impl<S: Size> TryFrom<&str> for IpnsName<S> {
+  type Error = Error;
+
+  /// Parses a name in Base36 or Base32, in either case.
+  fn try_from(name: &str) -> Result<Self> {
+    let base = Base::from_code(name.chars().next().unwrap_or_default())?;
+    match base {
+      Base::Base36Lower
+      | Base::Base36Upper
+      | Base::Base32Lower
+      | Base::Base32Upper => Self::new(Cid::try_from(name)?),
+      _ => Err(Error::UnsupportedBase(base)),
+    }
+  }
+}
+
+impl<S: Size> str::FromStr for IpnsName<S> {
+  type Err = Error;
+
+  fn from_str(name: &str) -> Result<Self> {
+    Self::try_from(name)
+  }
+}
+
+impl<S: Size> TryFrom<Cid<S>> for IpnsName<S> {
+  type Error = Error;
+
+  fn try_from(cid: Cid<S>) -> Result<Self> {
+    Self::new(cid)
+  }
+}
+
+impl<S: Size> From<IpnsName<S>> for Cid<S> {
+  fn from(name: IpnsName<S>) -> Self {
+    name.0
+  }
+}
+
+impl<S: Size> AsRef<Cid<S>> for IpnsName<S> {
+  fn as_ref(&self) -> &Cid<S> {
+    &self.0
+  }
+}
@@ -0,0 +1,75 @@
+//! Conversion between CIDs and untyped JSON values, behind the `json`
+//! feature.
+//!
+//! RPC layers often hold requests as [`serde_json::Value`] before they are
+//! checked against a schema. A CID in such a value is either a plain string
+//! or, in DAG-JSON, a link object `{"/": "<cid>"}`.
+use sp_std::convert::TryFrom;
+
+use alloc::string::ToString;
+use serde_json::{Map, Value};
+use sp_multihash::Size;
+
+use crate::{
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// The JSON shape of a CID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonStyle {
+  /// The default string form.
+  String,
+  /// A DAG-JSON link, an object with the default string form as the only
+  /// value, under the key `/`.
+  DagJson,
+}
+
+impl<S: Size> Cid<S> {
+  /// Reads a CID from a JSON string or a DAG-JSON link object.
+  ///
+  /// Other values, including link objects with further keys and DAG-JSON
+  /// bytes, return [`multibase::Error::InvalidBaseString`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use serde_json::json;
+  /// use sp_cid::{Cid, JsonStyle};
+  ///
+  /// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let cid: Cid = s.parse().unwrap();
+  /// assert_eq!(Cid::from_json_value(&json!(s)).unwrap(), cid);
+  /// assert_eq!(Cid::from_json_value(&json!({ "/": s })).unwrap(), cid);
+  /// assert_eq!(cid.to_json_value(JsonStyle::DagJson), json!({ "/": s }));
+  /// ```
+  pub fn from_json_value(value: &Value) -> Result<Self> {
+    let cid_str = match value {
+      Value::String(s) => s,
+      Value::Object(map) if map.len() == 1 => match map.get("/") {
+        Some(Value::String(s)) => s,
+        _ => return Err(invalid_json()),
+      },
+      _ => return Err(invalid_json()),
+    };
+    Self::try_from(cid_str.as_str())
+  }
+
+  /// Returns the CID as a JSON value of the given style.
+  pub fn to_json_value(&self, style: JsonStyle) -> Value {
+    let s = Value::String(self.to_string());
+    match style {
+      JsonStyle::String => s,
+      JsonStyle::DagJson => {
+        let mut map = Map::new();
+        map.insert("/".into(), s);
+        Value::Object(map)
+      }
+    }
+  }
+}
+
+/// The error of JSON values that aren't a CID.
+fn invalid_json() -> Error {
+  Error::MultibaseError(multibase::Error::InvalidBaseString)
+}
@@ -0,0 +1,154 @@
+//! Map lookups of CIDs by borrowed CIDs.
+//!
+//! Maps keyed by [`Cid`] can only be queried with a type the key borrows as.
+//! A [`CidRef`] read from the wire doesn't own a multihash, so converting it
+//! to a `Cid` for every lookup copies the digest. Instead, `Cid` borrows as
+//! `dyn CidKey`, which every CID type implements:
+//!
+//! ```
+//! use std::collections::HashMap;
+//!
+//! use sp_cid::{Cid, CidKey, CidRef};
+//! use sp_multihash::{Code, MultihashDigest};
+//!
+//! let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+//! let mut providers = HashMap::new();
+//! providers.insert(cid, "peer");
+//!
+//! let wire = cid.to_bytes();
+//! let (cid_ref, _) = CidRef::read(&wire).unwrap();
+//! assert_eq!(providers.get(&cid_ref as &dyn CidKey), Some(&"peer"));
+//! ```
+use sp_std::{borrow::Borrow, cmp::Ordering, hash};
+
+use sp_multihash::Size;
+
+use crate::{
+  cid::{write_parts, Cid, MAX_LEN},
+  cid_dyn::CidDyn,
+  cid_ref::CidRef,
+  version::Version,
+};
+
+/// The parts that identify a CID, for looking up [`Cid`] keys by other CID
+/// types.
+///
+/// Equality, hashing and ordering of `dyn CidKey` are the ones of `Cid`.
+pub trait CidKey {
+  /// Returns the version of the CID.
+  fn version(&self) -> Version;
+
+  /// Returns the codec of the CID.
+  fn codec(&self) -> u64;
+
+  /// Returns the multihash code of the CID.
+  fn hash_code(&self) -> u64;
+
+  /// Returns the digest of the CID, at most 255 bytes long.
+  fn digest(&self) -> &[u8];
+}
+
+impl<S: Size> CidKey for Cid<S> {
+  fn version(&self) -> Version {
+    Cid::version(self)
+  }
+
+  fn codec(&self) -> u64 {
+    Cid::codec(self)
+  }
+
+  fn hash_code(&self) -> u64 {
+    self.hash().code()
+  }
+
+  fn digest(&self) -> &[u8] {
+    self.hash().digest()
+  }
+}
+
+impl CidKey for CidRef<'_> {
+  fn version(&self) -> Version {
+    CidRef::version(self)
+  }
+
+  fn codec(&self) -> u64 {
+    CidRef::codec(self)
+  }
+
+  fn hash_code(&self) -> u64 {
+    CidRef::hash_code(self)
+  }
+
+  fn digest(&self) -> &[u8] {
+    CidRef::digest(self)
+  }
+}
+
+impl CidKey for CidDyn {
+  fn version(&self) -> Version {
+    CidDyn::version(self)
+  }
+
+  fn codec(&self) -> u64 {
+    CidDyn::codec(self)
+  }
+
+  fn hash_code(&self) -> u64 {
+    CidDyn::hash_code(self)
+  }
+
+  fn digest(&self) -> &[u8] {
+    CidDyn::digest(self)
+  }
+}
+
+impl<'a, S: Size> Borrow<dyn CidKey + 'a> for Cid<S> {
+  fn borrow(&self) -> &(dyn CidKey + 'a) {
+    self
+  }
+}
+
+impl PartialEq for dyn CidKey + '_ {
+  fn eq(&self, other: &Self) -> bool {
+    self.version() == other.version()
+      && self.codec() == other.codec()
+      && self.hash_code() == other.hash_code()
+      && self.digest() == other.digest()
+  }
+}
+
+impl Eq for dyn CidKey + '_ {}
+
+impl PartialOrd for dyn CidKey + '_ {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+/// Orders by the binary form, like [`Cid`].
+impl Ord for dyn CidKey + '_ {
+  fn cmp(&self, other: &Self) -> Ordering {
+    let mut buf = [0u8; MAX_LEN];
+    let len = write_key(self, &mut buf);
+    let mut other_buf = [0u8; MAX_LEN];
+    let other_len = write_key(other, &mut other_buf);
+    buf[..len].cmp(&other_buf[..other_len])
+  }
+}
+
+/// Hashes like [`Cid`].
+impl hash::Hash for dyn CidKey + '_ {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    self.version().hash(state);
+    self.codec().hash(state);
+    self.hash_code().hash(state);
+    self.digest().hash(state);
+  }
+}
+
+/// Writes the binary form of the CID into `buf`, returning its length.
+pub(crate) fn write_key(key: &dyn CidKey, buf: &mut [u8; MAX_LEN]) -> usize {
+  // The length of the digest is written as a single byte
+  let digest = &key.digest()[..key.digest().len().min(255)];
+  write_parts(key.version(), key.codec(), key.hash_code(), digest, buf)
+}
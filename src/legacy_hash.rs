@@ -0,0 +1,240 @@
+//! Storage values of chains migrating from bare hashes to CIDs.
+//!
+//! A chain that stored content as bare 32 byte hashes can't turn them into
+//! CIDs in a single runtime upgrade, as it may not know the codec of every
+//! item. [`CidOrLegacyHash`] holds either, so a migration only wraps the
+//! existing hashes, and items are upgraded to CIDs when their codec is known.
+use sp_std::fmt;
+
+use parity_scale_codec::{
+  Decode, Encode, EncodeLike, Error, Input, MaxEncodedLen, Output,
+};
+use sp_multihash::{MultihashGeneric as Multihash, Size, U64};
+
+use crate::{cid::Cid, error};
+
+/// The SCALE variant index of [`CidOrLegacyHash::Legacy`].
+const LEGACY_INDEX: u8 = 0;
+/// The SCALE variant index of [`CidOrLegacyHash::Cid`].
+const CID_INDEX: u8 = 1;
+
+/// Either a bare 32 byte hash stored before a migration, or a CID.
+///
+/// The SCALE encoding is the one of an enum with these variants, in this
+/// order.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidOrLegacyHash};
+///
+/// const RAW: u64 = 0x55;
+/// const SHA2_256: u64 = 0x12;
+///
+/// let mut item = CidOrLegacyHash::from([0u8; 32]);
+/// assert!(item.is_legacy());
+/// item.upgrade(RAW, SHA2_256).unwrap();
+/// let cid: &Cid = item.as_cid().unwrap();
+/// assert_eq!(cid.codec(), RAW);
+/// assert_eq!(cid.hash().digest(), &[0u8; 32]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum CidOrLegacyHash<S: Size = U64> {
+  /// A bare hash.
+  Legacy([u8; 32]),
+  /// A CID.
+  Cid(Cid<S>),
+}
+
+impl<S: Size> CidOrLegacyHash<S> {
+  /// Returns whether this is a bare hash.
+  pub fn is_legacy(&self) -> bool {
+    matches!(self, Self::Legacy(_))
+  }
+
+  /// Returns the bare hash, if this is one.
+  pub fn legacy_hash(&self) -> Option<&[u8; 32]> {
+    match self {
+      Self::Legacy(hash) => Some(hash),
+      Self::Cid(_) => None,
+    }
+  }
+
+  /// Returns the CID, if this is one.
+  pub fn as_cid(&self) -> Option<&Cid<S>> {
+    match self {
+      Self::Legacy(_) => None,
+      Self::Cid(cid) => Some(cid),
+    }
+  }
+
+  /// Converts to a CID, turning a bare hash into a CIDv1 of `codec` whose
+  /// multihash is the hash with the multihash code `hash_code`.
+  ///
+  /// A CID is returned as is, no matter its codec and hash function.
+  pub fn into_cid(self, codec: u64, hash_code: u64) -> error::Result<Cid<S>> {
+    match self {
+      Self::Legacy(hash) => {
+        Ok(Cid::new_v1(codec, Multihash::wrap(hash_code, &hash)?))
+      }
+      Self::Cid(cid) => Ok(cid),
+    }
+  }
+
+  /// Replaces a bare hash with a CID like [`CidOrLegacyHash::into_cid`].
+  pub fn upgrade(&mut self, codec: u64, hash_code: u64) -> error::Result<()> {
+    if let Self::Legacy(hash) = self {
+      let hash = Multihash::wrap(hash_code, hash)?;
+      *self = Self::Cid(Cid::new_v1(codec, hash));
+    }
+    Ok(())
+  }
+}
+
+impl<S: Size> From<[u8; 32]> for CidOrLegacyHash<S> {
+  fn from(hash: [u8; 32]) -> Self {
+    Self::Legacy(hash)
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CidOrLegacyHash<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self::Cid(cid)
+  }
+}
+
+impl<S: Size> Encode for CidOrLegacyHash<S>
+where
+  Multihash<S>: Encode,
+{
+  fn size_hint(&self) -> usize {
+    match self {
+      Self::Legacy(hash) => 1 + hash.len(),
+      Self::Cid(cid) => 1 + cid.size_hint(),
+    }
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    match self {
+      Self::Legacy(hash) => {
+        dest.push_byte(LEGACY_INDEX);
+        dest.write(hash);
+      }
+      Self::Cid(cid) => {
+        dest.push_byte(CID_INDEX);
+        cid.encode_to(dest);
+      }
+    }
+  }
+}
+
+impl<S: Size> EncodeLike for CidOrLegacyHash<S> where Multihash<S>: Encode {}
+
+impl<S: Size> Decode for CidOrLegacyHash<S>
+where
+  Multihash<S>: Decode,
+{
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    match input.read_byte()? {
+      LEGACY_INDEX => <[u8; 32]>::decode(input).map(Self::Legacy),
+      CID_INDEX => Cid::decode(input).map(Self::Cid),
+      _ => Err("invalid CidOrLegacyHash variant".into()),
+    }
+  }
+}
+
+impl<S: Size> MaxEncodedLen for CidOrLegacyHash<S>
+where
+  Multihash<S>: Encode,
+{
+  fn max_encoded_len() -> usize {
+    1 + Cid::<S>::max_encoded_len().max(32)
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<S: Size + 'static> scale_info::TypeInfo for CidOrLegacyHash<S> {
+  type Identity = Self;
+
+  fn type_info() -> scale_info::Type {
+    use scale_info::{
+      build::{Fields, Variants},
+      Path, Type,
+    };
+
+    Type::builder()
+      .path(Path::new("CidOrLegacyHash", module_path!()))
+      .variant(
+        Variants::new()
+          .variant("Legacy", |v| {
+            v.index(LEGACY_INDEX).fields(
+              Fields::unnamed()
+                .field(|f| f.ty::<[u8; 32]>().type_name("[u8; 32]")),
+            )
+          })
+          .variant("Cid", |v| {
+            v.index(CID_INDEX).fields(
+              Fields::unnamed().field(|f| f.ty::<Cid<S>>().type_name("Cid")),
+            )
+          }),
+      )
+  }
+}
+
+impl<S: Size> fmt::Debug for CidOrLegacyHash<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Legacy(hash) => {
+        f.write_str("Legacy(0x")?;
+        hash.iter().try_for_each(|byte| write!(f, "{:02x}", byte))?;
+        f.write_str(")")
+      }
+      Self::Cid(cid) => f.debug_tuple("Cid").field(cid).finish(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
+  use sp_multihash::{Code, MultihashDigest, U64};
+
+  use super::CidOrLegacyHash;
+  use crate::cid::Cid;
+
+  #[test]
+  fn encodes_as_enum() {
+    let legacy = CidOrLegacyHash::<U64>::from([7u8; 32]);
+    let encoded = legacy.encode();
+    assert_eq!(encoded, ([0u8], [7u8; 32]).encode());
+    assert_eq!(CidOrLegacyHash::decode(&mut &encoded[..]).unwrap(), legacy);
+
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let item = CidOrLegacyHash::from(cid);
+    let encoded = item.encode();
+    assert_eq!(encoded[0], 1);
+    assert_eq!(&encoded[1..], &cid.encode()[..]);
+    assert_eq!(CidOrLegacyHash::decode(&mut &encoded[..]).unwrap(), item);
+    assert!(encoded.len() <= CidOrLegacyHash::<U64>::max_encoded_len());
+
+    assert!(CidOrLegacyHash::<U64>::decode(&mut &[2u8][..]).is_err());
+  }
+
+  #[test]
+  fn upgrades_hashes() {
+    let digest = Code::Sha2_256.digest(b"foo");
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(digest.digest());
+    let cid = Cid::<U64>::new_v1(0x55, digest);
+
+    let legacy = CidOrLegacyHash::<U64>::from(hash);
+    assert_eq!(legacy.legacy_hash(), Some(&hash));
+    assert_eq!(legacy.clone().into_cid(0x55, 0x12).unwrap(), cid);
+    let mut item = legacy;
+    item.upgrade(0x55, 0x12).unwrap();
+    assert_eq!(item.as_cid(), Some(&cid));
+    // CIDs are kept
+    item.upgrade(0x70, 0x12).unwrap();
+    assert_eq!(item.as_cid(), Some(&cid));
+  }
+}
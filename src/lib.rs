@@ -1,48 +1,225 @@
 //! # cid
 //!
 //! Implementation of [cid](https://github.com/ipld/cid) in Rust.
+//!
+//! ## Allocation
+//!
+//! Targets without an allocator aren't supported, and there is no `alloc`
+//! feature to opt out of it. The multihash of every CID comes from
+//! `sp-multihash`, which links `alloc` unconditionally, as do `bytecursor`,
+//! whose cursor the streaming readers take, and `multibase`. Gating the
+//! `String` and `Vec` APIs of this crate wouldn't remove the allocator from
+//! the build until those crates can be built without it.
+//!
+//! What is supported is using the binary APIs without allocating, e.g. in
+//! enclaves that forbid heap allocation after startup. Creating CIDs,
+//! parsing their binary form with `TryFrom<&[u8]>`, [`CidRef`] and
+//! [`CidPrefix`], encoding it with [`Cid::encode_into`] and accessing the
+//! parts and digest never allocate. String APIs and the APIs returning
+//! `Vec`s allocate.
+//!
+//! [`Cid::encode_into`]: CidGeneric::encode_into
 
 #![cfg_attr(not(feature = "std"), no_std)]
+// The crate runs inside blockchain runtimes, where a panic is consensus
+// critical. Errors are returned instead.
+#![cfg_attr(
+  not(test),
+  deny(clippy::unwrap_used, clippy::expect_used, clippy::panic)
+)]
 
+mod audit;
+mod base32;
+mod bases;
+mod block;
+mod by_digest;
+mod cache_key;
+pub mod car;
+#[cfg(feature = "std")]
+mod cached;
 mod cid;
+mod cid_bytes;
+mod cid_dyn;
+mod cid_ref;
+mod const_bytes;
+pub mod consts;
+mod context;
+mod ct;
+#[cfg(feature = "std")]
+mod decoder;
+mod diff;
+mod display;
+mod distance;
+mod dump;
+mod encoder;
 mod error;
+mod fixture;
+mod hashed;
+mod hasher;
+mod hint;
+mod ipns;
+mod key;
+mod link;
+mod list;
+pub mod names;
+mod options;
+mod parsed;
+mod path;
+mod policy;
+mod prefix;
+mod raw;
+mod registry;
+mod shard;
+mod stats;
+mod storage_key;
+mod str_decoder;
+mod to_cid;
+mod trace;
+pub mod util;
 mod version;
+pub mod weights;
 
-#[cfg(any(test, feature = "arb"))]
+#[cfg(feature = "arb")]
 mod arb;
+#[cfg(feature = "scale-codec")]
+mod bounded;
+#[cfg(feature = "scale-codec")]
+mod cid_list;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(any(feature = "sqlx-codec", feature = "diesel-codec"))]
+mod database;
+#[cfg(feature = "uniffi-bindings")]
+pub mod ffi;
+#[cfg(feature = "serde-codec")]
+mod hex_bytes;
+#[cfg(feature = "ink")]
+mod ink;
+#[cfg(feature = "json")]
+mod json;
+#[cfg(feature = "scale-codec")]
+mod legacy_hash;
+#[cfg(feature = "rmp")]
+mod msgpack;
+#[cfg(feature = "multiaddr")]
+mod multiaddr;
+#[cfg(feature = "prost")]
+mod proto;
+#[cfg(feature = "scale-codec")]
+mod scale;
+#[cfg(feature = "serde-codec")]
+mod serde_codec;
+#[cfg(feature = "serde-codec")]
+mod serde_repr;
+#[cfg(feature = "speedy-codec")]
+mod speedy_codec;
+#[cfg(feature = "ufmt")]
+mod ufmt_display;
 
 pub use self::{
-  cid::Cid as CidGeneric,
-  error::{Error, Result},
+  audit::AUDIT_RECORD_VERSION,
+  block::Block,
+  by_digest::ByDigest,
+  cache_key::CacheKey,
+  cid::{
+    detect_base, is_valid_cid_str, transcode_base, Cid as CidGeneric, CidBase,
+    DisplayBase, ShortCid,
+  },
+  cid_bytes::CidBytes,
+  cid_dyn::CidDyn,
+  cid_ref::CidRef,
+  const_bytes::{cid_binary_len, cid_to_bytes},
+  context::DecodeContext,
+  diff::CidDiff,
+  distance::Distance,
+  dump::render_table,
+  encoder::CidEncoder,
+  error::{Error, ErrorKind, Result},
+  hashed::HashedCid,
+  hasher::CidHasher,
+  hint::{Hint, HintedError},
+  ipns::IpnsName,
+  key::CidKey,
+  link::{Link, LinkTarget},
+  list::{parse_all, parse_cid_list, LineError},
+  options::ParseOptions,
+  parsed::{CidWrapper, ParsedMeta},
+  path::CidOrPath,
+  policy::{AnyCid, CidPolicy, PolicyCid},
+  prefix::CidPrefix,
+  raw::RawCid,
+  registry::{DefaultHashers, HasherRegistry},
+  shard::ShardingScheme,
+  stats::CidStats,
+  str_decoder::CidStrDecoder,
+  to_cid::ToCid,
   version::Version,
 };
 
+#[cfg(feature = "scale-codec")]
+pub use self::bounded::BoundedCid;
+#[cfg(feature = "std")]
+pub use self::cached::CachedCid;
+#[cfg(feature = "scale-codec")]
+pub use self::cid_list::{CidList, CidListError};
+#[cfg(feature = "std")]
+pub use self::decoder::CidDecoder;
+#[cfg(feature = "serde-codec")]
+pub use self::hex_bytes::CidHexBytes;
+#[cfg(feature = "json")]
+pub use self::json::JsonStyle;
+#[cfg(feature = "scale-codec")]
+pub use self::legacy_hash::CidOrLegacyHash;
+#[cfg(feature = "prost")]
+pub use self::proto::ProtoCid;
+#[cfg(feature = "scale-codec")]
+pub use self::scale::CanonicalCid;
+#[cfg(feature = "serde-codec")]
+pub use self::serde_repr::{AsBytes, AsDagJson, AsString};
+#[cfg(feature = "derive")]
+pub use sp_cid_derive::ToCid;
+
 pub use multibase;
 pub use sp_multihash;
 
+/// Items used by the code the `ToCid` derive macro expands to.
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+  pub use alloc::vec::Vec;
+  #[cfg(feature = "scale-codec")]
+  pub use parity_scale_codec::Encode;
+}
+
+#[cfg(feature = "uniffi-bindings")]
+uniffi::setup_scaffolding!();
+
 extern crate alloc;
 use bytecursor::ByteCursor;
 use unsigned_varint::{decode, encode as varint_encode};
 
 /// Reader function from unsigned_varint
 pub fn varint_read_u64(r: &mut ByteCursor) -> Result<u64> {
+  let offset = r.position() as usize;
   let mut b = varint_encode::u64_buffer();
   for i in 0..b.len() {
     let n = r.read(&mut (b[i..i + 1]));
     if n == 0 {
-      return Err(Error::VarIntDecodeError);
+      return Err(Error::UnexpectedEof { offset });
     }
     if decode::is_last(b[i]) {
-      return Ok(decode::u64(&b[..=i]).unwrap().0);
+      return Ok(decode::u64(&b[..=i])?.0);
     }
   }
-  Err(Error::VarIntDecodeError)
+  Err(Error::VarIntDecodeError(decode::Error::Overflow))
 }
 
 /// A Cid that contains a multihash with an allocated size of 512 bits.
 ///
 /// This is the same digest size the default multihash code table has.
 ///
+/// It is `Copy`, `Send`, `Sync` and `Unpin`.
+///
 /// If you need a CID that is generic over its digest size, use [`CidGeneric`]
 /// instead.
 pub type Cid = CidGeneric<sp_multihash::U64>;
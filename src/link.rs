@@ -0,0 +1,208 @@
+//! Typed links between blocks.
+//!
+//! IPLD schemas describe which type of block a link points to. A
+//! [`Link<T>`](Link) carries that type as a parameter, so a link to one type
+//! of block can't be passed where a link to another one is expected. The
+//! encodings of a link are the ones of the wrapped CID, decoding checks that
+//! its codec is the one of the target type.
+use sp_std::{cmp::Ordering, convert::TryFrom, fmt, hash, marker::PhantomData};
+
+use sp_multihash::{Size, U64};
+
+use crate::{
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// The type of block a [`Link`] points to.
+pub trait LinkTarget {
+  /// The codec blocks of this type are encoded with.
+  const CODEC: u64;
+}
+
+/// A CID that links to a block of type `T`.
+pub struct Link<T, S: Size = U64> {
+  /// The wrapped CID.
+  cid: Cid<S>,
+  /// The type of the linked block, `fn() -> T` keeps the link `Send` and
+  /// `Sync` for any `T`.
+  target: PhantomData<fn() -> T>,
+}
+
+impl<T, S: Size> Link<T, S> {
+  /// Wraps a CID without checking its codec, e.g. for CIDs from trusted
+  /// storage.
+  pub fn new_unchecked(cid: Cid<S>) -> Self {
+    Self { cid, target: PhantomData }
+  }
+
+  /// Returns the wrapped CID.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+
+  /// Casts the link to a link to blocks of type `U`.
+  pub fn cast<U>(self) -> Link<U, S> {
+    Link::new_unchecked(self.cid)
+  }
+}
+
+impl<T: LinkTarget, S: Size> Link<T, S> {
+  /// Wraps a CID, checking that its codec is the one of `T`.
+  pub fn new(cid: Cid<S>) -> Result<Self> {
+    if cid.codec() != T::CODEC {
+      return Err(Error::UnexpectedCodec {
+        expected: T::CODEC,
+        found: cid.codec(),
+      });
+    }
+    Ok(Self::new_unchecked(cid))
+  }
+}
+
+impl<T: LinkTarget, S: Size> TryFrom<Cid<S>> for Link<T, S> {
+  type Error = Error;
+
+  fn try_from(cid: Cid<S>) -> Result<Self> {
+    Self::new(cid)
+  }
+}
+
+impl<T, S: Size> From<Link<T, S>> for Cid<S> {
+  fn from(link: Link<T, S>) -> Self {
+    link.cid
+  }
+}
+
+impl<T, S: Size> AsRef<Cid<S>> for Link<T, S> {
+  fn as_ref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<T, S: Size> Clone for Link<T, S> {
+  fn clone(&self) -> Self {
+    Self::new_unchecked(self.cid.clone())
+  }
+}
+
+impl<T, S: Size> Copy for Link<T, S> where S::ArrayType: Copy {}
+
+impl<T, S: Size> PartialEq for Link<T, S> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cid == other.cid
+  }
+}
+
+impl<T, S: Size> Eq for Link<T, S> {}
+
+impl<T, S: Size> PartialOrd for Link<T, S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<T, S: Size> Ord for Link<T, S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.cid.cmp(&other.cid)
+  }
+}
+
+impl<T, S: Size> hash::Hash for Link<T, S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    hash::Hash::hash(&self.cid, state)
+  }
+}
+
+impl<T, S: Size> fmt::Display for Link<T, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.cid, f)
+  }
+}
+
+impl<T, S: Size> fmt::Debug for Link<T, S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
+
+#[cfg(feature = "serde-codec")]
+impl<T, S: Size> serde::Serialize for Link<T, S> {
+  fn serialize<Ser: serde::Serializer>(
+    &self,
+    serializer: Ser,
+  ) -> core::result::Result<Ser::Ok, Ser::Error> {
+    self.cid.serialize(serializer)
+  }
+}
+
+/// Deserializes the wrapped CID, failing if its codec isn't the one of `T`.
+#[cfg(feature = "serde-codec")]
+impl<'de, T: LinkTarget, S: Size> serde::Deserialize<'de> for Link<T, S> {
+  fn deserialize<D: serde::Deserializer<'de>>(
+    deserializer: D,
+  ) -> core::result::Result<Self, D::Error> {
+    let cid = Cid::deserialize(deserializer)?;
+    Self::new(cid).map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(feature = "scale-codec")]
+mod scale {
+  use parity_scale_codec::{
+    Decode, Encode, EncodeLike, Error, Input, MaxEncodedLen, Output,
+  };
+  use sp_multihash::{MultihashGeneric as Multihash, Size};
+
+  use super::{Link, LinkTarget};
+  use crate::cid::Cid;
+
+  impl<T, S: Size> Encode for Link<T, S>
+  where
+    Multihash<S>: Encode,
+  {
+    fn size_hint(&self) -> usize {
+      self.cid.size_hint()
+    }
+
+    fn encode_to<O: Output + ?Sized>(&self, dest: &mut O) {
+      self.cid.encode_to(dest)
+    }
+  }
+
+  impl<T, S: Size> EncodeLike for Link<T, S> where Multihash<S>: Encode {}
+
+  /// Decodes the wrapped CID, failing if its codec isn't the one of `T`.
+  impl<T: LinkTarget, S: Size> Decode for Link<T, S>
+  where
+    Multihash<S>: Decode,
+  {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+      let cid = Cid::decode(input)?;
+      Self::new(cid).map_err(|_| "CID codec doesn't match the link".into())
+    }
+  }
+
+  impl<T, S: Size> MaxEncodedLen for Link<T, S>
+  where
+    Multihash<S>: Encode,
+  {
+    fn max_encoded_len() -> usize {
+      Cid::<S>::max_encoded_len()
+    }
+  }
+
+  #[cfg(feature = "scale-info")]
+  impl<T: 'static, S: Size + 'static> scale_info::TypeInfo for Link<T, S> {
+    type Identity = Cid<S>;
+
+    fn type_info() -> scale_info::Type {
+      Cid::<S>::type_info()
+    }
+  }
+}
@@ -0,0 +1,141 @@
+//! Parsing of CID list files.
+//!
+//! Pin lists and allowlists are text files with CIDs separated by
+//! whitespace, usually one per line. Blank lines are skipped and `#` starts a
+//! comment that runs to the end of the line.
+//!
+//! Bulk imports parse many CIDs in a row. [`parse_all`] and
+//! [`parse_cid_list`] decode them into one scratch buffer instead of
+//! allocating for every CID.
+use sp_std::{convert::TryFrom, fmt, vec::Vec};
+
+use sp_multihash::Size;
+
+use crate::{
+  base32,
+  cid::{read_slice, strip_ipfs_path, Cid, MAX_LEN},
+  error::{Error, Result},
+};
+
+/// Parses CID strings with a shared scratch buffer.
+struct BatchParser {
+  /// The binary form of the last CID.
+  scratch: Vec<u8>,
+}
+
+impl BatchParser {
+  /// Create a parser.
+  fn new() -> Self {
+    Self { scratch: Vec::with_capacity(MAX_LEN) }
+  }
+
+  /// Parses a CID string like [`Cid::try_from`].
+  fn parse<S: Size>(&mut self, cid_str: &str) -> Result<Cid<S>> {
+    // CIDv1 strings are base32 by default. The other bases, strings with
+    // upper case letters and errors take the regular path, so results are
+    // exactly the ones of `Cid::try_from`
+    if let Some(base32) = strip_ipfs_path(cid_str).strip_prefix('b') {
+      if base32::decode_lower_into(base32, &mut self.scratch) {
+        if let Ok(cid) = read_slice(&self.scratch) {
+          return Ok(cid);
+        }
+      }
+    }
+    Cid::try_from(cid_str)
+  }
+}
+
+/// Parses CID strings in bulk, returning the result of every string in
+/// order.
+///
+/// The results are the ones of [`Cid::try_from`], but CIDv1 strings in the
+/// default base32 are decoded into a scratch buffer shared by all of them,
+/// which saves an allocation per CID.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{parse_all, sp_multihash::U64};
+///
+/// let pins = [
+///   "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+///   "/ipfs/QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n",
+///   "foo",
+/// ];
+/// let results = parse_all::<U64>(pins.iter().copied());
+/// assert!(results[0].is_ok() && results[1].is_ok());
+/// assert!(results[2].is_err());
+/// ```
+pub fn parse_all<'a, S: Size>(
+  iter: impl Iterator<Item = &'a str>,
+) -> Vec<Result<Cid<S>>> {
+  let mut parser = BatchParser::new();
+  iter.map(|cid_str| parser.parse(cid_str)).collect()
+}
+
+/// The error for an entry of a CID list that failed to parse.
+#[derive(Debug)]
+pub struct LineError {
+  /// The line of the entry, starting at 1.
+  pub line: usize,
+  /// Why the entry failed to parse.
+  pub error: Error,
+}
+
+impl fmt::Display for LineError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "line {}: {}", self.line, self.error)
+  }
+}
+
+impl core::error::Error for LineError {
+  fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+    Some(&self.error)
+  }
+}
+
+/// Parses the CIDs of a list, in order.
+///
+/// All entries are parsed, so the error lists every invalid entry instead of
+/// only the first one.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{parse_cid_list, sp_multihash::U64, Cid};
+///
+/// let list = "
+///   bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy
+///
+///   QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n  # site root
+/// ";
+/// let cids: Vec<Cid> = parse_cid_list(list).unwrap();
+/// assert_eq!(cids.len(), 2);
+///
+/// let errors = parse_cid_list::<U64>("\nfoo").unwrap_err();
+/// assert_eq!(errors[0].line, 2);
+/// ```
+pub fn parse_cid_list<S: Size>(
+  text: &str,
+) -> sp_std::result::Result<Vec<Cid<S>>, Vec<LineError>> {
+  let mut parser = BatchParser::new();
+  let mut cids = Vec::new();
+  let mut errors = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    let entries = match line.find('#') {
+      Some(comment) => &line[..comment],
+      None => line,
+    };
+    for entry in entries.split_whitespace() {
+      match parser.parse(entry) {
+        Ok(cid) => cids.push(cid),
+        Err(error) => errors.push(LineError { line: index + 1, error }),
+      }
+    }
+  }
+  if errors.is_empty() {
+    Ok(cids)
+  } else {
+    Err(errors)
+  }
+}
@@ -0,0 +1,88 @@
+//! MessagePack support for CIDs, behind the `rmp` feature.
+//!
+//! A CID is written as a MessagePack extension of type
+//! [`Cid::MSGPACK_EXT_TYPE`] whose payload is the binary CID, so readers can
+//! tell CIDs apart from other binary data, like tag 42 does in DAG-CBOR.
+use std::{
+  convert::TryFrom,
+  io::{self, Read, Write},
+};
+
+use rmp::{
+  decode::{self, ValueReadError},
+  encode::{self, ValueWriteError},
+};
+use sp_multihash::Size;
+
+use crate::{
+  cid::{Cid, MAX_LEN},
+  error::{Error, Result},
+};
+
+/// Returns an I/O error for input that isn't a CID extension.
+fn invalid_data(message: &'static str) -> Error {
+  Error::Io(io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+impl<S: Size> Cid<S> {
+  /// The MessagePack extension type of CIDs, the number of the DAG-CBOR tag.
+  pub const MSGPACK_EXT_TYPE: i8 = 42;
+
+  /// Writes the CID as a MessagePack extension value.
+  pub fn write_msgpack<W: Write>(&self, w: &mut W) -> Result<()> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    encode::write_ext_meta(w, len as u32, Self::MSGPACK_EXT_TYPE).map_err(
+      |err| match err {
+        ValueWriteError::InvalidMarkerWrite(err)
+        | ValueWriteError::InvalidDataWrite(err) => Error::Io(err),
+      },
+    )?;
+    w.write_all(&buf[..len])?;
+    Ok(())
+  }
+
+  /// Reads a CID written by [`Cid::write_msgpack`].
+  ///
+  /// Values that aren't extensions of [`Cid::MSGPACK_EXT_TYPE`] are rejected
+  /// with an [`io::ErrorKind::InvalidData`] error.
+  pub fn read_msgpack<R: Read>(r: &mut R) -> Result<Self> {
+    let meta = decode::read_ext_meta(r).map_err(|err| match err {
+      ValueReadError::InvalidMarkerRead(err)
+      | ValueReadError::InvalidDataRead(err) => Error::Io(err),
+      ValueReadError::TypeMismatch(_) => invalid_data("not an extension"),
+    })?;
+    if meta.typeid != Self::MSGPACK_EXT_TYPE {
+      return Err(invalid_data("not a CID extension"));
+    }
+    let len = meta.size as usize;
+    if len > MAX_LEN {
+      return Err(invalid_data("CID extension is too long"));
+    }
+    let mut buf = [0u8; MAX_LEN];
+    r.read_exact(&mut buf[..len])?;
+    Self::try_from(&buf[..len])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sp_multihash::{Code, MultihashDigest};
+
+  use crate::Cid;
+
+  #[test]
+  fn roundtrip() {
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let mut bytes = Vec::new();
+    cid.write_msgpack(&mut bytes).unwrap();
+    // The ext 8 marker, the length, the type and the CID
+    assert_eq!(bytes[..3], [0xc7, 36, 42]);
+    assert_eq!(&bytes[3..], cid.to_bytes().as_slice());
+    assert_eq!(Cid::read_msgpack(&mut &bytes[..]).unwrap(), cid);
+
+    bytes[2] = 7;
+    assert!(Cid::read_msgpack(&mut &bytes[..]).is_err());
+    assert!(Cid::read_msgpack(&mut &[0xc4, 0x00][..]).is_err());
+  }
+}
@@ -0,0 +1,189 @@
+//! Helpers for CIDs in multiaddr components.
+//!
+//! Content routing code mixes transport addresses such as
+//! `/ip4/1.2.3.4/tcp/4001/p2p/<peer-id>` with content paths such as
+//! `/ipfs/<cid>`. The helpers in this module work on the text form of
+//! multiaddrs and don't depend on a multiaddr implementation.
+use sp_std::convert::TryFrom;
+
+use alloc::string::{String, ToString};
+
+use multibase::Base;
+use sp_multihash::{MultihashGeneric as Multihash, Size};
+
+use crate::{
+  bases,
+  cid::Cid,
+  error::{Error, Result},
+  ipns::LIBP2P_KEY,
+};
+
+/// Protocols without a value.
+const NO_VALUE: &[&str] = &[
+  "http",
+  "https",
+  "noise",
+  "p2p-circuit",
+  "p2p-stardust",
+  "p2p-webrtc-direct",
+  "p2p-websocket-star",
+  "quic",
+  "quic-v1",
+  "tls",
+  "udt",
+  "utp",
+  "webrtc",
+  "webrtc-direct",
+  "webtransport",
+  "ws",
+  "wss",
+];
+
+/// Protocols whose value is a path, which takes the rest of the multiaddr.
+const PATH_VALUE: &[&str] = &["unix"];
+
+impl<S: Size> Cid<S> {
+  /// Extracts the CID of the last `/ipfs/` or `/p2p/` component of a
+  /// multiaddr.
+  ///
+  /// The multiaddr is read protocol by protocol, so values such as a host
+  /// named `ipfs` aren't taken for a protocol. Protocols not known to have
+  /// no value, or a path as value, are read with one value.
+  ///
+  /// The value of a `/p2p/` component is a peer id, either a Base58Btc
+  /// encoded multihash or a CID with the libp2p-key codec. It is returned as
+  /// a CIDv1 with the libp2p-key codec. Peer ids that are CIDs of another
+  /// codec fail with [`Error::UnexpectedCodec`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// let addr = "/dns4/example.com/tcp/443/https/ipfs/\
+  ///             bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let cid = Cid::from_multiaddr(addr).unwrap();
+  /// assert_eq!(cid.codec(), 0x55);
+  /// ```
+  pub fn from_multiaddr(addr: &str) -> Result<Self> {
+    let mut component = None;
+    // The text form starts with a slash, so the first part is empty
+    let mut parts = addr.split('/').skip(1);
+    while let Some(protocol) = parts.next() {
+      if NO_VALUE.contains(&protocol) {
+        continue;
+      }
+      if PATH_VALUE.contains(&protocol) {
+        break;
+      }
+      match parts.next() {
+        Some(value) if protocol == "ipfs" || protocol == "p2p" => {
+          component = Some((protocol, value));
+        }
+        _ => {}
+      }
+    }
+    match component {
+      Some(("p2p", value)) => Self::from_peer_id(value),
+      Some((_, value)) => Self::try_from(value),
+      None => Err(Error::NoMultiaddrCid),
+    }
+  }
+
+  /// Parses a peer id into a CIDv1 with the libp2p-key codec.
+  fn from_peer_id(peer_id: &str) -> Result<Self> {
+    // Legacy peer ids are Base58Btc multihashes: `Qm` for SHA2-256 and
+    // `1` for identity multihashes.
+    if peer_id.starts_with('Q') || peer_id.starts_with('1') {
+      let bytes = bases::decode(Base::Base58Btc, peer_id)?;
+      let hash = Multihash::from_bytes(&bytes)?;
+      return Ok(Self::new_v1(LIBP2P_KEY, hash));
+    }
+    let cid = Self::try_from(peer_id)?;
+    if cid.codec() != LIBP2P_KEY {
+      return Err(Error::UnexpectedCodec {
+        expected: LIBP2P_KEY,
+        found: cid.codec(),
+      });
+    }
+    Ok(cid)
+  }
+
+  /// Appends the CID as a component to the text form of a multiaddr.
+  ///
+  /// CIDs with the libp2p-key codec are appended as a `/p2p/` component with
+  /// the Base58Btc peer id, all others as an `/ipfs/` component.
+  pub fn push_to_multiaddr(&self, addr: &mut String) {
+    if self.codec() == LIBP2P_KEY {
+      addr.push_str("/p2p/");
+      addr.push_str(&bases::encode_base58btc(&self.hash().to_bytes()));
+    } else {
+      addr.push_str("/ipfs/");
+      addr.push_str(&self.to_string());
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Cid;
+  use alloc::format;
+
+  const PEER_ID: &str = "12D3KooWD3eckifWpRn9wQpMG9R9hX3sD158z7EqHWmweQAJU5SA";
+
+  #[test]
+  fn peer_id_round_trip() {
+    let mut addr = String::from("/ip4/127.0.0.1/tcp/4001/p2p/");
+    addr.push_str(PEER_ID);
+    let cid = Cid::from_multiaddr(&addr).unwrap();
+    assert_eq!(cid.codec(), LIBP2P_KEY);
+    assert_eq!(cid.hash().code(), 0x00);
+
+    // Peer ids can also be written as CIDs
+    let as_cid = format!("/p2p/{}", cid);
+    assert_eq!(Cid::from_multiaddr(&as_cid).unwrap(), cid);
+
+    let mut out = String::from("/ip4/127.0.0.1/tcp/4001");
+    cid.push_to_multiaddr(&mut out);
+    assert_eq!(out, addr);
+  }
+
+  #[test]
+  fn content_path() {
+    let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+    let cid = Cid::try_from(s).unwrap();
+    let mut addr = String::from("/dns4/example.com");
+    cid.push_to_multiaddr(&mut addr);
+    assert_eq!(addr, format!("/dns4/example.com/ipfs/{}", s));
+    assert_eq!(Cid::from_multiaddr(&addr).unwrap(), cid);
+
+    assert!(matches!(
+      Cid::from_multiaddr("/ip4/127.0.0.1/tcp/4001"),
+      Err(Error::NoMultiaddrCid)
+    ));
+    let not_a_key = format!("/p2p/{}", s);
+    assert!(matches!(
+      Cid::from_multiaddr(&not_a_key),
+      Err(Error::UnexpectedCodec { expected: LIBP2P_KEY, found: 0x55 })
+    ));
+  }
+
+  #[test]
+  fn reads_protocol_values() {
+    let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+    // Host names aren't protocols
+    let host = format!("/dns4/ipfs/tcp/443/dns4/p2p/tcp/{}", s);
+    assert!(matches!(Cid::from_multiaddr(&host), Err(Error::NoMultiaddrCid)));
+
+    // Protocols without a value are followed by the next protocol
+    let quic = format!("/ip4/1.2.3.4/udp/4001/quic-v1/p2p/{}", PEER_ID);
+    assert_eq!(Cid::from_multiaddr(&quic).unwrap().codec(), LIBP2P_KEY);
+    let content = format!("/dns4/ipfs/tcp/443/https/ipfs/{}", s);
+    assert_eq!(Cid::from_multiaddr(&content).unwrap().to_string(), s);
+
+    // Paths take the rest of the multiaddr
+    let unix = format!("/unix/run/ipfs/{}", s);
+    assert!(matches!(Cid::from_multiaddr(&unix), Err(Error::NoMultiaddrCid)));
+  }
+}
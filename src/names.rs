@@ -0,0 +1,217 @@
+//! Names of common multicodec codes.
+//!
+//! The tables cover the content codecs and multihash functions that are in
+//! common use with CIDs. The names are the ones of the
+//! [multicodec table](https://github.com/multiformats/multicodec).
+
+/// Names of content codecs.
+pub const CODECS: &[(u64, &str)] = &[
+  (0x00, "identity"),
+  (0x51, "cbor"),
+  (0x55, "raw"),
+  (0x70, "dag-pb"),
+  (0x71, "dag-cbor"),
+  (0x72, "libp2p-key"),
+  (0x78, "git-raw"),
+  (0x7b, "torrent-info"),
+  (0x7c, "torrent-file"),
+  (0x85, "dag-jose"),
+  (0x86, "dag-cose"),
+  (0x90, "eth-block"),
+  (0x91, "eth-block-list"),
+  (0x92, "eth-tx-trie"),
+  (0x93, "eth-tx"),
+  (0x94, "eth-tx-receipt-trie"),
+  (0x95, "eth-tx-receipt"),
+  (0x96, "eth-state-trie"),
+  (0x97, "eth-account-snapshot"),
+  (0x98, "eth-storage-trie"),
+  (0xb0, "bitcoin-block"),
+  (0xb1, "bitcoin-tx"),
+  (0xc0, "zcash-block"),
+  (0xc1, "zcash-tx"),
+  (0x0129, "dag-json"),
+  (0x0200, "json"),
+  (0x0202, "car"),
+  (0xf101, "fil-commitment-unsealed"),
+  (0xf102, "fil-commitment-sealed"),
+];
+
+/// Names of multihash functions.
+pub const HASHES: &[(u64, &str)] = &[
+  (0x00, "identity"),
+  (0x11, "sha1"),
+  (0x12, "sha2-256"),
+  (0x13, "sha2-512"),
+  (0x14, "sha3-512"),
+  (0x15, "sha3-384"),
+  (0x16, "sha3-256"),
+  (0x17, "sha3-224"),
+  (0x1a, "keccak-224"),
+  (0x1b, "keccak-256"),
+  (0x1c, "keccak-384"),
+  (0x1d, "keccak-512"),
+  (0x1e, "blake3"),
+  (0x20, "sha2-384"),
+  (0x56, "dbl-sha2-256"),
+  (0xd5, "md5"),
+  (0x1012, "sha2-256-trunc254-padded"),
+  (0x1013, "sha2-224"),
+  (0x1014, "sha2-512-224"),
+  (0x1015, "sha2-512-256"),
+  (0xb220, "blake2b-256"),
+  (0xb240, "blake2b-512"),
+  (0xb250, "blake2s-128"),
+  (0xb260, "blake2s-256"),
+  (0xb401, "poseidon-bls12_381-a2-fc1"),
+];
+
+/// Media types of content codecs, for HTTP gateways.
+///
+/// The `vnd.ipld` types are the IANA registered types of the IPFS trustless
+/// gateway responses. Codecs without a registered type, e.g. dag-pb, are left
+/// out, gateways serve their blocks as raw.
+pub const CONTENT_TYPES: &[(u64, &str)] = &[
+  (0x51, "application/cbor"),
+  (0x55, "application/vnd.ipld.raw"),
+  (0x71, "application/vnd.ipld.dag-cbor"),
+  (0x0129, "application/vnd.ipld.dag-json"),
+  (0x0200, "application/json"),
+  (0x0202, "application/vnd.ipld.car"),
+];
+
+/// Looks up the name of `code` in `table`.
+fn name_of(table: &[(u64, &'static str)], code: u64) -> Option<&'static str> {
+  table.iter().find(|(c, _)| *c == code).map(|(_, name)| *name)
+}
+
+/// Looks up the code of `name` in `table`.
+fn code_of(table: &[(u64, &str)], name: &str) -> Option<u64> {
+  table.iter().find(|(_, n)| *n == name).map(|(code, _)| *code)
+}
+
+/// Returns the name of a content codec.
+pub fn codec_name(code: u64) -> Option<&'static str> {
+  name_of(CODECS, code)
+}
+
+/// Returns the code of a content codec by its name.
+pub fn codec_code(name: &str) -> Option<u64> {
+  code_of(CODECS, name)
+}
+
+/// Returns the name of a multihash function.
+pub fn hash_name(code: u64) -> Option<&'static str> {
+  name_of(HASHES, code)
+}
+
+/// Returns the code of a multihash function by its name.
+pub fn hash_code(name: &str) -> Option<u64> {
+  code_of(HASHES, name)
+}
+
+/// Returns the media type of a content codec, see [`CONTENT_TYPES`].
+pub fn content_type(code: u64) -> Option<&'static str> {
+  name_of(CONTENT_TYPES, code)
+}
+
+/// Returns the content codec of a media type, see [`CONTENT_TYPES`].
+///
+/// The type is compared case-insensitively and parameters are ignored, so
+/// the value of a `Content-Type` header or an `Accept` header entry can be
+/// passed as is.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::names;
+///
+/// let car = names::content_type_codec("application/vnd.ipld.car; version=1");
+/// assert_eq!(car, Some(0x0202));
+/// assert_eq!(names::content_type_codec("text/html"), None);
+/// ```
+pub fn content_type_codec(media_type: &str) -> Option<u64> {
+  let essence = media_type.split(';').next().unwrap_or_default().trim();
+  CONTENT_TYPES
+    .iter()
+    .find(|(_, t)| t.eq_ignore_ascii_case(essence))
+    .map(|(code, _)| *code)
+}
+
+/// Returns whether `code` is in `table`, in const context.
+#[doc(hidden)]
+pub const fn contains(table: &[(u64, &str)], code: u64) -> bool {
+  let mut i = 0;
+  while i < table.len() {
+    if table[i].0 == code {
+      return true;
+    }
+    i += 1;
+  }
+  false
+}
+
+/// Registers additional content codecs, e.g. from the private use range of
+/// the multicodec table.
+///
+/// The macro defines a constant for every codec, and, in the module it is
+/// invoked in, a `CODECS` table and `codec_name` and `codec_code` functions
+/// that look up the registered codecs before the ones of [`CODECS`]. A
+/// registered code that is already in [`CODECS`] fails to compile.
+///
+/// # Example
+///
+/// ```
+/// mod codecs {
+///   sp_cid::register_codecs! {
+///     /// Blocks of the chain state.
+///     STATE_BLOCK = 0x300001 => "chain-state-block",
+///   }
+/// }
+///
+/// use sp_cid::Cid;
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(codecs::STATE_BLOCK, Code::Sha2_256.digest(b"foo"));
+/// assert_eq!(codecs::codec_name(cid.codec()), Some("chain-state-block"));
+/// assert_eq!(codecs::codec_name(0x55), Some("raw"));
+/// assert_eq!(codecs::codec_code("chain-state-block"), Some(0x300001));
+/// ```
+#[macro_export]
+macro_rules! register_codecs {
+  (
+    $($(#[$attr:meta])* $name:ident = $code:expr => $codec:literal),* $(,)?
+  ) => {
+    $(
+      $(#[$attr])*
+      pub const $name: u64 = $code;
+
+      const _: () = assert!(
+        !$crate::names::contains($crate::names::CODECS, $name),
+        concat!("the code of ", stringify!($name), " is already registered"),
+      );
+    )*
+
+    /// Names of the registered content codecs.
+    pub const CODECS: &[(u64, &str)] = &[$(($name, $codec)),*];
+
+    /// Returns the name of a registered or well-known content codec.
+    pub fn codec_name(code: u64) -> Option<&'static str> {
+      CODECS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+        .or_else(|| $crate::names::codec_name(code))
+    }
+
+    /// Returns the code of a registered or well-known content codec by its
+    /// name.
+    pub fn codec_code(name: &str) -> Option<u64> {
+      CODECS
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(code, _)| *code)
+        .or_else(|| $crate::names::codec_code(name))
+    }
+  };
+}
@@ -0,0 +1,215 @@
+//! Configurable parsing of CID strings.
+//!
+//! The [`FromStr`](core::str::FromStr) implementation of [`Cid`] is lenient:
+//! like the reference implementations, it accepts every spelling the multibase
+//! decoders accept. Applications that use CID strings as identifiers, e.g. as
+//! dedupe keys or in on-chain uniqueness checks, can parse with
+//! [`ParseOptions::strict`] instead, which only accepts one spelling. Public
+//! APIs can also restrict the accepted multibases with
+//! [`ParseOptions::bases`]. Web services that take CIDs from URLs can
+//! enable [`ParseOptions::percent_decode`].
+use alloc::{borrow::Cow, string::String};
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  bases,
+  cid::{detect_base, strip_ipfs_path, Cid},
+  context::DecodeContext,
+  error::{Error, Result},
+  hint::HintedError,
+};
+
+/// Options for parsing CID strings.
+///
+/// The default options accept the same strings as [`Cid::from_str`].
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, ParseOptions};
+///
+/// let mixed = "BAFKREIBME22GW2H7Y2H7TG2FHQOTAQJUCNBC24DEQO72B6MKL2EGEZXHVy";
+/// let lenient: Result<Cid, _> = ParseOptions::new().parse(mixed);
+/// assert!(lenient.is_ok());
+/// let strict: Result<Cid, _> = ParseOptions::strict().parse(mixed);
+/// assert!(strict.is_err());
+/// ```
+///
+/// [`Cid::from_str`]: core::str::FromStr::from_str
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+  /// Whether to reject base32 strings with letters of both cases.
+  canonical_case: bool,
+  /// The accepted multibases, all if `None`.
+  bases: Option<&'static [Base]>,
+  /// Whether to decode `%XX` sequences before parsing.
+  percent_decode: bool,
+}
+
+impl ParseOptions {
+  /// Create the lenient default options.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Create options with all checks enabled.
+  pub fn strict() -> Self {
+    Self { canonical_case: true, ..Self::default() }
+  }
+
+  /// Sets whether base32 strings have to be in the case of their multibase,
+  /// e.g. all upper case for `B` prefixed strings. Off by default.
+  ///
+  /// The base32 decoders ignore case, so otherwise several strings decode to
+  /// the same CID. Mixed case strings return
+  /// [`multibase::Error::InvalidBaseString`].
+  pub fn canonical_case(mut self, canonical_case: bool) -> Self {
+    self.canonical_case = canonical_case;
+    self
+  }
+
+  /// Restricts the accepted multibases, e.g. to the common ones for
+  /// strings from untrusted input. All bases are accepted by default.
+  ///
+  /// CIDv0 strings count as Base58Btc. Strings in other bases return
+  /// [`Error::UnsupportedBase`] before they are decoded.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::{Cid, Error, ParseOptions};
+  ///
+  /// let options = ParseOptions::new().bases(&[Base::Base32Lower]);
+  /// let cid: Cid = options
+  ///   .parse("bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy")
+  ///   .unwrap();
+  /// let base36 = cid.to_string_of_base(Base::Base36Lower).unwrap();
+  /// assert_eq!(
+  ///   options.parse::<sp_multihash::U64>(&base36),
+  ///   Err(Error::UnsupportedBase(Base::Base36Lower))
+  /// );
+  /// ```
+  pub fn bases(mut self, bases: &'static [Base]) -> Self {
+    self.bases = Some(bases);
+    self
+  }
+
+  /// Sets whether `%XX` sequences are decoded before the string is parsed,
+  /// e.g. for CIDs taken from URL query parameters. Off by default.
+  ///
+  /// Base64 CIDs contain `/` and `+`, which URL encoders escape as `%2F` and
+  /// `%2B`. A `+` is kept as it is, not read as a space. Malformed sequences
+  /// and sequences of non-ASCII bytes return
+  /// [`multibase::Error::InvalidBaseString`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::{Cid, ParseOptions};
+  /// use sp_multihash::{Code, MultihashDigest, U64};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let base64 = cid.to_string_of_base(Base::Base64).unwrap();
+  /// let encoded = base64.replace('/', "%2F").replace('+', "%2B");
+  ///
+  /// let options = ParseOptions::new().percent_decode(true);
+  /// assert_eq!(options.parse::<U64>(&encoded), Ok(cid));
+  /// assert!(ParseOptions::new().parse::<U64>(&encoded).is_err());
+  /// ```
+  pub fn percent_decode(mut self, percent_decode: bool) -> Self {
+    self.percent_decode = percent_decode;
+    self
+  }
+
+  /// Parses a CID string with these options.
+  ///
+  /// The decoded CID is limited like with [`Cid::from_str`], see
+  /// [`DecodeContext::parse`] for tighter limits.
+  ///
+  /// [`Cid::from_str`]: core::str::FromStr::from_str
+  pub fn parse<S: Size>(&self, cid_str: &str) -> Result<Cid<S>> {
+    self.parse_within(cid_str, &DecodeContext::new())
+  }
+
+  /// Parses a CID string with these options, within the limits of
+  /// `context`.
+  pub(crate) fn parse_within<S: Size>(
+    &self,
+    cid_str: &str,
+    context: &DecodeContext,
+  ) -> Result<Cid<S>> {
+    let cid_str = self.decode(cid_str)?;
+    let stripped = strip_ipfs_path(&cid_str);
+    self.check(stripped)?;
+    context.read_str(stripped)
+  }
+
+  /// Checks the base and the case of a CID string without a path prefix,
+  /// before it is decoded.
+  pub(crate) fn check(&self, stripped: &str) -> Result<()> {
+    if let Some(accepted) = self.bases {
+      let base = detect_base(stripped)?.base();
+      if !accepted.contains(&base) {
+        return Err(Error::UnsupportedBase(base));
+      }
+    }
+    if self.canonical_case && !bases::is_canonical_case(stripped) {
+      return Err(Error::MultibaseError(multibase::Error::InvalidBaseString));
+    }
+    Ok(())
+  }
+
+  /// Parses a CID string with these options, and looks for a likely fix of
+  /// the string if it doesn't parse.
+  ///
+  /// The fix is only looked for after a failure, successful parses cost the
+  /// same as with [`ParseOptions::parse`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Hint, ParseOptions};
+  /// use sp_multihash::U64;
+  ///
+  /// let v0 = "bQmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  /// let err = ParseOptions::new().parse_with_hint::<U64>(v0).unwrap_err();
+  /// assert_eq!(err.hint(), Some(Hint::PrefixedV0('b')));
+  /// ```
+  pub fn parse_with_hint<S: Size>(
+    &self,
+    cid_str: &str,
+  ) -> sp_std::result::Result<Cid<S>, HintedError> {
+    self.parse(cid_str).map_err(|err| {
+      let decoded = self.decode(cid_str).unwrap_or(Cow::Borrowed(cid_str));
+      HintedError::new::<S>(err, strip_ipfs_path(&decoded))
+    })
+  }
+
+  /// Returns the string with `%XX` sequences decoded if enabled.
+  pub(crate) fn decode<'a>(&self, cid_str: &'a str) -> Result<Cow<'a, str>> {
+    if !self.percent_decode || !cid_str.contains('%') {
+      return Ok(Cow::Borrowed(cid_str));
+    }
+    let invalid = Error::MultibaseError(multibase::Error::InvalidBaseString);
+    let mut decoded = String::with_capacity(cid_str.len());
+    let mut chars = cid_str.chars();
+    while let Some(c) = chars.next() {
+      if c != '%' {
+        decoded.push(c);
+        continue;
+      }
+      let mut digit = || chars.next().and_then(|c| c.to_digit(16));
+      match (digit(), digit()) {
+        (Some(hi), Some(lo)) if hi < 8 => {
+          decoded.push(char::from((hi << 4 | lo) as u8))
+        }
+        _ => return Err(invalid),
+      }
+    }
+    Ok(Cow::Owned(decoded))
+  }
+}
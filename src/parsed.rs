@@ -0,0 +1,91 @@
+//! Parsing of CID strings with the details of their spelling.
+//!
+//! Gateways log and redirect by how a CID was written, e.g. to send CIDv0
+//! requests to their CIDv1 subdomain or to count URI requests. Parsing with
+//! [`Cid::parse`] reports these details along with the CID, so they don't
+//! have to be derived from the string again.
+use sp_multihash::Size;
+
+use crate::{
+  cid::{
+    decode_stripped, detect_base, read_slice, strip_ipfs_path, Cid, CidBase,
+  },
+  error::Result,
+  trace,
+};
+
+/// The wrapper around a CID string that [`Cid::parse`] stripped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CidWrapper {
+  /// An IPFS path or a URL containing one, e.g. `/ipfs/<cid>`.
+  IpfsPath,
+  /// An `ipfs://<cid>` URI.
+  IpfsUri,
+}
+
+/// How a CID string was written, see [`Cid::parse`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParsedMeta {
+  /// The encoding of the string.
+  base: CidBase,
+  /// The stripped wrapper, if any.
+  wrapper: Option<CidWrapper>,
+  /// The length of the binary CID.
+  byte_len: usize,
+}
+
+impl ParsedMeta {
+  /// Returns the encoding of the string, [`CidBase::V0`] for a CIDv0.
+  pub fn base(&self) -> CidBase {
+    self.base
+  }
+
+  /// Returns the wrapper that was stripped from the string, if any.
+  pub fn wrapper(&self) -> Option<CidWrapper> {
+    self.wrapper
+  }
+
+  /// Returns the length of the binary form of the CID.
+  pub fn byte_len(&self) -> usize {
+    self.byte_len
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Parses a CID string and reports how it was written.
+  ///
+  /// Accepts everything [`Cid::from_str`] accepts, as well as an
+  /// `ipfs://<cid>` URI without a path.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use multibase::Base;
+  /// use sp_cid::{Cid, CidBase, CidWrapper};
+  ///
+  /// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let (cid, meta) = Cid::parse(&format!("ipfs://{}", s)).unwrap();
+  /// assert_eq!(cid.to_string(), s);
+  /// assert_eq!(meta.base(), CidBase::Multibase(Base::Base32Lower));
+  /// assert_eq!(meta.wrapper(), Some(CidWrapper::IpfsUri));
+  /// assert_eq!(meta.byte_len(), 36);
+  /// ```
+  ///
+  /// [`Cid::from_str`]: core::str::FromStr::from_str
+  pub fn parse(cid_str: &str) -> Result<(Self, ParsedMeta)> {
+    let (wrapper, stripped) = match cid_str.strip_prefix("ipfs://") {
+      Some(rest) => (Some(CidWrapper::IpfsUri), rest),
+      None => {
+        let rest = strip_ipfs_path(cid_str);
+        let stripped = rest.len() != cid_str.len();
+        (stripped.then_some(CidWrapper::IpfsPath), rest)
+      }
+    };
+    let result = decode_stripped(stripped).and_then(|bytes| {
+      let cid = read_slice(&bytes)?;
+      let base = detect_base(stripped)?;
+      Ok((cid, ParsedMeta { base, wrapper, byte_len: bytes.len() }))
+    });
+    trace::parse_result(result, cid_str.len())
+  }
+}
@@ -0,0 +1,125 @@
+//! This module contains the parser for CIDs and IPFS paths.
+//!
+//! Gateway-style services accept a bare CID as well as an IPFS path such as
+//! `/ipfs/<cid>/dir/file` or a URI such as `ipfs://<cid>/dir/file`.
+//! [`CidOrPath`] parses all of them with one call. It also reads and writes
+//! the `dnslink=/ipfs/<cid>/...` values of DNSLink TXT records.
+use sp_std::{convert::TryFrom, fmt, str};
+
+use alloc::{format, string::String};
+use sp_multihash::Size;
+
+use crate::{
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// A bare CID or an IPFS path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CidOrPath<S: Size> {
+  /// A bare CID.
+  Cid(Cid<S>),
+  /// An IPFS path or URI.
+  Path {
+    /// The CID the path starts at.
+    cid: Cid<S>,
+    /// The path within the CID, starting with `/`, or empty.
+    path: String,
+  },
+}
+
+impl<S: Size> CidOrPath<S> {
+  /// Returns the CID, which is the root of a path.
+  pub fn cid(&self) -> &Cid<S> {
+    match self {
+      CidOrPath::Cid(cid) | CidOrPath::Path { cid, .. } => cid,
+    }
+  }
+
+  /// Returns the path within the CID, empty for a bare CID.
+  pub fn path(&self) -> &str {
+    match self {
+      CidOrPath::Cid(_) => "",
+      CidOrPath::Path { path, .. } => path,
+    }
+  }
+
+  /// Returns the value of a DNSLink TXT record pointing to the CID and path,
+  /// `dnslink=/ipfs/<cid><path>`.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Cid, CidOrPath};
+  ///
+  /// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  /// let link = CidOrPath::from(s.parse::<Cid>().unwrap());
+  /// assert_eq!(link.to_dnslink(), format!("dnslink=/ipfs/{}", s));
+  /// assert_eq!(CidOrPath::from_dnslink(&link.to_dnslink()).unwrap(), link);
+  /// ```
+  pub fn to_dnslink(&self) -> String {
+    format!("dnslink=/ipfs/{}{}", self.cid(), self.path())
+  }
+
+  /// Parses the value of a DNSLink TXT record.
+  ///
+  /// The value has to be `dnslink=/ipfs/<cid>` followed by an optional path
+  /// without empty segments, queries or fragments. Surrounding whitespace is
+  /// ignored. Other values, including `/ipns/` links, return
+  /// [`Error::InvalidDnsLink`].
+  pub fn from_dnslink(value: &str) -> Result<Self> {
+    let rest = value
+      .trim()
+      .strip_prefix("dnslink=/ipfs/")
+      .ok_or(Error::InvalidDnsLink)?;
+    let (cid, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let cid = Cid::try_from(cid)?;
+    if path.is_empty() {
+      return Ok(CidOrPath::Cid(cid));
+    }
+    if path.contains("//")
+      || path.contains(|c: char| c.is_whitespace() || c == '?' || c == '#')
+    {
+      return Err(Error::InvalidDnsLink);
+    }
+    Ok(CidOrPath::Path { cid, path: path.into() })
+  }
+}
+
+/// Parses a bare CID, an `ipfs://` URI, or a path or URL containing an
+/// `/ipfs/` segment.
+///
+/// The query and fragment of URIs are dropped.
+impl<S: Size> str::FromStr for CidOrPath<S> {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let rest = if let Some(rest) = s.strip_prefix("ipfs://") {
+      rest
+    } else if let Some(index) = s.find("/ipfs/") {
+      &s[index + "/ipfs/".len()..]
+    } else {
+      return Ok(CidOrPath::Cid(Cid::try_from(s)?));
+    };
+    let end = rest.find(['?', '#']).unwrap_or(rest.len());
+    let rest = &rest[..end];
+    let (cid, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    Ok(CidOrPath::Path { cid: Cid::try_from(cid)?, path: path.into() })
+  }
+}
+
+/// Formats a path as `/ipfs/<cid><path>`.
+impl<S: Size> fmt::Display for CidOrPath<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      CidOrPath::Cid(cid) => write!(f, "{}", cid),
+      CidOrPath::Path { cid, path } => write!(f, "/ipfs/{}{}", cid, path),
+    }
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CidOrPath<S> {
+  fn from(cid: Cid<S>) -> Self {
+    CidOrPath::Cid(cid)
+  }
+}
@@ -0,0 +1,297 @@
+//! Codec and hash function policies checked when CIDs are decoded.
+//!
+//! A runtime that only accepts, say, raw blocks hashed with BLAKE2b would
+//! otherwise have to check every CID after it was decoded from an extrinsic
+//! or a request, and a forgotten check lets a disallowed CID into storage.
+//! [`PolicyCid`] carries a [`CidPolicy`] in its type and checks it in its
+//! `Deserialize` and `Decode` implementations, so a value of the type always
+//! satisfies the policy.
+use sp_std::{
+  cmp::Ordering, convert::TryFrom, fmt, hash, marker::PhantomData,
+};
+
+use sp_multihash::{Size, U64};
+
+use crate::{
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// The CIDs a [`PolicyCid`] accepts.
+///
+/// The default check only accepts the codecs in [`CidPolicy::CODECS`] and
+/// the multihash codes in [`CidPolicy::HASH_CODES`], where `None` accepts
+/// all of them.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidPolicy, Error, PolicyCid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// struct RawBlake2b;
+///
+/// impl CidPolicy for RawBlake2b {
+///   const CODECS: Option<&'static [u64]> = Some(&[0x55]);
+///   const HASH_CODES: Option<&'static [u64]> = Some(&[0xb220]);
+/// }
+///
+/// let cid = Cid::new_v1(0x55, Code::Blake2b256.digest(b"foo"));
+/// assert!(PolicyCid::<RawBlake2b>::new(cid).is_ok());
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// assert_eq!(
+///   PolicyCid::<RawBlake2b>::new(cid).unwrap_err(),
+///   Error::DisallowedHash(0x12)
+/// );
+/// ```
+pub trait CidPolicy {
+  /// The accepted codecs, all if `None`.
+  const CODECS: Option<&'static [u64]> = None;
+  /// The accepted multihash codes, all if `None`.
+  const HASH_CODES: Option<&'static [u64]> = None;
+
+  /// Checks that the policy accepts the CID.
+  ///
+  /// Fails with [`Error::DisallowedCodec`] or [`Error::DisallowedHash`].
+  fn check<S: Size>(cid: &Cid<S>) -> Result<()> {
+    if let Some(codecs) = Self::CODECS {
+      if !codecs.contains(&cid.codec()) {
+        return Err(Error::DisallowedCodec(cid.codec()));
+      }
+    }
+    if let Some(codes) = Self::HASH_CODES {
+      if !codes.contains(&cid.hash().code()) {
+        return Err(Error::DisallowedHash(cid.hash().code()));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// The policy that accepts every CID.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AnyCid;
+
+impl CidPolicy for AnyCid {}
+
+/// A CID that satisfies the policy `P`.
+///
+/// Its serde and SCALE encodings are the ones of [`Cid`], decoding fails
+/// for CIDs the policy rejects.
+pub struct PolicyCid<P: CidPolicy, S: Size = U64> {
+  /// The checked CID.
+  cid: Cid<S>,
+  /// The policy, which is only a type.
+  _policy: PhantomData<fn() -> P>,
+}
+
+impl<P: CidPolicy, S: Size> PolicyCid<P, S> {
+  /// Wraps a CID that the policy accepts.
+  pub fn new(cid: Cid<S>) -> Result<Self> {
+    P::check(&cid)?;
+    Ok(Self { cid, _policy: PhantomData })
+  }
+
+  /// Returns the CID.
+  pub fn cid(&self) -> &Cid<S> {
+    &self.cid
+  }
+
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.cid
+  }
+}
+
+impl<P: CidPolicy, S: Size> sp_std::ops::Deref for PolicyCid<P, S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.cid
+  }
+}
+
+impl<P: CidPolicy, S: Size> TryFrom<Cid<S>> for PolicyCid<P, S> {
+  type Error = Error;
+
+  fn try_from(cid: Cid<S>) -> Result<Self> {
+    Self::new(cid)
+  }
+}
+
+impl<P: CidPolicy, S: Size> From<PolicyCid<P, S>> for Cid<S> {
+  fn from(checked: PolicyCid<P, S>) -> Self {
+    checked.cid
+  }
+}
+
+// The policy is only a marker, so these are implemented by hand instead of
+// derived to not require them from `P`.
+
+impl<P: CidPolicy, S: Size> Clone for PolicyCid<P, S> {
+  fn clone(&self) -> Self {
+    Self { cid: self.cid.clone(), _policy: PhantomData }
+  }
+}
+
+impl<P: CidPolicy, S: Size> Copy for PolicyCid<P, S> where
+  S::ArrayType: Copy
+{
+}
+
+impl<P: CidPolicy, S: Size> PartialEq for PolicyCid<P, S> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cid == other.cid
+  }
+}
+
+impl<P: CidPolicy, S: Size> Eq for PolicyCid<P, S> {}
+
+impl<P: CidPolicy, S: Size> PartialOrd for PolicyCid<P, S> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<P: CidPolicy, S: Size> Ord for PolicyCid<P, S> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.cid.cmp(&other.cid)
+  }
+}
+
+impl<P: CidPolicy, S: Size> hash::Hash for PolicyCid<P, S> {
+  fn hash<H: hash::Hasher>(&self, state: &mut H) {
+    hash::Hash::hash(&self.cid, state)
+  }
+}
+
+impl<P: CidPolicy, S: Size> fmt::Display for PolicyCid<P, S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.cid, f)
+  }
+}
+
+impl<P: CidPolicy, S: Size> fmt::Debug for PolicyCid<P, S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.cid, f)
+  }
+}
+
+#[cfg(feature = "serde-codec")]
+impl<P: CidPolicy, S: Size> serde::Serialize for PolicyCid<P, S> {
+  fn serialize<T: serde::Serializer>(
+    &self,
+    serializer: T,
+  ) -> sp_std::result::Result<T::Ok, T::Error> {
+    self.cid.serialize(serializer)
+  }
+}
+
+#[cfg(feature = "serde-codec")]
+impl<'de, P: CidPolicy, S: Size> serde::Deserialize<'de> for PolicyCid<P, S> {
+  fn deserialize<D: serde::Deserializer<'de>>(
+    deserializer: D,
+  ) -> sp_std::result::Result<Self, D::Error> {
+    let cid = Cid::deserialize(deserializer)?;
+    Self::new(cid).map_err(serde::de::Error::custom)
+  }
+}
+
+#[cfg(feature = "scale-codec")]
+impl<P: CidPolicy, S: Size> parity_scale_codec::Encode for PolicyCid<P, S>
+where
+  sp_multihash::MultihashGeneric<S>: parity_scale_codec::Encode,
+{
+  fn size_hint(&self) -> usize {
+    self.cid.size_hint()
+  }
+
+  fn encode_to<T: parity_scale_codec::Output + ?Sized>(&self, dest: &mut T) {
+    self.cid.encode_to(dest)
+  }
+}
+
+#[cfg(feature = "scale-codec")]
+impl<P: CidPolicy, S: Size> parity_scale_codec::EncodeLike
+  for PolicyCid<P, S>
+where
+  sp_multihash::MultihashGeneric<S>: parity_scale_codec::Encode,
+{
+}
+
+#[cfg(feature = "scale-codec")]
+impl<P: CidPolicy, S: Size> parity_scale_codec::Decode for PolicyCid<P, S>
+where
+  sp_multihash::MultihashGeneric<S>: parity_scale_codec::Decode,
+{
+  fn decode<I: parity_scale_codec::Input>(
+    input: &mut I,
+  ) -> sp_std::result::Result<Self, parity_scale_codec::Error> {
+    let cid = Cid::decode(input)?;
+    Self::new(cid).map_err(|_| "CID rejected by policy".into())
+  }
+}
+
+#[cfg(feature = "scale-codec")]
+impl<P: CidPolicy, S: Size> parity_scale_codec::MaxEncodedLen
+  for PolicyCid<P, S>
+where
+  sp_multihash::MultihashGeneric<S>: parity_scale_codec::Encode,
+{
+  fn max_encoded_len() -> usize {
+    Cid::<S>::max_encoded_len()
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<P: CidPolicy + 'static, S: Size + 'static> scale_info::TypeInfo
+  for PolicyCid<P, S>
+{
+  type Identity = Cid<S>;
+
+  fn type_info() -> scale_info::Type {
+    <Cid<S> as scale_info::TypeInfo>::type_info()
+  }
+}
+
+#[cfg(all(test, any(feature = "serde-codec", feature = "scale-codec")))]
+mod tests {
+  use sp_multihash::{Code, MultihashDigest};
+
+  use super::{CidPolicy, PolicyCid};
+  use crate::Cid;
+
+  struct OnlyRaw;
+
+  impl CidPolicy for OnlyRaw {
+    const CODECS: Option<&'static [u64]> = Some(&[0x55]);
+  }
+
+  #[cfg(feature = "serde-codec")]
+  #[test]
+  fn deserialize_checks_policy() {
+    let raw = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let json = serde_json::to_string(&raw).unwrap();
+    let checked: PolicyCid<OnlyRaw> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&checked).unwrap(), json);
+    assert_eq!(checked.into_inner(), raw);
+
+    let dag_pb = Cid::new_v1(0x70, Code::Sha2_256.digest(b"foo"));
+    let json = serde_json::to_string(&dag_pb).unwrap();
+    assert!(serde_json::from_str::<PolicyCid<OnlyRaw>>(&json).is_err());
+  }
+
+  #[cfg(feature = "scale-codec")]
+  #[test]
+  fn decode_checks_policy() {
+    use parity_scale_codec::{Decode, Encode};
+
+    let raw = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let checked = PolicyCid::<OnlyRaw>::decode(&mut &raw.encode()[..]);
+    assert_eq!(checked.unwrap().encode(), raw.encode());
+
+    let dag_pb = Cid::new_v1(0x70, Code::Sha2_256.digest(b"foo"));
+    let encoded = dag_pb.encode();
+    assert!(PolicyCid::<OnlyRaw>::decode(&mut &encoded[..]).is_err());
+  }
+}
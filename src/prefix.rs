@@ -0,0 +1,176 @@
+//! The header of a binary CID.
+//!
+//! Protocol framers that read CIDs from a stream need to know how many bytes
+//! to pull before the whole CID is available. A [`CidPrefix`] is read from
+//! the version, codec and multihash header alone and tells the total length.
+use sp_std::vec::Vec;
+
+use sp_multihash::Size;
+use unsigned_varint::encode as varint_encode;
+
+use crate::{
+  cid::{Cid, DAG_PB, SHA2_256},
+  context::DecodeContext,
+  error::{Error, Result},
+  version::Version,
+};
+
+/// The fields of a binary CID that precede its digest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CidPrefix {
+  /// The version of the CID.
+  version: Version,
+  /// The codec of the CID.
+  codec: u64,
+  /// The multihash code of the CID.
+  hash_code: u64,
+  /// The length of the digest.
+  digest_len: usize,
+  /// Offset of the multihash.
+  hash_offset: usize,
+  /// Offset of the digest, the length of the header.
+  digest_offset: usize,
+}
+
+impl CidPrefix {
+  /// Reads the header of the CID at the start of `bytes`, which doesn't have
+  /// to contain the digest.
+  ///
+  /// Byte offsets in errors are positions within `bytes`. Digests longer than
+  /// 255 bytes are rejected, no multihash can hold them. See
+  /// [`DecodeContext::read_prefix`] for tighter limits.
+  pub fn read(bytes: &[u8]) -> Result<Self> {
+    DecodeContext::new().read_prefix(bytes)
+  }
+
+  /// Create the header of a CID from its fields and the offsets of its
+  /// multihash and digest.
+  pub(crate) fn new(
+    version: Version,
+    codec: u64,
+    hash_code: u64,
+    digest_len: usize,
+    hash_offset: usize,
+    digest_offset: usize,
+  ) -> Self {
+    Self {
+      version,
+      codec,
+      hash_code,
+      digest_len,
+      hash_offset,
+      digest_offset,
+    }
+  }
+
+  /// Returns the cid version.
+  pub fn version(&self) -> Version {
+    self.version
+  }
+
+  /// Returns the cid codec.
+  pub fn codec(&self) -> u64 {
+    self.codec
+  }
+
+  /// Returns the code of the cid multihash.
+  pub fn hash_code(&self) -> u64 {
+    self.hash_code
+  }
+
+  /// Returns the length of the digest.
+  pub fn digest_len(&self) -> usize {
+    self.digest_len
+  }
+
+  /// Returns the offset of the multihash.
+  pub(crate) fn hash_offset(&self) -> usize {
+    self.hash_offset
+  }
+
+  /// Returns the length of the header, which is the offset of the digest.
+  pub fn header_len(&self) -> usize {
+    self.digest_offset
+  }
+
+  /// Returns the length of the whole CID.
+  pub fn total_len(&self) -> usize {
+    // Only digests read with a raised limit can overflow
+    self.digest_offset.saturating_add(self.digest_len)
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Reads the header of the CID at the start of `bytes`, see
+  /// [`CidPrefix::read`].
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  ///
+  /// // A CIDv1 header: version, raw codec, SHA2-256 and a 32 byte digest
+  /// let prefix = Cid::parse_prefix(&[0x01, 0x55, 0x12, 0x20]).unwrap();
+  /// assert_eq!(prefix.codec(), 0x55);
+  /// assert_eq!(prefix.total_len(), 36);
+  /// ```
+  pub fn parse_prefix(bytes: &[u8]) -> Result<CidPrefix> {
+    CidPrefix::read(bytes)
+  }
+
+  /// Returns the bounds of the binary CIDs with the given version, codec and
+  /// optionally multihash code, for range scans over databases keyed by
+  /// binary CIDs.
+  ///
+  /// The start is inclusive and the end exclusive. Fails if a CIDv0 doesn't
+  /// have the DAG-PB codec or a SHA2-256 multihash.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Cid, Version};
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let (start, end) = Cid::prefix_range(Version::V1, 0x55, None).unwrap();
+  /// assert_eq!(start, [0x01, 0x55]);
+  /// assert_eq!(end, [0x01, 0x56]);
+  /// let key = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo")).to_bytes();
+  /// assert!(start <= key && key < end);
+  /// ```
+  pub fn prefix_range(
+    version: Version,
+    codec: u64,
+    hash_code: Option<u64>,
+  ) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut start = Vec::with_capacity(21);
+    match version {
+      Version::V0 => {
+        if codec != DAG_PB {
+          return Err(Error::InvalidCidV0Codec(codec));
+        }
+        match hash_code {
+          Some(code) if code != SHA2_256 => {
+            return Err(Error::InvalidCidV0Multihash(code))
+          }
+          _ => start.extend_from_slice(&[SHA2_256 as u8, 0x20]),
+        }
+      }
+      Version::V1 => {
+        let mut buf = varint_encode::u64_buffer();
+        start.extend_from_slice(varint_encode::u64(version.into(), &mut buf));
+        start.extend_from_slice(varint_encode::u64(codec, &mut buf));
+        if let Some(code) = hash_code {
+          start.extend_from_slice(varint_encode::u64(code, &mut buf));
+        }
+      }
+    }
+    // The last byte of a varint and the CIDv0 digest length are below 0x80,
+    // so the end is the start with its last byte incremented. Varints are
+    // prefix free, so no other prefix falls into the range.
+    let mut end = start.clone();
+    if let Some(last) = end.last_mut() {
+      *last += 1;
+    }
+    Ok((start, end))
+  }
+}
@@ -0,0 +1,138 @@
+//! Protobuf support for CIDs, behind the `prost` feature.
+//!
+//! [`ProtoCid`] is a message with the binary CID in a single `bytes` field:
+//!
+//! ```proto
+//! message Cid {
+//!   bytes cid = 1;
+//! }
+//! ```
+//!
+//! Map that message to the Rust type with `prost_build::Config::extern_path`,
+//! and CID fields of generated messages are validated when they are decoded.
+use sp_std::{convert::TryFrom, vec::Vec};
+
+use prost::{
+  bytes::{Buf, BufMut},
+  encoding::{self, DecodeContext, WireType},
+  DecodeError, Message,
+};
+use sp_multihash::{Size, U64};
+
+use crate::cid::{Cid, MAX_LEN};
+
+/// The tag of the `bytes` field holding the CID.
+const TAG: u32 = 1;
+
+/// A protobuf message holding a CID.
+///
+/// A message without the field decodes to the [`Cid::zero`] sentinel, which
+/// is also not encoded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ProtoCid<S: Size = U64>(pub Cid<S>);
+
+impl<S: Size> From<Cid<S>> for ProtoCid<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self(cid)
+  }
+}
+
+impl<S: Size> From<ProtoCid<S>> for Cid<S> {
+  fn from(proto: ProtoCid<S>) -> Self {
+    proto.0
+  }
+}
+
+impl<S: Size> Message for ProtoCid<S> {
+  fn encode_raw<B: BufMut>(&self, buf: &mut B) {
+    if self.0.is_zero() {
+      return;
+    }
+    let mut bytes = [0u8; MAX_LEN];
+    let len = self.0.write_to_slice(&mut bytes);
+    encoding::encode_key(TAG, WireType::LengthDelimited, buf);
+    encoding::encode_varint(len as u64, buf);
+    buf.put_slice(&bytes[..len]);
+  }
+
+  fn merge_field<B: Buf>(
+    &mut self,
+    tag: u32,
+    wire_type: WireType,
+    buf: &mut B,
+    ctx: DecodeContext,
+  ) -> Result<(), DecodeError> {
+    if tag != TAG {
+      return encoding::skip_field(wire_type, tag, buf, ctx);
+    }
+    let mut bytes = Vec::new();
+    encoding::bytes::merge(wire_type, &mut bytes, buf, ctx)?;
+    self.0 = Cid::try_from(bytes.as_slice())
+      .map_err(|_| DecodeError::new("invalid CID"))?;
+    Ok(())
+  }
+
+  fn encoded_len(&self) -> usize {
+    if self.0.is_zero() {
+      return 0;
+    }
+    let mut bytes = [0u8; MAX_LEN];
+    let len = self.0.write_to_slice(&mut bytes);
+    encoding::key_len(TAG) + encoding::encoded_len_varint(len as u64) + len
+  }
+
+  fn clear(&mut self) {
+    self.0 = Cid::zero();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sp_std::{vec, vec::Vec};
+
+  use prost::Message;
+  use sp_multihash::{Code, MultihashDigest, U64};
+
+  use super::ProtoCid;
+  use crate::cid::Cid;
+
+  fn encode(message: &ProtoCid) -> Vec<u8> {
+    let mut buf = Vec::new();
+    message.encode(&mut buf).unwrap();
+    buf
+  }
+
+  #[test]
+  fn round_trip() {
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let encoded = encode(&ProtoCid(cid));
+    let bytes = cid.to_bytes();
+    assert_eq!(encoded[..2], [0x0a, bytes.len() as u8]);
+    assert_eq!(encoded[2..], bytes[..]);
+    assert_eq!(encoded.len(), ProtoCid(cid).encoded_len());
+    assert_eq!(ProtoCid::decode(&encoded[..]).unwrap(), ProtoCid(cid));
+
+    // The zero sentinel is the empty message
+    assert!(encode(&ProtoCid(Cid::<U64>::zero())).is_empty());
+    let empty = ProtoCid::<U64>::decode(&[][..]).unwrap();
+    assert_eq!(empty, ProtoCid(Cid::zero()));
+    // Unknown fields are skipped
+    let mut unknown = vec![0x10, 0x01];
+    unknown.extend_from_slice(&encoded);
+    assert_eq!(ProtoCid::decode(&unknown[..]).unwrap(), ProtoCid(cid));
+  }
+
+  #[test]
+  fn rejects_invalid_cid() {
+    let truncated = [0x0a, 0x02, 0x01, 0x55];
+    assert!(ProtoCid::<U64>::decode(&truncated[..]).is_err());
+
+    // A CID of version 2
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let mut bytes = cid.to_bytes();
+    bytes[0] = 2;
+    let mut encoded = vec![0x0a, bytes.len() as u8];
+    encoded.extend_from_slice(&bytes);
+    assert!(ProtoCid::<U64>::decode(&encoded[..]).is_err());
+  }
+}
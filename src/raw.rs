@@ -0,0 +1,164 @@
+//! A CID that is validated on demand.
+//!
+//! Runtimes that mostly store CIDs and emit them again in events don't need
+//! the parsed form. A [`RawCid`] keeps the binary encoding and only parses it
+//! when asked to, so passing it through costs a copy of the bytes.
+use sp_std::{convert::TryFrom, vec::Vec};
+
+use sp_multihash::Size;
+
+use crate::{
+  cid::Cid,
+  cid_ref::CidRef,
+  error::{Error, Result},
+};
+
+/// The binary encoding of a CID, which isn't validated until it is parsed.
+///
+/// A `RawCid` built from a [`Cid`] holds its canonical encoding. One decoded
+/// from SCALE or built with [`RawCid::from_bytes_unchecked`] holds whatever
+/// it was given, and [`RawCid::to_cid`] reports if that isn't a CID. The SCALE
+/// encoding is the canonical one of a `CanonicalCid`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RawCid(Vec<u8>);
+
+impl RawCid {
+  /// Encodes a CID.
+  pub fn new<S: Size>(cid: &Cid<S>) -> Self {
+    Self(cid.to_bytes())
+  }
+
+  /// Wraps bytes without checking that they are a CID.
+  pub fn from_bytes_unchecked(bytes: Vec<u8>) -> Self {
+    Self(bytes)
+  }
+
+  /// Returns the wrapped bytes.
+  pub fn as_bytes(&self) -> &[u8] {
+    &self.0
+  }
+
+  /// Unwraps the bytes.
+  pub fn into_bytes(self) -> Vec<u8> {
+    self.0
+  }
+
+  /// Parses the bytes, which have to be exactly one CID.
+  pub fn to_cid<S: Size>(&self) -> Result<Cid<S>> {
+    let (cid, rest) = CidRef::read(&self.0)?;
+    if !rest.is_empty() {
+      return Err(Error::TrailingBytes { offset: self.0.len() - rest.len() });
+    }
+    cid.to_owned()
+  }
+}
+
+impl<S: Size> From<Cid<S>> for RawCid {
+  fn from(cid: Cid<S>) -> Self {
+    Self::new(&cid)
+  }
+}
+
+impl<S: Size> From<&Cid<S>> for RawCid {
+  fn from(cid: &Cid<S>) -> Self {
+    Self::new(cid)
+  }
+}
+
+impl<S: Size> TryFrom<&RawCid> for Cid<S> {
+  type Error = Error;
+
+  fn try_from(raw: &RawCid) -> Result<Self> {
+    raw.to_cid()
+  }
+}
+
+impl AsRef<[u8]> for RawCid {
+  fn as_ref(&self) -> &[u8] {
+    &self.0
+  }
+}
+
+#[cfg(feature = "scale-codec")]
+mod scale {
+  use sp_std::vec;
+
+  use parity_scale_codec::{
+    Compact, CompactLen, Decode, Encode, EncodeLike, Error, Input,
+    MaxEncodedLen, Output,
+  };
+
+  use super::RawCid;
+  use crate::{cid::MAX_LEN, trace};
+
+  impl Encode for RawCid {
+    fn size_hint(&self) -> usize {
+      self.0.size_hint()
+    }
+
+    fn encode_to<O: Output + ?Sized>(&self, dest: &mut O) {
+      self.0.encode_to(dest)
+    }
+  }
+
+  impl EncodeLike for RawCid {}
+
+  /// Decodes the bytes without parsing them, only their length is checked.
+  impl Decode for RawCid {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+      let len = Compact::<u32>::decode(input)?.0 as usize;
+      if len > MAX_LEN {
+        trace::rejected("CID exceeds the maximum length", len);
+        return Err("CID exceeds the maximum length".into());
+      }
+      let mut bytes = vec![0u8; len];
+      input.read(&mut bytes)?;
+      Ok(Self(bytes))
+    }
+  }
+
+  impl MaxEncodedLen for RawCid {
+    fn max_encoded_len() -> usize {
+      Compact::<u32>::compact_len(&(MAX_LEN as u32)) + MAX_LEN
+    }
+  }
+
+  #[cfg(feature = "scale-info")]
+  impl scale_info::TypeInfo for RawCid {
+    type Identity = crate::scale::CanonicalCid;
+
+    fn type_info() -> scale_info::Type {
+      Self::Identity::type_info()
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use sp_std::vec;
+
+    use parity_scale_codec::{Decode, Encode, MaxEncodedLen};
+    use sp_multihash::{Code, MultihashDigest, U64};
+
+    use super::RawCid;
+    use crate::{
+      cid::{Cid, MAX_LEN},
+      scale::CanonicalCid,
+    };
+
+    #[test]
+    fn passes_through_scale() {
+      let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+      let encoded = CanonicalCid::new(cid).encode();
+      let raw = RawCid::decode(&mut &encoded[..]).unwrap();
+      assert_eq!(raw.encode(), encoded);
+      assert_eq!(raw.to_cid::<U64>().unwrap(), cid);
+
+      // Decoding doesn't parse, only the length is checked
+      let garbage = RawCid::decode(&mut &vec![0xffu8; 3].encode()[..]);
+      assert!(garbage.unwrap().to_cid::<U64>().is_err());
+      let long = vec![0u8; MAX_LEN + 1].encode();
+      assert!(RawCid::decode(&mut &long[..]).is_err());
+      assert!(long.len() > RawCid::max_encoded_len());
+    }
+  }
+}
@@ -0,0 +1,138 @@
+//! Hash functions used to create and verify CIDs.
+//!
+//! [`Cid::from_data`] and [`Code`] only know the hash functions of the
+//! sp-multihash code table. Chains that hash blocks with their own function,
+//! e.g. Poseidon under a private multicodec, provide it with a
+//! [`HasherRegistry`] to [`Cid::from_data_with`] and [`Cid::verify_with`].
+use sp_std::convert::TryFrom;
+
+use sp_multihash::{Code, Multihash, MultihashDigest, Size};
+
+use crate::{
+  cid::{wrap_digest, Cid, IDENTITY},
+  error::{Error, Result},
+};
+
+/// Hash functions by multihash code.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, DefaultHashers, HasherRegistry};
+/// use sp_multihash::Multihash;
+///
+/// const XOR_HASH: u64 = 0x30_0001;
+///
+/// struct WithXor;
+///
+/// impl HasherRegistry for WithXor {
+///   fn digest(&self, code: u64, data: &[u8]) -> Option<Multihash> {
+///     if code != XOR_HASH {
+///       return DefaultHashers.digest(code, data);
+///     }
+///     let xor = data.iter().fold(0, |xor, byte| xor ^ byte);
+///     Multihash::wrap(XOR_HASH, &[xor]).ok()
+///   }
+/// }
+///
+/// let cid = Cid::from_data_with(&WithXor, 0x55, XOR_HASH, b"foo");
+/// let cid = cid.unwrap();
+/// assert_eq!(cid.verify_with(&WithXor, b"foo"), Ok(true));
+/// assert_eq!(cid.verify_with(&WithXor, b"bar"), Ok(false));
+/// assert!(cid.verify(b"foo").is_err());
+/// ```
+pub trait HasherRegistry {
+  /// Hashes `data` with the hash function of the multihash `code`.
+  ///
+  /// Returns `None` if the registry has no hash function for `code`.
+  /// Registries that add hash functions to the code table fall back to
+  /// [`DefaultHashers`] for the other codes.
+  fn digest(&self, code: u64, data: &[u8]) -> Option<Multihash>;
+}
+
+/// The hash functions of the sp-multihash [`Code`] table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DefaultHashers;
+
+impl HasherRegistry for DefaultHashers {
+  fn digest(&self, code: u64, data: &[u8]) -> Option<Multihash> {
+    Code::try_from(code).ok().map(|code| code.digest(data))
+  }
+}
+
+impl<R: HasherRegistry + ?Sized> HasherRegistry for &R {
+  fn digest(&self, code: u64, data: &[u8]) -> Option<Multihash> {
+    (**self).digest(code, data)
+  }
+}
+
+/// Returns the error for a code the registry has no hash function for.
+fn unsupported(code: u64) -> Error {
+  Error::ParsingError(sp_multihash::Error::UnsupportedCode(code))
+}
+
+impl<S: Size> Cid<S> {
+  /// Create a CIDv1 of `data` hashed with the hash function of `code` in
+  /// `registry`.
+  ///
+  /// Fails if the registry has no hash function for `code` or the digest
+  /// doesn't fit into the allocated size.
+  pub fn from_data_with<R: HasherRegistry + ?Sized>(
+    registry: &R,
+    codec: u64,
+    code: u64,
+    data: &[u8],
+  ) -> Result<Self> {
+    let hash = registry.digest(code, data).ok_or_else(|| unsupported(code))?;
+    Ok(Self::new_v1(codec, wrap_digest(code, hash.digest())?))
+  }
+
+  /// Checks whether `data` is the block of the CID, with the hash functions
+  /// of the sp-multihash [`Code`] table.
+  ///
+  /// See [`Cid::verify_with`].
+  pub fn verify(&self, data: &[u8]) -> Result<bool> {
+    self.verify_with(&DefaultHashers, data)
+  }
+
+  /// Checks whether `data` is the block of the CID, with the hash functions
+  /// of `registry`.
+  ///
+  /// An identity multihash matches if its digest is `data`. Any other
+  /// multihash matches if its digest equals the digest of `data`, or, if it
+  /// is truncated, the first bytes of it. Truncated digests need at least
+  /// [`Cid::MIN_TRUNCATED_DIGEST_LEN`] bytes, shorter ones and empty ones
+  /// never match, as data matching them is easy to find. Fails if the
+  /// registry has no hash function for the multihash code.
+  pub fn verify_with<R: HasherRegistry + ?Sized>(
+    &self,
+    registry: &R,
+    data: &[u8],
+  ) -> Result<bool> {
+    self
+      .check_with(registry, data)
+      .ok_or_else(|| unsupported(self.hash().code()))
+  }
+
+  /// Checks whether `data` is the block of the CID like
+  /// [`Cid::verify_with`], returns `None` if the registry has no hash
+  /// function for the multihash code.
+  pub(crate) fn check_with<R: HasherRegistry + ?Sized>(
+    &self,
+    registry: &R,
+    data: &[u8],
+  ) -> Option<bool> {
+    let hash = self.hash();
+    if hash.code() == IDENTITY {
+      return Some(hash.digest() == data);
+    }
+    let full = registry.digest(hash.code(), data)?;
+    let (full, digest) = (full.digest(), hash.digest());
+    let min_len = if digest.len() < full.len() {
+      Self::MIN_TRUNCATED_DIGEST_LEN
+    } else {
+      1
+    };
+    Some(digest.len() >= min_len && full.starts_with(digest))
+  }
+}
@@ -0,0 +1,429 @@
+//! SCALE encoding of CIDs.
+//!
+//! CIDs are SCALE encoded in the legacy format that was derived from their
+//! fields, so values stored by earlier versions still decode: the version
+//! index as a `u8`, the codec as a fixed width `u64` and the multihash. The
+//! format is only defined for the sizes the multihash has an encoding for.
+//! Decoding validates the CID. [`Cid::encode_legacy`] and
+//! [`Cid::decode_legacy`] name the format explicitly.
+//!
+//! [`CanonicalCid`] opts into the canonical binary form instead, prefixed
+//! with its compact encoded length like a `Vec<u8>`. It is shorter, defined
+//! for every size, and its decoding rejects inputs longer than
+//! [`Cid::MAX_BINARY_LEN`]. Stored values are converted with
+//! [`Cid::migrate`].
+//!
+//! The `scale-info` feature adds the `TypeInfo` of the encodings, for the
+//! metadata of runtimes.
+use sp_std::{convert::TryFrom, fmt, ops::Deref, vec, vec::Vec};
+
+use parity_scale_codec::{
+  Compact, CompactLen, Decode, Encode, EncodeLike, Error, Input,
+  MaxEncodedLen, Output,
+};
+use sp_multihash::{MultihashGeneric as Multihash, Size, U64};
+
+use crate::{cid::Cid, cid_ref::CidRef, trace, version::Version};
+
+impl<S: Size> Encode for Cid<S>
+where
+  Multihash<S>: Encode,
+{
+  fn size_hint(&self) -> usize {
+    1 + 8 + self.hash().size_hint()
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    // The version was derived as an enum, so it is encoded as its index
+    (self.version().to_u64() as u8).encode_to(dest);
+    self.codec().encode_to(dest);
+    self.hash().encode_to(dest);
+  }
+}
+
+impl<S: Size> EncodeLike for Cid<S> where Multihash<S>: Encode {}
+
+impl<S: Size> Decode for Cid<S>
+where
+  Multihash<S>: Decode,
+{
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    let (version, codec, hash) = <(u8, u64, Multihash<S>)>::decode(input)?;
+    if usize::from(hash.size()) > S::USIZE {
+      return Err("CID digest exceeds its size".into());
+    }
+    Version::try_from(version)
+      .and_then(|version| Self::new(version, codec, hash))
+      .map_err(|_| "invalid CID".into())
+  }
+}
+
+impl<S: Size> MaxEncodedLen for Cid<S>
+where
+  Multihash<S>: Encode,
+{
+  fn max_encoded_len() -> usize {
+    // The version index, the codec, and the multihash code, size and digest
+    1 + 8 + 8 + 1 + S::USIZE
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<S: Size + 'static> scale_info::TypeInfo for Cid<S> {
+  type Identity = Self;
+
+  fn type_info() -> scale_info::Type {
+    use scale_info::{build::Fields, Path, Type};
+
+    Type::builder().path(Path::new("Cid", module_path!())).composite(
+      Fields::named()
+        .field(|f| f.ty::<u8>().name("version").type_name("Version"))
+        .field(|f| f.ty::<u64>().name("codec").type_name("u64"))
+        .field(|f| {
+          let ty = f.ty::<type_info::Multihash<S>>();
+          ty.name("hash").type_name("Multihash")
+        }),
+    )
+  }
+}
+
+/// Descriptions of the multihash fields of the default encoding, which has
+/// no type information of its own.
+#[cfg(feature = "scale-info")]
+mod type_info {
+  use sp_std::marker::PhantomData;
+
+  use scale_info::{
+    build::Fields, MetaType, Path, Type, TypeDefArray, TypeInfo,
+  };
+  use sp_multihash::Size;
+
+  /// The multihash: its code, digest size and the digest padded to `S`.
+  pub(super) struct Multihash<S>(PhantomData<S>);
+
+  impl<S: Size + 'static> TypeInfo for Multihash<S> {
+    type Identity = Self;
+
+    fn type_info() -> Type {
+      Type::builder().path(Path::new("Multihash", module_path!())).composite(
+        Fields::named()
+          .field(|f| f.ty::<u64>().name("code").type_name("u64"))
+          .field(|f| f.ty::<u8>().name("size").type_name("u8"))
+          .field(|f| f.ty::<Digest<S>>().name("digest").type_name("Digest")),
+      )
+    }
+  }
+
+  /// The digest array of `S` bytes.
+  pub(super) struct Digest<S>(PhantomData<S>);
+
+  impl<S: Size + 'static> TypeInfo for Digest<S> {
+    type Identity = Self;
+
+    fn type_info() -> Type {
+      TypeDefArray::new(S::U32, MetaType::new::<u8>()).into()
+    }
+  }
+}
+
+/// A CID that is SCALE encoded as its canonical binary form, see the
+/// [module documentation](self).
+///
+/// # Example
+///
+/// ```
+/// use parity_scale_codec::{Decode, Encode};
+/// use sp_cid::{CanonicalCid, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let encoded = CanonicalCid::new(cid).encode();
+/// assert_eq!(encoded, cid.to_bytes().encode());
+/// let decoded: CanonicalCid = Decode::decode(&mut &encoded[..]).unwrap();
+/// assert_eq!(decoded.into_inner(), cid);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CanonicalCid<S: Size = U64>(Cid<S>);
+
+impl<S: Size> CanonicalCid<S> {
+  /// Wraps a CID.
+  pub fn new(cid: Cid<S>) -> Self {
+    Self(cid)
+  }
+
+  /// Unwraps the CID.
+  pub fn into_inner(self) -> Cid<S> {
+    self.0
+  }
+}
+
+impl<S: Size> Deref for CanonicalCid<S> {
+  type Target = Cid<S>;
+
+  fn deref(&self) -> &Cid<S> {
+    &self.0
+  }
+}
+
+impl<S: Size> From<Cid<S>> for CanonicalCid<S> {
+  fn from(cid: Cid<S>) -> Self {
+    Self(cid)
+  }
+}
+
+impl<S: Size> From<CanonicalCid<S>> for Cid<S> {
+  fn from(canonical: CanonicalCid<S>) -> Self {
+    canonical.0
+  }
+}
+
+impl<S: Size> Encode for CanonicalCid<S> {
+  fn size_hint(&self) -> usize {
+    Cid::<S>::MAX_BINARY_LEN + 2
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    self.0.to_bytes().encode_to(dest)
+  }
+}
+
+impl<S: Size> EncodeLike for CanonicalCid<S> {}
+
+impl<S: Size> Decode for CanonicalCid<S> {
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    decode_canonical(input, Cid::<S>::MAX_BINARY_LEN).map(Self)
+  }
+}
+
+impl<S: Size> MaxEncodedLen for CanonicalCid<S> {
+  fn max_encoded_len() -> usize {
+    let len = Cid::<S>::MAX_BINARY_LEN;
+    Compact::<u32>::compact_len(&(len as u32)) + len
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl<S: Size + 'static> scale_info::TypeInfo for CanonicalCid<S> {
+  type Identity = Self;
+
+  fn type_info() -> scale_info::Type {
+    use scale_info::{build::Fields, Path, Type};
+
+    Type::builder()
+      .path(Path::new("CanonicalCid", module_path!()))
+      .composite(
+        Fields::unnamed().field(|f| f.ty::<Vec<u8>>().type_name("Vec<u8>")),
+      )
+  }
+}
+
+impl<S: Size> fmt::Display for CanonicalCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    fmt::Display::fmt(&self.0, f)
+  }
+}
+
+impl<S: Size> fmt::Debug for CanonicalCid<S> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Debug::fmt(&self.0, f)
+  }
+}
+
+/// Decodes a CID in its canonical binary form of at most `max_len` bytes,
+/// prefixed with its compact encoded length.
+pub(crate) fn decode_canonical<S: Size, I: Input>(
+  input: &mut I,
+  max_len: usize,
+) -> Result<Cid<S>, Error> {
+  let len = Compact::<u32>::decode(input)?.0 as usize;
+  if len > max_len {
+    trace::rejected("CID exceeds the maximum length", len);
+    return Err("CID exceeds the maximum length".into());
+  }
+  let mut bytes = vec![0u8; len];
+  input.read(&mut bytes)?;
+  match CidRef::read(&bytes) {
+    Ok((cid, [])) => {
+      cid.to_owned().map_err(|_| "CID digest exceeds its size".into())
+    }
+    Ok(_) => Err("trailing bytes after CID".into()),
+    Err(_) => Err("invalid CID".into()),
+  }
+}
+
+impl Encode for Version {
+  fn size_hint(&self) -> usize {
+    8
+  }
+
+  fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+    self.to_u64().encode_to(dest)
+  }
+}
+
+impl EncodeLike for Version {}
+
+impl Decode for Version {
+  fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+    Version::try_from(u64::decode(input)?)
+      .map_err(|_| "unsupported CID version".into())
+  }
+}
+
+impl MaxEncodedLen for Version {
+  fn max_encoded_len() -> usize {
+    u64::max_encoded_len()
+  }
+}
+
+#[cfg(feature = "scale-info")]
+impl scale_info::TypeInfo for Version {
+  type Identity = Self;
+
+  fn type_info() -> scale_info::Type {
+    use scale_info::{build::Fields, Path, Type};
+
+    Type::builder()
+      .path(Path::new("Version", module_path!()))
+      .composite(Fields::unnamed().field(|f| f.ty::<u64>().type_name("u64")))
+  }
+}
+
+impl<S: Size> Cid<S>
+where
+  Multihash<S>: Encode,
+{
+  /// Encodes the CID in the legacy SCALE format derived from its fields,
+  /// which is its default encoding.
+  ///
+  /// Unlike [`Encode::encode`], the name keeps the format explicit in
+  /// storage migrations, should the default change.
+  pub fn encode_legacy(&self) -> Vec<u8> {
+    self.encode()
+  }
+}
+
+impl<S: Size> Cid<S>
+where
+  Multihash<S>: Decode,
+{
+  /// Decodes a CID in the legacy SCALE format of [`Cid::encode_legacy`].
+  ///
+  /// Fails if `encoded` has bytes after the CID.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// assert_eq!(Cid::decode_legacy(&cid.encode_legacy()).unwrap(), cid);
+  /// ```
+  pub fn decode_legacy(encoded: &[u8]) -> Result<Self, Error> {
+    let mut input = encoded;
+    let cid = Self::decode(&mut input)?;
+    if !input.is_empty() {
+      return Err("trailing bytes after CID".into());
+    }
+    Ok(cid)
+  }
+
+  /// Converts a CID in the legacy SCALE encoding to the one of
+  /// [`CanonicalCid`], e.g. in a storage migration.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use parity_scale_codec::Encode;
+  /// use sp_cid::{CanonicalCid, Cid};
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// let migrated = Cid::migrate(&cid.encode_legacy()).unwrap();
+  /// assert_eq!(migrated, CanonicalCid::new(cid).encode());
+  /// ```
+  pub fn migrate(encoded: &[u8]) -> Result<Vec<u8>, Error> {
+    Self::decode_legacy(encoded).map(|cid| CanonicalCid::new(cid).encode())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use parity_scale_codec::{Compact, Decode, Encode, MaxEncodedLen};
+  use sp_multihash::{Code, MultihashDigest, U32, U64};
+
+  use super::CanonicalCid;
+  use crate::{cid::Cid, version::Version};
+
+  #[test]
+  fn encodes_version_number() {
+    assert_eq!(Version::V1.encode(), 1u64.encode());
+    assert_eq!(Version::decode(&mut &0u64.encode()[..]).unwrap(), Version::V0);
+    assert!(Version::decode(&mut &2u64.encode()[..]).is_err());
+  }
+
+  #[test]
+  fn encodes_fields() {
+    let cid = Cid::<U64>::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+    let encoded = cid.encode();
+    assert_eq!(encoded[0], 0);
+    assert_eq!(&encoded[1..9], &0x70u64.encode()[..]);
+    assert_eq!(&encoded[9..], &cid.hash().encode()[..]);
+    assert_eq!(encoded.len(), Cid::<U64>::max_encoded_len());
+    assert_eq!(Cid::<U64>::decode(&mut &encoded[..]).unwrap(), cid);
+    assert_eq!(Cid::<U32>::max_encoded_len(), 1 + 8 + 8 + 1 + 32);
+
+    let mut version = encoded.clone();
+    version[0] = 2;
+    assert!(Cid::<U64>::decode(&mut &version[..]).is_err());
+    // CIDv0 only has DAG-PB as codec
+    let mut codec = encoded.clone();
+    codec[1] = 0x55;
+    assert!(Cid::<U64>::decode(&mut &codec[..]).is_err());
+    // The digest size is the byte after the multihash code
+    let mut size = encoded;
+    size[17] = 65;
+    assert!(Cid::<U64>::decode(&mut &size[..]).is_err());
+  }
+
+  #[test]
+  fn encodes_canonical_bytes() {
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let canonical = CanonicalCid::new(cid);
+    assert_eq!(canonical.encode(), cid.to_bytes().encode());
+    let decoded = CanonicalCid::<U64>::decode(&mut &canonical.encode()[..]);
+    assert_eq!(decoded.unwrap(), canonical);
+
+    let mut long = Compact(Cid::<U64>::MAX_BINARY_LEN as u32 + 1).encode();
+    long.resize(long.len() + Cid::<U64>::MAX_BINARY_LEN + 1, 0);
+    assert!(CanonicalCid::<U64>::decode(&mut &long[..]).is_err());
+
+    let mut trailing = cid.to_bytes();
+    trailing.push(0);
+    let trailing = trailing.encode();
+    assert!(CanonicalCid::<U64>::decode(&mut &trailing[..]).is_err());
+  }
+
+  #[test]
+  fn migrates_to_canonical_bytes() {
+    let cid = Cid::<U64>::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+    let migrated = Cid::<U64>::migrate(&cid.encode()).unwrap();
+    assert_eq!(migrated, CanonicalCid::new(cid).encode());
+
+    let mut trailing = cid.encode();
+    trailing.push(0);
+    assert!(Cid::<U64>::migrate(&trailing).is_err());
+  }
+
+  #[test]
+  fn legacy_format_is_the_default() {
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    assert_eq!(cid.encode_legacy(), cid.encode());
+    assert_eq!(Cid::<U64>::decode_legacy(&cid.encode()).unwrap(), cid);
+
+    let mut trailing = cid.encode_legacy();
+    trailing.push(0);
+    assert!(Cid::<U64>::decode_legacy(&trailing).is_err());
+  }
+}
@@ -0,0 +1,134 @@
+//! Serde support for CIDs.
+//!
+//! Human readable formats such as JSON represent a CID as its canonical
+//! string, binary formats as its binary encoding. Deserialization parses
+//! borrowed strings and bytes in place, without copying them into an
+//! intermediate `String` or `Vec`.
+use sp_std::{convert::TryFrom, fmt, marker::PhantomData};
+
+use serde::{
+  de::{self, SeqAccess, Visitor},
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+use sp_multihash::Size;
+
+use crate::{
+  cid::{Cid, MAX_LEN},
+  version::Version,
+};
+
+impl<S: Size> Serialize for Cid<S> {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    if serializer.is_human_readable() {
+      serializer.collect_str(self)
+    } else {
+      let mut buf = [0u8; MAX_LEN];
+      let len = self.write_to_slice(&mut buf);
+      serializer.serialize_bytes(&buf[..len])
+    }
+  }
+}
+
+impl<'de, S: Size> Deserialize<'de> for Cid<S> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let visitor = CidVisitor::new(CidForms::Any);
+    if deserializer.is_human_readable() {
+      deserializer.deserialize_str(visitor)
+    } else {
+      deserializer.deserialize_bytes(visitor)
+    }
+  }
+}
+
+/// The forms of a CID a [`CidVisitor`] accepts.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CidForms {
+  /// Only the string form.
+  String,
+  /// Only the binary form, as bytes or a sequence of bytes.
+  Binary,
+  /// Both forms.
+  Any,
+}
+
+/// Visitor for the string and binary forms of a CID.
+///
+/// Borrowed and owned strings and bytes end up in `visit_str` and
+/// `visit_bytes`, which parse the borrow directly.
+pub(crate) struct CidVisitor<S> {
+  /// The accepted forms.
+  forms: CidForms,
+  /// The allocated size of the CID.
+  _size: PhantomData<S>,
+}
+
+impl<S> CidVisitor<S> {
+  /// Creates a visitor for the given forms.
+  pub(crate) fn new(forms: CidForms) -> Self {
+    Self { forms, _size: PhantomData }
+  }
+}
+
+impl<'de, S: Size> Visitor<'de> for CidVisitor<S> {
+  type Value = Cid<S>;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(match self.forms {
+      CidForms::String => "a CID string",
+      CidForms::Binary => "a binary CID",
+      CidForms::Any => "a CID string or binary CID",
+    })
+  }
+
+  fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+    if self.forms == CidForms::Binary {
+      return Err(E::invalid_type(de::Unexpected::Str(s), &self));
+    }
+    Cid::try_from(s).map_err(E::custom)
+  }
+
+  fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+    if self.forms == CidForms::String {
+      return Err(E::invalid_type(de::Unexpected::Bytes(bytes), &self));
+    }
+    Cid::try_from(bytes).map_err(E::custom)
+  }
+
+  /// Formats without a bytes type, e.g. JSON arrays of numbers, send bytes
+  /// as a sequence. They are collected on the stack.
+  fn visit_seq<A: SeqAccess<'de>>(
+    self,
+    mut seq: A,
+  ) -> Result<Self::Value, A::Error> {
+    if self.forms == CidForms::String {
+      return Err(de::Error::invalid_type(de::Unexpected::Seq, &self));
+    }
+    let mut buf = [0u8; MAX_LEN];
+    let mut len = 0;
+    while let Some(byte) = seq.next_element::<u8>()? {
+      match buf.get_mut(len) {
+        Some(slot) => *slot = byte,
+        None => return Err(de::Error::invalid_length(len + 1, &self)),
+      }
+      len += 1;
+    }
+    self.visit_bytes(&buf[..len])
+  }
+}
+
+impl Serialize for Version {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    serializer.serialize_u64(self.to_u64())
+  }
+}
+
+impl<'de> Deserialize<'de> for Version {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let raw = u64::deserialize(deserializer)?;
+    Version::try_from(raw).map_err(de::Error::custom)
+  }
+}
@@ -0,0 +1,204 @@
+//! Wrappers that fix the serde representation of a CID, behind the
+//! `serde-codec` feature.
+//!
+//! [`Cid`] serializes as a string in human readable formats and as bytes in
+//! binary ones. APIs that need one form regardless of the format, e.g. a
+//! JSON-RPC response next to a CBOR storage record, choose it per field with
+//! [`AsString`], [`AsBytes`] or [`AsDagJson`].
+use sp_std::{fmt, marker::PhantomData, ops::Deref};
+
+use alloc::string::String;
+use serde::{
+  de::{self, IgnoredAny, MapAccess, Visitor},
+  ser::SerializeMap,
+  Deserialize, Deserializer, Serialize, Serializer,
+};
+use sp_multihash::Size;
+
+use crate::{
+  cid::{Cid, MAX_LEN},
+  serde_codec::{CidForms, CidVisitor},
+};
+
+/// A CID that serializes as its canonical string in every format.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{AsString, Cid};
+///
+/// let cid: Cid = "bafkqaaa".parse().unwrap();
+/// let json = serde_json::to_string(&AsString(cid)).unwrap();
+/// assert_eq!(json, r#""bafkqaaa""#);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AsString<C>(pub C);
+
+/// A CID that serializes as its binary form in every format.
+///
+/// Formats without a bytes type, such as JSON, write an array of numbers.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{AsBytes, Cid};
+///
+/// let cid: Cid = "bafkqaaa".parse().unwrap();
+/// let json = serde_json::to_string(&AsBytes(cid)).unwrap();
+/// assert_eq!(json, "[1,85,0,0]");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AsBytes<C>(pub C);
+
+/// A CID that serializes as a DAG-JSON link, a map `{"/": "<cid>"}`, in
+/// every format.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{AsDagJson, Cid};
+///
+/// let cid: Cid = "bafkqaaa".parse().unwrap();
+/// let json = serde_json::to_string(&AsDagJson(cid)).unwrap();
+/// assert_eq!(json, r#"{"/":"bafkqaaa"}"#);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AsDagJson<C>(pub C);
+
+/// Implements the conversions and `Deref` of a wrapper.
+macro_rules! wrapper_conversions {
+  ($($wrapper:ident),*) => {
+    $(
+      impl<S: Size> Deref for $wrapper<Cid<S>> {
+        type Target = Cid<S>;
+
+        fn deref(&self) -> &Cid<S> {
+          &self.0
+        }
+      }
+
+      impl<S: Size> From<Cid<S>> for $wrapper<Cid<S>> {
+        fn from(cid: Cid<S>) -> Self {
+          Self(cid)
+        }
+      }
+
+      impl<S: Size> From<$wrapper<Cid<S>>> for Cid<S> {
+        fn from(wrapper: $wrapper<Cid<S>>) -> Self {
+          wrapper.0
+        }
+      }
+    )*
+  };
+}
+
+wrapper_conversions!(AsString, AsBytes, AsDagJson);
+
+impl<S: Size> Serialize for AsString<Cid<S>> {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    serializer.collect_str(&self.0)
+  }
+}
+
+impl<'de, S: Size> Deserialize<'de> for AsString<Cid<S>> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let visitor = CidVisitor::new(CidForms::String);
+    deserializer.deserialize_str(visitor).map(Self)
+  }
+}
+
+impl<S: Size> Serialize for AsBytes<Cid<S>> {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.0.write_to_slice(&mut buf);
+    serializer.serialize_bytes(&buf[..len])
+  }
+}
+
+impl<'de, S: Size> Deserialize<'de> for AsBytes<Cid<S>> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let visitor = CidVisitor::new(CidForms::Binary);
+    deserializer.deserialize_bytes(visitor).map(Self)
+  }
+}
+
+impl<S: Size> Serialize for AsDagJson<Cid<S>> {
+  fn serialize<T: Serializer>(&self, serializer: T) -> Result<T::Ok, T::Error> {
+    let mut map = serializer.serialize_map(Some(1))?;
+    map.serialize_entry("/", &AsString(self.0.clone()))?;
+    map.end()
+  }
+}
+
+impl<'de, S: Size> Deserialize<'de> for AsDagJson<Cid<S>> {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    deserializer.deserialize_map(LinkVisitor(PhantomData)).map(Self)
+  }
+}
+
+/// Visitor for DAG-JSON links, maps with the single key `/`.
+struct LinkVisitor<S>(PhantomData<S>);
+
+impl<'de, S: Size> Visitor<'de> for LinkVisitor<S> {
+  type Value = Cid<S>;
+
+  fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(r#"a DAG-JSON link {"/": "<cid>"}"#)
+  }
+
+  fn visit_map<A: MapAccess<'de>>(
+    self,
+    mut map: A,
+  ) -> Result<Self::Value, A::Error> {
+    match map.next_key::<String>()? {
+      Some(key) if key == "/" => {}
+      Some(key) => return Err(de::Error::unknown_field(&key, &["/"])),
+      None => return Err(de::Error::missing_field("/")),
+    }
+    let cid = map.next_value::<AsString<Cid<S>>>()?.0;
+    if map.next_key::<IgnoredAny>()?.is_some() {
+      return Err(de::Error::invalid_length(2, &self));
+    }
+    Ok(cid)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use sp_multihash::{Code, MultihashDigest, U64};
+
+  use super::{AsBytes, AsDagJson, AsString};
+  use crate::cid::Cid;
+
+  #[test]
+  fn fixed_representations() {
+    let cid = Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let s = cid.to_string();
+
+    let json = serde_json::to_string(&AsString(cid)).unwrap();
+    assert_eq!(json, format!("\"{}\"", s));
+    let back: AsString<Cid<U64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.0, cid);
+    assert!(serde_json::from_str::<AsBytes<Cid<U64>>>(&json).is_err());
+
+    let json = serde_json::to_string(&AsBytes(cid)).unwrap();
+    let back: AsBytes<Cid<U64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.0, cid);
+    assert!(serde_json::from_str::<AsString<Cid<U64>>>(&json).is_err());
+
+    let json = serde_json::to_string(&AsDagJson(cid)).unwrap();
+    assert_eq!(json, format!(r#"{{"/":"{}"}}"#, s));
+    let back: AsDagJson<Cid<U64>> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.0, cid);
+    let extra = format!(r#"{{"/":"{}","a":1}}"#, s);
+    assert!(serde_json::from_str::<AsDagJson<Cid<U64>>>(&extra).is_err());
+    let other = format!(r#"{{"a":"{}"}}"#, s);
+    assert!(serde_json::from_str::<AsDagJson<Cid<U64>>>(&other).is_err());
+  }
+}
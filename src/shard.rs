@@ -0,0 +1,197 @@
+//! Flatfs-style sharding of blocks on disk.
+//!
+//! Flatfs datastores store every block in a file named by its datastore key,
+//! the unpadded uppercase Base32 encoding of the multihash, inside a shard
+//! directory derived from that key. The shard functions here match the ones
+//! of `go-ds-flatfs`.
+//!
+//! Blockstores that key files by the whole CID use [`Cid::to_filename`]
+//! instead, which keeps the version and codec.
+use sp_std::{convert::TryFrom, fmt, iter};
+
+use alloc::{format, string::String};
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::{
+  base32, bases,
+  cid::Cid,
+  error::{Error, Result},
+};
+
+/// The padding character for keys that are shorter than the shard length.
+const PADDING: char = '_';
+
+/// Appends the last `n` characters of `s`, padded in front to `n`
+/// characters.
+fn push_last(shard: &mut String, s: &str, n: usize) {
+  let len = s.chars().count();
+  shard.extend(iter::repeat(PADDING).take(n.saturating_sub(len)));
+  shard.extend(s.chars().skip(len.saturating_sub(n)));
+}
+
+/// A flatfs sharding function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShardingScheme {
+  /// The first `n` characters of the key.
+  Prefix(usize),
+  /// The last `n` characters of the key.
+  Suffix(usize),
+  /// The `n` characters before the last character of the key. This is the
+  /// default of go-ipfs, with `n = 2`.
+  NextToLast(usize),
+}
+
+impl ShardingScheme {
+  /// Returns the shard directory of a datastore key.
+  ///
+  /// Keys shorter than the shard are padded with `_`. Lengths are counted in
+  /// characters, so keys that aren't ASCII are never split inside one.
+  pub fn shard(&self, key: &str) -> String {
+    let mut shard = String::new();
+    match *self {
+      ShardingScheme::Prefix(n) => {
+        shard.extend(key.chars().chain(iter::repeat(PADDING)).take(n));
+      }
+      ShardingScheme::Suffix(n) => push_last(&mut shard, key, n),
+      ShardingScheme::NextToLast(n) => {
+        // The key without its last character
+        let head = key.char_indices().last().map_or("", |(i, _)| &key[..i]);
+        push_last(&mut shard, head, n);
+      }
+    }
+    shard
+  }
+}
+
+/// Formats the scheme like the `SHARDING` file of a flatfs datastore, e.g.
+/// `/repo/flatfs/shard/v1/next-to-last/2`.
+impl fmt::Display for ShardingScheme {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let (name, n) = match self {
+      ShardingScheme::Prefix(n) => ("prefix", n),
+      ShardingScheme::Suffix(n) => ("suffix", n),
+      ShardingScheme::NextToLast(n) => ("next-to-last", n),
+    };
+    write!(f, "/repo/flatfs/shard/v1/{}/{}", name, n)
+  }
+}
+
+impl<S: Size> Cid<S> {
+  /// Returns the flatfs datastore key of the CID, the unpadded uppercase
+  /// Base32 encoding of its multihash.
+  ///
+  /// The key only depends on the multihash, so CIDs that only differ in
+  /// version or codec share a block.
+  pub fn datastore_key(&self) -> String {
+    let hash = self.hash().to_bytes();
+    let mut key = String::with_capacity(base32::encoded_len(hash.len()));
+    base32::push_lower(&hash, &mut key);
+    key.make_ascii_uppercase();
+    key
+  }
+
+  /// Returns the flatfs shard directory of the CID.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Cid, ShardingScheme};
+  /// use std::convert::TryFrom;
+  ///
+  /// let cid =
+  ///   Cid::try_from("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n").unwrap();
+  /// let key = cid.datastore_key();
+  /// let shard = cid.shard_key(ShardingScheme::NextToLast(2));
+  /// assert_eq!(shard, key[key.len() - 3..key.len() - 1]);
+  /// ```
+  pub fn shard_key(&self, scheme: ShardingScheme) -> String {
+    scheme.shard(&self.datastore_key())
+  }
+
+  /// The maximum length of a filename, the limit of NTFS, APFS and ext4.
+  pub const MAX_FILENAME_LEN: usize = 255;
+
+  /// Returns a filename for the block of the CID, optionally in a shard
+  /// directory, e.g. `vy/bafkrei...vy` with [`ShardingScheme::Suffix`].
+  ///
+  /// The name is the lower case Base32 encoding of the binary CID with the
+  /// `b` multibase prefix. It only has lower case letters and digits, so it
+  /// is safe on case insensitive filesystems, and a CIDv0 keeps its version.
+  /// The shard is taken from the name and joined with `/`.
+  ///
+  /// Fails with [`Error::FilenameTooLong`] if the name is longer than
+  /// [`Cid::MAX_FILENAME_LEN`], which takes a digest of about 150 bytes.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::{Cid, ShardingScheme};
+  ///
+  /// let cid: Cid = "bafkqaaa".parse().unwrap();
+  /// let scheme = ShardingScheme::Suffix(2);
+  /// assert_eq!(cid.to_filename(Some(scheme)).unwrap(), "aa/bafkqaaa");
+  /// assert_eq!(Cid::from_filename("aa/bafkqaaa").unwrap(), cid);
+  /// ```
+  pub fn to_filename(&self, scheme: Option<ShardingScheme>) -> Result<String> {
+    let mut name = String::from("b");
+    base32::push_lower(&self.to_bytes(), &mut name);
+    if name.len() > Self::MAX_FILENAME_LEN {
+      return Err(Error::FilenameTooLong { len: name.len() });
+    }
+    match scheme {
+      Some(scheme) => Ok(format!("{}/{}", scheme.shard(&name), name)),
+      None => Ok(name),
+    }
+  }
+
+  /// Parses a filename of [`Cid::to_filename`].
+  ///
+  /// Only the last component of a path is parsed, with `/` or `\` as the
+  /// separator. Upper case names, e.g. from a filesystem that changed the
+  /// case, are accepted as well.
+  pub fn from_filename(path: &str) -> Result<Self> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let body = name
+      .strip_prefix(['b', 'B'])
+      .ok_or(Error::MultibaseError(multibase::Error::InvalidBaseString))?;
+    let bytes = bases::decode(Base::Base32Upper, body)?;
+    Self::try_from(bytes.as_slice())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::string::ToString;
+
+  #[test]
+  fn shard_functions() {
+    let key = "CIQABCDEFG";
+    assert_eq!(ShardingScheme::Prefix(3).shard(key), "CIQ");
+    assert_eq!(ShardingScheme::Suffix(3).shard(key), "EFG");
+    assert_eq!(ShardingScheme::NextToLast(2).shard(key), "EF");
+
+    // Short keys are padded
+    assert_eq!(ShardingScheme::Prefix(3).shard("A"), "A__");
+    assert_eq!(ShardingScheme::Suffix(3).shard("A"), "__A");
+    assert_eq!(ShardingScheme::NextToLast(2).shard("AB"), "_A");
+    assert_eq!(ShardingScheme::NextToLast(2).shard(""), "__");
+
+    // Multi-byte characters are kept whole
+    let key = "ÄBÇDÉ";
+    assert_eq!(ShardingScheme::Prefix(2).shard(key), "ÄB");
+    assert_eq!(ShardingScheme::Suffix(2).shard(key), "DÉ");
+    assert_eq!(ShardingScheme::NextToLast(2).shard(key), "ÇD");
+    assert_eq!(ShardingScheme::Suffix(3).shard("É"), "__É");
+    assert_eq!(ShardingScheme::NextToLast(2).shard("ÉÄ"), "_É");
+  }
+
+  #[test]
+  fn sharding_file() {
+    assert_eq!(
+      ShardingScheme::NextToLast(2).to_string(),
+      "/repo/flatfs/shard/v1/next-to-last/2"
+    );
+  }
+}
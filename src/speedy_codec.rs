@@ -0,0 +1,68 @@
+//! Speedy support for CIDs.
+//!
+//! A CID is written like a `Vec<u8>` holding its binary encoding: a `u32`
+//! length followed by the bytes. Readers that borrow their input parse the
+//! bytes in place, others go through a buffer on the stack.
+use std::convert::TryFrom;
+
+use speedy::{Context, Readable, Reader, Writable, Writer};
+use sp_multihash::Size;
+
+use crate::cid::{Cid, MAX_LEN};
+
+impl<'a, C: Context, S: Size> Readable<'a, C> for Cid<S> {
+  fn read_from<R: Reader<'a, C>>(reader: &mut R) -> Result<Self, C::Error> {
+    let len = reader.read_u32()? as usize;
+    if len > MAX_LEN {
+      return Err(speedy::Error::custom("CID is too long").into());
+    }
+    let result = match reader.read_bytes_borrowed(len) {
+      Some(bytes) => Cid::try_from(bytes?),
+      None => {
+        let mut buf = [0u8; MAX_LEN];
+        reader.read_bytes(&mut buf[..len])?;
+        Cid::try_from(&buf[..len])
+      }
+    };
+    result.map_err(|err| speedy::Error::custom(err).into())
+  }
+
+  fn minimum_bytes_needed() -> usize {
+    4
+  }
+}
+
+impl<C: Context, S: Size> Writable<C> for Cid<S> {
+  fn write_to<T: ?Sized + Writer<C>>(
+    &self,
+    writer: &mut T,
+  ) -> Result<(), C::Error> {
+    let mut buf = [0u8; MAX_LEN];
+    let len = self.write_to_slice(&mut buf);
+    writer.write_u32(len as u32)?;
+    writer.write_bytes(&buf[..len])
+  }
+
+  fn bytes_needed(&self) -> Result<usize, C::Error> {
+    let mut buf = [0u8; MAX_LEN];
+    Ok(4 + self.write_to_slice(&mut buf))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use speedy::{Readable, Writable};
+  use sp_multihash::{Code, MultihashDigest};
+
+  use crate::Cid;
+
+  #[test]
+  fn roundtrip() {
+    let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+    let bytes = cid.write_to_vec().unwrap();
+    assert_eq!(bytes.len(), 4 + cid.to_bytes().len());
+    assert_eq!(&bytes[4..], cid.to_bytes().as_slice());
+    assert_eq!(Cid::read_from_buffer(&bytes).unwrap(), cid);
+    assert!(Cid::read_from_buffer(&bytes[..10]).is_err());
+  }
+}
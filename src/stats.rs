@@ -0,0 +1,153 @@
+//! Statistics of CID collections.
+//!
+//! Operators auditing a blockstore or on-chain storage want to know which
+//! kinds of CIDs it holds, e.g. whether legacy CIDv0 or weak hash functions
+//! are still in use. [`CidStats`] counts CIDs by their parts.
+use sp_std::{fmt, iter::FromIterator};
+
+use alloc::collections::BTreeMap;
+use sp_multihash::Size;
+
+use crate::{cid::Cid, names, version::Version};
+
+/// Counts of CIDs by version, codec, hash function and digest size.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidStats, Version};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let v1 = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let v0 = Cid::new_v0(Code::Sha2_256.digest(b"bar")).unwrap();
+/// let stats: CidStats = vec![v0, v1, v1].iter().collect();
+/// assert_eq!(stats.total(), 3);
+/// assert_eq!(stats.versions()[&Version::V1], 2);
+/// assert_eq!(stats.hash_codes()[&0x12], 3);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CidStats {
+  /// The number of CIDs.
+  total: usize,
+  /// The number of CIDs of every version.
+  versions: BTreeMap<Version, usize>,
+  /// The number of CIDs of every codec.
+  codecs: BTreeMap<u64, usize>,
+  /// The number of CIDs of every multihash code.
+  hash_codes: BTreeMap<u64, usize>,
+  /// The number of CIDs of every digest size.
+  digest_sizes: BTreeMap<u8, usize>,
+}
+
+impl CidStats {
+  /// Creates empty statistics.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Counts a CID.
+  pub fn add<S: Size>(&mut self, cid: &Cid<S>) {
+    self.total += 1;
+    *self.versions.entry(cid.version()).or_default() += 1;
+    *self.codecs.entry(cid.codec()).or_default() += 1;
+    *self.hash_codes.entry(cid.hash().code()).or_default() += 1;
+    *self.digest_sizes.entry(cid.hash().size()).or_default() += 1;
+  }
+
+  /// Returns the number of CIDs.
+  pub fn total(&self) -> usize {
+    self.total
+  }
+
+  /// Returns the number of CIDs of every version.
+  pub fn versions(&self) -> &BTreeMap<Version, usize> {
+    &self.versions
+  }
+
+  /// Returns the number of CIDs of every codec.
+  pub fn codecs(&self) -> &BTreeMap<u64, usize> {
+    &self.codecs
+  }
+
+  /// Returns the number of CIDs of every multihash code.
+  pub fn hash_codes(&self) -> &BTreeMap<u64, usize> {
+    &self.hash_codes
+  }
+
+  /// Returns the number of CIDs of every digest size in bytes.
+  pub fn digest_sizes(&self) -> &BTreeMap<u8, usize> {
+    &self.digest_sizes
+  }
+}
+
+impl<'a, S: Size + 'a> Extend<&'a Cid<S>> for CidStats {
+  fn extend<I: IntoIterator<Item = &'a Cid<S>>>(&mut self, cids: I) {
+    cids.into_iter().for_each(|cid| self.add(cid))
+  }
+}
+
+impl<S: Size> Extend<Cid<S>> for CidStats {
+  fn extend<I: IntoIterator<Item = Cid<S>>>(&mut self, cids: I) {
+    cids.into_iter().for_each(|cid| self.add(&cid))
+  }
+}
+
+impl<'a, S: Size + 'a> FromIterator<&'a Cid<S>> for CidStats {
+  fn from_iter<I: IntoIterator<Item = &'a Cid<S>>>(cids: I) -> Self {
+    let mut stats = Self::new();
+    stats.extend(cids);
+    stats
+  }
+}
+
+impl<S: Size> FromIterator<Cid<S>> for CidStats {
+  fn from_iter<I: IntoIterator<Item = Cid<S>>>(cids: I) -> Self {
+    let mut stats = Self::new();
+    stats.extend(cids);
+    stats
+  }
+}
+
+/// Writes the counts of a table of codes, with their names if they are
+/// known.
+fn write_codes(
+  f: &mut fmt::Formatter,
+  counts: &BTreeMap<u64, usize>,
+  name_of: fn(u64) -> Option<&'static str>,
+) -> fmt::Result {
+  for (code, count) in counts {
+    match name_of(*code) {
+      Some(name) => write!(f, " {} (0x{:x}): {}", name, code, count)?,
+      None => write!(f, " 0x{:x}: {}", code, count)?,
+    }
+  }
+  Ok(())
+}
+
+/// Formats a report with one line per part, e.g.
+///
+/// ```text
+/// 3 CIDs
+/// versions: v0: 1 v1: 2
+/// codecs: raw (0x55): 2 dag-pb (0x70): 1
+/// hashes: sha2-256 (0x12): 3
+/// digest sizes: 32: 3
+/// ```
+impl fmt::Display for CidStats {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "{} CIDs", self.total)?;
+    f.write_str("versions:")?;
+    for (version, count) in &self.versions {
+      write!(f, " v{}: {}", version.to_u64(), count)?;
+    }
+    f.write_str("\ncodecs:")?;
+    write_codes(f, &self.codecs, names::codec_name)?;
+    f.write_str("\nhashes:")?;
+    write_codes(f, &self.hash_codes, names::hash_name)?;
+    f.write_str("\ndigest sizes:")?;
+    for (size, count) in &self.digest_sizes {
+      write!(f, " {}: {}", size, count)?;
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,59 @@
+//! Fixed-width keys for tries and key-value stores.
+//!
+//! Merkle tries and many key-value stores only take keys of one width, while
+//! CIDs vary in length with their codec and hash function.
+//! [`Cid::storage_key32`] maps every CID to 32 bytes with a derivation that
+//! never changes, so keys written by one release are found by the next.
+use alloc::vec::Vec;
+
+use sp_multihash::{Code, MultihashDigest, Size};
+
+use crate::cid::{Cid, IDENTITY};
+
+/// The length of storage keys.
+const KEY_LEN: usize = 32;
+
+/// The bytes hashed in front of a CID whose digest isn't used as its key.
+const TAG: &[u8] = b"sp-cid:storage-key32:";
+
+impl<S: Size> Cid<S> {
+  /// Returns a 32 byte key for storing the CID, derived as follows:
+  ///
+  /// 1. If the multihash digest is 32 bytes long and the hash function isn't
+  ///    identity, the key is the digest.
+  /// 2. Otherwise the key is the SHA2-256 digest of the ASCII bytes
+  ///    `sp-cid:storage-key32:` followed by the binary CID, see
+  ///    [`Cid::to_bytes`].
+  ///
+  /// Keys of CIDs with SHA2-256 or BLAKE2b-256 multihashes can be looked up
+  /// by the digest alone. CIDs of the same block that only differ in their
+  /// version or codec share the key. Identity digests are hashed, as their
+  /// content could be chosen to equal any other key.
+  ///
+  /// # Example
+  ///
+  /// ```
+  /// use sp_cid::Cid;
+  /// use sp_multihash::{Code, MultihashDigest};
+  ///
+  /// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+  /// assert_eq!(&cid.storage_key32()[..], cid.hash().digest());
+  ///
+  /// let long = Cid::new_v1(0x55, Code::Sha2_512.digest(b"foo"));
+  /// assert_eq!(long.storage_key32(), long.storage_key32());
+  /// assert_ne!(long.storage_key32(), cid.storage_key32());
+  /// ```
+  pub fn storage_key32(&self) -> [u8; KEY_LEN] {
+    let hash = self.hash();
+    let mut key = [0u8; KEY_LEN];
+    if hash.code() != IDENTITY && hash.digest().len() == KEY_LEN {
+      key.copy_from_slice(hash.digest());
+    } else {
+      let mut data = Vec::with_capacity(TAG.len() + self.binary_len());
+      data.extend_from_slice(TAG);
+      data.extend_from_slice(&self.to_bytes());
+      key.copy_from_slice(Code::Sha2_256.digest(&data).digest());
+    }
+    key
+  }
+}
@@ -0,0 +1,351 @@
+//! Incremental decoding of CID strings.
+//!
+//! Services that find CIDs inside larger streaming payloads, e.g. NDJSON
+//! firehoses, receive a CID string in pieces. A [`CidStrDecoder`] decodes
+//! the pieces as they arrive into a fixed buffer of the longest binary CID,
+//! instead of collecting the whole string first.
+use sp_std::{convert::TryFrom, marker::PhantomData};
+
+use multibase::Base;
+use sp_multihash::{Size, U64};
+
+use crate::{
+  bases::{self, Kind},
+  cid::{Cid, MAX_LEN},
+  error::{Error, Result},
+  version::Version,
+};
+
+/// Returns the error of a string that isn't valid in its base.
+fn invalid() -> Error {
+  Error::MultibaseError(multibase::Error::InvalidBaseString)
+}
+
+/// The base of the string being decoded and the decoding state.
+struct State {
+  /// The base of the string.
+  base: Base,
+  /// The decoding of the base.
+  kind: Kind,
+  /// The alphabet of the base.
+  alphabet: &'static str,
+  /// Whether the string is a CIDv0, without a multibase prefix.
+  v0: bool,
+  /// Decoded bits that don't fill a byte yet, or the Base45 group.
+  acc: u32,
+  /// The number of bits, or Base45 symbols, in `acc`.
+  acc_len: u32,
+  /// The number of symbols, without padding.
+  symbols: usize,
+  /// The number of `=` padding symbols.
+  padding: usize,
+  /// The number of leading zero digits of a big integer.
+  zeros: usize,
+}
+
+/// An incremental decoder of a CID string.
+///
+/// The string is pushed in chunks of bytes, which may split it anywhere,
+/// and decoded as it arrives. The decoder holds at most the longest binary
+/// CID and a few bytes of state, whatever the length of the input, and
+/// rejects invalid symbols and input that decodes to more bytes than any CID
+/// as soon as they are pushed.
+///
+/// All bases of [`Cid::try_from`] are supported, except for Base256Emoji.
+/// The input is a bare CID string, without an `/ipfs/` prefix.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{Cid, CidStrDecoder};
+///
+/// let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+/// let mut decoder = CidStrDecoder::new();
+/// for chunk in s.as_bytes().chunks(7) {
+///   decoder.push(chunk).unwrap();
+/// }
+/// let cid: Cid = decoder.finish().unwrap();
+/// assert_eq!(cid.to_string(), s);
+/// ```
+pub struct CidStrDecoder<S: Size = U64> {
+  /// The decoding, once the first symbol was pushed.
+  state: Option<State>,
+  /// The decoded bytes, or the little endian digits of a big integer.
+  out: [u8; MAX_LEN],
+  /// The number of bytes in `out`.
+  len: usize,
+  /// Whether an error was returned.
+  failed: bool,
+  /// The allocated size of the decoded CID.
+  size: PhantomData<S>,
+}
+
+impl<S: Size> Default for CidStrDecoder<S> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<S: Size> CidStrDecoder<S> {
+  /// Create a decoder for a new string.
+  pub fn new() -> Self {
+    Self {
+      state: None,
+      out: [0; MAX_LEN],
+      len: 0,
+      failed: false,
+      size: PhantomData,
+    }
+  }
+
+  /// Returns the base of the string, once its first byte was pushed.
+  ///
+  /// A CIDv0 is Base58Btc without a multibase prefix.
+  pub fn base(&self) -> Option<Base> {
+    self.state.as_ref().map(|state| state.base)
+  }
+
+  /// Decodes the next chunk of the string.
+  ///
+  /// Fails if the chunk contains a byte that isn't valid in the base, or if
+  /// the input decodes to more bytes than any CID, with
+  /// [`Error::TrailingBytes`] at the maximum CID length. Once it failed,
+  /// the decoder rejects all further input.
+  pub fn push(&mut self, chunk: &[u8]) -> Result<()> {
+    if self.failed {
+      return Err(invalid());
+    }
+    let result = chunk.iter().try_for_each(|byte| self.push_byte(*byte));
+    self.failed = result.is_err();
+    result
+  }
+
+  /// Ends the string and returns its CID.
+  ///
+  /// Fails with the errors of [`Cid::try_from`] for the complete string.
+  pub fn finish(self) -> Result<Cid<S>> {
+    if self.failed {
+      return Err(invalid());
+    }
+    let state = match &self.state {
+      Some(state) => state,
+      None => return Err(Error::InputTooShort),
+    };
+    // Like `decode_str`, a string needs a prefix and a symbol
+    if state.symbols + state.padding + usize::from(!state.v0) < 2 {
+      return Err(Error::InputTooShort);
+    }
+    let mut out = self.out;
+    let mut len = self.len;
+    match state.kind {
+      Kind::Identity => {}
+      Kind::Bits { bits, group } => {
+        // Leftover bits must be the zero bits of a partial last symbol
+        if state.acc_len >= bits || state.acc != 0 {
+          return Err(invalid());
+        }
+        if let Some(group) = group {
+          let symbols = state.symbols + state.padding;
+          if state.padding >= group || symbols % group != 0 {
+            return Err(invalid());
+          }
+        }
+      }
+      Kind::BigInt(_) => {
+        if state.zeros + len > MAX_LEN {
+          return Err(Error::TrailingBytes { offset: MAX_LEN });
+        }
+        out[..len].reverse();
+        out.copy_within(..len, state.zeros);
+        out[..state.zeros].fill(0);
+        len += state.zeros;
+      }
+      Kind::Base45 => match state.acc_len {
+        0 => {}
+        2 if state.acc <= 0xff => {
+          Self::push_out(&mut out, &mut len, state.acc as u8)?;
+        }
+        _ => return Err(invalid()),
+      },
+    }
+    let bytes = &out[..len];
+    if state.v0 && !Version::is_v0_binary(bytes) {
+      return Err(Error::MultibaseError(multibase::Error::UnknownBase('Q')));
+    }
+    Cid::try_from(bytes)
+  }
+
+  /// Decodes the next byte of the string.
+  fn push_byte(&mut self, byte: u8) -> Result<()> {
+    if self.state.is_none() {
+      let state = Self::start(byte)?;
+      let v0 = state.v0;
+      self.state = Some(state);
+      // The first symbol of a CIDv0 is data, a multibase prefix isn't
+      if !v0 {
+        return Ok(());
+      }
+    }
+    let state = match &mut self.state {
+      Some(state) => state,
+      None => return Err(invalid()),
+    };
+    if let Kind::Identity = state.kind {
+      state.symbols += 1;
+      return Self::push_out(&mut self.out, &mut self.len, byte);
+    }
+    let c = char::from(byte);
+    if !byte.is_ascii() || !bases::is_alphabet_char(state.base, c) {
+      return Err(invalid());
+    }
+    if byte == b'=' {
+      state.padding += 1;
+      return Ok(());
+    }
+    if state.padding > 0 {
+      return Err(invalid());
+    }
+    let value = bases::value(state.alphabet, byte).ok_or_else(invalid)?;
+    state.symbols += 1;
+    match state.kind {
+      Kind::Identity => Ok(()),
+      Kind::Bits { bits, .. } => {
+        state.acc = (state.acc << bits) | value;
+        state.acc_len += bits;
+        if state.acc_len < 8 {
+          return Ok(());
+        }
+        state.acc_len -= 8;
+        let byte = (state.acc >> state.acc_len) as u8;
+        state.acc &= (1 << state.acc_len) - 1;
+        Self::push_out(&mut self.out, &mut self.len, byte)
+      }
+      Kind::BigInt(radix) => {
+        // Leading zero digits are leading zero bytes
+        if value == 0 && self.len == 0 {
+          state.zeros += 1;
+          return Ok(());
+        }
+        let mut carry = value;
+        for digit in &mut self.out[..self.len] {
+          carry += u32::from(*digit) * radix;
+          *digit = carry as u8;
+          carry >>= 8;
+        }
+        while carry > 0 {
+          Self::push_out(&mut self.out, &mut self.len, carry as u8)?;
+          carry >>= 8;
+        }
+        if state.zeros + self.len > MAX_LEN {
+          return Err(Error::TrailingBytes { offset: MAX_LEN });
+        }
+        Ok(())
+      }
+      Kind::Base45 => {
+        state.acc += value * 45u32.pow(state.acc_len);
+        state.acc_len += 1;
+        if state.acc_len < 3 {
+          return Ok(());
+        }
+        let group = state.acc;
+        state.acc = 0;
+        state.acc_len = 0;
+        if group > 0xffff {
+          return Err(invalid());
+        }
+        Self::push_out(&mut self.out, &mut self.len, (group >> 8) as u8)?;
+        Self::push_out(&mut self.out, &mut self.len, group as u8)
+      }
+    }
+  }
+
+  /// Starts decoding a string that starts with `byte`.
+  fn start(byte: u8) -> Result<State> {
+    let v0 = byte == b'Q';
+    let base =
+      if v0 { Base::Base58Btc } else { Base::from_code(char::from(byte))? };
+    if !bases::is_enabled(base) {
+      return Err(Error::UnsupportedBase(base));
+    }
+    let (kind, alphabet) = bases::kind(base)?;
+    Ok(State {
+      base,
+      kind,
+      alphabet,
+      v0,
+      acc: 0,
+      acc_len: 0,
+      symbols: 0,
+      padding: 0,
+      zeros: 0,
+    })
+  }
+
+  /// Appends a decoded byte, failing once the output is longer than any CID.
+  fn push_out(
+    out: &mut [u8; MAX_LEN],
+    len: &mut usize,
+    byte: u8,
+  ) -> Result<()> {
+    match out.get_mut(*len) {
+      Some(slot) => {
+        *slot = byte;
+        *len += 1;
+        Ok(())
+      }
+      None => Err(Error::TrailingBytes { offset: MAX_LEN }),
+    }
+  }
+}
+
+#[cfg(all(test, feature = "all-bases"))]
+mod tests {
+  use sp_std::convert::TryFrom;
+
+  use alloc::format;
+  use multibase::Base;
+  use sp_multihash::{Code, MultihashDigest, U64};
+
+  use super::CidStrDecoder;
+  use crate::{cid::Cid, error::Result, weights::BASES};
+
+  fn decode(s: &str, chunk_len: usize) -> Result<Cid<U64>> {
+    let mut decoder = CidStrDecoder::new();
+    s.as_bytes().chunks(chunk_len).try_for_each(|c| decoder.push(c))?;
+    decoder.finish()
+  }
+
+  #[test]
+  fn matches_whole_string_decoding() {
+    let cids = [
+      Cid::<U64>::new_v1(0x55, Code::Sha2_256.digest(b"foo")),
+      Cid::new_v1(0x3fff, Code::Sha2_512.digest(b"bar")),
+      Cid::new_v1(0, sp_multihash::Multihash::wrap(0, &[0; 3]).unwrap()),
+    ];
+    for cid in &cids {
+      for base in BASES.iter().filter(|b| **b != Base::Base256Emoji) {
+        let s = match cid.to_string_of_base(*base) {
+          Ok(s) => s,
+          Err(_) => continue,
+        };
+        for chunk_len in [1, 2, 7] {
+          assert_eq!(decode(&s, chunk_len).unwrap(), *cid, "{}", s);
+        }
+        // Truncated and corrupted strings fail like the whole string
+        for len in 1..s.len() {
+          if !s.is_char_boundary(len) {
+            continue;
+          }
+          let truncated = &s[..len];
+          let expected = Cid::<U64>::try_from(truncated);
+          assert_eq!(decode(truncated, 3).is_ok(), expected.is_ok(), "{}", s);
+        }
+        // Identity strings can contain any symbol
+        if *base != Base::Identity {
+          let corrupted = format!("{}!", s);
+          assert!(decode(&corrupted, 3).is_err(), "{}", corrupted);
+        }
+      }
+    }
+  }
+}
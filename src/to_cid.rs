@@ -0,0 +1,63 @@
+//! Content addressing of values.
+//!
+//! Types that are stored as blocks can implement [`ToCid`] to address their
+//! serialized form. Byte slices and strings address their own bytes.
+use sp_std::vec::Vec;
+
+use alloc::string::String;
+use sp_multihash::{MultihashDigest, Size};
+
+use crate::{cid::Cid, error::Result};
+
+/// A value that can be addressed by a CID.
+pub trait ToCid {
+  /// Returns the CIDv1 of the serialized value, encoded with `codec` and
+  /// hashed with `code`.
+  ///
+  /// Fails if the digest doesn't fit into the allocated size `S`.
+  fn to_cid<S: Size, C: MultihashDigest>(
+    &self,
+    codec: u64,
+    code: C,
+  ) -> Result<Cid<S>>;
+}
+
+impl ToCid for [u8] {
+  fn to_cid<S: Size, C: MultihashDigest>(
+    &self,
+    codec: u64,
+    code: C,
+  ) -> Result<Cid<S>> {
+    Cid::from_data(codec, code, self)
+  }
+}
+
+impl ToCid for Vec<u8> {
+  fn to_cid<S: Size, C: MultihashDigest>(
+    &self,
+    codec: u64,
+    code: C,
+  ) -> Result<Cid<S>> {
+    self.as_slice().to_cid(codec, code)
+  }
+}
+
+impl ToCid for str {
+  fn to_cid<S: Size, C: MultihashDigest>(
+    &self,
+    codec: u64,
+    code: C,
+  ) -> Result<Cid<S>> {
+    self.as_bytes().to_cid(codec, code)
+  }
+}
+
+impl ToCid for String {
+  fn to_cid<S: Size, C: MultihashDigest>(
+    &self,
+    codec: u64,
+    code: C,
+  ) -> Result<Cid<S>> {
+    self.as_bytes().to_cid(codec, code)
+  }
+}
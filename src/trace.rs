@@ -0,0 +1,30 @@
+//! Optional `tracing` events for rejected input.
+//!
+//! With the `tracing` feature, parse failures and rejections of the decoding
+//! limits are reported as debug events, with the error kind and the input
+//! length. Services can diagnose malformed CID traffic without wrapping every
+//! call site. Without the feature the functions compile to nothing.
+use crate::error::Result;
+
+/// Reports the error of a failed parse of an input of `input_len` bytes.
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+#[inline]
+pub(crate) fn parse_result<T>(
+  result: Result<T>,
+  input_len: usize,
+) -> Result<T> {
+  #[cfg(feature = "tracing")]
+  if let Err(err) = &result {
+    tracing::debug!(error = ?err, input_len, "failed to parse CID");
+  }
+  result
+}
+
+/// Reports an input of `input_len` bytes that was rejected by a limit.
+#[cfg(feature = "scale-codec")]
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+#[inline]
+pub(crate) fn rejected(reason: &'static str, input_len: usize) {
+  #[cfg(feature = "tracing")]
+  tracing::debug!(reason, input_len, "rejected CID");
+}
@@ -0,0 +1,47 @@
+//! `ufmt` support for CIDs, behind the `ufmt` feature.
+//!
+//! Firmware that logs with `ufmt` can print CIDs in their default string form,
+//! which is written without `core::fmt` formatting and without allocating.
+use core::fmt;
+
+use sp_multihash::Size;
+use ufmt::{uDisplay, uWrite, Formatter};
+
+use crate::cid::Cid;
+
+/// Passes the strings of the CID encoders on to a `ufmt` formatter, keeping
+/// the error of its writer.
+struct Adapter<'a, 'w, W: uWrite + ?Sized> {
+  /// The formatter written to.
+  f: &'a mut Formatter<'w, W>,
+  /// The first error of the writer.
+  error: Option<W::Error>,
+}
+
+impl<W: uWrite + ?Sized> fmt::Write for Adapter<'_, '_, W> {
+  fn write_str(&mut self, s: &str) -> fmt::Result {
+    match self.f.write_str(s) {
+      Ok(()) => Ok(()),
+      Err(err) => {
+        self.error = Some(err);
+        Err(fmt::Error)
+      }
+    }
+  }
+}
+
+/// Writes the same string as `Display`.
+impl<S: Size> uDisplay for Cid<S> {
+  fn fmt<W: uWrite + ?Sized>(
+    &self,
+    f: &mut Formatter<'_, W>,
+  ) -> Result<(), W::Error> {
+    let mut adapter = Adapter { f, error: None };
+    // The encoders only fail if the writer does
+    let _ = self.fmt_string(&mut adapter, false);
+    match adapter.error {
+      Some(err) => Err(err),
+      None => Ok(()),
+    }
+  }
+}
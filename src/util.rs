@@ -0,0 +1,362 @@
+//! Bulk operations on CID collections.
+//!
+//! Provider lists and block indexes hold many CIDs that share long prefixes,
+//! e.g. every CIDv1 of raw SHA2-256 blocks starts with the same four bytes.
+//! Sorting them with [`Ord`] encodes both CIDs of every comparison. The
+//! functions in this module encode every CID once and radix sort the
+//! encodings instead.
+//!
+//! [`union`], [`intersection`] and [`difference`] compare two sorted sets of
+//! CIDs, e.g. the pin sets of two nodes, while streaming them.
+//!
+//! With the `rayon` feature, [`verify_all`] checks the blocks of many CIDs in
+//! parallel.
+use sp_std::{cmp::Ordering, iter::Peekable, vec::Vec};
+
+use unsigned_varint::encode as varint_encode;
+
+use sp_multihash::Size;
+
+use crate::cid::{Cid, MAX_LEN};
+
+/// Buckets of at most this many keys are sorted by comparison.
+const SMALL_BUCKET: usize = 32;
+
+/// Sorts CIDs in the order of [`Ord`] and removes duplicates.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{util, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let foo = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let bar = Cid::new_v1(0x55, Code::Sha2_256.digest(b"bar"));
+/// let mut cids = vec![foo, bar, foo];
+/// util::sort_dedup(&mut cids);
+/// let mut expected = vec![foo, bar];
+/// expected.sort();
+/// assert_eq!(cids, expected);
+/// ```
+pub fn sort_dedup<S: Size>(cids: &mut Vec<Cid<S>>) {
+  sort_dedup_by_key(cids, |cid, out| {
+    let mut buf = [0u8; MAX_LEN];
+    let len = cid.write_to_slice(&mut buf);
+    out.extend_from_slice(&buf[..len]);
+  })
+}
+
+/// Sorts CIDs by their multihash and keeps only the first CID of every
+/// multihash, no matter its version and codec.
+///
+/// CIDs of the same content under different codecs, e.g. a CIDv0 and the raw
+/// CIDv1 of a block, are kept once.
+pub fn dedup_by_digest<S: Size>(cids: &mut Vec<Cid<S>>) {
+  sort_dedup_by_key(cids, |cid, out| {
+    let hash = cid.hash();
+    let mut code_buf = varint_encode::u64_buffer();
+    out.extend_from_slice(varint_encode::u64(hash.code(), &mut code_buf));
+    out.push(hash.size());
+    out.extend_from_slice(hash.digest());
+  })
+}
+
+/// The key of a CID, a range of the shared key buffer.
+#[derive(Clone, Copy)]
+struct Key {
+  /// The position of the CID in the input.
+  index: usize,
+  /// The start of the key in the buffer.
+  start: usize,
+  /// The end of the key in the buffer.
+  end: usize,
+}
+
+impl Key {
+  /// Returns the bytes of the key, starting at `depth`.
+  fn bytes<'a>(&self, buf: &'a [u8], depth: usize) -> &'a [u8] {
+    &buf[(self.start + depth).min(self.end)..self.end]
+  }
+
+  /// Returns the bucket of the key at `depth`, 0 if the key is shorter and
+  /// one more than the byte otherwise.
+  fn bucket(&self, buf: &[u8], depth: usize) -> usize {
+    self.bytes(buf, depth).first().map_or(0, |byte| usize::from(*byte) + 1)
+  }
+}
+
+/// Sorts CIDs by the keys `write_key` appends to a buffer and keeps the first
+/// CID of every key.
+fn sort_dedup_by_key<S: Size, F>(cids: &mut Vec<Cid<S>>, write_key: F)
+where
+  F: Fn(&Cid<S>, &mut Vec<u8>),
+{
+  let mut buf = Vec::with_capacity(cids.len() * 40);
+  let mut keys = Vec::with_capacity(cids.len());
+  for (index, cid) in cids.iter().enumerate() {
+    let start = buf.len();
+    write_key(cid, &mut buf);
+    keys.push(Key { index, start, end: buf.len() });
+  }
+  radix_sort(&buf, &mut keys, 0);
+
+  let mut slots: Vec<Option<Cid<S>>> = cids.drain(..).map(Some).collect();
+  let mut last: Option<&[u8]> = None;
+  for key in &keys {
+    let bytes = key.bytes(&buf, 0);
+    if last == Some(bytes) {
+      continue;
+    }
+    last = Some(bytes);
+    if let Some(cid) = slots[key.index].take() {
+      cids.push(cid);
+    }
+  }
+}
+
+/// Stably sorts `keys` by their bytes from `depth` on, with a most
+/// significant byte first radix sort.
+fn radix_sort(buf: &[u8], keys: &mut [Key], depth: usize) {
+  if keys.len() <= SMALL_BUCKET {
+    keys.sort_by(|a, b| cmp_keys(buf, a, b, depth));
+    return;
+  }
+  let mut counts = [0usize; 257];
+  for key in keys.iter() {
+    counts[key.bucket(buf, depth)] += 1;
+  }
+  let mut starts = [0usize; 257];
+  let mut sum = 0;
+  for (start, count) in starts.iter_mut().zip(counts.iter()) {
+    *start = sum;
+    sum += count;
+  }
+  let mut sorted = keys.to_vec();
+  let mut next = starts;
+  for key in keys.iter() {
+    let bucket = key.bucket(buf, depth);
+    sorted[next[bucket]] = *key;
+    next[bucket] += 1;
+  }
+  keys.copy_from_slice(&sorted);
+  // Keys in bucket 0 ended and are equal
+  for bucket in 1..counts.len() {
+    if counts[bucket] > 1 {
+      let range = starts[bucket]..starts[bucket] + counts[bucket];
+      radix_sort(buf, &mut keys[range], depth + 1);
+    }
+  }
+}
+
+/// Compares the bytes of two keys from `depth` on.
+fn cmp_keys(buf: &[u8], a: &Key, b: &Key, depth: usize) -> Ordering {
+  a.bytes(buf, depth).cmp(b.bytes(buf, depth))
+}
+
+/// An iterator over the CIDs of either of two sorted iterators, see
+/// [`union`].
+pub struct Union<A: Iterator, B: Iterator> {
+  /// The first set.
+  a: Peekable<A>,
+  /// The second set.
+  b: Peekable<B>,
+}
+
+/// Returns the CIDs that are in `a` or `b`, in ascending order.
+///
+/// The set operations of this module merge two iterators over CIDs in the
+/// order of [`Ord`] without duplicates, e.g. the output of [`sort_dedup`] or
+/// a `BTreeSet`, and only hold the next CID of each side. Unsorted input
+/// gives unspecified but safe results. The results are sorted iterators
+/// again, so operations can be chained.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{util, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let mut cids: Vec<Cid> = (0u8..4)
+///   .map(|i| Cid::new_v1(0x55, Code::Sha2_256.digest(&[i])))
+///   .collect();
+/// util::sort_dedup(&mut cids);
+/// let local = vec![cids[0], cids[1], cids[2]];
+/// let remote = vec![cids[1], cids[2], cids[3]];
+///
+/// let all: Vec<Cid> = util::union(local.clone(), remote.clone()).collect();
+/// assert_eq!(all, cids);
+/// let both: Vec<Cid> =
+///   util::intersection(local.clone(), remote.clone()).collect();
+/// assert_eq!(both, [cids[1], cids[2]]);
+/// let missing: Vec<Cid> = util::difference(remote, local).collect();
+/// assert_eq!(missing, [cids[3]]);
+/// ```
+pub fn union<S, A, B>(a: A, b: B) -> Union<A::IntoIter, B::IntoIter>
+where
+  S: Size,
+  A: IntoIterator<Item = Cid<S>>,
+  B: IntoIterator<Item = Cid<S>>,
+{
+  Union { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+impl<S, A, B> Iterator for Union<A, B>
+where
+  S: Size,
+  A: Iterator<Item = Cid<S>>,
+  B: Iterator<Item = Cid<S>>,
+{
+  type Item = Cid<S>;
+
+  fn next(&mut self) -> Option<Cid<S>> {
+    let order = match (self.a.peek(), self.b.peek()) {
+      (Some(a), Some(b)) => a.cmp(b),
+      (Some(_), None) => Ordering::Less,
+      (None, _) => Ordering::Greater,
+    };
+    match order {
+      Ordering::Less => self.a.next(),
+      Ordering::Greater => self.b.next(),
+      Ordering::Equal => {
+        self.b.next();
+        self.a.next()
+      }
+    }
+  }
+}
+
+/// An iterator over the CIDs of both of two sorted iterators, see
+/// [`intersection`].
+pub struct Intersection<A: Iterator, B: Iterator> {
+  /// The first set.
+  a: Peekable<A>,
+  /// The second set.
+  b: Peekable<B>,
+}
+
+/// Returns the CIDs that are in both `a` and `b`, in ascending order.
+///
+/// See [`union`] for the requirements on the input.
+pub fn intersection<S, A, B>(
+  a: A,
+  b: B,
+) -> Intersection<A::IntoIter, B::IntoIter>
+where
+  S: Size,
+  A: IntoIterator<Item = Cid<S>>,
+  B: IntoIterator<Item = Cid<S>>,
+{
+  Intersection { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+impl<S, A, B> Iterator for Intersection<A, B>
+where
+  S: Size,
+  A: Iterator<Item = Cid<S>>,
+  B: Iterator<Item = Cid<S>>,
+{
+  type Item = Cid<S>;
+
+  fn next(&mut self) -> Option<Cid<S>> {
+    loop {
+      match self.a.peek()?.cmp(self.b.peek()?) {
+        Ordering::Less => {
+          self.a.next();
+        }
+        Ordering::Greater => {
+          self.b.next();
+        }
+        Ordering::Equal => {
+          self.b.next();
+          return self.a.next();
+        }
+      }
+    }
+  }
+}
+
+/// An iterator over the CIDs of a sorted iterator that aren't in another
+/// one, see [`difference`].
+pub struct Difference<A: Iterator, B: Iterator> {
+  /// The set the CIDs are taken from.
+  a: Peekable<A>,
+  /// The set of the CIDs that are left out.
+  b: Peekable<B>,
+}
+
+/// Returns the CIDs that are in `a` but not in `b`, in ascending order.
+///
+/// `b` is only read up to the last CID of `a`. See [`union`] for the
+/// requirements on the input.
+pub fn difference<S, A, B>(a: A, b: B) -> Difference<A::IntoIter, B::IntoIter>
+where
+  S: Size,
+  A: IntoIterator<Item = Cid<S>>,
+  B: IntoIterator<Item = Cid<S>>,
+{
+  Difference { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+impl<S, A, B> Iterator for Difference<A, B>
+where
+  S: Size,
+  A: Iterator<Item = Cid<S>>,
+  B: Iterator<Item = Cid<S>>,
+{
+  type Item = Cid<S>;
+
+  fn next(&mut self) -> Option<Cid<S>> {
+    loop {
+      let a = self.a.peek()?;
+      let order = match self.b.peek() {
+        Some(b) => a.cmp(b),
+        None => Ordering::Less,
+      };
+      match order {
+        Ordering::Less => return self.a.next(),
+        Ordering::Greater => {
+          self.b.next();
+        }
+        Ordering::Equal => {
+          self.a.next();
+          self.b.next();
+        }
+      }
+    }
+  }
+}
+
+/// Hashes the blocks of many CIDs in parallel and returns the indexes of the
+/// pairs whose block doesn't match the CID, in ascending order.
+///
+/// Blocks are hashed with the default multihash code table. A CID whose hash
+/// function isn't in the table fails. Digests are matched like with
+/// [`Cid::verify`], so short truncated digests fail.
+///
+/// # Example
+///
+/// ```
+/// use sp_cid::{util, Cid};
+/// use sp_multihash::{Code, MultihashDigest};
+///
+/// let cid = Cid::new_v1(0x55, Code::Sha2_256.digest(b"foo"));
+/// let pairs = [(cid, &b"foo"[..]), (cid, &b"bar"[..])];
+/// assert_eq!(util::verify_all(&pairs), vec![1]);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn verify_all<S: Size>(pairs: &[(Cid<S>, &[u8])]) -> Vec<usize> {
+  use rayon::prelude::*;
+
+  pairs
+    .par_iter()
+    .enumerate()
+    .filter(|(_, (cid, block))| !verifies(cid, block))
+    .map(|(index, _)| index)
+    .collect()
+}
+
+/// Checks whether `block` hashes to the digest of `cid`.
+#[cfg(feature = "rayon")]
+fn verifies<S: Size>(cid: &Cid<S>, block: &[u8]) -> bool {
+  cid.verify(block).unwrap_or(false)
+}
@@ -1,13 +1,17 @@
-use core::convert::TryFrom;
+use core::{convert::TryFrom, fmt};
 
-use crate::error::{Error, Result};
+use multibase::Base;
+
+use crate::{
+  bases,
+  error::{Error, Result},
+};
 
 /// The version of the CID.
+///
+/// The SCALE and serde encodings of a version are its number as a `u64`, see
+/// [`Version::to_u64`].
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
-#[cfg_attr(feature = "scale-codec", derive(parity_scale_codec::Decode))]
-#[cfg_attr(feature = "scale-codec", derive(parity_scale_codec::Encode))]
-#[cfg_attr(feature = "serde-codec", derive(serde::Deserialize))]
-#[cfg_attr(feature = "serde-codec", derive(serde::Serialize))]
 pub enum Version {
   /// CID version 0.
   V0,
@@ -16,11 +20,37 @@ pub enum Version {
 }
 
 impl Version {
+  /// All supported versions, in ascending order.
+  pub const SUPPORTED: &'static [Version] = &[Version::V0, Version::V1];
+
+  /// Returns the number of the version.
+  pub const fn to_u64(self) -> u64 {
+    match self {
+      Version::V0 => 0,
+      Version::V1 => 1,
+    }
+  }
+
+  /// Check if `raw` is the number of a supported version.
+  pub fn is_supported(raw: u64) -> bool {
+    Self::try_from(raw).is_ok()
+  }
+
+  /// Returns an iterator over all supported versions, in ascending order.
+  pub fn iter() -> impl Iterator<Item = Version> {
+    Self::SUPPORTED.iter().copied()
+  }
+
   /// Check if the version of `data` string is CIDv0.
+  ///
+  /// The string is decoded and checked to be a SHA2-256 multihash.
   pub fn is_v0_str(data: &str) -> bool {
     // v0 is a Base58Btc encoded sha hash, so it has
     // fixed length and always begins with "Qm"
-    data.len() == 46 && data.starts_with("Qm")
+    data.len() == 46
+      && data.starts_with("Qm")
+      && bases::decode(Base::Base58Btc, data)
+        .is_ok_and(|bytes| Self::is_v0_binary(&bytes))
   }
 
   /// Check if the version of `data` bytes is CIDv0.
@@ -29,8 +59,8 @@ impl Version {
   }
 }
 
-/// Convert a number to the matching version, or `Error` if no valid version is
-/// matching.
+/// Convert a number to the matching version, or `Error::UnsupportedVersion` if
+/// no valid version is matching.
 impl TryFrom<u64> for Version {
   type Error = Error;
 
@@ -38,16 +68,30 @@ impl TryFrom<u64> for Version {
     match raw {
       0 => Ok(Self::V0),
       1 => Ok(Self::V1),
-      _ => Err(Error::InvalidCidVersion),
+      _ => Err(Error::UnsupportedVersion(raw)),
     }
   }
 }
 
+impl TryFrom<u8> for Version {
+  type Error = Error;
+
+  fn try_from(raw: u8) -> Result<Self> {
+    Self::try_from(u64::from(raw))
+  }
+}
+
 impl From<Version> for u64 {
   fn from(ver: Version) -> u64 {
-    match ver {
-      Version::V0 => 0,
-      Version::V1 => 1,
+    ver.to_u64()
+  }
+}
+
+impl fmt::Display for Version {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Version::V0 => f.pad("CIDv0"),
+      Version::V1 => f.pad("CIDv1"),
     }
   }
 }
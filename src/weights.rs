@@ -0,0 +1,132 @@
+//! Deterministic cost bounds for runtime weight calculation.
+//!
+//! Runtimes that validate CIDs inside extrinsics need weights that are known
+//! before the CID is parsed. The functions in this module return upper bounds
+//! of the work done by the parse and encode paths, counted in operations,
+//! where one operation is one byte or character touched by a linear pass.
+//! Benchmarking code can measure the cost of a single operation and multiply.
+//!
+//! All bounds only depend on the input length, the digest size `S` and, for
+//! strings, the multibase.
+use sp_std::cmp;
+
+use multibase::Base;
+use sp_multihash::Size;
+
+use crate::cid::Cid;
+
+/// All multibases supported by the string parser.
+pub(crate) const BASES: [Base; 25] = [
+  Base::Identity,
+  Base::Base2,
+  Base::Base8,
+  Base::Base10,
+  Base::Base16Lower,
+  Base::Base16Upper,
+  Base::Base32Lower,
+  Base::Base32Upper,
+  Base::Base32PadLower,
+  Base::Base32PadUpper,
+  Base::Base32HexLower,
+  Base::Base32HexUpper,
+  Base::Base32HexPadLower,
+  Base::Base32HexPadUpper,
+  Base::Base32Z,
+  Base::Base36Lower,
+  Base::Base36Upper,
+  Base::Base45,
+  Base::Base58Flickr,
+  Base::Base58Btc,
+  Base::Base64,
+  Base::Base64Pad,
+  Base::Base64Url,
+  Base::Base64UrlPad,
+  Base::Base256Emoji,
+];
+
+/// Returns whether `base` is implemented with big integer arithmetic, which
+/// is quadratic in the input length.
+fn is_quadratic(base: Base) -> bool {
+  matches!(
+    base,
+    Base::Base10
+      | Base::Base36Lower
+      | Base::Base36Upper
+      | Base::Base58Flickr
+      | Base::Base58Btc
+  )
+}
+
+/// Upper bound of the number of characters `len` bytes encode to in `base`,
+/// excluding the multibase prefix.
+pub fn max_base_len(base: Base, len: usize) -> usize {
+  match base {
+    Base::Identity | Base::Base256Emoji => len,
+    Base::Base2 => len * 8,
+    Base::Base8 => (len * 8).div_ceil(3),
+    Base::Base10 => len * 3,
+    Base::Base16Lower | Base::Base16Upper => len * 2,
+    Base::Base32PadLower
+    | Base::Base32PadUpper
+    | Base::Base32HexPadLower
+    | Base::Base32HexPadUpper => len.div_ceil(5) * 8,
+    Base::Base32Lower
+    | Base::Base32Upper
+    | Base::Base32HexLower
+    | Base::Base32HexUpper
+    | Base::Base32Z => (len * 8).div_ceil(5),
+    Base::Base36Lower
+    | Base::Base36Upper
+    | Base::Base58Flickr
+    | Base::Base58Btc => len * 2,
+    Base::Base45 => len.div_ceil(2) * 3,
+    Base::Base64Pad | Base::Base64UrlPad => len.div_ceil(3) * 4,
+    Base::Base64 | Base::Base64Url => (len * 8).div_ceil(6),
+  }
+}
+
+/// Operations of converting between `bytes` bytes and `chars` characters in
+/// `base`, in either direction.
+fn base_ops(base: Base, bytes: usize, chars: usize) -> u64 {
+  if is_quadratic(base) {
+    (bytes as u64) * (chars as u64)
+  } else {
+    (bytes + chars) as u64
+  }
+}
+
+/// Operations of decoding a binary CID from an input of `len` bytes.
+///
+/// Decoding never reads past [`Cid::MAX_BINARY_LEN`] bytes and initializes
+/// the digest of the allocated size once.
+pub fn decode_bytes<S: Size>(len: usize) -> u64 {
+  (cmp::min(len, Cid::<S>::MAX_BINARY_LEN) + S::USIZE) as u64
+}
+
+/// Operations of encoding a CID to its binary form.
+pub fn encode_bytes<S: Size>() -> u64 {
+  Cid::<S>::MAX_BINARY_LEN as u64
+}
+
+/// Operations of decoding a CID from a string of `len` characters, encoded
+/// in `base`.
+pub fn decode_str_with_base<S: Size>(base: Base, len: usize) -> u64 {
+  // Every base uses at least one character per byte.
+  base_ops(base, len, len) + decode_bytes::<S>(len)
+}
+
+/// Operations of decoding a CID from a string of `len` characters, in the
+/// most expensive multibase.
+pub fn decode_str<S: Size>(len: usize) -> u64 {
+  BASES
+    .iter()
+    .map(|base| decode_str_with_base::<S>(*base, len))
+    .max()
+    .unwrap_or_default()
+}
+
+/// Operations of encoding a CID to a string in `base`.
+pub fn encode_str<S: Size>(base: Base) -> u64 {
+  let bytes = Cid::<S>::MAX_BINARY_LEN;
+  encode_bytes::<S>() + base_ops(base, bytes, max_base_len(base, bytes))
+}
@@ -0,0 +1,130 @@
+//! Checks that the binary CID APIs don't allocate.
+//!
+//! The test counts the allocations of its own thread with a global
+//! allocator, so it lives in its own test binary.
+use std::{
+  alloc::{GlobalAlloc, Layout, System},
+  cell::Cell,
+  convert::TryFrom,
+  fmt::Write,
+};
+
+use multibase::Base;
+use sp_cid::{Cid, CidEncoder, CidGeneric, CidPrefix, CidRef, Version};
+use sp_multihash::{Code, MultihashDigest, U32};
+
+struct CountingAlloc;
+
+thread_local! {
+  static ALLOCS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+  unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+    let _ = ALLOCS.try_with(|allocs| allocs.set(allocs.get() + 1));
+    System.alloc(layout)
+  }
+
+  unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+    System.dealloc(ptr, layout)
+  }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the number of allocations of `f`.
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+  let before = ALLOCS.with(Cell::get);
+  let result = f();
+  let after = ALLOCS.with(Cell::get);
+  drop(result);
+  after - before
+}
+
+#[test]
+fn binary_apis_dont_allocate() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let v1 = Cid::new_v1(0x55, hash);
+  let v0 = Cid::new_v0(hash).unwrap();
+  let bytes = v1.to_bytes();
+  let v0_bytes = v0.to_bytes();
+
+  // The counter sees allocations
+  assert!(allocations(|| v1.to_bytes()) > 0);
+
+  assert_eq!(allocations(|| Cid::new_v1(0x71, hash)), 0);
+  assert_eq!(allocations(|| Cid::new(Version::V0, 0x70, hash)), 0);
+  assert_eq!(allocations(|| Cid::try_from(&bytes[..]).unwrap()), 0);
+  assert_eq!(allocations(|| Cid::try_from(&v0_bytes[..]).unwrap()), 0);
+  assert_eq!(allocations(|| CidGeneric::<U32>::try_from(&bytes[..])), 0);
+  assert_eq!(allocations(|| Cid::try_from(&bytes[..3])), 0);
+  assert_eq!(allocations(|| Cid::from_byte_iter(bytes.iter().copied())), 0);
+  assert_eq!(allocations(|| CidRef::read(&bytes).unwrap()), 0);
+  assert_eq!(allocations(|| CidPrefix::read(&bytes).unwrap()), 0);
+  assert_eq!(
+    allocations(|| {
+      let mut buf = [0u8; Cid::MAX_BINARY_LEN];
+      let len = v1.encode_into(&mut buf).unwrap();
+      buf[..len] == bytes[..]
+    }),
+    0
+  );
+  assert_eq!(
+    allocations(|| (
+      v1.version(),
+      v1.codec(),
+      v1.hash().code(),
+      v1.hash().digest().len(),
+      v1.binary_len(),
+      v1 == v0,
+      v1.cmp(&v0),
+    )),
+    0
+  );
+}
+
+#[test]
+fn encoder_doesnt_allocate() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let v1 = Cid::new_v1(0x55, hash);
+  let v0 = Cid::new_v0(hash).unwrap();
+  let mut encoder = CidEncoder::with_capacity(Cid::MAX_BINARY_LEN);
+
+  assert_eq!(allocations(|| encoder.encode_bytes(&v1).len()), 0);
+  assert_eq!(
+    allocations(|| encoder.encode_str(&v0, Base::Base58Btc).unwrap().len()),
+    0
+  );
+  let mut bases = vec![
+    Base::Base32Lower,
+    Base::Base32Upper,
+    Base::Base36Lower,
+    Base::Base58Btc,
+  ];
+  if cfg!(feature = "all-bases") {
+    bases.extend([Base::Base16Lower, Base::Base45, Base::Base64Url]);
+  }
+  for base in bases {
+    let expected = v1.to_string_of_base(base).unwrap();
+    let mut encode = || encoder.encode_str(&v1, base).unwrap() == expected;
+    assert_eq!(allocations(&mut encode), 0, "{:?}", base);
+    assert!(encode());
+  }
+}
+
+#[test]
+fn cid_ref_formatting_doesnt_allocate() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  for cid in [Cid::new_v1(0x55, hash), Cid::new_v0(hash).unwrap()] {
+    let bytes = cid.to_bytes();
+    let (cid_ref, _) = CidRef::read(&bytes).unwrap();
+    let mut s = String::with_capacity(2 * Cid::MAX_STRING_LEN);
+    let format = |s: &mut String| {
+      write!(s, "{}", cid_ref).unwrap();
+      write!(s, "{:?}", cid_ref).unwrap();
+    };
+    assert_eq!(allocations(|| format(&mut s)), 0);
+    assert_eq!(s, format!("{}CidRef({})", cid, cid));
+  }
+}
@@ -4,9 +4,21 @@ use std::{
   str::FromStr,
 };
 
+use bytecursor::ByteCursor;
 use multibase::Base;
-use sp_cid::{Cid, CidGeneric, Error, Version};
-use sp_multihash::{derive::Multihash, typenum::U128, Code, MultihashDigest, Size};
+use sp_cid::{
+  cid_binary_len, cid_to_bytes, consts, detect_base, is_valid_cid_str, names,
+  parse_all, parse_cid_list, render_table, transcode_base, AUDIT_RECORD_VERSION,
+  AnyCid, Block, ByDigest, CacheKey, Cid, CidBase, CidBytes, CidDiff, CidDyn,
+  CidEncoder, CidGeneric, CidHasher, CidOrPath, CidPolicy, CidPrefix, CidRef,
+  CidStats, CidStrDecoder, CidWrapper, DecodeContext, Distance, Error,
+  ErrorKind, HashedCid, HasherRegistry, Hint, IpnsName, Link, LinkTarget,
+  ParseOptions, PolicyCid, RawCid, ShardingScheme, ToCid, Version,
+};
+use sp_multihash::{
+  derive::Multihash, typenum::U128, Code, MultihashDigest, MultihashGeneric,
+  Sha2_512, Size, U32, U64,
+};
 
 const RAW: u64 = 0x55;
 const DAG_PB: u64 = 0x70;
@@ -54,13 +66,805 @@ fn from_str() {
   assert_eq!(cid.version(), Version::V0);
 
   let bad = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zIII".parse::<Cid>();
-  assert!(matches!(bad, Err(Error::ParsingError)));
+  assert!(matches!(bad, Err(Error::MultibaseError(_))));
+}
+
+#[test]
+fn cid_or_path() {
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let cid = Cid::from_str(s).unwrap();
+
+  let bare: CidOrPath<U64> = s.parse().unwrap();
+  assert_eq!(bare, CidOrPath::Cid(cid));
+  assert_eq!(bare.path(), "");
+
+  let inputs = [
+    format!("/ipfs/{}/dir/file", s),
+    format!("ipfs://{}/dir/file?download=true", s),
+    format!("https://ipfs.io/ipfs/{}/dir/file#top", s),
+  ];
+  for input in &inputs {
+    let path: CidOrPath<U64> = input.parse().unwrap();
+    assert_eq!(path.cid(), &cid);
+    assert_eq!(path.path(), "/dir/file");
+    assert_eq!(path.to_string(), inputs[0]);
+  }
+
+  let root: CidOrPath<U64> = format!("ipfs://{}", s).parse().unwrap();
+  assert_eq!(root, CidOrPath::Path { cid, path: String::new() });
+  assert!("/ipfs/".parse::<CidOrPath<U64>>().is_err());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_values() {
+  use serde_json::json;
+  use sp_cid::JsonStyle;
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  for cid in &[v0, v1] {
+    for style in &[JsonStyle::String, JsonStyle::DagJson] {
+      let value = cid.to_json_value(*style);
+      assert_eq!(Cid::from_json_value(&value).unwrap(), *cid);
+    }
+  }
+  assert_eq!(v1.to_json_value(JsonStyle::String), json!(v1.to_string()));
+
+  let s = v1.to_string();
+  for invalid in &[
+    json!(null),
+    json!([s]),
+    json!({ "/": s, "x": 1 }),
+    json!({ "/": { "bytes": "AAA" } }),
+    json!({ "cid": s }),
+  ] {
+    assert!(Cid::from_json_value(invalid).is_err());
+  }
+  assert!(Cid::from_json_value(&json!("bafy")).is_err());
+}
+
+#[test]
+fn qr_strings() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let qr = v0.to_qr_string();
+  assert!(qr.starts_with("BAFY"));
+  assert!(qr.chars().all(|c| matches!(c, 'A'..='Z' | '2'..='7')));
+  assert_eq!(qr, v0.to_string_of_base_upgrading(Base::Base32Upper).unwrap());
+  let v1: Cid = qr.parse().unwrap();
+  assert_eq!(v1, Cid::new_v1(DAG_PB, *v0.hash()));
+}
+
+#[test]
+fn ipns_names() {
+  let text = "k51qzi5uqu5dlvj2baxnqndepeb86cbk3ng7n3i46uzyxzyqj2xjonzllnv0v8";
+  let name: IpnsName = text.parse().unwrap();
+  assert_eq!(name.to_string(), text);
+  assert_eq!(name.as_cid().codec(), 0x72);
+  let base32 = name.to_base32_string();
+  assert!(base32.starts_with('b'));
+  assert_eq!(IpnsName::try_from(base32.as_str()).unwrap(), name);
+  assert_eq!(IpnsName::from_str(&text.to_uppercase()).unwrap(), name);
+  assert_eq!(IpnsName::from_hash(*name.hash()), name);
+  let cid = Cid::from(name.clone());
+  assert_eq!(IpnsName::try_from(cid).unwrap(), name);
+
+  let raw = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  assert_eq!(
+    IpnsName::new(raw),
+    Err(Error::UnexpectedCodec { expected: 0x72, found: RAW })
+  );
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  assert_eq!(
+    IpnsName::new(v0),
+    Err(Error::UnexpectedCodec { expected: 0x72, found: DAG_PB })
+  );
+  let base58 = cid.to_string_of_base(Base::Base58Btc).unwrap();
+  assert_eq!(
+    IpnsName::<U64>::from_str(&base58),
+    Err(Error::UnsupportedBase(Base::Base58Btc))
+  );
+}
+
+#[test]
+fn etags() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  for cid in &[v0, v1] {
+    let etag = cid.to_etag();
+    assert_eq!(etag, format!("\"{}\"", cid));
+    assert_eq!(Cid::from_etag(&etag).unwrap(), *cid);
+    assert_eq!(Cid::from_etag(&format!("W/{}", etag)).unwrap(), *cid);
+    assert!(cid.matches_etag(&etag));
+    assert!(cid.matches_etag("*"));
+  }
+  assert_eq!(
+    Cid::from_etag(&v1.to_string()),
+    Err(Error::MultibaseError(multibase::Error::InvalidBaseString))
+  );
+  let tag = format!("\"{}.dir-index-html\"", v1);
+  assert_eq!(Cid::from_etag(&tag).unwrap(), v1);
+  assert!(!v0.matches_etag(&v1.to_etag()));
+  assert!(v0.matches_etag(&format!("\"x\", W/{}", v0.to_etag())));
+  assert!(!v0.matches_etag(""));
+}
+
+#[test]
+fn dnslink_records() {
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let cid = Cid::from_str(s).unwrap();
+
+  let value = format!("dnslink=/ipfs/{}/dir/file", s);
+  let link = CidOrPath::<U64>::from_dnslink(&value).unwrap();
+  assert_eq!(link.cid(), &cid);
+  assert_eq!(link.path(), "/dir/file");
+  assert_eq!(link.to_dnslink(), value);
+  let bare = CidOrPath::<U64>::from_dnslink(&format!(" dnslink=/ipfs/{} ", s));
+  assert_eq!(bare.unwrap(), CidOrPath::Cid(cid));
+
+  for invalid in &[
+    format!("/ipfs/{}", s),
+    format!("dnslink=/ipns/{}", s),
+    format!("dnslink=ipfs://{}", s),
+    format!("dnslink=/ipfs/{}//file", s),
+    format!("dnslink=/ipfs/{}/dir?x=1", s),
+  ] {
+    assert_eq!(
+      CidOrPath::<U64>::from_dnslink(invalid),
+      Err(Error::InvalidDnsLink)
+    );
+  }
+  assert!(CidOrPath::<U64>::from_dnslink("dnslink=/ipfs/bafy").is_err());
+}
+
+#[test]
+fn valid_cid_str_precheck() {
+  let valid = [
+    "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n",
+    "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+    // Base32 ignores case
+    "bAfkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+    "zb2rhe5P4gXftAwvA4eXQ5HJwsER2owDyS9sKaQRRVQPn93bA",
+  ];
+  for s in &valid {
+    assert!(is_valid_cid_str(s), "{}", s);
+    assert!(Cid::from_str(s).is_ok());
+  }
+
+  let invalid = [
+    "",
+    "b",
+    "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1",
+    "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR10",
+    "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhv1",
+    "?afkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+  ];
+  for s in &invalid {
+    assert!(!is_valid_cid_str(s), "{}", s);
+    assert!(Cid::from_str(s).is_err());
+  }
+}
+
+#[test]
+fn detect_bases() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let s = v1.to_string();
+  assert_eq!(detect_base(&s).unwrap(), CidBase::Multibase(Base::Base32Lower));
+  assert_eq!(detect_base(&v0.to_string()).unwrap(), CidBase::V0);
+  assert_eq!(CidBase::V0.base(), Base::Base58Btc);
+  let base64 = multibase::encode(Base::Base64, v1.to_bytes());
+  assert_eq!(detect_base(&base64).unwrap().base(), Base::Base64);
+
+  assert_eq!(detect_base("").unwrap_err(), Error::InputTooShort);
+  assert_eq!(
+    detect_base("?bafk").unwrap_err(),
+    Error::MultibaseError(multibase::Error::UnknownBase('?'))
+  );
+}
+
+#[test]
+fn error_codes() {
+  let err = Cid::try_from(&[0x01][..]).unwrap_err();
+  assert_eq!(err.code(), 12);
+  assert!(matches!(
+    Error::from_code(err.code()),
+    Some(Error::UnexpectedEof { offset: 0 })
+  ));
+  for code in 1..=14 {
+    assert_eq!(Error::from_code(code).unwrap().code(), code);
+  }
+  assert!(Error::from_code(0).is_none());
+  for code in 16..=25 {
+    assert_eq!(Error::from_code(code).unwrap().code(), code);
+  }
+  assert!(Error::from_code(26).is_none());
+}
+
+#[test]
+fn v0_detection() {
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  assert!(Version::is_v0_str(v0));
+  let invalid = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zIII";
+  assert!(!Version::is_v0_str(invalid));
+  // Right length and prefix, but not a SHA2-256 multihash.
+  let not_v0 = format!("Qm{}", "z".repeat(44));
+  assert!(!Version::is_v0_str(&not_v0));
+  assert!(matches!(
+    Cid::try_from(not_v0.as_str()),
+    Err(Error::MultibaseError(_))
+  ));
+  // A CIDv0 followed by another character.
+  let long = format!("{}1", v0);
+  assert!(matches!(
+    Cid::try_from(long.as_str()),
+    Err(Error::MultibaseError(_))
+  ));
+
+  // CIDv0 bytes in a multibase are still read as a CIDv0.
+  let cid = Cid::try_from(v0).unwrap();
+  let base58 = format!("z{}", v0);
+  assert_eq!(Cid::try_from(base58.as_str()).unwrap(), cid);
+  let base32 = multibase::encode(Base::Base32Lower, cid.to_bytes());
+  assert_eq!(Cid::try_from(base32.as_str()).unwrap(), cid);
 }
 
 #[test]
 fn v0_error() {
   let bad = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zIII";
-  assert!(matches!(Cid::try_from(bad), Err(Error::ParsingError)));
+  assert!(matches!(Cid::try_from(bad), Err(Error::MultibaseError(_))));
+}
+
+#[test]
+fn parse_error_kinds() {
+  let bad = "zInvalidBase58!".parse::<Cid>();
+  assert!(matches!(
+    bad,
+    Err(Error::MultibaseError(multibase::Error::InvalidBaseString))
+  ));
+  let bad = "?bafk".parse::<Cid>();
+  assert!(matches!(
+    bad,
+    Err(Error::MultibaseError(multibase::Error::UnknownBase('?')))
+  ));
+
+  let err: Error = sp_multihash::Multihash::from_bytes(&[0x12])
+    .unwrap_err()
+    .into();
+  assert!(matches!(err, Error::ParsingError(_)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn error_sources() {
+  use std::error::Error as _;
+
+  let err = "?bafk".parse::<Cid>().unwrap_err();
+  let source = err.source().unwrap();
+  assert_eq!(source.to_string(), "Unknown base code: ?");
+
+  let err = Cid::try_from(&[0x01, 0x80][..]).unwrap_err();
+  assert!(err.source().is_none());
+  let mut overflow = vec![0x01];
+  overflow.extend_from_slice(&[0xff; 11]);
+  let err = Cid::try_from(overflow).unwrap_err();
+  assert!(matches!(err, Error::VarIntDecodeError(_)));
+  assert!(err.source().is_some());
+}
+
+#[test]
+fn error_equality() {
+  assert_eq!(
+    Cid::try_from(&[0x01][..]).unwrap_err(),
+    Error::UnexpectedEof { offset: 1 }
+  );
+  assert_ne!(
+    Error::UnexpectedEof { offset: 1 },
+    Error::UnexpectedEof { offset: 2 }
+  );
+  assert_ne!(
+    Error::UnexpectedEof { offset: 1 },
+    Error::InvalidMultihash { offset: 1 }
+  );
+  assert_eq!(
+    "?bafk".parse::<Cid>().unwrap_err(),
+    Error::MultibaseError(multibase::Error::UnknownBase('?'))
+  );
+  assert_eq!(
+    Error::ParsingError(sp_multihash::Error::InvalidSize(3)),
+    Error::ParsingError(sp_multihash::Error::InvalidSize(3))
+  );
+  assert_ne!(
+    Error::ParsingError(sp_multihash::Error::InvalidSize(3)),
+    Error::ParsingError(sp_multihash::Error::UnsupportedCode(3))
+  );
+}
+
+#[test]
+fn core_error_trait() {
+  let err: Box<dyn core::error::Error> = Box::new(Error::InputTooShort);
+  assert_eq!(err.to_string(), Error::InputTooShort.to_string());
+  assert!(err.source().is_none());
+}
+
+#[test]
+fn hash_ignores_allocated_size() {
+  use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+  };
+
+  fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+  }
+
+  let large = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let hash = MultihashGeneric::<U32>::wrap(0x12, large.hash().digest());
+  let small = CidGeneric::new_v1(RAW, hash.unwrap());
+  assert_eq!(hash_of(&small), hash_of(&large));
+}
+
+#[cfg(not(feature = "base64"))]
+#[test]
+fn canonical_bases_only() {
+  let v1 = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  for cid in &[v0, v1] {
+    assert_eq!(Cid::try_from(cid.to_string()).unwrap(), *cid);
+  }
+  assert!(matches!(
+    v1.to_string_of_base(Base::Base64),
+    Err(Error::UnsupportedBase(Base::Base64))
+  ));
+  assert!(matches!(
+    CidEncoder::new().encode_str(&v1, Base::Base64),
+    Err(Error::UnsupportedBase(Base::Base64))
+  ));
+  let base64 = multibase::encode(Base::Base64, v1.to_bytes());
+  assert!(matches!(
+    Cid::try_from(base64),
+    Err(Error::UnsupportedBase(Base::Base64))
+  ));
+}
+
+#[cfg(all(feature = "base64", not(feature = "base16")))]
+#[test]
+fn selected_bases_only() {
+  let v1 = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  let base64 = v1.to_string_of_base(Base::Base64).unwrap();
+  assert_eq!(Cid::try_from(base64).unwrap(), v1);
+  assert!(matches!(
+    v1.to_string_of_base(Base::Base16Lower),
+    Err(Error::UnsupportedBase(Base::Base16Lower))
+  ));
+  let base16 = multibase::encode(Base::Base16Lower, v1.to_bytes());
+  assert!(matches!(
+    Cid::try_from(base16),
+    Err(Error::UnsupportedBase(Base::Base16Lower))
+  ));
+}
+
+#[cfg(feature = "all-bases")]
+#[test]
+fn all_bases_round_trip() {
+  let bases = [
+    Base::Base2,
+    Base::Base8,
+    Base::Base10,
+    Base::Base16Lower,
+    Base::Base16Upper,
+    Base::Base32Lower,
+    Base::Base32Upper,
+    Base::Base32PadLower,
+    Base::Base32PadUpper,
+    Base::Base32HexLower,
+    Base::Base32HexUpper,
+    Base::Base32HexPadLower,
+    Base::Base32HexPadUpper,
+    Base::Base32Z,
+    Base::Base36Lower,
+    Base::Base36Upper,
+    Base::Base45,
+    Base::Base58Flickr,
+    Base::Base58Btc,
+    Base::Base64,
+    Base::Base64Pad,
+    Base::Base64Url,
+    Base::Base64UrlPad,
+    Base::Base256Emoji,
+  ];
+  let cid = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  for base in &bases {
+    let s = cid.to_string_of_base(*base).unwrap();
+    assert_eq!(Cid::try_from(s.as_str()).unwrap(), cid, "{:?}", base);
+  }
+  assert!(cid.to_string_of_base(Base::Base36Lower).unwrap().starts_with('k'));
+  assert!(cid.to_string_of_base(Base::Base256Emoji).unwrap().starts_with('🚀'));
+
+  assert!(matches!(
+    cid.to_string_of_base(Base::Identity),
+    Err(Error::UnsupportedBase(Base::Identity))
+  ));
+  let mut encoder = CidEncoder::new();
+  assert!(matches!(
+    encoder.encode_str(&cid, Base::Identity),
+    Err(Error::UnsupportedBase(Base::Identity))
+  ));
+}
+
+#[test]
+fn version_utils() {
+  assert_eq!(Version::iter().collect::<Vec<_>>(), [Version::V0, Version::V1]);
+  for version in Version::iter() {
+    assert!(Version::is_supported(version.into()));
+  }
+  assert!(!Version::is_supported(2));
+  for version in Version::SUPPORTED {
+    let raw = version.to_u64();
+    assert_eq!(Version::try_from(raw).unwrap(), *version);
+    assert_eq!(Version::try_from(raw as u8).unwrap(), *version);
+  }
+  assert_eq!(Version::try_from(1u8).unwrap(), Version::V1);
+  assert!(Version::try_from(3u8).is_err());
+  assert_eq!(Version::V0.to_string(), "CIDv0");
+  assert_eq!(format!("{:>6}", Version::V1), " CIDv1");
+}
+
+#[test]
+fn display_base() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  for base in &[Base::Base32Lower, Base::Base58Btc] {
+    assert_eq!(
+      v1.display_base(*base).unwrap().to_string(),
+      v1.to_string_of_base(*base).unwrap()
+    );
+  }
+  assert_eq!(
+    format!("{:.5}", v0.display_base(Base::Base58Btc).unwrap()),
+    "QmRJz"
+  );
+  assert!(matches!(
+    v0.display_base(Base::Base32Lower),
+    Err(Error::InvalidCidV0Base)
+  ));
+  assert!(matches!(
+    v1.display_base(Base::Identity),
+    Err(Error::UnsupportedBase(Base::Identity))
+  ));
+}
+
+#[test]
+fn short_display() {
+  let cid = Cid::from_str(
+    "bafyreibjo4xmgaevkgud7mbifn3dzp4v4lyaui4yvqp3f2bqwtxcjrdqg4",
+  )
+  .unwrap();
+  assert_eq!(cid.short().to_string(), "bafy…dqg4");
+  assert_eq!(cid.short().head(0).tail(3).to_string(), "…qg4");
+  assert_eq!(format!("{:>10}", cid.short()), " bafy…dqg4");
+  assert_eq!(cid.short().head(40).tail(40).to_string(), cid.to_string());
+  let whole = cid.short().head(usize::MAX).tail(1);
+  assert_eq!(whole.to_string(), cid.to_string());
+}
+
+#[test]
+fn display_flags() {
+  let cid: Cid = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n"
+    .parse()
+    .unwrap();
+  let s = cid.to_string();
+  assert_eq!(format!("{:>50}", cid), format!("    {}", s));
+  assert_eq!(format!("{:-<48}|", cid), format!("{}--|", s));
+  assert_eq!(format!("{:.8}", cid), "QmdfTbBq");
+
+  let v1 = Cid::new_v1(DAG_PB, *cid.hash());
+  assert_eq!(format!("{:#}", cid), v1.to_string());
+  assert_eq!(format!("{:#}", v1), v1.to_string());
+  assert_eq!(format!("{:#.9}", cid), v1.to_string()[..9]);
+}
+
+#[test]
+fn ord_follows_bytes() {
+  let h = Code::Sha2_256.digest(b"foo");
+  let mut cids = [
+    Cid::new_v0(h).unwrap(),
+    Cid::new_v1(0x100, h),
+    Cid::new_v1(0xff, h),
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar")),
+    Cid::new_v1(RAW, h),
+  ];
+  let mut bytes: Vec<_> = cids.iter().map(Cid::to_bytes).collect();
+  cids.sort();
+  bytes.sort();
+  assert_eq!(cids.iter().map(Cid::to_bytes).collect::<Vec<_>>(), bytes);
+  assert_eq!(cids[4].version(), Version::V0);
+  assert_eq!(cids[2].codec(), 0x100);
+}
+
+#[test]
+fn eq_across_sizes() {
+  let large = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let hash = MultihashGeneric::<U32>::wrap(0x12, large.hash().digest());
+  let small = CidGeneric::new_v1(RAW, hash.unwrap());
+  assert!(small.eq_across_sizes(&large));
+  assert!(large.eq_across_sizes(&small));
+
+  let other = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  assert!(!small.eq_across_sizes(&other));
+}
+
+#[test]
+fn from_data() {
+  let cid = Cid::from_data(RAW, Code::Sha2_256, b"foo").unwrap();
+  assert_eq!(cid, Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")));
+
+  let limit = Cid::DEFAULT_INLINE_LIMIT;
+  let inline = Cid::from_data_inline(RAW, Code::Sha2_256, b"foo", limit);
+  let inline = inline.unwrap();
+  assert_eq!(inline.hash().code(), 0x00);
+  assert_eq!(inline.hash().digest(), b"foo");
+
+  let data = [0u8; 33];
+  let hashed = Cid::from_data_inline(RAW, Code::Sha2_256, &data, limit);
+  assert_eq!(hashed.unwrap(), Cid::new_v1(RAW, Code::Sha2_256.digest(&data)));
+
+  // Inline data has to fit into the allocated size
+  let large = [0u8; 40];
+  let res =
+    CidGeneric::<U32>::from_data_inline(RAW, Code::Sha2_256, &large, 64);
+  assert!(matches!(res, Err(Error::ParsingError(_))));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn from_data_in_parallel() {
+  let threshold = Cid::PARALLEL_HASH_THRESHOLD;
+  // Inputs on both sides of the threshold, with a partial last chunk
+  for len in [threshold - 1, threshold, 4 * threshold + 1] {
+    let data: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+    let cid = Cid::from_data(RAW, Code::Blake3_256, &data).unwrap();
+    assert_eq!(cid, Cid::new_v1(RAW, Code::Blake3_256.digest(&data)));
+  }
+}
+
+#[test]
+fn cid_diff() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  assert!(v0.diff(&v0).is_empty());
+  let diff = v0.diff(&v1);
+  assert_eq!(
+    diff,
+    [
+      CidDiff::Version { expected: Version::V0, found: Version::V1 },
+      CidDiff::Codec { expected: DAG_PB, found: RAW },
+      CidDiff::HashCode { expected: 0x12, found: 0x13 },
+      CidDiff::Digest,
+    ]
+  );
+  assert_eq!(diff[0].to_string(), "expected CIDv0, found CIDv1");
+  assert_eq!(diff[1].to_string(), "expected codec 0x70, found 0x55");
+
+  let other = Cid::new_v0(Code::Sha2_256.digest(b"bar")).unwrap();
+  assert_eq!(v0.diff(&other), [CidDiff::Digest]);
+}
+
+#[test]
+fn xor_distance() {
+  let a = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let b = Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar"));
+  assert!(a.xor_distance(&a).is_zero());
+  assert_eq!(a.xor_distance(&b), b.xor_distance(&a));
+  assert_eq!(a.xor_distance_key(b.hash().digest()), a.xor_distance(&b));
+
+  // Shorter keys are padded at their end
+  assert_eq!(Distance::between(&[0x80], &[0x00, 0x00]).as_bytes(), [0x80, 0]);
+  assert_eq!(Distance::between(&[0x01], &[]), Distance::between(&[1, 0], &[]));
+  assert!(Distance::between(&[0x01], &[]) < Distance::between(&[1, 1], &[]));
+  assert!(Distance::between(&[0, 0xff], &[]) < Distance::between(&[1], &[]));
+  assert_eq!(Distance::between(&[0, 0x10], &[]).leading_zeros(), Some(11));
+  assert_eq!(Distance::between(&[0, 0], &[]).leading_zeros(), None);
+}
+
+#[test]
+fn to_string_len() {
+  let cids = [
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(RAW, MultihashGeneric::wrap(0x00, b"").unwrap()),
+    Cid::new_v1(0, MultihashGeneric::wrap(0x00, b"\0\0\0").unwrap()),
+    Cid::new_v1(DAG_PB, Code::Sha2_512.digest(b"foo")),
+  ];
+  #[cfg(feature = "all-bases")]
+  let bases = [
+    Base::Base2,
+    Base::Base8,
+    Base::Base10,
+    Base::Base16Upper,
+    Base::Base32Lower,
+    Base::Base32PadUpper,
+    Base::Base32Z,
+    Base::Base36Lower,
+    Base::Base45,
+    Base::Base58Flickr,
+    Base::Base58Btc,
+    Base::Base64,
+    Base::Base64UrlPad,
+    Base::Base256Emoji,
+  ];
+  #[cfg(not(feature = "all-bases"))]
+  let bases = [Base::Base32Lower, Base::Base32Upper, Base::Base58Btc];
+  for cid in &cids {
+    for base in &bases {
+      let s = cid.to_string_of_base(*base).unwrap();
+      assert_eq!(cid.to_string_len(*base).unwrap(), s.len(), "{:?}", base);
+    }
+  }
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  assert_eq!(v0.to_string_len(Base::Base58Btc).unwrap(), 46);
+  assert!(v0.to_string_len(Base::Base32Lower).is_err());
+}
+
+#[test]
+fn codec_and_hash_names() {
+  let cid = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  assert_eq!(cid.codec_name(), Some("dag-pb"));
+  assert_eq!(cid.hash_name(), Some("sha2-256"));
+
+  let cid = Cid::new_v1(0x300001, Code::Blake2b256.digest(b"foo"));
+  assert_eq!(cid.codec_name(), None);
+  assert_eq!(cid.hash_name(), Some("blake2b-256"));
+
+  assert_eq!(names::codec_code("dag-cbor"), Some(0x71));
+  assert_eq!(names::hash_code("sha3-512"), Some(0x14));
+  assert_eq!(names::hash_code("sha2"), None);
+}
+
+#[test]
+fn registered_codecs() {
+  mod chain {
+    sp_cid::register_codecs! {
+      STATE = 0x300001 => "chain-state",
+      /// Extrinsics of a block.
+      EXTRINSICS = 0x300002 => "chain-extrinsics",
+    }
+  }
+
+  assert_eq!(chain::CODECS.len(), 2);
+  let cid = Cid::new_v1(chain::EXTRINSICS, Code::Sha2_256.digest(b"foo"));
+  assert_eq!(chain::codec_name(cid.codec()), Some("chain-extrinsics"));
+  assert_eq!(chain::codec_name(DAG_PB), Some("dag-pb"));
+  assert_eq!(chain::codec_name(0x300003), None);
+  assert_eq!(chain::codec_code("chain-state"), Some(chain::STATE));
+  assert_eq!(chain::codec_code("raw"), Some(RAW));
+}
+
+#[test]
+fn resize() {
+  let large = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let small = large.resize::<U32>().unwrap();
+  assert_eq!(small.to_bytes(), large.to_bytes());
+  assert_eq!(small.resize::<U64>().unwrap(), large);
+
+  let sha512 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  assert!(matches!(sha512.resize::<U32>(), Err(Error::ParsingError(_))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_c_str() {
+  use std::ffi::{CStr, CString};
+
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let c_string = CString::new(s).unwrap();
+  let cid = Cid::try_from(c_string.as_c_str()).unwrap();
+  assert_eq!(cid.to_string(), s);
+  assert_eq!(unsafe { Cid::from_c_str(c_string.as_ptr()) }.unwrap(), cid);
+  assert!(matches!(
+    unsafe { Cid::from_c_str(std::ptr::null()) },
+    Err(Error::InputTooShort)
+  ));
+
+  let invalid = CStr::from_bytes_with_nul(b"b\xff\0").unwrap();
+  assert!(matches!(Cid::try_from(invalid), Err(Error::MultibaseError(_))));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_file_names() {
+  use std::{ffi::OsStr, path::Path};
+
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let cid = Cid::from_str(s).unwrap();
+  assert_eq!(Cid::try_from(OsStr::new(s)).unwrap(), cid);
+  let name = format!("{}.data", s);
+  assert_eq!(Cid::try_from(OsStr::new(&name)).unwrap(), cid);
+  let path = format!("/blocks/{}.tar.gz", s);
+  assert_eq!(Cid::try_from(Path::new(&path)).unwrap(), cid);
+
+  assert!(Cid::try_from(Path::new("/blocks/")).is_err());
+  assert!(Cid::try_from(Path::new("/")).is_err());
+  assert!(Cid::try_from(OsStr::new("foo.data")).is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn decode_streams() {
+  use sp_cid::CidDecoder;
+
+  /// A reader returning a byte at a time.
+  struct Trickle<'a>(&'a [u8]);
+
+  impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+      let (byte, rest) = match self.0.split_first() {
+        Some(split) => split,
+        None => return Ok(0),
+      };
+      buf[0] = *byte;
+      self.0 = rest;
+      Ok(1)
+    }
+  }
+
+  let cids = [
+    Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+    Cid::new_v1(RAW, Code::Sha2_512.digest(b"bar")),
+  ];
+  let stream: Vec<u8> = cids.iter().flat_map(Cid::to_bytes).collect();
+  let decoded: Vec<Cid> =
+    CidDecoder::new(Trickle(&stream)).map(Result::unwrap).collect();
+  assert_eq!(decoded, cids);
+  assert_eq!(CidDecoder::<_>::new(&b""[..]).next(), None);
+
+  let mut decoder = CidDecoder::<_>::new(&stream[..stream.len() - 1]);
+  assert_eq!(decoder.next(), Some(Ok(cids[0])));
+  assert_eq!(decoder.offset(), 34);
+  assert_eq!(decoder.next(), Some(Err(Error::UnexpectedEof { offset: 38 })));
+  assert_eq!(decoder.next(), None);
+}
+
+#[test]
+fn zero_sentinel() {
+  let zero = Cid::zero();
+  assert!(zero.is_zero());
+  assert_eq!(Cid::default(), zero);
+  assert!(Cid::try_from(zero.to_bytes()).unwrap().is_zero());
+
+  let empty = MultihashGeneric::wrap(0, b"").unwrap();
+  assert!(!Cid::new_v1(RAW, empty).is_zero());
+  assert!(!Cid::new_v1(RAW, Code::Sha2_256.digest(b"")).is_zero());
+}
+
+#[test]
+fn from_raw_parts_unchecked() {
+  let h = Code::Sha2_256.digest(b"foo");
+  let cid = Cid::from_raw_parts_unchecked(Version::V0, DAG_PB, h);
+  assert_eq!(cid, Cid::new_v0(h).unwrap());
+  let cid = Cid::from_raw_parts_unchecked(Version::V1, RAW, h);
+  assert_eq!(cid, Cid::new_v1(RAW, h));
+}
+
+#[test]
+fn from_byte_iter() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"bar")).unwrap();
+  let (a, b) = (v1.to_bytes(), v0.to_bytes());
+  let chunks = [&a[..3], &a[3..], &b[..]];
+  let mut iter = chunks.iter().flat_map(|chunk| chunk.iter().copied());
+  assert_eq!(Cid::from_byte_iter(&mut iter).unwrap(), v1);
+  assert_eq!(Cid::from_byte_iter(&mut iter).unwrap(), v0);
+  assert!(matches!(
+    Cid::from_byte_iter(iter),
+    Err(Error::UnexpectedEof { offset: 0 })
+  ));
+  assert!(matches!(
+    Cid::from_byte_iter(a[..a.len() - 1].iter().copied()),
+    Err(Error::UnexpectedEof { offset: 4 })
+  ));
 }
 
 #[test]
@@ -115,6 +919,7 @@ fn to_string_of_base32() {
   );
 }
 
+#[cfg(feature = "all-bases")]
 #[test]
 fn to_string_of_base64() {
   let expected_cid = "mAVUSICwmtGto/8aP+ZtFPB0wQTQTQi1wZIO/oPmKXohiZueu";
@@ -141,6 +946,37 @@ fn to_string_of_base_v0_error() {
   ));
 }
 
+#[test]
+fn to_string_of_base_upgrading() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  assert_eq!(
+    v0.to_string_of_base_upgrading(Base::Base58Btc).unwrap(),
+    v0.to_string()
+  );
+  assert_eq!(
+    v0.to_string_of_base_upgrading(Base::Base32Lower).unwrap(),
+    v1.to_string()
+  );
+  assert_eq!(
+    v1.to_string_of_base_upgrading(Base::Base58Btc).unwrap(),
+    v1.to_string_of_base(Base::Base58Btc).unwrap()
+  );
+}
+
+#[test]
+fn from_str_multibase() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let s = v1.to_string();
+  assert_eq!(Cid::from_str_multibase(Base::Base32Lower, &s[1..]).unwrap(), v1);
+  assert!(Cid::from_str_multibase(Base::Base32Lower, &s).is_err());
+  assert!(Cid::from_str_multibase(Base::Base58Btc, &s[1..]).is_err());
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let s = v0.to_string();
+  assert_eq!(Cid::from_str_multibase(Base::Base58Btc, &s).unwrap(), v0);
+}
+
 fn a_function_that_takes_a_generic_cid<S: Size>(cid: &CidGeneric<S>) -> String {
   cid.to_string()
 }
@@ -165,3 +1001,1984 @@ fn method_can_take_differently_sized_cids() {
     a_function_that_takes_a_generic_cid(&cid_128)
   );
 }
+
+#[cfg(feature = "all-bases")]
+#[test]
+fn encoder_reuses_buffers() {
+  let mut encoder = CidEncoder::new();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+
+  assert_eq!(encoder.encode_bytes(&v1), v1.to_bytes().as_slice());
+  assert_eq!(encoder.encode_bytes(&v0), v0.to_bytes().as_slice());
+  assert_eq!(
+    encoder.encode_str(&v1, Base::Base32Lower).unwrap(),
+    v1.to_string()
+  );
+  assert_eq!(encoder.encode_str(&v0, Base::Base58Btc).unwrap(), v0.to_string());
+  assert_eq!(
+    encoder.encode_str(&v1, Base::Base64).unwrap(),
+    v1.to_string_of_base(Base::Base64).unwrap()
+  );
+  assert!(matches!(
+    encoder.encode_str(&v0, Base::Base32Lower),
+    Err(Error::InvalidCidV0Base)
+  ));
+}
+
+#[test]
+fn encoder_batches() {
+  let mut encoder = CidEncoder::new();
+  let cids = [
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+    Cid::new_v1(DAG_PB, Code::Sha2_512.digest(b"bar")),
+  ];
+  let strings = encoder.encode_all(&cids);
+  let expected: Vec<_> = cids.iter().map(Cid::to_string).collect();
+  assert_eq!(strings, expected);
+  assert!(strings.iter().all(|s| s.len() == s.capacity()));
+
+  let mut out = String::new();
+  encoder.write_all(&cids, &mut out);
+  assert_eq!(out, format!("{}\n", expected.join("\n")));
+  assert_eq!(parse_cid_list::<U64>(&out).unwrap(), cids);
+}
+
+#[test]
+fn cid_ref_borrows_bytes() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let mut bytes = cid.to_bytes();
+  let len = bytes.len();
+  bytes.extend_from_slice(&[1, 2, 3]);
+
+  let (cid_ref, rest) = CidRef::read(&bytes).unwrap();
+  assert_eq!(rest, &[1, 2, 3]);
+  assert_eq!(cid_ref.as_bytes(), &bytes[..len]);
+  assert_eq!(cid_ref.version(), Version::V1);
+  assert_eq!(cid_ref.codec(), RAW);
+  assert_eq!(cid_ref.hash_code(), cid.hash().code());
+  assert_eq!(cid_ref.digest(), cid.hash().digest());
+  assert_eq!(cid_ref.to_string(), cid.to_string());
+  assert_eq!(cid_ref.to_owned::<U64>().unwrap(), cid);
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v0_bytes = v0.to_bytes();
+  let v0_ref = CidRef::try_from(&v0_bytes[..]).unwrap();
+  assert_eq!(v0_ref.version(), Version::V0);
+  assert_eq!(v0_ref.to_string(), v0.to_string());
+  assert_eq!(v0_ref.to_owned::<U64>().unwrap(), v0);
+
+  assert!(CidRef::read(&bytes[..len - 1]).is_err());
+}
+
+#[test]
+fn cid_dyn_large_digests() {
+  // An identity multihash larger than any allocated size
+  let digest = vec![7u8; 300];
+  let cid = CidDyn::new_v1(RAW, 0x00, digest.clone());
+  let bytes = cid.to_bytes();
+  assert_eq!(CidDyn::try_from(&bytes[..]).unwrap(), cid);
+  assert_eq!(cid.to_string().parse::<CidDyn>().unwrap(), cid);
+  assert!(Cid::try_from(&bytes[..]).is_err());
+  assert!(cid.to_cid::<U64>().is_err());
+
+  let small = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let dyn_cid = CidDyn::from(small);
+  assert_eq!(dyn_cid.to_bytes(), small.to_bytes());
+  assert_eq!(dyn_cid.to_string(), small.to_string());
+  assert_eq!(dyn_cid.to_cid::<U64>().unwrap(), small);
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  assert_eq!(CidDyn::from(v0).to_string(), v0.to_string());
+}
+
+#[cfg(feature = "serde-codec")]
+#[test]
+fn serde_forms() {
+  use serde::{
+    de::value::{
+      BorrowedBytesDeserializer, Error as ValueError, SeqDeserializer,
+    },
+    Deserialize,
+  };
+
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let cid = Cid::from_str(s).unwrap();
+  let json = serde_json::to_string(&cid).unwrap();
+  assert_eq!(json, format!("\"{}\"", s));
+  assert_eq!(serde_json::from_str::<Cid>(&json).unwrap(), cid);
+
+  let bytes = cid.to_bytes();
+  let de = BorrowedBytesDeserializer::<ValueError>::new(&bytes);
+  assert_eq!(Cid::deserialize(de).unwrap(), cid);
+  let de = SeqDeserializer::<_, ValueError>::new(bytes.iter().copied());
+  assert_eq!(Cid::deserialize(de).unwrap(), cid);
+
+  assert!(serde_json::from_str::<Cid>("\"bafy\"").is_err());
+
+  assert_eq!(serde_json::to_string(&Version::V1).unwrap(), "1");
+  assert_eq!(serde_json::from_str::<Version>("0").unwrap(), Version::V0);
+  assert!(serde_json::from_str::<Version>("2").is_err());
+}
+
+#[cfg(feature = "serde-codec")]
+#[test]
+fn serde_hex_bytes() {
+  use sp_cid::CidHexBytes;
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let hex: String =
+    cid.to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+  let json = serde_json::to_string(&CidHexBytes(cid)).unwrap();
+  assert_eq!(json, format!("\"0x{}\"", hex));
+  let parsed: CidHexBytes = serde_json::from_str(&json).unwrap();
+  assert_eq!(parsed.0, cid);
+  let upper = format!("\"0X{}\"", hex.to_uppercase());
+  assert_eq!(serde_json::from_str::<CidHexBytes>(&upper).unwrap().0, cid);
+
+  let unprefixed = format!("\"{}\"", hex);
+  assert!(serde_json::from_str::<CidHexBytes>(&unprefixed).is_err());
+  assert!(serde_json::from_str::<CidHexBytes>("\"0x0155\"").is_err());
+  assert!(serde_json::from_str::<CidHexBytes>("\"0x015\"").is_err());
+}
+
+#[test]
+fn typed_links() {
+  struct Metadata;
+  impl LinkTarget for Metadata {
+    const CODEC: u64 = 0x71;
+  }
+  struct Chunk;
+  impl LinkTarget for Chunk {
+    const CODEC: u64 = RAW;
+  }
+
+  fn chunk_size(_: &Link<Chunk>) {}
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let chunk = Link::<Chunk>::new(cid).unwrap();
+  chunk_size(&chunk);
+  assert_eq!(chunk.cid(), &cid);
+  assert_eq!(chunk.to_string(), cid.to_string());
+  assert!(matches!(
+    Link::<Metadata>::try_from(cid),
+    Err(Error::UnexpectedCodec { expected: 0x71, found: RAW })
+  ));
+  let metadata: Link<Metadata> = chunk.cast();
+  assert_eq!(Cid::from(metadata), cid);
+
+  // Decoding checks the codec too
+  #[cfg(feature = "serde-codec")]
+  {
+    let json = serde_json::to_string(&cid).unwrap();
+    let link: Link<Chunk> = serde_json::from_str(&json).unwrap();
+    assert_eq!(link, chunk);
+    assert!(serde_json::from_str::<Link<Metadata>>(&json).is_err());
+  }
+  #[cfg(feature = "scale-codec")]
+  {
+    use parity_scale_codec::{Decode, Encode};
+
+    let encoded = cid.encode();
+    let link = Link::<Chunk>::decode(&mut &encoded[..]).unwrap();
+    assert_eq!(link, chunk);
+    assert!(Link::<Metadata>::decode(&mut &encoded[..]).is_err());
+  }
+}
+
+#[test]
+fn to_cid() {
+  let expected = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let cid: Cid = b"foo"[..].to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, expected);
+  let cid: Cid = b"foo".to_vec().to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, expected);
+  let cid: Cid = "foo".to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, expected);
+  let cid: Cid = String::from("foo").to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, expected);
+
+  let small = "foo".to_cid::<U32, _>(RAW, Code::Sha2_512);
+  assert!(matches!(small, Err(Error::ParsingError(_))));
+}
+
+#[test]
+fn incremental_hashing() {
+  let data = vec![7u8; 10_000];
+  let expected = Cid::new_v1(RAW, Code::Sha2_512.digest(&data));
+  let mut hasher = CidHasher::<Sha2_512>::new(RAW, Code::Sha2_512);
+  for chunk in data.chunks(999) {
+    hasher.update(chunk);
+  }
+  assert_eq!(hasher.finalize::<U64>().unwrap(), expected);
+  assert!(hasher.finalize::<U32>().is_err());
+
+  hasher.reset();
+  hasher.update(b"foo");
+  let cid: Cid = hasher.finalize().unwrap();
+  assert_eq!(cid, Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")));
+}
+
+#[test]
+fn truncated_digests() {
+  let full = Code::Sha2_256.digest(b"foo");
+  let cid = Cid::from_data_truncated(RAW, Code::Sha2_256, b"foo", 20).unwrap();
+  assert_eq!(cid.hash().code(), 0x12);
+  assert_eq!(cid.hash().digest(), &full.digest()[..20]);
+  assert_eq!(Cid::try_from(cid.to_bytes()).unwrap(), cid);
+  assert!(Cid::from_data_truncated(RAW, Code::Sha2_256, b"foo", 33).is_err());
+
+  let mut hasher = CidHasher::<Sha2_512>::new(RAW, Code::Sha2_512);
+  hasher.update(b"foo");
+  let cid: Cid = hasher.finalize_truncated(32).unwrap();
+  assert_eq!(
+    cid,
+    Cid::from_data_truncated(RAW, Code::Sha2_512, b"foo", 32).unwrap()
+  );
+}
+
+#[test]
+fn well_known_cids() {
+  let empty_dir = [0x0a, 0x02, 0x08, 0x01];
+  let dir_v0 = Cid::new_v0(Code::Sha2_256.digest(&empty_dir)).unwrap();
+  let dir_v1 = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(&empty_dir));
+  let raw = Cid::new_v1(RAW, Code::Sha2_256.digest(b""));
+  let identity = Cid::from_data_inline(RAW, Code::Sha2_256, b"", 0).unwrap();
+
+  assert_eq!(dir_v0.to_string(), consts::EMPTY_DIR_V0);
+  assert_eq!(dir_v0.to_bytes(), consts::EMPTY_DIR_V0_BYTES);
+  assert_eq!(dir_v1.to_string(), consts::EMPTY_DIR);
+  assert_eq!(dir_v1.to_bytes(), consts::EMPTY_DIR_BYTES);
+  assert_eq!(raw.to_string(), consts::EMPTY_RAW);
+  assert_eq!(raw.to_bytes(), consts::EMPTY_RAW_BYTES);
+  assert_eq!(identity.to_string(), consts::IDENTITY_EMPTY);
+  assert_eq!(identity.to_bytes(), consts::IDENTITY_EMPTY_BYTES);
+  assert_eq!(Cid::try_from(&consts::IDENTITY_EMPTY_BYTES).unwrap(), identity);
+}
+
+#[test]
+fn parse_prefix() {
+  let v1 = Cid::new_v1(DAG_PB, Code::Sha2_512.digest(b"foo"));
+  let bytes = v1.to_bytes();
+  let prefix = Cid::parse_prefix(&bytes[..4]).unwrap();
+  assert_eq!(prefix.version(), Version::V1);
+  assert_eq!(prefix.codec(), DAG_PB);
+  assert_eq!(prefix.hash_code(), 0x13);
+  assert_eq!(prefix.digest_len(), 64);
+  assert_eq!(prefix.header_len(), 4);
+  assert_eq!(prefix.total_len(), bytes.len());
+  assert_eq!(CidPrefix::read(&bytes).unwrap(), prefix);
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let prefix = Cid::parse_prefix(&v0.to_bytes()[..2]).unwrap();
+  assert_eq!(prefix.version(), Version::V0);
+  assert_eq!(prefix.total_len(), 34);
+
+  assert_eq!(
+    Cid::parse_prefix(&bytes[..2]).unwrap_err(),
+    Error::UnexpectedEof { offset: 2 }
+  );
+  assert_eq!(
+    Cid::parse_prefix(&[0x01, 0x55, 0x12, 0x80, 0x02]).unwrap_err(),
+    Error::InvalidMultihash { offset: 2 }
+  );
+}
+
+#[test]
+fn raw_cid() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let raw = RawCid::from(&cid);
+  assert_eq!(raw.as_bytes(), cid.to_bytes().as_slice());
+  assert_eq!(raw.to_cid::<U64>().unwrap(), cid);
+  assert_eq!(Cid::try_from(&raw).unwrap(), cid);
+
+  let mut bytes = cid.to_bytes();
+  let len = bytes.len();
+  bytes.push(0);
+  let trailing = RawCid::from_bytes_unchecked(bytes);
+  assert_eq!(
+    trailing.to_cid::<U64>().unwrap_err(),
+    Error::TrailingBytes { offset: len }
+  );
+  let invalid = RawCid::from_bytes_unchecked(vec![0x01]);
+  assert!(invalid.to_cid::<U64>().is_err());
+}
+
+#[test]
+fn cid_bytes_as_key() {
+  use std::collections::{BTreeSet, HashMap};
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let key = CidBytes::new(cid);
+  assert_eq!(key.as_ref(), &cid.to_bytes()[..]);
+  assert_eq!(CidBytes::try_from(key.as_bytes()).unwrap(), key);
+
+  let mut map = HashMap::new();
+  map.insert(key.clone(), "foo");
+  assert_eq!(map.get(&cid.to_bytes()[..]), Some(&"foo"));
+
+  // Keys sort like their bytes
+  let other = CidBytes::new(Cid::new_v1(DAG_PB, *cid.hash()));
+  let set: BTreeSet<_> = vec![other.clone(), key.clone()].into_iter().collect();
+  let sorted: Vec<&[u8]> = set.iter().map(|k| k.as_bytes()).collect();
+  assert_eq!(sorted, [key.as_bytes(), other.as_bytes()]);
+}
+
+#[test]
+fn hashed_cid() {
+  use std::{
+    collections::HashSet,
+    hash::{BuildHasherDefault, Hasher},
+  };
+
+  // A hasher that passes the precomputed hash through
+  #[derive(Default)]
+  struct PassThrough(u64);
+  impl Hasher for PassThrough {
+    fn write(&mut self, _: &[u8]) {
+      panic!("HashedCid must only write its precomputed hash");
+    }
+    fn write_u64(&mut self, n: u64) {
+      self.0 = n;
+    }
+    fn finish(&self) -> u64 {
+      self.0
+    }
+  }
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let hashed = HashedCid::new(cid);
+  assert_eq!(hashed.precomputed_hash(), HashedCid::new(cid).precomputed_hash());
+  let other = HashedCid::new(Cid::new_v1(DAG_PB, *cid.hash()));
+  assert_ne!(hashed.precomputed_hash(), other.precomputed_hash());
+
+  let mut set = HashSet::<_, BuildHasherDefault<PassThrough>>::default();
+  assert!(set.insert(hashed.clone()));
+  assert!(!set.insert(HashedCid::new(cid)));
+  assert!(set.insert(other));
+  assert!(set.contains(&hashed));
+}
+
+#[test]
+fn by_digest() {
+  use std::collections::HashSet;
+
+  let raw = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let dag_pb = Cid::new_v0(*raw.hash()).unwrap();
+  let other = Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar"));
+  assert_eq!(ByDigest(raw), ByDigest(dag_pb));
+  assert_ne!(ByDigest(raw), ByDigest(other));
+
+  let mut set = HashSet::new();
+  assert!(set.insert(ByDigest(raw)));
+  assert!(!set.insert(ByDigest(dag_pb)));
+  assert!(set.insert(ByDigest(other)));
+  assert_eq!(set.get(&ByDigest(dag_pb)).unwrap().codec(), RAW);
+
+  assert_eq!(
+    ByDigest(dag_pb).cmp(&ByDigest(other)),
+    raw.hash().digest().cmp(other.hash().digest())
+  );
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn cached_cid_string() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let cached = sp_cid::CachedCid::new(cid);
+  assert_eq!(cached.as_str(), cid.to_string());
+  assert_eq!(cached.to_string(), cid.to_string());
+  assert_eq!(cached.codec(), RAW);
+  assert_eq!(cached.clone(), cached);
+  assert_eq!(cached.into_inner(), cid);
+}
+
+#[test]
+fn from_fixed_arrays() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let bytes: [u8; 34] = v0.to_bytes().try_into().unwrap();
+  assert_eq!(Cid::try_from(&bytes).unwrap(), v0);
+
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let bytes: [u8; 36] = v1.to_bytes().try_into().unwrap();
+  assert_eq!(Cid::try_from(&bytes).unwrap(), v1);
+
+  // A CID that doesn't fill the array is rejected
+  let mut padded = [0u8; 38];
+  padded[..36].copy_from_slice(&bytes);
+  assert!(matches!(
+    Cid::try_from(&padded),
+    Err(Error::TrailingBytes { offset: 36 })
+  ));
+}
+
+#[test]
+fn from_borrowed_bytes() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let mut bytes = cid.to_bytes();
+  bytes.push(0xff);
+  assert_eq!(Cid::try_from(&bytes[..]).unwrap(), cid);
+  assert!(Cid::try_from(&bytes[..10]).is_err());
+}
+
+#[test]
+fn max_encoded_len() {
+  assert_eq!(Cid::MAX_BINARY_LEN, 86);
+  assert_eq!(Cid::MAX_STRING_LEN, 139);
+  assert_eq!(CidGeneric::<U128>::MAX_BINARY_LEN, 151);
+
+  let hash = sp_multihash::Multihash::wrap(u64::MAX, &[0xff; 64]).unwrap();
+  let cid = Cid::new_v1(u64::MAX, hash);
+  assert_eq!(cid.to_bytes().len(), Cid::MAX_BINARY_LEN);
+  assert_eq!(cid.to_string().len(), Cid::MAX_STRING_LEN);
+}
+
+#[cfg(feature = "all-bases")]
+#[test]
+fn weights_are_upper_bounds() {
+  use sp_cid::weights;
+
+  assert_eq!(weights::decode_bytes::<U64>(10), 74);
+  assert_eq!(
+    weights::decode_bytes::<U64>(10_000),
+    weights::decode_bytes::<U64>(Cid::MAX_BINARY_LEN)
+  );
+  assert!(
+    weights::encode_str::<U64>(Base::Base32Lower)
+      < weights::encode_str::<U64>(Base::Base58Btc)
+  );
+  assert!(
+    weights::decode_str_with_base::<U64>(Base::Base58Btc, 46)
+      <= weights::decode_str::<U64>(46)
+  );
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  for base in &[Base::Base2, Base::Base32Lower, Base::Base58Btc, Base::Base64] {
+    let encoded = cid.to_string_of_base(*base).unwrap();
+    assert!(
+      encoded.len() - 1 <= weights::max_base_len(*base, cid.to_bytes().len())
+    );
+  }
+}
+
+#[test]
+fn canonical_case() {
+  let lower = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let upper = lower.to_ascii_uppercase();
+  let mixed = format!("B{}", &lower[1..]);
+  let strict = ParseOptions::strict();
+  let cid: Cid = strict.parse(lower).unwrap();
+  assert_eq!(strict.parse::<U64>(&upper).unwrap(), cid);
+  assert_eq!(ParseOptions::new().parse::<U64>(&mixed).unwrap(), cid);
+  assert_eq!(
+    strict.parse::<U64>(&mixed),
+    Err(Error::MultibaseError(multibase::Error::InvalidBaseString))
+  );
+  assert!(strict.parse::<U64>(&format!("/ipfs/{}", mixed)).is_err());
+  assert!(strict.canonical_case(false).parse::<U64>(&mixed).is_ok());
+
+  // The base32 decoders ignore case for both prefixes, like multibase
+  let lower_prefix = format!("b{}", &upper[1..]);
+  assert_eq!(Cid::from_str(&lower_prefix).unwrap(), cid);
+  assert!(is_valid_cid_str(&lower_prefix));
+  let parsed = parse_all::<U64>(vec![lower_prefix.as_str(), lower].into_iter());
+  assert_eq!(parsed, [Ok(cid), Ok(cid)]);
+  assert!(strict.parse::<U64>(&lower_prefix).is_err());
+
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  assert!(strict.parse::<U64>(v0).is_ok());
+}
+
+#[test]
+fn accepted_bases() {
+  let base32 = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  let cid: Cid = base32.parse().unwrap();
+  let base36 = cid.to_string_of_base(Base::Base36Lower).unwrap();
+  let upper = base32.to_uppercase();
+
+  let options =
+    ParseOptions::new().bases(&[Base::Base32Lower, Base::Base58Btc]);
+  assert_eq!(options.parse::<U64>(base32).unwrap(), cid);
+  assert!(options.parse::<U64>(v0).is_ok());
+  assert!(options.parse::<U64>(&format!("/ipfs/{}", base32)).is_ok());
+  assert_eq!(
+    options.parse::<U64>(&base36),
+    Err(Error::UnsupportedBase(Base::Base36Lower))
+  );
+  assert_eq!(
+    options.parse::<U64>(&upper),
+    Err(Error::UnsupportedBase(Base::Base32Upper))
+  );
+  assert_eq!(options.parse::<U64>(""), Err(Error::InputTooShort));
+
+  let v1_only = ParseOptions::strict().bases(&[Base::Base32Lower]);
+  assert_eq!(
+    v1_only.parse::<U64>(v0),
+    Err(Error::UnsupportedBase(Base::Base58Btc))
+  );
+  assert!(ParseOptions::new().parse::<U64>(&base36).is_ok());
+}
+
+#[test]
+fn parse_hints() {
+  let lower = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let cid: Cid = lower.parse().unwrap();
+  let options = ParseOptions::new();
+  let hint = |input: &str| {
+    options.parse_with_hint::<U64>(input).unwrap_err().hint()
+  };
+  assert_eq!(options.parse_with_hint::<U64>(lower).unwrap(), cid);
+
+  let hex: String =
+    cid.to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+  assert_eq!(hint(&hex), Some(Hint::HexEncoded));
+  assert_eq!(hint(&format!("0x{}", hex)), Some(Hint::HexEncoded));
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  assert_eq!(hint(&format!("b{}", v0)), Some(Hint::PrefixedV0('b')));
+  assert_eq!(hint(&lower[1..]), Some(Hint::MissingPrefix('b')));
+  assert_eq!(
+    hint(&format!("/ipfs/z{}", &lower[1..])),
+    Some(Hint::WrongPrefix { found: 'z', expected: 'b' })
+  );
+
+  let err = options.parse_with_hint::<U64>("bnotacid").unwrap_err();
+  assert_eq!(err.hint(), None);
+  assert_eq!(err.to_string(), err.error().to_string());
+  let err = options.parse_with_hint::<U64>(&lower[1..]).unwrap_err();
+  assert!(err
+    .to_string()
+    .ends_with("(the multibase prefix is missing, add `b`)"));
+}
+
+#[test]
+fn borrowed_map_keys() {
+  use std::collections::{BTreeMap, HashMap};
+
+  use sp_cid::CidKey;
+
+  let cids = [
+    Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x71, Code::Sha2_512.digest(b"bar")),
+  ];
+  let hashed: HashMap<Cid, usize> =
+    cids.iter().enumerate().map(|(i, cid)| (*cid, i)).collect();
+  let ordered: BTreeMap<Cid, usize> =
+    cids.iter().enumerate().map(|(i, cid)| (*cid, i)).collect();
+  for (i, cid) in cids.iter().enumerate() {
+    let bytes = cid.to_bytes();
+    let (cid_ref, _) = CidRef::read(&bytes).unwrap();
+    assert_eq!(hashed.get(&cid_ref as &dyn CidKey), Some(&i));
+    assert_eq!(ordered.get(&cid_ref as &dyn CidKey), Some(&i));
+    let cid_dyn = CidDyn::from(cid_ref);
+    assert_eq!(hashed.get(&cid_dyn as &dyn CidKey), Some(&i));
+  }
+  let other = Cid::new_v1(RAW, Code::Sha2_256.digest(b"baz"));
+  assert_eq!(hashed.get(&other as &dyn CidKey), None);
+  assert_eq!(ordered.get(&other as &dyn CidKey), None);
+}
+
+#[test]
+fn sort_dedup() {
+  let mut cids: Vec<Cid> = (0..500u32)
+    .map(|i| {
+      let hash = Code::Sha2_256.digest(&(i % 200).to_le_bytes());
+      match i % 3 {
+        0 => Cid::new_v0(hash).unwrap(),
+        1 => Cid::new_v1(RAW, hash),
+        _ => Cid::new_v1(0x71, Code::Sha2_512.digest(&i.to_le_bytes())),
+      }
+    })
+    .collect();
+  let mut expected = cids.clone();
+  expected.sort();
+  expected.dedup();
+  let mut sorted = cids.clone();
+  sp_cid::util::sort_dedup(&mut sorted);
+  assert_eq!(sorted, expected);
+
+  sp_cid::util::dedup_by_digest(&mut cids);
+  let hashes: Vec<_> = cids.iter().map(|cid| cid.hash().to_bytes()).collect();
+  assert!(hashes.windows(2).all(|w| w[0] < w[1]));
+  assert_eq!(cids.len(), 200 + 166);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn verify_all() {
+  let blocks: Vec<Vec<u8>> =
+    (0..1000u32).map(|i| i.to_le_bytes().to_vec()).collect();
+  let pairs: Vec<(Cid, &[u8])> = blocks
+    .iter()
+    .enumerate()
+    .map(|(i, block)| {
+      let cid = match i % 7 {
+        // The block of another CID
+        3 => Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+        // A hash function that isn't in the code table
+        5 => {
+          let hash = MultihashGeneric::wrap(0x30_0001, block).unwrap();
+          Cid::new_v1(RAW, hash)
+        }
+        6 => Cid::new_v1(RAW, Code::Blake2b256.digest(block)),
+        _ => Cid::new_v1(RAW, Code::Sha2_256.digest(block)),
+      };
+      (cid, &block[..])
+    })
+    .collect();
+
+  let sequential: Vec<usize> = pairs
+    .iter()
+    .enumerate()
+    .filter(|(_, (cid, block))| !cid.verify(block).unwrap_or(false))
+    .map(|(index, _)| index)
+    .collect();
+  let expected: Vec<usize> =
+    (0..1000).filter(|i| i % 7 == 3 || i % 7 == 5).collect();
+  assert_eq!(sequential, expected);
+  assert_eq!(sp_cid::util::verify_all(&pairs), expected);
+  assert!(sp_cid::util::verify_all::<U64>(&[]).is_empty());
+}
+
+#[test]
+fn multibase_bytes() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(*v1.hash()).unwrap();
+  for cid in &[v0, v1] {
+    let bytes = cid.to_multibase_bytes(Base::Identity).unwrap();
+    assert_eq!(bytes[0], 0x00);
+    assert_eq!(&bytes[1..], cid.to_bytes().as_slice());
+  }
+  assert_eq!(
+    v1.to_multibase_bytes(Base::Base32Lower).unwrap(),
+    v1.to_string().into_bytes()
+  );
+  assert_eq!(
+    v0.to_multibase_bytes(Base::Base32Lower),
+    Err(Error::InvalidCidV0Base)
+  );
+}
+
+#[test]
+fn filenames() {
+  use sp_multihash::typenum::U256;
+
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(*v1.hash()).unwrap();
+  for cid in &[v0, v1] {
+    let name = cid.to_filename(None).unwrap();
+    assert!(name.starts_with('b'));
+    assert!(name.chars().all(|c| matches!(c, 'a'..='z' | '2'..='7')));
+    assert_eq!(Cid::from_filename(&name).unwrap(), *cid);
+    assert_eq!(Cid::from_filename(&name.to_uppercase()).unwrap(), *cid);
+
+    let scheme = ShardingScheme::NextToLast(2);
+    let path = cid.to_filename(Some(scheme)).unwrap();
+    assert_eq!(path, format!("{}/{}", scheme.shard(&name), name));
+    assert_eq!(Cid::from_filename(&path).unwrap(), *cid);
+    let windows = format!("C:\\blocks\\{}", path.replace('/', "\\"));
+    assert_eq!(Cid::from_filename(&windows).unwrap(), *cid);
+  }
+
+  let hash = MultihashGeneric::<U256>::wrap(0x00, &[0; 200]).unwrap();
+  let identity = CidGeneric::new_v1(RAW, hash);
+  assert!(matches!(
+    identity.to_filename(None),
+    Err(Error::FilenameTooLong { .. })
+  ));
+  assert!(Cid::from_filename("zQmfoo").is_err());
+}
+
+#[test]
+fn cid_stats() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"bar")).unwrap();
+  let private = Cid::new_v1(0x300001, Code::Sha2_512.digest(b"foo"));
+  let mut stats: CidStats = vec![v0, v1, v1].into_iter().collect();
+  stats.extend(&[private]);
+  assert_eq!(stats.total(), 4);
+  assert_eq!(stats.versions()[&Version::V0], 1);
+  assert_eq!(stats.versions()[&Version::V1], 3);
+  assert_eq!(stats.codecs()[&RAW], 2);
+  assert_eq!(stats.codecs()[&DAG_PB], 1);
+  assert_eq!(stats.hash_codes()[&0x12], 3);
+  assert_eq!(stats.digest_sizes()[&64], 1);
+  assert_eq!(
+    stats.to_string(),
+    "4 CIDs\n\
+     versions: v0: 1 v1: 3\n\
+     codecs: raw (0x55): 2 dag-pb (0x70): 1 0x300001: 1\n\
+     hashes: sha2-256 (0x12): 3 sha2-512 (0x13): 1\n\
+     digest sizes: 32: 3 64: 1"
+  );
+  assert_eq!(CidStats::new().total(), 0);
+}
+
+#[test]
+fn fixed_size_shapes() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(*v1.hash()).unwrap();
+  assert!(v0.is_fixed_size());
+  assert_eq!(v0.binary_len(), Cid::V0_BINARY_LEN);
+  assert_eq!(v0.to_bytes().len(), Cid::V0_BINARY_LEN);
+  assert!(v1.is_fixed_size());
+  assert_eq!(v1.to_bytes().len(), Cid::V1_SHA256_BINARY_LEN);
+
+  let sha512 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  let dag_json = Cid::new_v1(0x0129, *v1.hash());
+  for cid in &[sha512, dag_json] {
+    assert!(!cid.is_fixed_size());
+    assert_eq!(cid.binary_len(), cid.to_bytes().len());
+  }
+  // Only the length counts, other 32 byte digests fit the column as well
+  let blake3 = MultihashGeneric::wrap(0x1e, &[0; 32]).unwrap();
+  assert!(Cid::new_v1(RAW, blake3).is_fixed_size());
+}
+
+#[test]
+fn from_multihash_bytes() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let bytes = hash.to_bytes();
+  let cid = Cid::from_multihash_bytes(DAG_PB, &bytes).unwrap();
+  assert_eq!(cid, Cid::new_v1(DAG_PB, hash));
+  assert_eq!(cid.version(), Version::V1);
+
+  assert!(Cid::from_multihash_bytes(RAW, &bytes[..10]).is_err());
+  let trailing = [bytes.as_slice(), &[0]].concat();
+  assert!(Cid::from_multihash_bytes(RAW, &trailing).is_err());
+  assert!(Cid::from_multihash_bytes(RAW, &[]).is_err());
+}
+
+#[test]
+fn prefix_ranges() {
+  let cids = [
+    Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+    Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")),
+    Cid::new_v1(0x7f, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x80, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x3fff, Code::Sha2_256.digest(b"foo")),
+  ];
+  let in_range = |range: &(Vec<u8>, Vec<u8>)| {
+    cids
+      .iter()
+      .map(|cid| cid.to_bytes())
+      .filter(|key| range.0 <= *key && *key < range.1)
+      .count()
+  };
+
+  let v0 = Cid::prefix_range(Version::V0, DAG_PB, None).unwrap();
+  assert_eq!(in_range(&v0), 1);
+  let dag_pb = Cid::prefix_range(Version::V1, DAG_PB, None).unwrap();
+  assert_eq!(in_range(&dag_pb), 1);
+  let raw = Cid::prefix_range(Version::V1, RAW, None).unwrap();
+  assert_eq!(in_range(&raw), 2);
+  let raw_sha2_512 = Cid::prefix_range(Version::V1, RAW, Some(0x13)).unwrap();
+  assert_eq!(in_range(&raw_sha2_512), 1);
+  for codec in [0x7f, 0x80, 0x3fff] {
+    let range = Cid::prefix_range(Version::V1, codec, None).unwrap();
+    assert_eq!(in_range(&range), 1);
+  }
+
+  assert_eq!(
+    Cid::prefix_range(Version::V0, RAW, None),
+    Err(Error::InvalidCidV0Codec(RAW))
+  );
+  assert_eq!(
+    Cid::prefix_range(Version::V0, DAG_PB, Some(0x13)),
+    Err(Error::InvalidCidV0Multihash(0x13))
+  );
+}
+
+#[test]
+fn parse_with_meta() {
+  let v1 = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+
+  let (cid, meta) = Cid::parse(v1).unwrap();
+  assert_eq!(cid, v1.parse::<Cid>().unwrap());
+  assert_eq!(meta.base(), CidBase::Multibase(Base::Base32Lower));
+  assert_eq!(meta.wrapper(), None);
+  assert_eq!(meta.byte_len(), 36);
+
+  let (cid, meta) = Cid::parse(&format!("/ipfs/{}", v0)).unwrap();
+  assert_eq!(cid, v0.parse::<Cid>().unwrap());
+  assert_eq!(meta.base(), CidBase::V0);
+  assert_eq!(meta.wrapper(), Some(CidWrapper::IpfsPath));
+  assert_eq!(meta.byte_len(), 34);
+
+  let url = format!("https://dweb.link/ipfs/{}", v1);
+  let (_, meta) = Cid::parse(&url).unwrap();
+  assert_eq!(meta.wrapper(), Some(CidWrapper::IpfsPath));
+  let (_, meta) = Cid::parse(&format!("ipfs://{}", v1)).unwrap();
+  assert_eq!(meta.wrapper(), Some(CidWrapper::IpfsUri));
+
+  assert!(Cid::parse(&format!("ipfs://{}/file", v1)).is_err());
+  assert_eq!(Cid::parse("b"), Err(Error::InputTooShort));
+}
+
+#[test]
+fn into_hash() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let cid = Cid::new_v1(RAW, hash);
+  assert_eq!(cid.into_hash(), hash);
+  assert_eq!(MultihashGeneric::from(cid), hash);
+}
+
+#[test]
+fn digest_arrays() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  let digest: [u8; 64] = cid.digest_as_array().unwrap();
+  assert_eq!(&digest[..], cid.hash().digest());
+  assert_eq!(
+    cid.digest_as_array::<32>(),
+    Err(Error::ParsingError(sp_multihash::Error::InvalidSize(64)))
+  );
+}
+
+#[test]
+fn framed_cids() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar"));
+  let mut cursor = ByteCursor::new(Vec::new());
+  v0.write_framed(&mut cursor).unwrap();
+  v1.write_framed(&mut cursor).unwrap();
+  let bytes = cursor.into_inner();
+  assert_eq!(bytes[0], 34);
+  assert_eq!(&bytes[1..35], &v0.to_bytes()[..]);
+
+  let mut cursor = ByteCursor::new(bytes.clone());
+  assert_eq!(Cid::read_framed(&mut cursor).unwrap(), v0);
+  assert_eq!(Cid::read_framed(&mut cursor).unwrap(), v1);
+  assert!(matches!(
+    Cid::read_framed(&mut cursor),
+    Err(Error::UnexpectedEof { offset: 72 })
+  ));
+
+  let mut long = bytes[..35].to_vec();
+  long[0] = 35;
+  long.push(0);
+  let mut cursor = ByteCursor::new(long);
+  assert!(matches!(
+    Cid::read_framed(&mut cursor),
+    Err(Error::TrailingBytes { offset: 35 })
+  ));
+  let mut cursor = ByteCursor::new(bytes[..34].to_vec());
+  assert!(matches!(
+    Cid::read_framed(&mut cursor),
+    Err(Error::UnexpectedEof { offset: 1 })
+  ));
+}
+
+#[test]
+fn granular_decode_errors() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let bytes = cid.to_bytes();
+  let mut cursor = ByteCursor::new(bytes[..bytes.len() - 1].to_vec());
+  assert!(matches!(
+    Cid::read_bytes(&mut cursor),
+    Err(Error::UnexpectedEof { offset: 4 })
+  ));
+  assert!(matches!(
+    Cid::try_from(&bytes[..bytes.len() - 1]),
+    Err(Error::UnexpectedEof { offset: 4 })
+  ));
+  assert!(matches!(
+    Cid::try_from(&bytes[..3]),
+    Err(Error::UnexpectedEof { offset: 3 })
+  ));
+
+  let mut v2 = bytes.clone();
+  v2[0] = 2;
+  let mut cursor = ByteCursor::new(v2.clone());
+  assert!(matches!(
+    Cid::read_bytes(&mut cursor),
+    Err(Error::UnsupportedVersion(2))
+  ));
+  assert!(matches!(
+    Cid::try_from(v2.clone()),
+    Err(Error::UnsupportedVersion(2))
+  ));
+  let v2 = multibase::encode(Base::Base32Lower, v2);
+  assert!(matches!(Cid::try_from(v2), Err(Error::UnsupportedVersion(2))));
+  assert!(matches!(
+    Version::try_from(7u64),
+    Err(Error::UnsupportedVersion(7))
+  ));
+
+  // A 128 byte digest doesn't fit into the 64 byte default size.
+  let mut big = vec![0x01, 0x55, 0x12, 0x80, 0x01];
+  big.extend_from_slice(&[0; 128]);
+  let mut cursor = ByteCursor::new(big.clone());
+  assert!(matches!(
+    Cid::read_bytes(&mut cursor),
+    Err(Error::DigestTooLarge { size: 128, max: 64 })
+  ));
+  let mut cursor = ByteCursor::new(big.clone());
+  let cid = Cid::read_bytes_lossy(&mut cursor).unwrap();
+  assert_eq!(cid.digest(), &big[5..]);
+  assert!(matches!(
+    Cid::try_from(big),
+    Err(Error::DigestTooLarge { size: 128, max: 64 })
+  ));
+
+  let sha3 = Code::Sha3_256.digest(b"foo");
+  assert!(matches!(
+    Cid::new(Version::V0, DAG_PB, sha3),
+    Err(Error::InvalidCidV0Multihash(0x16))
+  ));
+  assert!(matches!(
+    Cid::new(Version::V0, RAW, sha3),
+    Err(Error::InvalidCidV0Codec(RAW))
+  ));
+}
+
+#[test]
+fn cid_list() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"bar"));
+  let list = format!("# pins\n{}\n\n  {} {}  # both\n", v1, v0, v1);
+  let cids: Vec<Cid> = parse_cid_list(&list).unwrap();
+  assert_eq!(cids, [v1, v0, v1]);
+
+  let list = format!("{}\nfoo\n# {}\n{} bar", v1, v0, v0);
+  let errors = parse_cid_list::<U64>(&list).unwrap_err();
+  let lines: Vec<_> = errors.iter().map(|err| err.line).collect();
+  assert_eq!(lines, [2, 4]);
+  assert!(errors[1].to_string().starts_with("line 4: "));
+}
+
+#[cfg(all(feature = "derive", feature = "scale-codec"))]
+#[test]
+fn derive_to_cid() {
+  use parity_scale_codec::Encode;
+
+  #[derive(Encode, ToCid)]
+  struct Scale {
+    id: u32,
+    name: Vec<u8>,
+  }
+
+  fn name_bytes<T>(value: &Named<T>) -> Vec<u8> {
+    value.name.as_bytes().to_vec()
+  }
+
+  #[derive(ToCid)]
+  #[to_cid(encode_with = "name_bytes")]
+  struct Named<T> {
+    name: &'static str,
+    #[allow(dead_code)]
+    other: T,
+  }
+
+  let value = Scale { id: 7, name: b"foo".to_vec() };
+  let cid: Cid = value.to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, Cid::new_v1(RAW, Code::Sha2_256.digest(&value.encode())));
+
+  let value = Named { name: "foo", other: 0u8 };
+  let cid: Cid = value.to_cid(RAW, Code::Sha2_256).unwrap();
+  assert_eq!(cid, Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")));
+}
+
+#[test]
+fn cid_policies() {
+  struct Sha2;
+
+  impl CidPolicy for Sha2 {
+    const HASH_CODES: Option<&'static [u64]> = Some(&[0x12, 0x13]);
+  }
+
+  let sha2_256 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let sha2_512 = Cid::new_v1(DAG_PB, Code::Sha2_512.digest(b"foo"));
+  let blake2b = Cid::new_v1(RAW, Code::Blake2b256.digest(b"foo"));
+
+  assert_eq!(*PolicyCid::<Sha2>::new(sha2_256).unwrap(), sha2_256);
+  assert!(PolicyCid::<Sha2>::try_from(sha2_512).is_ok());
+  assert_eq!(
+    PolicyCid::<Sha2>::new(blake2b),
+    Err(Error::DisallowedHash(0xb220))
+  );
+  assert!(PolicyCid::<AnyCid>::new(blake2b).is_ok());
+}
+
+#[test]
+fn cache_keys() {
+  let sha2 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let dag_pb = Cid::new_v1(DAG_PB, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let inline = |data| Cid::from_data_inline(RAW, Code::Sha2_256, data, 32);
+  let (short, long) = (inline(b"foo").unwrap(), inline(&[7; 32]).unwrap());
+  let fingerprints = [sha2, dag_pb, v0, short, long].map(|cid| {
+    let bytes = cid.to_bytes();
+    let cid_ref = CidRef::try_from(bytes.as_slice()).unwrap();
+    assert_eq!(cid_ref.fingerprint(), cid.fingerprint());
+    assert_eq!(CidDyn::from(cid).fingerprint(), cid.fingerprint());
+    cid.fingerprint()
+  });
+  for (i, a) in fingerprints.iter().enumerate() {
+    for b in &fingerprints[i + 1..] {
+      assert_ne!(a, b);
+    }
+  }
+
+  assert_eq!(sha2.heap_size(), 0);
+  assert_eq!(sha2.weight(), std::mem::size_of::<Cid>());
+  assert_eq!(CidDyn::from(sha2).heap_size(), 32);
+  assert_eq!(CidBytes::new(sha2).heap_size(), 36);
+  assert_eq!(HashedCid::new(sha2).fingerprint(), sha2.fingerprint());
+}
+
+
+#[test]
+fn debug_bytes() {
+  let v0 = Cid::try_from("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n")
+    .unwrap();
+  let dump = v0.to_debug_bytes();
+  let lines: Vec<_> = dump.lines().collect();
+  assert_eq!(lines.len(), 5);
+  assert_eq!(lines[0], "CIDv0: implicit version 0, codec dag-pb");
+  assert!(lines[1].starts_with("12 ") && lines[1].ends_with("(0x12)"));
+  assert!(lines[2].starts_with("20 ") && lines[2].ends_with("length: 32"));
+  assert!(lines[3].ends_with("  digest"));
+  assert_eq!(lines[4].len(), 32);
+
+  // Multi-byte varints stay on one line
+  let cid = Cid::new_v1(0x0129, Code::Sha2_256.digest(b"foo"));
+  let dump = cid.to_debug_bytes();
+  assert!(dump.lines().nth(1).unwrap().starts_with("a902 "));
+  assert!(dump.contains("codec: dag-json (0x129)"));
+  let hex: String = dump
+    .lines()
+    .filter_map(|line| line.split(' ').next())
+    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+    .collect();
+  let bytes: String =
+    cid.to_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+  assert_eq!(hex, bytes);
+}
+
+#[test]
+fn hasher_registries() {
+  use sp_cid::DefaultHashers;
+  use sp_multihash::Multihash;
+
+  const XOR_HASH: u64 = 0x30_0001;
+
+  struct WithXor;
+
+  impl HasherRegistry for WithXor {
+    fn digest(&self, code: u64, data: &[u8]) -> Option<Multihash> {
+      if code != XOR_HASH {
+        return DefaultHashers.digest(code, data);
+      }
+      let xor = data.iter().fold(0, |xor, byte| xor ^ byte);
+      Multihash::wrap(XOR_HASH, &[xor]).ok()
+    }
+  }
+
+  let cid = Cid::from_data_with(&WithXor, RAW, XOR_HASH, b"foo").unwrap();
+  assert_eq!(cid.hash().digest(), [b'f' ^ b'o' ^ b'o']);
+  assert_eq!(cid.verify_with(&WithXor, b"foo"), Ok(true));
+  assert_eq!(cid.verify_with(&WithXor, b"oof"), Ok(true));
+  assert_eq!(cid.verify_with(&WithXor, b"bar"), Ok(false));
+  let unsupported = Error::ParsingError(
+    sp_multihash::Error::UnsupportedCode(XOR_HASH),
+  );
+  assert_eq!(cid.verify(b"foo"), Err(unsupported));
+  assert!(Cid::from_data_with(&DefaultHashers, RAW, XOR_HASH, b"").is_err());
+
+  // The code table is still available through the registry
+  let sha = Cid::from_data_with(&WithXor, RAW, 0x12, b"foo").unwrap();
+  assert_eq!(sha, Cid::from_data(RAW, Code::Sha2_256, b"foo").unwrap());
+  assert_eq!(sha.verify(b"foo"), Ok(true));
+  assert_eq!(sha.verify_with(&WithXor, b"bar"), Ok(false));
+  let truncated =
+    Cid::from_data_truncated(RAW, Code::Sha2_256, b"foo", 20).unwrap();
+  assert_eq!(truncated.verify(b"foo"), Ok(true));
+  let inline = Cid::from_data_inline(RAW, Code::Sha2_256, b"foo", 32);
+  assert_eq!(inline.unwrap().verify_with(&WithXor, b"foo"), Ok(true));
+}
+
+#[test]
+fn verify_short_digests() {
+  use sp_multihash::Multihash as Mh;
+
+  let full = Code::Sha2_256.digest(b"foo");
+  // Empty and short prefixes of any digest would match unrelated data
+  for len in [0, 1, 2, Cid::MIN_TRUNCATED_DIGEST_LEN - 1] {
+    let prefix = &full.digest()[..len];
+    let cid = Cid::new_v1(RAW, Mh::wrap(0x12, prefix).unwrap());
+    assert_eq!(cid.verify(b"foo"), Ok(false));
+    assert_eq!(cid.verify(b"bar"), Ok(false));
+    let mut w = ByteCursor::new(Vec::new());
+    cid.write_block(b"bar", &mut w).unwrap();
+    let mut r = ByteCursor::new(w.into_inner());
+    let err = Cid::read_block(&mut r).unwrap_err();
+    assert_eq!(err, Error::DigestMismatch { offset: 0 });
+  }
+  let prefix = &full.digest()[..Cid::MIN_TRUNCATED_DIGEST_LEN];
+  let cid = Cid::new_v1(RAW, Mh::wrap(0x12, prefix).unwrap());
+  assert_eq!(cid.verify(b"foo"), Ok(true));
+  assert_eq!(cid.verify(b"bar"), Ok(false));
+
+  // Digests longer than the one of the hash function never match
+  let mut long = full.digest().to_vec();
+  long.push(0);
+  let cid = Cid::new_v1(RAW, Mh::wrap(0x12, &long).unwrap());
+  assert_eq!(cid.verify(b"foo"), Ok(false));
+}
+
+#[test]
+fn const_bytes() {
+  const DIGEST: [u8; 32] = [0xab; 32];
+  static V1: [u8; 37] = sp_cid::cid_bytes!(Version::V1, 0x0129, 0x12, &DIGEST);
+  static V0: &[u8] = &sp_cid::cid_bytes!(Version::V0, DAG_PB, 0x12, &DIGEST);
+  static LARGE: &[u8] = &sp_cid::cid_bytes!(Version::V1, u64::MAX, 0xb220, &[]);
+
+  let hash = MultihashGeneric::wrap(0x12, &DIGEST).unwrap();
+  assert_eq!(V1.to_vec(), Cid::new_v1(0x0129, hash).to_bytes());
+  assert_eq!(V0.to_vec(), Cid::new_v0(hash).unwrap().to_bytes());
+  let empty = MultihashGeneric::wrap(0xb220, &[]).unwrap();
+  assert_eq!(LARGE.to_vec(), Cid::new_v1(u64::MAX, empty).to_bytes());
+  assert_eq!(cid_binary_len(Version::V1, u64::MAX, 0xb220, 0), LARGE.len());
+
+  let bytes: [u8; 4] = cid_to_bytes(Version::V1, RAW, 0x00, &[]);
+  assert_eq!(Cid::try_from(&bytes[..]).unwrap().to_string(), "bafkqaaa");
+}
+
+#[test]
+fn str_decoder() {
+  fn decode(s: &str, chunk_len: usize) -> Result<Cid, Error> {
+    let mut decoder = CidStrDecoder::new();
+    s.as_bytes().chunks(chunk_len).try_for_each(|c| decoder.push(c))?;
+    decoder.finish()
+  }
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let cids = [
+    v0,
+    Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")),
+    Cid::new_v1(0x3fff, Code::Blake2b256.digest(b"foo")),
+    Cid::from_data_inline(RAW, Code::Sha2_256, b"", 32).unwrap(),
+  ];
+  for cid in &cids {
+    for chunk_len in [1, 3, 64] {
+      assert_eq!(decode(&cid.to_string(), chunk_len).unwrap(), *cid);
+    }
+    for base in [Base::Base32Upper, Base::Base36Lower, Base::Base58Btc] {
+      let s = cid.to_string_of_base(base).unwrap_or_default();
+      if cid.version() == Version::V1 {
+        assert_eq!(decode(&s, 5).unwrap(), *cid, "{:?}", base);
+      }
+    }
+  }
+
+  // Errors are the ones of the whole string, invalid symbols fail at once
+  let mut decoder = CidStrDecoder::<U64>::new();
+  assert_eq!(decoder.push(b"bafk"), Ok(()));
+  assert_eq!(decoder.base(), Some(Base::Base32Lower));
+  assert!(decoder.push(b"rei0").is_err());
+  assert!(decoder.push(b"a").is_err());
+  assert!(decoder.finish().is_err());
+  assert_eq!(decode("", 1), Err(Error::InputTooShort));
+  assert_eq!(decode("b", 1), Err(Error::InputTooShort));
+  let long = format!("b{}", "a".repeat(1000));
+  assert_eq!(
+    decode(&long, 10),
+    Err(Error::TrailingBytes { offset: 1 + 10 + 10 + 2 + 255 })
+  );
+  let s = "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy";
+  for len in 1..s.len() {
+    assert_eq!(decode(&s[..len], 4), Cid::try_from(&s[..len]));
+  }
+}
+
+#[test]
+fn display_adapters() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(DAG_PB, *v0.hash());
+  assert_eq!(v0.fmt_v1().to_string(), v1.to_string());
+  assert_eq!(v1.fmt_v1().to_string(), v1.to_string());
+  assert_eq!(format!("{:.6}", v0.fmt_v1()), "bafybe");
+
+  let s = v0.to_string();
+  assert_eq!(v0.fmt_short(4).to_string(), v0.short().to_string());
+  assert_eq!(v0.fmt_short(0).to_string(), "…");
+  assert_eq!(v0.fmt_short(s.len() / 2).to_string(), s);
+  assert_eq!(v0.fmt_short(usize::MAX).to_string(), s);
+  assert_eq!(format!("{:^9}", v1.fmt_short(2)), "  ba…vy  ");
+
+  let base58 = v1.to_string_of_base(Base::Base58Btc).unwrap();
+  assert_eq!(v1.fmt_base(Base::Base58Btc).unwrap().to_string(), base58);
+  assert_eq!(v0.fmt_base(Base::Base58Btc).unwrap().to_string(), s);
+  let upper = v0.fmt_base(Base::Base32Upper).unwrap().to_string();
+  assert_eq!(upper, v1.to_string().to_ascii_uppercase());
+  let latin1 = Cid::new_v1(RAW, MultihashGeneric::wrap(0, &[0xff]).unwrap());
+  assert!(latin1.fmt_base(Base::Identity).is_err());
+}
+
+#[test]
+fn parse_all_strings() {
+  let v1 = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let upper = v1.to_string().to_ascii_uppercase();
+  let base36 = v1.to_string_of_base(Base::Base36Lower).unwrap();
+  let ipfs = format!("/ipfs/{}", v0);
+  let inputs = [
+    v1.to_string(),
+    v0.to_string(),
+    upper,
+    base36,
+    ipfs,
+    String::new(),
+    "b".to_string(),
+    "bafkrei0".to_string(),
+    "Qmfoo".to_string(),
+    format!("b{}", "a".repeat(600)),
+    format!("{}!", v1),
+  ];
+  let results = parse_all::<U64>(inputs.iter().map(String::as_str));
+  assert_eq!(results.len(), inputs.len());
+  for (input, result) in inputs.iter().zip(&results) {
+    assert_eq!(*result, Cid::try_from(input.as_str()), "{}", input);
+  }
+  assert_eq!(results[..5].iter().filter(|r| r.is_ok()).count(), 5);
+  assert!(parse_all::<U64>(std::iter::empty()).is_empty());
+}
+
+#[test]
+fn seeded_fixtures() {
+  let cids: Vec<Cid> =
+    (0..1000).map(|seed| Cid::from_seed(seed, RAW).unwrap()).collect();
+  let distinct: std::collections::HashSet<_> = cids.iter().collect();
+  assert_eq!(distinct.len(), cids.len());
+  assert_eq!(Cid::from_seed(7, RAW).unwrap(), cids[7]);
+  // Fixtures are stable across versions
+  assert_eq!(
+    cids[0].to_string(),
+    "bafkreidgnf4hi5lsmu5oeifihf5r3tnpnz4j42vbxfs7ibwelumiackfj4"
+  );
+
+  let cid = Cid::from_seed(u64::MAX, DAG_PB).unwrap();
+  assert_eq!(cid.codec(), DAG_PB);
+  assert_eq!(cid.hash().code(), 0x12);
+  assert!(cid.hash().digest().starts_with(b"fixture:"));
+  assert!(cid.is_fixture());
+  let parsed: Cid = cid.to_string().parse().unwrap();
+  assert!(parsed.is_fixture());
+  assert!(!Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")).is_fixture());
+  assert!(CidGeneric::<U32>::from_seed(0, RAW).unwrap().is_fixture());
+  assert!(CidGeneric::<sp_multihash::U16>::from_seed(0, RAW).is_err());
+}
+
+#[test]
+fn error_kinds() {
+  let syntax = Cid::try_from("bafk!").unwrap_err();
+  assert_eq!(syntax.kind(), ErrorKind::Syntax);
+  let eof = Cid::try_from(&[0x01, 0x55][..]).unwrap_err();
+  assert_eq!(eof.kind(), ErrorKind::Syntax);
+  let version = Cid::try_from(&[0x03, 0x55, 0x00, 0x00][..]).unwrap_err();
+  assert_eq!(version.kind(), ErrorKind::UnsupportedFeature);
+  let hashers = sp_cid::DefaultHashers;
+  let hash = Cid::from_data_with(&hashers, RAW, 0x30_0001, b"foo");
+  assert_eq!(hash.unwrap_err().kind(), ErrorKind::UnsupportedFeature);
+  let large = CidGeneric::<sp_multihash::U16>::from_seed(0, RAW);
+  assert_eq!(large.unwrap_err().kind(), ErrorKind::Limit);
+  assert_eq!(Error::WriteError.kind(), ErrorKind::Io);
+  // Every variant has a kind
+  for code in (1..=14).chain(17..=24) {
+    Error::from_code(code).unwrap().kind();
+  }
+}
+
+#[test]
+fn framed_blocks() {
+  use sp_multihash::Multihash as Mh;
+
+  let raw = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let prefix = &raw.hash().digest()[..Cid::MIN_TRUNCATED_DIGEST_LEN];
+  let truncated = Cid::new_v1(RAW, Mh::wrap(0x12, prefix).unwrap());
+  let identity = Cid::new_v1(RAW, Mh::wrap(0x00, b"bar").unwrap());
+  let unknown = Cid::new_v1(RAW, Mh::wrap(0x30_0001, &[1]).unwrap());
+  let mut w = ByteCursor::new(Vec::new());
+  raw.write_block(b"foo", &mut w).unwrap();
+  truncated.write_block(b"foo", &mut w).unwrap();
+  identity.write_block(b"bar", &mut w).unwrap();
+  unknown.write_block(b"", &mut w).unwrap();
+  let mut r = ByteCursor::new(w.into_inner());
+  for (cid, data, verified) in [
+    (&raw, &b"foo"[..], true),
+    (&truncated, b"foo", true),
+    (&identity, b"bar", true),
+    (&unknown, b"", false),
+  ] {
+    let block: Block = Cid::read_block(&mut r).unwrap();
+    assert_eq!(block.cid(), cid);
+    assert_eq!(block.data(), data);
+    assert_eq!(block.is_verified(), verified);
+    assert_eq!(block.into_parts(), (*cid, data.to_vec()));
+  }
+  let end = r.position() as usize;
+  let err = Cid::read_block(&mut r).unwrap_err();
+  assert_eq!(err, Error::UnexpectedEof { offset: end });
+
+  // The digest is checked, and a frame can't reach past the stream
+  let mut w = ByteCursor::new(Vec::new());
+  raw.write_block(b"bar", &mut w).unwrap();
+  let mut bytes = w.into_inner();
+  bytes.insert(0, 0xff);
+  let mut r = ByteCursor::new(bytes.clone());
+  r.set_position(1);
+  let err = Cid::read_block(&mut r).unwrap_err();
+  assert_eq!(err, Error::DigestMismatch { offset: 1 });
+  bytes.pop();
+  let mut r = ByteCursor::new(bytes);
+  r.set_position(1);
+  let err = Cid::read_block(&mut r).unwrap_err();
+  assert_eq!(err, Error::UnexpectedEof { offset: 2 });
+  // The CID must fit into the frame
+  let mut r = ByteCursor::new(vec![0x02, 0x01, 0x55]);
+  let err = Cid::read_block(&mut r).unwrap_err();
+  assert_eq!(err, Error::UnexpectedEof { offset: 3 });
+}
+
+#[test]
+fn sorted_set_operations() {
+  use std::collections::BTreeSet;
+  use sp_cid::util;
+
+  let cid = |i: u32| Cid::new_v1(RAW, Code::Sha2_256.digest(&i.to_le_bytes()));
+  let a: BTreeSet<Cid> = (0..300).filter(|i| i % 2 == 0).map(cid).collect();
+  let b: BTreeSet<Cid> = (0..300).filter(|i| i % 3 == 0).map(cid).collect();
+  let ops = |a: &BTreeSet<Cid>, b: &BTreeSet<Cid>| {
+    let union: Vec<Cid> = util::union(a.clone(), b.clone()).collect();
+    assert!(union.iter().eq(a.union(b)));
+    let both: Vec<Cid> = util::intersection(a.clone(), b.clone()).collect();
+    assert!(both.iter().eq(a.intersection(b)));
+    let diff: Vec<Cid> = util::difference(a.clone(), b.clone()).collect();
+    assert!(diff.iter().eq(a.difference(b)));
+  };
+  ops(&a, &b);
+  ops(&b, &a);
+  ops(&a, &BTreeSet::new());
+  ops(&BTreeSet::new(), &b);
+  ops(&a, &a);
+
+  // Results are sorted sets again
+  let chained: Vec<Cid> =
+    util::difference(util::union(a.clone(), b.clone()), a.clone()).collect();
+  assert!(chained.iter().eq(b.difference(&a)));
+}
+
+#[test]
+fn parse_with_base() {
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  for base in [Base::Base32Lower, Base::Base36Lower, Base::Base58Btc] {
+    let s = cid.to_string_of_base(base).unwrap();
+    assert_eq!(Cid::try_from_with_base(&s).unwrap(), (cid, base));
+    let path = format!("/ipfs/{}", s);
+    assert_eq!(Cid::try_from_with_base(&path).unwrap(), (cid, base));
+  }
+  let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+  let (parsed, base) = Cid::try_from_with_base(v0).unwrap();
+  assert_eq!((parsed.version(), base), (Version::V0, Base::Base58Btc));
+  for s in ["", "b", "bafk!", "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1"] {
+    assert_eq!(
+      Cid::try_from_with_base(s).map(|(cid, _)| cid),
+      Cid::try_from(s)
+    );
+  }
+}
+
+#[test]
+fn auto_traits() {
+  fn auto_traits<T: Send + Sync + Unpin + Copy>(value: T) -> T {
+    value
+  }
+
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  auto_traits(cid);
+  auto_traits(CidGeneric::<U32>::default());
+  auto_traits(CidGeneric::<U128>::default());
+
+  // A future holding a CID across an await point can be spawned
+  fn spawnable<F: std::future::Future + Send + 'static>(future: F) -> F {
+    future
+  }
+  fn generic<S: Size>(cid: CidGeneric<S>) -> impl Unpin + Send + Sync {
+    cid
+  }
+  let future = spawnable(async move {
+    std::future::ready(()).await;
+    generic(cid)
+  });
+  drop(future);
+}
+
+#[test]
+fn content_types() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let raw = Cid::new_v1(RAW, hash);
+  assert_eq!(raw.preferred_content_type(), Some("application/vnd.ipld.raw"));
+  let dag_json = Cid::new_v1(0x0129, hash);
+  let content_type = dag_json.preferred_content_type().unwrap();
+  assert_eq!(content_type, "application/vnd.ipld.dag-json");
+  assert_eq!(Cid::new_v0(hash).unwrap().preferred_content_type(), None);
+
+  for (code, content_type) in names::CONTENT_TYPES {
+    assert!(names::codec_name(*code).is_some());
+    assert_eq!(names::content_type_codec(content_type), Some(*code));
+  }
+  let accept = " Application/VND.IPLD.DAG-CBOR ;q=0.9";
+  assert_eq!(names::content_type_codec(accept), Some(0x71));
+  assert_eq!(names::content_type_codec("application/vnd.ipld"), None);
+  assert_eq!(names::content_type_codec(""), None);
+}
+
+#[test]
+fn string_equality() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let v0 = Cid::new_v0(hash).unwrap();
+  let v1 = Cid::new_v1(DAG_PB, hash);
+  let raw = Cid::new_v1(RAW, hash);
+  let strings = [
+    v0.to_string(),
+    v1.to_string(),
+    v1.to_string_of_base(Base::Base36Lower).unwrap(),
+    format!("/ipfs/{}", v1.to_string_of_base(Base::Base58Btc).unwrap()),
+  ];
+  for a in &strings {
+    for b in &strings {
+      assert!(Cid::str_eq(a, b), "{} {}", a, b);
+    }
+    assert!(!Cid::str_eq(a, &raw.to_string()));
+  }
+  let sha512 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")).to_string();
+  assert!(Cid::str_eq(&sha512, &sha512));
+  assert!(!CidGeneric::<U32>::str_eq(&sha512, &sha512));
+  assert!(!Cid::str_eq("", ""));
+}
+
+#[test]
+fn constant_time_decoding() {
+  let cids = [
+    Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap(),
+    Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x71, Code::Sha2_512.digest(b"bar")),
+    Cid::try_from(consts::IDENTITY_EMPTY).unwrap(),
+  ];
+  for cid in &cids {
+    let mut bytes = cid.to_bytes();
+    assert_eq!(Cid::from_bytes_ct(&bytes).unwrap(), *cid);
+    for len in 0..bytes.len() {
+      assert_eq!(
+        Cid::from_bytes_ct(&bytes[..len]),
+        Cid::try_from(&bytes[..len]),
+        "{}",
+        len
+      );
+    }
+    bytes.push(0);
+    assert_eq!(Cid::from_bytes_ct(&bytes).unwrap(), *cid);
+
+    for other in &cids {
+      assert_eq!(cid.ct_eq(other), cid == other);
+    }
+    assert!(cid.ct_eq(&cid.resize::<U128>().unwrap()));
+  }
+  let sha512 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")).to_bytes();
+  assert_eq!(
+    CidGeneric::<U32>::from_bytes_ct(&sha512),
+    CidGeneric::<U32>::try_from(&sha512[..])
+  );
+
+  let digest = Code::Sha2_256.digest(b"foo");
+  let mut flipped = digest.digest().to_vec();
+  flipped[31] ^= 1;
+  let flipped = sp_multihash::Multihash::wrap(0x12, &flipped).unwrap();
+  assert!(!Cid::new_v1(RAW, digest).ct_eq(&Cid::new_v1(RAW, flipped)));
+  assert!(!Cid::new_v1(RAW, digest).ct_eq(&Cid::new_v1(0x71, digest)));
+}
+
+#[test]
+fn cid_tables() {
+  assert_eq!(
+    render_table(&[] as &[Cid]),
+    "CID  VERSION  CODEC  HASH  DIGEST\n"
+  );
+
+  let unknown = sp_multihash::Multihash::wrap(0x30_0001, &[0; 44]).unwrap();
+  let cids = vec![
+    Cid::new_v1(0x0129, Code::Sha2_256.digest(b"foo")),
+    Cid::new_v1(0x30_0002, unknown),
+  ];
+  let table = render_table(&cids);
+  let lines: Vec<&str> = table.lines().collect();
+  assert_eq!(lines.len(), 3);
+  assert!(lines.iter().all(|line| line.chars().count() == lines[0].len()));
+  for (line, cid) in lines[1..].iter().zip(&cids) {
+    assert!(line.starts_with(&cid.short().to_string()));
+  }
+  assert!(lines[1].contains("  dag-json  sha2-256"));
+  assert!(lines[2].contains("  0x300002  0x300001"));
+  assert!(lines[2].ends_with("  44"));
+}
+
+#[test]
+fn base_transcoding() {
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  let v1 = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  let bases = [Base::Base32Lower, Base::Base36Lower, Base::Base58Btc];
+  for cid in [v0, v1] {
+    for from in bases {
+      let s = cid.to_string_of_base_upgrading(from).unwrap();
+      let parsed = Cid::try_from(s.as_str()).unwrap();
+      for to in bases {
+        let expected = parsed.to_string_of_base_upgrading(to).unwrap();
+        assert_eq!(transcode_base(&s, to).unwrap(), expected);
+      }
+    }
+  }
+  let v0_str = v0.to_string();
+  assert_eq!(transcode_base(&v0_str, Base::Base58Btc).unwrap(), v0_str);
+  let path = format!("/ipfs/{}", v0_str);
+  assert_eq!(transcode_base(&path, Base::Base58Btc).unwrap(), v0_str);
+  let upper = v1.to_string().to_uppercase();
+  let lower = transcode_base(&upper, Base::Base32Lower).unwrap();
+  assert_eq!(lower, v1.to_string());
+
+  // Digests of any size are kept
+  let large = MultihashGeneric::<U128>::wrap(0x30_0001, &[7; 100]).unwrap();
+  let s = CidGeneric::new_v1(RAW, large).to_string();
+  assert!(Cid::try_from(s.as_str()).is_err());
+  assert_eq!(transcode_base(&s, Base::Base32Lower).unwrap(), s);
+
+  let mut trailing = v1.to_bytes();
+  trailing.push(0);
+  let trailing = multibase::encode(Base::Base32Lower, &trailing);
+  assert_eq!(
+    transcode_base(&trailing, Base::Base58Btc),
+    Err(Error::TrailingBytes { offset: v1.to_bytes().len() })
+  );
+  for s in ["", "b", "bafk!", "bafkqaa"] {
+    let err = transcode_base(s, Base::Base58Btc).unwrap_err();
+    assert_eq!(err, Cid::try_from(s).unwrap_err());
+  }
+}
+
+#[test]
+fn car_statistics() {
+  use sp_cid::car;
+
+  // Blocks of the made up codec list the CIDs they link to
+  const LIST: u64 = 0x30_0001;
+  let list_links = |block: &Block| {
+    let mut data = block.data();
+    let mut links = Vec::new();
+    if block.cid().codec() != LIST {
+      return links;
+    }
+    while let Ok((cid, rest)) = CidRef::read(data) {
+      links.push(cid.to_owned().unwrap());
+      data = rest;
+    }
+    links
+  };
+  let list = |cids: &[Cid]| {
+    let data: Vec<u8> = cids.iter().flat_map(Cid::to_bytes).collect();
+    (Cid::new_v1(LIST, Code::Sha2_256.digest(&data)), data)
+  };
+  let leaf = Cid::new_v1(RAW, Code::Sha2_256.digest(b"leaf"));
+  let missing = Cid::new_v1(RAW, Code::Sha2_256.digest(b"missing"));
+  let inline = sp_multihash::Multihash::wrap(0x00, b"inline").unwrap();
+  let inline = Cid::new_v1(RAW, inline);
+  let (node, node_data) = list(&[leaf, missing, inline]);
+  let (root, root_data) = list(&[node, leaf, missing]);
+
+  let mut w = ByteCursor::new(vec![2, 0xa1, 0x00]);
+  w.set_position(3);
+  root.write_block(&root_data, &mut w).unwrap();
+  node.write_block(&node_data, &mut w).unwrap();
+  leaf.write_block(b"leaf", &mut w).unwrap();
+  leaf.write_block(b"leaf", &mut w).unwrap();
+  let bytes = w.into_inner();
+
+  let stats = car::stats(&mut ByteCursor::new(bytes.clone()), list_links);
+  let stats = stats.unwrap();
+  assert_eq!(stats.blocks(), 4);
+  assert_eq!(stats.unique_cids(), 3);
+  assert_eq!(stats.cid_stats().total(), 4);
+  assert_eq!(stats.cid_stats().codecs()[&RAW], 2);
+  assert_eq!(stats.cid_stats().codecs()[&LIST], 2);
+  assert_eq!(stats.dangling_links(), 1);
+  let no_links = |_: &Block| Vec::new();
+  let stats = car::stats(&mut ByteCursor::new(bytes.clone()), no_links);
+  assert_eq!(stats.unwrap().dangling_links(), 0);
+
+  // Blocks are checked against their CIDs
+  let mut corrupt = bytes.clone();
+  *corrupt.last_mut().unwrap() ^= 1;
+  let offset = bytes.len() - leaf.to_bytes().len() - 5;
+  assert_eq!(
+    car::stats(&mut ByteCursor::new(corrupt), list_links),
+    Err(Error::DigestMismatch { offset })
+  );
+  let mut header_only = ByteCursor::new(vec![2, 0xa1, 0x00]);
+  let stats = car::stats(&mut header_only, list_links).unwrap();
+  assert_eq!(stats.blocks(), 0);
+  assert_eq!(
+    car::stats(&mut ByteCursor::new(vec![3, 0xa1]), list_links),
+    Err(Error::UnexpectedEof { offset: 1 })
+  );
+}
+
+#[test]
+fn storage_keys() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let v0 = Cid::new_v0(hash).unwrap();
+  let raw = Cid::new_v1(RAW, hash);
+  assert_eq!(&raw.storage_key32()[..], hash.digest());
+  assert_eq!(v0.storage_key32(), raw.storage_key32());
+
+  // Other digests are hashed together with the rest of the CID
+  let derived = |cid: &Cid| {
+    let data = [&b"sp-cid:storage-key32:"[..], &cid.to_bytes()].concat();
+    Code::Sha2_256.digest(&data).digest().to_vec()
+  };
+  let long = Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo"));
+  let long_cbor = Cid::new_v1(0x71, Code::Sha2_512.digest(b"foo"));
+  assert_eq!(long.storage_key32().to_vec(), derived(&long));
+  assert_ne!(long.storage_key32(), long_cbor.storage_key32());
+
+  // Identity digests of 32 bytes can't pose as the digest of other content
+  let identity = sp_multihash::Multihash::wrap(0x00, hash.digest()).unwrap();
+  let identity = Cid::new_v1(RAW, identity);
+  assert_eq!(identity.storage_key32().to_vec(), derived(&identity));
+  assert_ne!(identity.storage_key32(), raw.storage_key32());
+
+  let small = CidGeneric::<U32>::try_from(raw.to_bytes()).unwrap();
+  assert_eq!(small.storage_key32(), raw.storage_key32());
+}
+
+#[test]
+fn percent_decoded_parsing() {
+  let options = ParseOptions::new().percent_decode(true);
+  let cid = Cid::new_v1(RAW, Code::Sha2_256.digest(b"foo"));
+  let encoded: String =
+    cid.to_string().chars().map(|c| format!("%{:02x}", c as u8)).collect();
+  assert_eq!(options.parse::<U64>(&encoded), Ok(cid));
+  assert!(ParseOptions::new().parse::<U64>(&encoded).is_err());
+  #[cfg(feature = "all-bases")]
+  for base in [Base::Base64, Base::Base64Pad] {
+    let s = cid.to_string_of_base(base).unwrap();
+    let encoded = s.replace('/', "%2F").replace('+', "%2B");
+    assert_eq!(options.parse::<U64>(&encoded), Ok(cid));
+    let lower = encoded.replace("%2F", "%2f").replace("%2B", "%2b");
+    assert_eq!(options.parse::<U64>(&lower), Ok(cid));
+  }
+  let path = format!("%2Fipfs%2F{}", cid);
+  assert_eq!(options.parse::<U64>(&path), Ok(cid));
+  assert_eq!(options.parse::<U64>(&cid.to_string()), Ok(cid));
+
+  for s in ["%", "m%2", "m%zz", "m%C3%A9", "bafy%"] {
+    assert_eq!(
+      options.parse::<U64>(s),
+      Err(Error::MultibaseError(multibase::Error::InvalidBaseString))
+    );
+  }
+  // A plus sign isn't a space
+  assert!(options.parse::<U64>("m+").is_err());
+}
+
+#[test]
+fn audit_records() {
+  let hash = Code::Sha2_256.digest(b"foo");
+  let v0 = Cid::new_v0(hash).unwrap();
+  let record: serde_json::Value =
+    serde_json::from_str(&v0.to_audit_record()).unwrap();
+  assert_eq!(
+    record,
+    serde_json::json!({
+      "cid": v0.to_string(),
+      "cid_version": 0,
+      "codec": 0x70,
+      "codec_name": "dag-pb",
+      "digest":
+        "2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae",
+      "hash": 0x12,
+      "hash_name": "sha2-256",
+      "record_version": AUDIT_RECORD_VERSION,
+    })
+  );
+
+  // Unknown codes have no name, the keys stay the same
+  let unknown = sp_multihash::Multihash::wrap(0x30_0001, b"digest").unwrap();
+  let unknown = Cid::new_v1(0x30_0002, unknown);
+  assert_eq!(
+    unknown.to_audit_record(),
+    format!(
+      concat!(
+        r#"{{"cid":"{}","cid_version":1,"codec":3145730,"codec_name":null,"#,
+        r#""digest":"646967657374","hash":3145729,"hash_name":null,"#,
+        r#""record_version":{}}}"#,
+      ),
+      unknown, AUDIT_RECORD_VERSION,
+    )
+  );
+}
+
+#[test]
+fn decode_contexts() {
+  let hash = Code::Sha2_512.digest(b"foo");
+  let cid = Cid::new_v1(RAW, hash);
+  let bytes = cid.to_bytes();
+  let read = |context: DecodeContext, bytes: &[u8]| {
+    context.read_bytes::<U64>(&mut ByteCursor::new(bytes.to_vec()))
+  };
+
+  // The default context reads like `Cid::read_bytes`
+  assert_eq!(read(DecodeContext::new(), &bytes), Ok(cid));
+  for len in 0..bytes.len() {
+    assert_eq!(
+      read(DecodeContext::new(), &bytes[..len]),
+      Cid::read_bytes(&mut ByteCursor::new(bytes[..len].to_vec()))
+    );
+  }
+  let overflow = [0xff; 11];
+  assert_eq!(
+    read(DecodeContext::new(), &overflow),
+    Err(Error::VarIntDecodeError(unsigned_varint::decode::Error::Overflow))
+  );
+
+  // Limits are checked before the input is read
+  let context = DecodeContext::new().max_bytes(bytes.len());
+  assert_eq!(read(context, &bytes), Ok(cid));
+  let context = DecodeContext::new().max_bytes(bytes.len() - 1);
+  assert_eq!(read(context, &bytes), Err(Error::LimitExceeded { offset: 4 }));
+  let context = DecodeContext::new().max_bytes(1);
+  assert_eq!(read(context, &bytes), Err(Error::LimitExceeded { offset: 1 }));
+  let err = read(DecodeContext::new().max_varint_len(2), &overflow);
+  assert_eq!(err, Err(Error::LimitExceeded { offset: 0 }));
+  assert_eq!(err.unwrap_err().kind(), ErrorKind::Limit);
+  let wide = Cid::new_v1(0x30_0001, hash).to_bytes();
+  assert_eq!(
+    read(DecodeContext::new().max_varint_len(2), &wide),
+    Err(Error::LimitExceeded { offset: 1 })
+  );
+
+  // Offsets are positions within the stream
+  let mut r = ByteCursor::new([&[0u8; 3][..], &bytes].concat());
+  r.set_position(3);
+  let context = DecodeContext::new().max_bytes(10);
+  assert_eq!(
+    context.read_bytes::<U64>(&mut r),
+    Err(Error::LimitExceeded { offset: 7 })
+  );
+
+  let mut r = ByteCursor::new(hash.to_bytes());
+  let multihash = DecodeContext::new().read_multihash::<U64>(&mut r);
+  assert_eq!(multihash.unwrap(), hash);
+  let mut r = ByteCursor::new(hash.to_bytes());
+  assert_eq!(
+    DecodeContext::new().read_multihash::<U32>(&mut r),
+    Err(Error::DigestTooLarge { size: 64, max: 32 })
+  );
+
+  // Strings are limited before they are decoded
+  let s = cid.to_string();
+  assert_eq!(DecodeContext::new().parse::<U64>(&s), Ok(cid));
+  let context = DecodeContext::new().max_bytes(bytes.len() - 1);
+  assert!(matches!(
+    context.parse::<U64>(&s),
+    Err(Error::LimitExceeded { .. })
+  ));
+  let long = format!("{}{}", s, "a".repeat(1000));
+  assert!(matches!(
+    DecodeContext::new().parse::<U64>(&long),
+    Err(Error::LimitExceeded { .. })
+  ));
+
+  // Strictness flags
+  let trailing =
+    multibase::encode(Base::Base32Lower, [&bytes[..], &[0]].concat());
+  assert_eq!(DecodeContext::new().parse::<U64>(&trailing), Ok(cid));
+  assert_eq!(
+    DecodeContext::strict().parse::<U64>(&trailing),
+    Err(Error::TrailingBytes { offset: bytes.len() })
+  );
+  let upper = s.to_uppercase();
+  let mixed = format!("{}{}", &upper[..s.len() - 1], &s[s.len() - 1..]);
+  assert_eq!(DecodeContext::new().parse::<U64>(&mixed), Ok(cid));
+  assert!(DecodeContext::strict().parse::<U64>(&mixed).is_err());
+  let options = ParseOptions::new().bases(&[Base::Base58Btc]);
+  assert_eq!(
+    DecodeContext::new().parse_options(options).parse::<U64>(&s),
+    Err(Error::UnsupportedBase(Base::Base32Lower))
+  );
+
+  // The other decoders use the default context
+  let context = DecodeContext::new();
+  for len in 0..=bytes.len() {
+    let bytes = &bytes[..len];
+    let iter = context.read_byte_iter::<U64, _>(bytes.iter().copied());
+    assert_eq!(Cid::from_byte_iter(bytes.iter().copied()), iter);
+    let read = context.read_ref(bytes).and_then(|(cid, _)| cid.to_owned());
+    assert_eq!(Cid::try_from(bytes), read);
+    assert_eq!(CidRef::read(bytes), context.read_ref(bytes));
+    assert_eq!(CidPrefix::read(bytes), context.read_prefix(bytes));
+  }
+  assert_eq!(Cid::from_str(&long), DecodeContext::new().parse::<U64>(&long));
+
+  // and the limits apply to all of them
+  let context = DecodeContext::new().max_bytes(bytes.len() - 1);
+  let limit = Error::LimitExceeded { offset: 4 };
+  let iter = context.read_byte_iter::<U64, _>(bytes.iter().copied());
+  assert_eq!(iter.unwrap_err(), limit);
+  assert_eq!(context.read_ref(&bytes).unwrap_err(), limit);
+  assert!(context.read_prefix(&bytes).is_ok());
+  let context = DecodeContext::new().max_bytes(3);
+  let err = context.read_prefix(&bytes).unwrap_err();
+  assert_eq!(err, Error::LimitExceeded { offset: 3 });
+  let mut w = ByteCursor::new(Vec::new());
+  cid.write_framed(&mut w).unwrap();
+  let framed = w.into_inner();
+  let mut r = ByteCursor::new(framed.clone());
+  assert_eq!(DecodeContext::new().read_framed::<U64>(&mut r), Ok(cid));
+  let mut r = ByteCursor::new(framed);
+  let context = DecodeContext::new().max_bytes(bytes.len() - 1);
+  let framed = context.read_framed::<U64>(&mut r);
+  assert_eq!(framed, Err(Error::LimitExceeded { offset: 5 }));
+}
+
+#[cfg(feature = "scale-codec")]
+#[test]
+fn scale_legacy_encoding() {
+  use parity_scale_codec::{Decode, Encode};
+  use sp_cid::CanonicalCid;
+
+  // The encoding derived by earlier versions of the crate
+  #[derive(Encode)]
+  enum LegacyVersion {
+    V0,
+    V1,
+  }
+
+  #[derive(Encode)]
+  struct LegacyCid {
+    version: LegacyVersion,
+    codec: u64,
+    hash: MultihashGeneric<U64>,
+  }
+
+  let hash = Code::Sha2_256.digest(b"foo");
+  let legacy = vec![
+    LegacyCid { version: LegacyVersion::V0, codec: DAG_PB, hash },
+    LegacyCid { version: LegacyVersion::V1, codec: RAW, hash },
+  ];
+  let cids = [Cid::new_v0(hash).unwrap(), Cid::new_v1(RAW, hash)];
+  for (legacy, cid) in legacy.into_iter().zip(cids.iter()) {
+    let bytes = legacy.encode();
+    assert_eq!(cid.encode(), bytes);
+    assert_eq!(Cid::decode(&mut &bytes[..]).unwrap(), *cid);
+
+    let migrated = Cid::migrate(&bytes).unwrap();
+    assert_eq!(migrated, cid.to_bytes().encode());
+    let canonical = CanonicalCid::<U64>::decode(&mut &migrated[..]).unwrap();
+    assert_eq!(canonical.into_inner(), *cid);
+  }
+
+  let invalid = LegacyCid { version: LegacyVersion::V0, codec: RAW, hash };
+  assert!(Cid::migrate(&invalid.encode()).is_err());
+}
+
+#[cfg(all(feature = "tracing", feature = "scale-codec"))]
+#[test]
+fn traces_rejections() {
+  use parity_scale_codec::{Compact, Decode, Encode};
+  use sp_cid::CanonicalCid;
+  use std::{fmt, sync::Mutex};
+  use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+  };
+
+  // Collects the fields of every event as `name=value` strings
+  #[derive(Default)]
+  struct Capture(Mutex<Vec<String>>);
+
+  struct Fields(String);
+
+  impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+      self.0.push_str(&format!("{}={:?} ", field.name(), value));
+    }
+  }
+
+  impl Subscriber for &'static Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+      true
+    }
+
+    fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+      span::Id::from_u64(1)
+    }
+
+    fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+      let mut fields = Fields(String::new());
+      event.record(&mut fields);
+      self.0.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &span::Id) {}
+
+    fn exit(&self, _: &span::Id) {}
+  }
+
+  let capture: &'static Capture = Box::leak(Box::default());
+  let len = Cid::MAX_BINARY_LEN + 1;
+  let err = tracing::subscriber::with_default(capture, || {
+    let mut long = Compact(len as u32).encode();
+    long.resize(long.len() + len, 0);
+    assert!(CanonicalCid::<U64>::decode(&mut &long[..]).is_err());
+    Cid::try_from(&[0x01][..]).unwrap_err()
+  });
+
+  let events = capture.0.lock().unwrap();
+  assert_eq!(events.len(), 2);
+  assert!(events[0].contains("reason=\"CID exceeds the maximum length\""));
+  assert!(events[0].contains(&format!("input_len={} ", len)));
+  assert!(events[1].contains(&format!("error={:?} ", err)));
+  assert!(events[1].contains("input_len=1 "));
+}
+
+#[cfg(feature = "conformance")]
+#[test]
+fn conformance_checks() {
+  use sp_cid::conformance::{assert_roundtrip, assert_string_roundtrip};
+
+  let v0 = Cid::new_v0(Code::Sha2_256.digest(b"foo")).unwrap();
+  assert_roundtrip(&v0);
+  assert_roundtrip(&Cid::new_v1(RAW, Code::Sha2_512.digest(b"foo")));
+  assert_roundtrip(&Cid::new_v1(0x3fff, Code::Blake2b256.digest(b"foo")));
+  let inline = Cid::from_data_inline(RAW, Code::Sha2_256, b"", 32).unwrap();
+  assert_roundtrip(&inline);
+
+  for s in [
+    "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n",
+    "bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+    "BAFKREIBME22GW2H7Y2H7TG2FHQOTAQJUCNBC24DEQO72B6MKL2EGEZXHVY",
+    "/ipfs/bafkreibme22gw2h7y2h7tg2fhqotaqjucnbc24deqo72b6mkl2egezxhvy",
+  ] {
+    assert_string_roundtrip::<U64>(s);
+  }
+  let v1 = Cid::new_v1(DAG_PB, *v0.hash());
+  let base36 = v1.to_string_of_base(Base::Base36Lower).unwrap();
+  assert_eq!(assert_string_roundtrip::<U64>(&base36), v1);
+}